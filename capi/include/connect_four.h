@@ -0,0 +1,82 @@
+/*
+ * Connect Four for C: an opaque game from c4_new, played through the calls below and given
+ * back with c4_free. Link with -lconnect_four_c. Columns are counted from 0.
+ *
+ * Every call returns one of the C4_* codes below, or a count or column, which is never
+ * negative. A game whose call hit a bug inside the library answers C4_ERR_PANIC from then on;
+ * it can still be freed.
+ *
+ * A game may be handed to another thread between calls, but must not be used from two
+ * threads at once: a call made while another is running on the same game returns
+ * C4_ERR_BUSY without touching it. Separate games don't share anything.
+ */
+#ifndef CONNECT_FOUR_H
+#define CONNECT_FOUR_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define C4_OK 0
+#define C4_ERR_NULL (-1)
+#define C4_ERR_GAME_FINISHED (-2)
+#define C4_ERR_INVALID_COLUMN (-3)
+#define C4_ERR_COLUMN_FULL (-4)
+#define C4_ERR_ILLEGAL (-5)
+#define C4_ERR_NOTHING_TO_UNDO (-6)
+#define C4_ERR_BUFFER (-7)
+#define C4_ERR_BUSY (-8)
+#define C4_ERR_PANIC (-9)
+
+/* Least score c4_evaluate gives a position the player to move can force a win from */
+#define C4_WIN 1000000
+
+typedef struct c4_game c4_game;
+
+/* A two-player game on a board `width` by `height` played to `connect` in a row, or NULL
+ * when those aren't rules a game can be played by (sides of 4 to 20, connect from 3) */
+c4_game *c4_new(uint32_t width, uint32_t height, uint32_t connect);
+
+/* Free a game from c4_new; NULL is ignored */
+void c4_free(c4_game *game);
+
+/* Drop a piece in `column` for the player to move */
+int32_t c4_play(c4_game *game, int32_t column);
+
+/* Take back the last move */
+int32_t c4_undo(c4_game *game);
+
+/* Write the columns a piece can be dropped in, from the left, to `buffer` and return how
+ * many there are; C4_ERR_BUFFER, with nothing written, when `len` is too few for them all */
+int32_t c4_legal_moves(c4_game *game, int32_t *buffer, size_t len);
+
+/* 1 once the game is over, 0 while it goes on */
+int32_t c4_is_finished(c4_game *game);
+
+/* The player who won, 1 or 2; 0 while the game goes on and for a draw */
+int32_t c4_winner(c4_game *game);
+
+/* The column the computer would play for the player to move, thinking for about `millis` */
+int32_t c4_best_move(c4_game *game, uint32_t millis);
+
+/* The column the computer would play for the player to move looking `depth` moves ahead */
+int32_t c4_best_move_at_depth(c4_game *game, uint32_t depth);
+
+/* Write how the position stands for the player to move to `score`, as the hardest level of
+ * the computer sees it: above 0 is better for them, and C4_WIN or more a win they can force.
+ * Returns the column that search likes best. */
+int32_t c4_evaluate(c4_game *game, int32_t *score);
+
+/* Write the board to `buffer` as rows of X, O, and . from the top, one per line, with a
+ * terminating NUL, and return its length without the NUL. When `len` is too small, as much
+ * as fits is written, still NUL-terminated, so the length says how much room to ask with. */
+int32_t c4_render_ascii(c4_game *game, char *buffer, size_t len);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
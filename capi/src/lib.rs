@@ -0,0 +1,3 @@
+// The game's C API, which lives in the game itself, built into libconnect_four_c.so and
+// libconnect_four_c.a for C programs to link against; include/connect_four.h declares it
+pub use connect_four::ffi::*;
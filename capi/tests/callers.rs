@@ -0,0 +1,78 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Where cargo put libconnect_four_c: the directory above the one this test runs from
+fn library_dir() -> PathBuf {
+    let exe = env::current_exe().expect("the test knows where it is");
+    exe.parent()
+        .and_then(Path::parent)
+        .expect("the test runs from target/<profile>/deps")
+        .to_path_buf()
+}
+
+// Compile tests/game.c against the header and the shared library and run it
+#[test]
+fn a_c_program_plays_whole_games() {
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    if Command::new(&compiler).arg("--version").output().is_err() {
+        eprintln!("skipped: no C compiler ({}) to build tests/game.c with", compiler);
+        return;
+    }
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let libraries = library_dir();
+    let program = Path::new(env!("CARGO_TARGET_TMPDIR")).join("game");
+    let built = Command::new(&compiler)
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg(root.join("tests").join("game.c"))
+        .arg("-o")
+        .arg(&program)
+        .arg("-L")
+        .arg(&libraries)
+        .arg("-lconnect_four_c")
+        .arg(format!("-Wl,-rpath,{}", libraries.display()))
+        .status()
+        .expect("the compiler runs");
+    assert!(built.success(), "tests/game.c didn't build");
+
+    let output = Command::new(&program).output().expect("the program runs");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}\n{}",
+        stdout,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains(" moves, winner "), "{}", stdout);
+}
+
+// Run the Python bindings' tests in python/tests against the library just built
+#[test]
+fn the_python_bindings_pass_their_tests() {
+    if Command::new("python3").arg("--version").output().is_err() {
+        eprintln!("skipped: no python3 to run python/tests with");
+        return;
+    }
+    let repo = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("capi/ is in the repository");
+    let library = library_dir().join(format!(
+        "{}connect_four_c{}",
+        env::consts::DLL_PREFIX,
+        env::consts::DLL_SUFFIX
+    ));
+    let output = Command::new("python3")
+        .args(["-m", "unittest", "discover", "-s"])
+        .arg(repo.join("python").join("tests"))
+        .env("CONNECT_FOUR_LIB", &library)
+        .output()
+        .expect("python3 runs");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,94 @@
+/* Plays games through the C API: a scripted win with every kind of mistake along the way,
+ * then a whole game of the computer against itself. Exits 0 when every check passes. */
+#include <stdio.h>
+#include <string.h>
+
+#include "connect_four.h"
+
+static int failures = 0;
+
+#define CHECK(expr)                                                          \
+    do {                                                                     \
+        if (!(expr)) {                                                       \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, \
+                    #expr);                                                  \
+            failures++;                                                      \
+        }                                                                    \
+    } while (0)
+
+static void scripted_win(void) {
+    CHECK(c4_new(3, 6, 4) == NULL);
+    c4_game *game = c4_new(7, 6, 4);
+    CHECK(game != NULL);
+    CHECK(c4_undo(game) == C4_ERR_NOTHING_TO_UNDO);
+    CHECK(c4_play(game, 7) == C4_ERR_INVALID_COLUMN);
+    CHECK(c4_play(game, -1) == C4_ERR_INVALID_COLUMN);
+
+    int32_t columns[] = {0, 1, 0, 1, 0, 1};
+    for (size_t i = 0; i < sizeof columns / sizeof columns[0]; i++) {
+        CHECK(c4_play(game, columns[i]) == C4_OK);
+    }
+    CHECK(c4_is_finished(game) == 0);
+    CHECK(c4_best_move(game, 50) == 0);
+    CHECK(c4_best_move_at_depth(game, 2) == 0);
+    int32_t score = 0;
+    CHECK(c4_evaluate(game, &score) == 0);
+    CHECK(score >= C4_WIN);
+    CHECK(c4_play(game, 0) == C4_OK);
+    CHECK(c4_is_finished(game) == 1);
+    CHECK(c4_winner(game) == 1);
+    CHECK(c4_play(game, 3) == C4_ERR_GAME_FINISHED);
+
+    char board[64];
+    const char *expected =
+        ".......\n.......\nX......\nXO.....\nXO.....\nXO.....";
+    CHECK(c4_render_ascii(game, board, sizeof board) == (int32_t)strlen(expected));
+    CHECK(strcmp(board, expected) == 0);
+    char short_board[4];
+    CHECK(c4_render_ascii(game, short_board, sizeof short_board) == (int32_t)strlen(expected));
+    CHECK(strcmp(short_board, "...") == 0);
+
+    CHECK(c4_undo(game) == C4_OK);
+    CHECK(c4_winner(game) == 0);
+    c4_free(game);
+    c4_free(NULL);
+    CHECK(c4_play(NULL, 0) == C4_ERR_NULL);
+}
+
+static void computer_against_itself(void) {
+    c4_game *game = c4_new(7, 6, 4);
+    int32_t legal[7];
+    int moves = 0;
+    while (c4_is_finished(game) == 0) {
+        int32_t count = c4_legal_moves(game, legal, 7);
+        CHECK(count > 0);
+        CHECK(c4_legal_moves(game, legal, 0) == C4_ERR_BUFFER);
+        int32_t column = c4_best_move(game, 20);
+        int found = 0;
+        for (int32_t i = 0; i < count; i++) {
+            found |= legal[i] == column;
+        }
+        CHECK(found);
+        CHECK(c4_play(game, column) == C4_OK);
+        moves++;
+        if (moves > 42 || failures > 0) {
+            break;
+        }
+    }
+    CHECK(c4_is_finished(game) == 1);
+    int32_t winner = c4_winner(game);
+    CHECK(winner >= 0 && winner <= 2);
+    CHECK(c4_legal_moves(game, legal, 7) == 0);
+    CHECK(c4_best_move(game, 20) == C4_ERR_GAME_FINISHED);
+
+    char board[64];
+    c4_render_ascii(game, board, sizeof board);
+    printf("%s\n%d moves, winner %d\n", board, moves, winner);
+    c4_free(game);
+}
+
+int main(void) {
+    scripted_win();
+    computer_against_itself();
+    return failures == 0 ? 0 : 1;
+}
@@ -0,0 +1,150 @@
+// The part of the rules that needs nothing from an operating system: finding a line of
+// pieces on the board. The game in the terminal asks it, and so can a board of lights run
+// from a microcontroller, which only has to give it a heap for the cells of the line.
+//
+// The board, the rest of the rules, playing a move, and the computer player aren't here yet.
+// They all work on the game's Game, which also holds its clocks, the players' names, what
+// saves and the log read back, and the text of every message in the player's language, all
+// of which need std; they move here once Game is split into the position and the rest. A
+// browser, which has no std clock either, gets the whole game through the `wasm` feature of
+// connect_four instead.
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+// A board's size, the pieces in a row that win on it, and whether its left and right edges
+// join up, as on a cylinder
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Grid {
+    pub width: usize,
+    pub height: usize,
+    pub connect: usize,
+    pub wraps: bool,
+}
+
+// Directions a line can run in, in the order they're checked
+const DIRECTIONS: [(isize, isize); 4] = [
+    (0, 1),  // horizontal
+    (1, 0),  // vertical
+    (1, 1),  // diagonal (top-left to bottom-right)
+    (-1, 1), // diagonal (bottom-left to top-right)
+];
+
+// Cells of a connected line through the piece at (row, column), rows counted from the top,
+// if one is long enough to win; `piece` says what's in a cell. The line runs from one end to
+// the other and takes in every connected cell when there are more than needed, and a piece
+// on two lines at once reports the first in the order of DIRECTIONS.
+pub fn line_through<P: PartialEq>(
+    grid: &Grid,
+    row: usize,
+    column: usize,
+    piece: impl Fn(usize, usize) -> Option<P>,
+) -> Option<Vec<(usize, usize)>> {
+    let own = piece(row, column)?;
+    let (width, height) = (grid.width as isize, grid.height as isize);
+    for (row_step, column_step) in DIRECTIONS {
+        // Walk away from the piece while the cells stay the same color, for at most `most`
+        // cells
+        let run = |row_step: isize, column_step: isize, most: usize| {
+            let mut cells = Vec::new();
+            let mut r = row as isize + row_step;
+            let mut c = column as isize + column_step;
+            while cells.len() < most && r >= 0 && r < height {
+                if grid.wraps {
+                    c = c.rem_euclid(width);
+                }
+                if c < 0 || c >= width || piece(r as usize, c as usize).as_ref() != Some(&own) {
+                    break;
+                }
+                cells.push((r as usize, c as usize));
+                r += row_step;
+                c += column_step;
+            }
+            cells
+        };
+        // On a cylinder the walk carries on round the seam; a sideways line can only get as
+        // long as the board is wide, or it would come back to cells already counted
+        let most = if row_step == 0 {
+            grid.width - 1
+        } else {
+            usize::MAX
+        };
+
+        // Order the line from one end to the other
+        let mut line = run(-row_step, -column_step, most);
+        line.reverse();
+        line.push((row, column));
+        let rest = most.saturating_sub(line.len() - 1);
+        line.extend(run(row_step, column_step, rest));
+
+        if line.len() >= grid.connect {
+            return Some(line);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A grid of the classic size, flat or round
+    fn grid(wraps: bool) -> Grid {
+        Grid {
+            width: 7,
+            height: 6,
+            connect: 4,
+            wraps,
+        }
+    }
+
+    // The pieces of a board drawn one row per line from the top, X and O for the two players
+    fn board<'a>(rows: &'a [&str]) -> impl Fn(usize, usize) -> Option<char> + 'a {
+        |row, column| Some(rows[row].as_bytes()[column] as char).filter(|&cell| cell != '.')
+    }
+
+    #[test]
+    fn lines_run_from_one_end_to_the_other() {
+        let rows = [
+            ".......", ".......", "...X...", "..XO...", ".XOO...", "XOOOX..",
+        ];
+        let cells = board(&rows);
+        assert_eq!(
+            line_through(&grid(false), 3, 2, &cells),
+            Some(Vec::from([(5, 0), (4, 1), (3, 2), (2, 3)]))
+        );
+        assert_eq!(
+            line_through(&grid(false), 5, 2, &cells),
+            None,
+            "three in a row isn't a line"
+        );
+        assert_eq!(
+            line_through(&grid(false), 0, 0, &cells),
+            None,
+            "an empty cell"
+        );
+    }
+
+    #[test]
+    fn lines_go_round_a_cylinder_but_count_each_cell_once() {
+        let rows = [
+            ".......", ".......", ".......", ".......", ".......", "XX...XX",
+        ];
+        let cells = board(&rows);
+        assert_eq!(line_through(&grid(false), 5, 0, &cells), None);
+        assert_eq!(
+            line_through(&grid(true), 5, 0, &cells),
+            Some(Vec::from([(5, 5), (5, 6), (5, 0), (5, 1)]))
+        );
+        // A full row round a four-wide cylinder is four in a row, not five
+        let narrow = Grid {
+            width: 4,
+            connect: 5,
+            ..grid(true)
+        };
+        let rows = ["....", "....", "....", "....", "....", "XXXX"];
+        assert_eq!(line_through(&narrow, 5, 0, board(&rows)), None);
+    }
+}
@@ -0,0 +1,38 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+// A target with no std at all, like the microcontroller the crate is meant to run on
+const TARGET: &str = "thumbv7em-none-eabihf";
+
+// Build the crate for a microcontroller, so nothing from std creeps into it, when the
+// toolchain has that target's core library to build it with
+#[test]
+fn the_core_builds_without_std() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let libdir = Command::new(&rustc)
+        .args(["--print", "target-libdir", "--target", TARGET])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    if !libdir.is_some_and(|libdir| Path::new(&libdir).exists()) {
+        eprintln!("skipped: no {0} target (rustup target add {0})", TARGET);
+        return;
+    }
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    // A target directory of its own, since the one running this test is locked
+    let output = Command::new(cargo)
+        .args(["build", "--lib", "--target", TARGET])
+        .arg("--manifest-path")
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_std"))
+        .output()
+        .expect("cargo runs");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
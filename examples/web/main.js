@@ -0,0 +1,80 @@
+// You play red and move first; the computer answers with yellow, thinking for about
+// THINK milliseconds a move so the page never hangs waiting for it
+import init, { WasmGame } from "./pkg/connect_four_web.js";
+
+const THINK = 500;
+
+const board = document.getElementById("board");
+const status = document.getElementById("status");
+let game;
+
+// Draw the board from boardJson and say whose move it is or how the game ended
+function draw() {
+  const state = JSON.parse(game.boardJson());
+  board.replaceChildren(...state.rows.map((row) => {
+    const tr = document.createElement("tr");
+    row.forEach((cell, column) => {
+      const td = document.createElement("td");
+      td.className = cell ? `p${cell}` : "";
+      td.appendChild(document.createElement("div"));
+      td.addEventListener("click", () => play(column));
+      tr.appendChild(td);
+    });
+    return tr;
+  }));
+  if (state.winner === 1) {
+    status.textContent = "You win!";
+  } else if (state.winner === 2) {
+    status.textContent = "The computer wins.";
+  } else if (state.toMove === null) {
+    status.textContent = "It's a draw.";
+  } else {
+    status.textContent = state.toMove === 1 ? "Your move." : "The computer is thinking…";
+  }
+}
+
+// Play the player's piece in `column`, then let the computer answer once the page has
+// shown it
+function play(column) {
+  try {
+    game.play(column);
+  } catch (err) {
+    // A full column or a finished game just isn't a move; anything else is a bug
+    if (err.name !== "ColumnFull" && err.name !== "GameFinished") {
+      throw err;
+    }
+    status.textContent = err.message;
+    return;
+  }
+  draw();
+  if (!game.isFinished()) {
+    setTimeout(() => {
+      game.play(game.bestMoveWithin(THINK));
+      draw();
+    }, 0);
+  }
+}
+
+document.getElementById("undo").addEventListener("click", () => {
+  // Take moves back until it's the player's move again: the computer's answer and the
+  // player's move before it, or just the player's move that ended the game
+  try {
+    do {
+      game.undo();
+    } while (JSON.parse(game.boardJson()).toMove !== 1);
+  } catch (err) {
+    if (err.name !== "NothingToUndo") {
+      throw err;
+    }
+  }
+  draw();
+});
+
+document.getElementById("new").addEventListener("click", () => {
+  game = new WasmGame(7, 6, 4);
+  draw();
+});
+
+await init();
+game = new WasmGame(7, 6, 4);
+draw();
@@ -0,0 +1,250 @@
+"""Connect Four for Python, played through the game's C library.
+
+Build the library first with ``cargo build -p connect_four_capi`` (add ``--release`` for a
+faster computer). It is looked for in ``$CONNECT_FOUR_LIB``, then next to this package, then
+in the repository's ``target/release`` and ``target/debug``.
+
+    >>> from connect_four import Engine, Game
+    >>> game = Game()
+    >>> game.play(3)
+    >>> Engine(game).best_move(depth=4)
+    3
+
+Columns are counted from 0. Searches run in the library without holding the GIL, so other
+Python threads carry on while the computer thinks. A game must not be used from two threads
+at once; a call made while another is running on the same game raises ``GameBusy``.
+"""
+
+from __future__ import annotations
+
+import ctypes
+import os
+import sys
+from pathlib import Path
+from typing import Iterable, List, Optional
+
+__all__ = [
+    "ColumnFull",
+    "ConnectFourError",
+    "Engine",
+    "Game",
+    "GameBusy",
+    "GameBroken",
+    "GameFinished",
+    "InvalidColumn",
+    "MoveError",
+    "NothingToUndo",
+    "WIN",
+]
+
+# Codes the library returns, from capi/include/connect_four.h
+_OK = 0
+_ERR_NULL = -1
+_ERR_GAME_FINISHED = -2
+_ERR_INVALID_COLUMN = -3
+_ERR_COLUMN_FULL = -4
+_ERR_ILLEGAL = -5
+_ERR_NOTHING_TO_UNDO = -6
+_ERR_BUFFER = -7
+_ERR_BUSY = -8
+_ERR_PANIC = -9
+
+# Least score Engine.evaluate gives a position the player to move can force a win from
+WIN = 1_000_000
+
+
+class ConnectFourError(Exception):
+    """Anything the library turned down."""
+
+
+class MoveError(ConnectFourError, ValueError):
+    """A move the rules don't allow."""
+
+
+class GameFinished(MoveError):
+    """The game is already over."""
+
+
+class InvalidColumn(MoveError):
+    """The column isn't on the board."""
+
+
+class ColumnFull(MoveError):
+    """The column has no room left."""
+
+
+class NothingToUndo(MoveError):
+    """No move has been played to take back."""
+
+
+class GameBusy(ConnectFourError):
+    """Another thread is using the game."""
+
+
+class GameBroken(ConnectFourError):
+    """A bug inside the library broke the game; only a new one can be played."""
+
+
+_ERRORS = {
+    _ERR_GAME_FINISHED: (GameFinished, "the game is over"),
+    _ERR_INVALID_COLUMN: (InvalidColumn, "column {column} is not on the board"),
+    _ERR_COLUMN_FULL: (ColumnFull, "column {column} is full"),
+    _ERR_ILLEGAL: (MoveError, "that move isn't allowed"),
+    _ERR_NOTHING_TO_UNDO: (NothingToUndo, "there is no move to take back"),
+    _ERR_BUSY: (GameBusy, "another thread is using the game"),
+    _ERR_PANIC: (GameBroken, "the game library hit a bug"),
+    _ERR_NULL: (ConnectFourError, "the library was given no game"),
+    _ERR_BUFFER: (ConnectFourError, "the library needed more room"),
+}
+
+
+def _library_path() -> Path:
+    if "CONNECT_FOUR_LIB" in os.environ:
+        return Path(os.environ["CONNECT_FOUR_LIB"])
+    name = {
+        "darwin": "libconnect_four_c.dylib",
+        "win32": "connect_four_c.dll",
+    }.get(sys.platform, "libconnect_four_c.so")
+    here = Path(__file__).resolve().parent
+    repo = here.parent.parent
+    candidates = [here / name, repo / "target" / "release" / name, repo / "target" / "debug" / name]
+    for candidate in candidates:
+        if candidate.exists():
+            return candidate
+    raise ImportError(
+        f"{name} not found; build it with `cargo build -p connect_four_capi` "
+        "or point CONNECT_FOUR_LIB at it"
+    )
+
+
+def _load() -> ctypes.CDLL:
+    # A CDLL lets go of the GIL for the length of every call
+    lib = ctypes.CDLL(str(_library_path()))
+    game = ctypes.c_void_p
+    i32 = ctypes.c_int32
+    signatures = {
+        "c4_new": ([ctypes.c_uint32] * 3, game),
+        "c4_free": ([game], None),
+        "c4_play": ([game, i32], i32),
+        "c4_undo": ([game], i32),
+        "c4_legal_moves": ([game, ctypes.POINTER(i32), ctypes.c_size_t], i32),
+        "c4_is_finished": ([game], i32),
+        "c4_winner": ([game], i32),
+        "c4_best_move": ([game, ctypes.c_uint32], i32),
+        "c4_best_move_at_depth": ([game, ctypes.c_uint32], i32),
+        "c4_evaluate": ([game, ctypes.POINTER(i32)], i32),
+        "c4_render_ascii": ([game, ctypes.c_char_p, ctypes.c_size_t], i32),
+    }
+    for name, (arguments, result) in signatures.items():
+        function = getattr(lib, name)
+        function.argtypes = arguments
+        function.restype = result
+    return lib
+
+
+_lib = _load()
+
+
+def _check(code: int, **details: object) -> int:
+    """The code itself when it's a count or column, or the exception it stands for."""
+    if code >= 0:
+        return code
+    error, message = _ERRORS.get(code, (ConnectFourError, f"unknown error {code}"))
+    raise error(message.format(**details))
+
+
+class Game:
+    """A two-player game, by default on the classic 7x6 board played to four in a row."""
+
+    def __init__(self, width: int = 7, height: int = 6, connect: int = 4) -> None:
+        self._handle = _lib.c4_new(width, height, connect)
+        if not self._handle:
+            raise ValueError(
+                f"can't play to {connect} in a row on a {width}x{height} board: sides run "
+                "from 4 to 20, and connect from 3 up to the longer side"
+            )
+        self.width = width
+        self.height = height
+        self.connect = connect
+        self._moves: List[int] = []
+
+    @classmethod
+    def from_moves(cls, moves: Iterable[int], **rules: int) -> "Game":
+        """A game with `moves` played, by the rules `Game` takes."""
+        game = cls(**rules)
+        for column in moves:
+            game.play(column)
+        return game
+
+    def __del__(self) -> None:
+        if getattr(self, "_handle", None):
+            _lib.c4_free(self._handle)
+            self._handle = None
+
+    def play(self, column: int) -> None:
+        """Drop a piece in `column` for the player to move."""
+        _check(_lib.c4_play(self._handle, column), column=column)
+        self._moves.append(column)
+
+    def undo(self) -> None:
+        """Take back the last move."""
+        _check(_lib.c4_undo(self._handle))
+        self._moves.pop()
+
+    def legal_moves(self) -> List[int]:
+        """The columns a piece can be dropped in, from the left."""
+        buffer = (ctypes.c_int32 * self.width)()
+        count = _check(_lib.c4_legal_moves(self._handle, buffer, self.width))
+        return list(buffer[:count])
+
+    @property
+    def board(self) -> List[List[int]]:
+        """Rows from the top, each cell 0 when empty or the number of the player in it."""
+        size = self.width * self.height + self.height
+        buffer = ctypes.create_string_buffer(size)
+        _check(_lib.c4_render_ascii(self._handle, buffer, size))
+        cells = {"X": 1, "O": 2, ".": 0}
+        return [[cells[cell] for cell in row] for row in buffer.value.decode().splitlines()]
+
+    @property
+    def is_finished(self) -> bool:
+        return _check(_lib.c4_is_finished(self._handle)) == 1
+
+    @property
+    def winner(self) -> Optional[int]:
+        """The player who won, 1 or 2; None while the game goes on and for a draw."""
+        return _check(_lib.c4_winner(self._handle)) or None
+
+    def to_moves(self) -> List[int]:
+        """The columns played so far, in order, for `from_moves`."""
+        return list(self._moves)
+
+    def __str__(self) -> str:
+        symbols = ".XO"
+        return "\n".join("".join(symbols[cell] for cell in row) for row in self.board)
+
+
+class Engine:
+    """The computer, thinking about the position of a game for the player to move."""
+
+    def __init__(self, game: Game) -> None:
+        self.game = game
+
+    def best_move(self, depth: Optional[int] = None, movetime: Optional[float] = None) -> int:
+        """The column to play, searching `depth` moves ahead or for about `movetime` seconds;
+        one second when neither is given."""
+        if depth is not None and movetime is not None:
+            raise ValueError("give a depth or a movetime, not both")
+        if depth is not None:
+            if depth < 1:
+                raise ValueError("the depth must be at least 1")
+            return _check(_lib.c4_best_move_at_depth(self.game._handle, depth))
+        millis = round(1000 * (1.0 if movetime is None else movetime))
+        return _check(_lib.c4_best_move(self.game._handle, max(millis, 0)))
+
+    def evaluate(self) -> int:
+        """How the position stands for the player to move: above 0 is better for them, and
+        WIN or more a win they can force."""
+        score = ctypes.c_int32()
+        _check(_lib.c4_evaluate(self.game._handle, ctypes.byref(score)))
+        return score.value
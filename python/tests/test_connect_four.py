@@ -0,0 +1,105 @@
+"""Tests of the Python bindings, run with `python3 -m unittest discover python/tests` (or
+pytest) once the C library is built; `cargo test --workspace` runs them too."""
+
+import sys
+import threading
+import time
+import unittest
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent))
+
+from connect_four import (  # noqa: E402
+    WIN,
+    ColumnFull,
+    ConnectFourError,
+    Engine,
+    Game,
+    GameFinished,
+    InvalidColumn,
+    MoveError,
+    NothingToUndo,
+)
+
+
+class GameTest(unittest.TestCase):
+    def test_a_scripted_game_is_won_by_the_first_player(self):
+        game = Game()
+        for column in [3, 3, 2, 4, 1, 5]:
+            game.play(column)
+        self.assertFalse(game.is_finished)
+        self.assertIsNone(game.winner)
+        game.play(0)
+        self.assertTrue(game.is_finished)
+        self.assertEqual(game.winner, 1)
+        self.assertEqual(game.board[-1], [1, 1, 1, 1, 2, 2, 0])
+        self.assertEqual(game.board[-2], [0, 0, 0, 2, 0, 0, 0])
+        self.assertEqual(game.legal_moves(), [])
+        with self.assertRaises(GameFinished):
+            game.play(6)
+
+    def test_moves_round_trip(self):
+        moves = [3, 3, 2, 4, 1]
+        game = Game.from_moves(moves, width=9, height=7, connect=5)
+        self.assertEqual(game.to_moves(), moves)
+        self.assertEqual(len(game.board), 7)
+        self.assertEqual(len(game.board[0]), 9)
+        game.undo()
+        self.assertEqual(game.to_moves(), moves[:-1])
+        self.assertEqual(str(Game.from_moves(game.to_moves(), width=9, height=7, connect=5)), str(game))
+
+    def test_bad_moves_raise_move_errors(self):
+        game = Game.from_moves([0] * 6)
+        with self.assertRaises(ColumnFull):
+            game.play(0)
+        with self.assertRaises(InvalidColumn):
+            game.play(7)
+        with self.assertRaises(InvalidColumn):
+            game.play(-1)
+        self.assertEqual(game.legal_moves(), [1, 2, 3, 4, 5, 6])
+        with self.assertRaises(NothingToUndo):
+            Game().undo()
+        self.assertTrue(issubclass(MoveError, ConnectFourError))
+        self.assertTrue(issubclass(MoveError, ValueError))
+        with self.assertRaises(ValueError):
+            Game(width=3)
+
+
+class EngineTest(unittest.TestCase):
+    def test_the_computer_blocks_an_immediate_threat(self):
+        # The first player has three along the bottom, the left end closed
+        game = Game.from_moves([1, 0, 2, 2, 3])
+        self.assertEqual(Engine(game).best_move(depth=2), 4)
+        self.assertEqual(Engine(game).best_move(movetime=0.05), 4)
+
+    def test_a_forced_win_scores_at_least_win(self):
+        game = Game.from_moves([0, 1, 0, 1, 0])
+        game.play(3)
+        self.assertGreaterEqual(Engine(game).evaluate(), WIN)
+        with self.assertRaises(ValueError):
+            Engine(game).best_move(depth=2, movetime=1)
+
+    def test_searches_let_other_threads_run(self):
+        game = Game(width=12, height=10)
+        ticks = []
+        done = threading.Event()
+
+        def count():
+            while not done.is_set():
+                ticks.append(time.monotonic())
+                time.sleep(0.001)
+
+        counter = threading.Thread(target=count)
+        counter.start()
+        started = time.monotonic()
+        Engine(game).best_move(movetime=0.5)
+        finished = time.monotonic()
+        done.set()
+        counter.join()
+        # Holding the GIL the search would have stopped the counter for as long as it ran
+        self.assertGreater(finished - started, 0.05)
+        during = [tick for tick in ticks if started < tick < finished]
+        self.assertGreater(len(during), (finished - started) / 0.005)
+
+if __name__ == "__main__":
+    unittest.main()
@@ -0,0 +1,216 @@
+use crate::ai;
+use crate::outcome::{DrawKind, WinKind};
+use crate::{Game, Player};
+
+// Something a player can earn once by how they finished a game
+#[derive(Debug)]
+pub struct Achievement {
+    // Stable name kept in the profile
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    // Whether the finished game earns it for the player in this seat
+    earned: fn(&Game, Player) -> bool,
+}
+
+// Id of the achievement for finishing `connect_four tutorial`, which no game earns
+pub const TUTORIAL: &str = "tutorial";
+
+// Every achievement, in the order :achievements lists them
+pub const ACHIEVEMENTS: [Achievement; 7] = [
+    Achievement {
+        id: "first_win",
+        name: "First win",
+        description: "Win a game",
+        earned: |game, seat| game.winner() == Some(seat),
+    },
+    Achievement {
+        id: "vertical_four",
+        name: "Straight down",
+        description: "Win with a vertical line",
+        earned: |game, seat| {
+            game.winner() == Some(seat)
+                && game.winning_line().is_some_and(|line| {
+                    let (row, column) = line[0];
+                    // Under misère the line is the loser's
+                    game.board[row][column] == Some(seat)
+                        && line.iter().all(|&(_, other)| other == column)
+                })
+        },
+    },
+    Achievement {
+        id: "off_center",
+        name: "Off center",
+        description: "Win without ever playing the center column",
+        earned: |game, seat| {
+            game.winner() == Some(seat)
+                && own_moves(game, seat).all(|column| column != game.rules.width / 2)
+        },
+    },
+    Achievement {
+        id: "comeback",
+        name: "Comeback",
+        description: "Win from a position the computer's search scored as lost",
+        earned: |game, seat| game.winner() == Some(seat) && was_lost(game, seat),
+    },
+    Achievement {
+        id: "quick_win",
+        name: "Quick win",
+        description: "Win in fewer than 10 of your own moves",
+        earned: |game, seat| {
+            game.winner() == Some(seat)
+                && game.won_by() == Some(WinKind::Connect)
+                && own_moves(game, seat).count() < 10
+        },
+    },
+    Achievement {
+        id: "full_house",
+        name: "Full house",
+        description: "Draw with every cell of the board filled",
+        earned: |game, _| {
+            game.drawn_by() == Some(DrawKind::FullBoard) && game.moves_remaining() == 0
+        },
+    },
+    Achievement {
+        id: TUTORIAL,
+        name: "Graduate",
+        description: "Finish every lesson of the tutorial",
+        earned: |_, _| false,
+    },
+];
+
+// Columns the player in `seat` played, in order
+fn own_moves(game: &Game, seat: Player) -> impl Iterator<Item = usize> + '_ {
+    game.history
+        .iter()
+        .skip(seat.index())
+        .step_by(game.players())
+        .map(|coord| coord.column)
+}
+
+// Whether the player in `seat` was ever to move in a position the search saw as lost
+fn was_lost(game: &Game, seat: Player) -> bool {
+    let mut replay = game.clone();
+    while replay.undo() {
+        if replay.current_player == seat && !replay.history.is_empty() && ai::lost(&replay) {
+            return true;
+        }
+    }
+    false
+}
+
+// The achievements a finished game earns for the player in `seat`
+pub fn earned(game: &Game, seat: Player) -> Vec<&'static Achievement> {
+    if !game.is_finished() {
+        return Vec::new();
+    }
+    ACHIEVEMENTS
+        .iter()
+        .filter(|achievement| (achievement.earned)(game, seat))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::variant::{Move, Variant};
+
+    // A game of `variant` on a `width` by `height` board with these columns played, counted
+    // from 1
+    fn played(variant: Variant, width: usize, height: usize, columns: &[usize]) -> Game {
+        let mut game = Game::builder(&Settings::default())
+            .variant(variant)
+            .size(width, height)
+            .build()
+            .expect("the rules are valid");
+        for &column in columns {
+            game.play(Move::Drop(column - 1))
+                .expect("the moves are legal");
+        }
+        game
+    }
+
+    fn classic(columns: &[usize]) -> Game {
+        played(Variant::Standard, 7, 6, columns)
+    }
+
+    fn ids(game: &Game, seat: Player) -> Vec<&'static str> {
+        earned(game, seat)
+            .iter()
+            .map(|achievement| achievement.id)
+            .collect()
+    }
+
+    #[test]
+    fn each_achievement_is_earned_by_one_game_and_not_another() {
+        let vertical = classic(&[1, 2, 1, 2, 1, 2, 1]);
+        let across = classic(&[1, 1, 2, 2, 3, 3, 4]);
+        // The first player faces an open three along the bottom, blocks one end of it, and
+        // wins down column 7 while the second player never takes the other end
+        let comeback = classic(&[7, 3, 7, 4, 1, 5, 2, 1, 7, 1, 7]);
+        let mut resigned = classic(&[4, 4]);
+        resigned.resign();
+        let long = classic(&[
+            2, 7, 4, 3, 1, 4, 7, 5, 6, 1, 2, 6, 6, 7, 3, 1, 6, 3, 6, 6, 5, 4, 5, 7, 2, 3, 3, 5, 4,
+            5, 4, 5, 1,
+        ]);
+        // The board filled with no line of four: rows of two and two, turned over each row
+        let full = played(
+            Variant::Standard,
+            4,
+            4,
+            &[1, 3, 2, 4, 3, 1, 4, 2, 1, 3, 2, 4, 3, 1, 4, 2],
+        );
+        let misere = played(Variant::Misere, 7, 6, &[1, 2, 1, 2, 1, 2, 1]);
+
+        let cases = [
+            (
+                "first_win",
+                (&vertical, Player::One),
+                (&vertical, Player::Two),
+            ),
+            (
+                "vertical_four",
+                (&vertical, Player::One),
+                (&across, Player::One),
+            ),
+            (
+                "off_center",
+                (&vertical, Player::One),
+                (&across, Player::One),
+            ),
+            (
+                "comeback",
+                (&comeback, Player::One),
+                (&vertical, Player::One),
+            ),
+            (
+                "quick_win",
+                (&across, Player::One),
+                (&resigned, Player::Two),
+            ),
+            ("full_house", (&full, Player::Two), (&misere, Player::Two)),
+        ];
+        for (id, (earns, seat), (misses, other)) in cases {
+            assert!(ids(earns, seat).contains(&id), "{} is earned", id);
+            assert!(!ids(misses, other).contains(&id), "{} isn't earned", id);
+        }
+
+        // A misère win isn't down the winner's own line, and a long game isn't a quick win
+        assert_eq!(misere.winner(), Some(Player::Two));
+        assert!(!ids(&misere, Player::Two).contains(&"vertical_four"));
+        assert_eq!(long.winner(), Some(Player::One));
+        assert!(!ids(&long, Player::One).contains(&"quick_win"));
+    }
+
+    #[test]
+    fn games_earn_nothing_until_they_end_and_never_the_tutorial() {
+        let unfinished = classic(&[1, 2, 1, 2, 1, 2]);
+        assert_eq!(ids(&unfinished, Player::One), Vec::<&str>::new());
+        let vertical = classic(&[1, 2, 1, 2, 1, 2, 1]);
+        for seat in [Player::One, Player::Two] {
+            assert!(!ids(&vertical, seat).contains(&TUTORIAL));
+        }
+    }
+}
@@ -0,0 +1,514 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use crate::board::Cell;
+use crate::now::{self, TimeSource};
+use crate::rules::GameRules;
+use crate::start::Rng;
+use crate::variant::{Move, Variant};
+use crate::{Game, Player};
+
+// How hard the computer opponent plays
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    // Moves the computer looks ahead
+    pub fn depth(self) -> usize {
+        match self {
+            Difficulty::Easy => 2,
+            Difficulty::Medium => 4,
+            Difficulty::Hard => 6,
+        }
+    }
+}
+
+// Who plays the second seat
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Opponent {
+    Human,
+    Computer(Difficulty),
+}
+
+// Version of the engine, which is the program's own: the same version makes the same moves
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Score of a won position before the bonus for winning sooner
+pub const WIN: i32 = 1_000_000;
+
+// Worth of a finished line to the heuristic under the scoring variant
+const LINE: i32 = 1_000;
+
+// Columns past the classic seven that cost the search a move of depth: each move looked
+// ahead multiplies the work by the number of columns, so a 20-column board searched as deep
+// as a 7-column one would keep the player waiting minutes for a move
+const COLUMNS_PER_PLY: usize = 5;
+
+// Evaluations this close to zero count as an even position when a draw is offered
+const DRAW_MARGIN: i32 = 4;
+
+// Searches run so far, numbering each one's log lines
+static SEARCHES: AtomicU32 = AtomicU32::new(0);
+
+// Pick a move for the player to move with a depth-limited negamax search. The easy level
+// also plays a random legal move now and then so it can be beaten.
+pub fn choose_move(game: &Game, difficulty: Difficulty) -> Option<Move> {
+    let moves = ordered_moves(game);
+    if difficulty == Difficulty::Easy {
+        let mut chance = chance(game);
+        if chance.below(3) == 0 {
+            log::debug!("easy level plays a random column");
+            return moves.get(chance.below(moves.len().max(1))).copied();
+        }
+    }
+    best_move(game, difficulty.depth()).map(|(column, _)| column)
+}
+
+// The easy level's dice for the move about to be played, rolled from the game's seed so they
+// come up the same whenever the game reaches that move
+fn chance(game: &Game) -> Rng {
+    let mut rng = Rng::new(game.engine_seed);
+    for _ in 0..game.history.len() {
+        rng.next_u64();
+    }
+    rng
+}
+
+// Any legal move, for a player whose time ran out before they moved
+pub fn random_move(game: &Game) -> Option<Move> {
+    let moves = ordered_moves(game);
+    moves.get(random() % moves.len().max(1)).copied()
+}
+
+// Whether the player to move takes a draw offer: only when the position looks even to its
+// search, or when neither side can connect anymore
+pub fn accepts_draw(game: &Game, difficulty: Difficulty) -> bool {
+    dead_drawn(game)
+        || best_move(game, difficulty.depth()).is_some_and(|(_, score)| score.abs() <= DRAW_MARGIN)
+}
+
+// Whether the player to move swaps under the pie rule: only when the first move was as good
+// as any other opening, as far as a search at `difficulty` sees. Every position looks a
+// little worse for the side with a piece fewer, so the move is weighed against the other
+// openings rather than against an even score.
+pub fn takes_swap(game: &Game, difficulty: Difficulty) -> bool {
+    let mut opening = game.clone();
+    if game.history.len() != 1 || !opening.undo() {
+        return false;
+    }
+    let depth = difficulty.depth();
+    match (best_move(&opening, depth), best_move(game, depth - 1)) {
+        (Some((_, best)), Some((_, reply))) => -reply >= best,
+        _ => false,
+    }
+}
+
+// The hard level's best move for the player to move and its score from that player's
+// side, for showing how a position stands; at least WIN when they can force a win
+pub fn evaluation(game: &Game) -> Option<(Move, i32)> {
+    if game.is_finished() {
+        return None;
+    }
+    best_move(game, Difficulty::Hard.depth())
+}
+
+// The best move found in about `budget` by the system's clock, for a caller giving the
+// computer time rather than a level. The next search is left out once the last says it
+// would run past the budget. Only the C API and the browser give the computer time so far.
+#[cfg(any(feature = "capi", feature = "wasm"))]
+pub fn move_within(game: &Game, budget: std::time::Duration) -> Option<Move> {
+    let started = now::System.monotonic();
+    move_until(game, &now::System, |next| {
+        now::since(&now::System, started) + next > budget
+    })
+}
+
+// The best move of searches that each look a move further ahead than the last, until
+// `should_stop` says not to start the next, which it's told is likely to take as long as the
+// last did again for every column, by `time`. A forced win ends the searching too, as does
+// looking as far as the board can be filled.
+#[cfg(any(feature = "capi", feature = "wasm"))]
+pub fn move_until(
+    game: &Game,
+    time: &impl TimeSource,
+    mut should_stop: impl FnMut(std::time::Duration) -> bool,
+) -> Option<Move> {
+    let mut best = None;
+    for depth in 1..=game.moves_remaining().max(1) {
+        let searched = time.monotonic();
+        let (mv, score) = best_move(game, depth)?;
+        best = Some(mv);
+        let next = now::since(time, searched) * game.rules.width as u32;
+        // With more than two players the search doesn't look ahead, so once is enough
+        if score.abs() >= WIN || game.players() > 2 || should_stop(next) {
+            break;
+        }
+    }
+    best
+}
+
+// The best move searching `depth` moves ahead, for a caller asking for a depth rather than
+// a level
+#[cfg(any(feature = "capi", feature = "wasm"))]
+pub fn move_at_depth(game: &Game, depth: usize) -> Option<Move> {
+    best_move(game, depth.max(1)).map(|(mv, _)| mv)
+}
+
+// Whether the player to move can't avoid losing against best play, as far as a medium
+// search sees
+pub fn lost(game: &Game) -> bool {
+    best_move(game, Difficulty::Medium.depth()).is_some_and(|(_, score)| score <= -WIN)
+}
+
+// Whether either side can force a win within `plies` from the position
+pub fn decided(game: &Game, plies: usize) -> bool {
+    best_move(game, plies).is_some_and(|(_, score)| score.abs() >= WIN)
+}
+
+// The best move for the player to move and its score, searching `depth` moves ahead
+fn best_move(game: &Game, depth: usize) -> Option<(Move, i32)> {
+    if game.players() > 2 {
+        return greedy(game);
+    }
+    let extra = game.rules.width.saturating_sub(GameRules::CLASSIC.width);
+    let depth = depth.saturating_sub(extra / COLUMNS_PER_PLY).max(1);
+    let id = SEARCHES.fetch_add(1, Ordering::Relaxed) + 1;
+    let started = now::System.monotonic();
+    log::trace!(
+        "search={} start game={} depth={} move={} player={}",
+        id,
+        game.id,
+        depth,
+        game.history.len() + 1,
+        game.current_player as u8
+    );
+    let mut search = game.clone();
+    let mut best = None;
+    let mut alpha = -WIN * 2;
+    for mv in ordered_moves(game) {
+        search.play(mv).expect("ordered moves are legal");
+        let score = -negamax(&mut search, depth - 1, -WIN * 2, -alpha);
+        search.undo();
+        if best.is_none() || score > alpha {
+            alpha = score;
+            best = Some((mv, score));
+        }
+    }
+    match best {
+        Some((mv, score)) => log::debug!(
+            "search={} done depth={} {}={} score={} took={:?}",
+            id,
+            depth,
+            match mv {
+                Move::Drop(_) => "col",
+                Move::Pop(_) => "pop",
+                Move::Anvil(_) => "anvil",
+                Move::Bomb(_) => "bomb",
+                Move::Flip => "flip",
+            },
+            mv.column() + 1,
+            score,
+            now::since(&now::System, started)
+        ),
+        None => log::debug!("search={} done depth={} no moves", id, depth),
+    }
+    best
+}
+
+// Value of the position for the player to move, searching `depth` more moves
+fn negamax(game: &mut Game, depth: usize, mut alpha: i32, beta: i32) -> i32 {
+    // The player who just moved, still the one to move once the game is over, ended it; a
+    // pop under PopOut can connect the other player's pieces instead, and under misère the
+    // line wins for the other player. Wins found sooner score higher.
+    if game.is_finished() {
+        return match game.winner() {
+            None => 0,
+            Some(winner) if winner == game.current_player => -(WIN + depth as i32),
+            Some(_) => WIN + depth as i32,
+        };
+    }
+    if depth == 0 {
+        return evaluate(game, game.current_player);
+    }
+
+    let mut best = -WIN * 2;
+    for mv in ordered_moves(game) {
+        game.play(mv).expect("ordered moves are legal");
+        let score = -negamax(game, depth - 1, -beta, -alpha);
+        game.undo();
+        best = best.max(score);
+        alpha = alpha.max(score);
+        if alpha >= beta {
+            break;
+        }
+    }
+    best
+}
+
+// The search takes the other side for one opponent, which a game of more than two players
+// doesn't have, so there the computer plays for itself alone: a move that wins now, then
+// one taking away the next player's win, then the one the heuristic likes best for it
+fn greedy(game: &Game) -> Option<(Move, i32)> {
+    let me = game.current_player;
+    let next = game.next_player(me);
+    let moves = ordered_moves(game);
+    if let Some(&mv) = winning_moves(game, me).first() {
+        return Some((mv, WIN));
+    }
+    let threats = winning_moves(game, next);
+    if let Some(&mv) = moves.iter().find(|mv| threats.contains(mv)) {
+        return Some((mv, 0));
+    }
+    let mut search = game.clone();
+    moves
+        .into_iter()
+        .map(|mv| {
+            search.play(mv).expect("ordered moves are legal");
+            let score = evaluate(&search, me);
+            search.undo();
+            (mv, score)
+        })
+        .reduce(|best, scored| if scored.1 > best.1 { scored } else { best })
+}
+
+// Every move that would win the game for `player` there and then, were it their turn, in
+// the order the search tries them: a threat when it's someone else's turn
+pub fn winning_moves(game: &Game, player: Player) -> Vec<Move> {
+    deciding_moves(game, player, |winner| winner == Some(player))
+}
+
+// Every move that would lose the game for `player` there and then, like making a line in
+// misère, in the same order
+pub fn losing_moves(game: &Game, player: Player) -> Vec<Move> {
+    deciding_moves(game, player, |winner| {
+        winner.is_some_and(|winner| winner != player)
+    })
+}
+
+// The moves `player` could make, were it their turn, after which the winner is one `keep`
+// accepts
+fn deciding_moves(game: &Game, player: Player, keep: impl Fn(Option<Player>) -> bool) -> Vec<Move> {
+    let mut search = game.clone();
+    // A move out of turn comes after the one the player to move would make first, which the
+    // count of moves, and so the earliest a line can be made, has to allow for
+    if player != game.current_player {
+        search.current_move += 1;
+    }
+    search.current_player = player;
+    ordered_moves(&search)
+        .into_iter()
+        .filter(|&mv| {
+            search.play(mv).expect("ordered moves are legal");
+            let winner = search.winner();
+            search.undo();
+            keep(winner)
+        })
+        .collect()
+}
+
+// Open columns from the center outwards, where the strongest moves usually are, then under
+// PopOut the columns the player could pop in the same order. Searching every anvil and bomb
+// as well would take too long, so under Power Up the search only bombs once the board is
+// full and there's nothing else to play. Under Flip a flip is a single move, so it's always
+// searched, after the drops.
+fn ordered_moves(game: &Game) -> Vec<Move> {
+    let middle = game.rules.width / 2;
+    let mut columns: Vec<usize> = (0..game.rules.width).collect();
+    columns.sort_by_key(|&column| column.abs_diff(middle));
+    let drops = columns
+        .iter()
+        .filter(|&&column| game.can_play(column))
+        .map(|&column| Move::Drop(column));
+    let pops = columns
+        .iter()
+        .filter(|&&column| game.can_pop(column))
+        .map(|&column| Move::Pop(column));
+    let mut moves: Vec<Move> = drops.chain(pops).collect();
+    if game.can_flip() {
+        moves.push(Move::Flip);
+    }
+    if moves.is_empty() {
+        moves.extend(
+            columns
+                .iter()
+                .filter(|&&column| game.can_bomb(column))
+                .map(|&column| Move::Bomb(column)),
+        );
+    }
+    moves
+}
+
+// Heuristic for the player with pieces `me`, usually the one to move: every line-sized
+// window holding pieces of only one side counts for that side, more the fuller it is.
+// Under misère a filling window is a line the side may be forced to finish, so it counts
+// against them instead, and under the scoring variant a finished line is a point kept for
+// good, worth far more than any unfinished one.
+fn evaluate(game: &Game, me: Player) -> i32 {
+    let scoring = game.rules.variant == Variant::Score;
+    let mut score = 0;
+    for cells in windows(game) {
+        let mine = cells.iter().filter(|&&cell| cell == Some(me)).count() as i32;
+        let theirs = cells
+            .iter()
+            .filter(|cell| cell.is_some_and(|player| player != me))
+            .count() as i32;
+        match (mine, theirs) {
+            (0, 0) => {}
+            (mine, 0) if scoring && mine as usize == game.rules.connect => score += LINE,
+            (0, theirs) if scoring && theirs as usize == game.rules.connect => score -= LINE,
+            (mine, 0) => score += mine * mine,
+            (0, theirs) => score -= theirs * theirs,
+            _ => {}
+        }
+    }
+    if game.rules.variant.misere() {
+        -score
+    } else {
+        score
+    }
+}
+
+// Whether every line-sized window already holds pieces of both players, so nobody can win
+fn dead_drawn(game: &Game) -> bool {
+    windows(game)
+        .iter()
+        .all(|cells| cells.contains(&Some(Player::One)) && cells.contains(&Some(Player::Two)))
+}
+
+// The cells of every line-sized window on the board, in all four directions, going round the
+// seam on a cylinder, or every line of the cube in a 3D game
+pub fn windows(game: &Game) -> Vec<Vec<Cell>> {
+    if game.rules.variant == Variant::Cube {
+        return game.cube_windows();
+    }
+    let directions: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (-1, 1)];
+    let wraps = game.rules.variant.wraps();
+    let mut windows = Vec::new();
+    for row in 0..game.rules.height as isize {
+        for col in 0..game.rules.width as isize {
+            for (row_step, col_step) in directions {
+                // A sideways window wider than a cylinder would hold some of its cells twice
+                if wraps && row_step == 0 && game.rules.connect > game.rules.width {
+                    continue;
+                }
+                let cells: Option<Vec<Cell>> = (0..game.rules.connect as isize)
+                    .map(|i| {
+                        let (r, mut c) = (row + i * row_step, col + i * col_step);
+                        if wraps {
+                            c = c.rem_euclid(game.rules.width as isize);
+                        }
+                        let inside = (0..game.rules.height as isize).contains(&r)
+                            && (0..game.rules.width as isize).contains(&c);
+                        inside.then(|| game.board[r as usize][c as usize])
+                    })
+                    .collect();
+                windows.extend(cells);
+            }
+        }
+    }
+    windows
+}
+
+// Cheap randomness for moves played for a player out of time, and coin flips
+pub fn random() -> usize {
+    now::System.wall().subsec_nanos() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+
+    // A game of `variant` with these columns, counted from 0, played
+    fn game(variant: Variant, columns: &[usize]) -> Game {
+        let mut game = Game::builder(&Settings::default())
+            .variant(variant)
+            .build()
+            .expect("the rules are valid");
+        for &column in columns {
+            game.play(Move::Drop(column)).expect("the moves are legal");
+        }
+        game
+    }
+
+    // A clock that moves on a millisecond every time it's read
+    #[cfg(any(feature = "capi", feature = "wasm"))]
+    struct Ticking(std::cell::Cell<std::time::Duration>);
+
+    #[cfg(any(feature = "capi", feature = "wasm"))]
+    impl TimeSource for Ticking {
+        fn monotonic(&self) -> std::time::Duration {
+            let now = self.0.get();
+            self.0.set(now + std::time::Duration::from_millis(1));
+            now
+        }
+
+        fn wall(&self) -> std::time::Duration {
+            self.0.get()
+        }
+    }
+
+    #[cfg(any(feature = "capi", feature = "wasm"))]
+    #[test]
+    fn a_search_stops_when_asked_and_is_told_what_the_next_would_cost() {
+        let time = Ticking(Default::default());
+        let mut asked = Vec::new();
+        let mv = move_until(&game(Variant::Standard, &[]), &time, |next| {
+            asked.push(next);
+            asked.len() == 3
+        });
+        assert!(mv.is_some());
+        // Each search reads the clock before and after, a millisecond apart, and the next is
+        // expected to cost that again for each of the seven columns
+        assert_eq!(asked, [std::time::Duration::from_millis(7); 3]);
+    }
+
+    #[test]
+    fn cylinder_windows_go_round_the_seam() {
+        // 24 sideways, 21 upright, and 12 each way diagonally on the flat board; round the
+        // seam every column starts a window in all but the upright direction
+        assert_eq!(windows(&game(Variant::Standard, &[])).len(), 69);
+        assert_eq!(
+            windows(&game(Variant::Cylinder, &[])).len(),
+            42 + 21 + 21 + 21
+        );
+    }
+
+    #[test]
+    fn threats_across_the_seam_are_seen() {
+        // Player one has the bottom of columns 6, 7, and 1
+        let threatened = game(Variant::Cylinder, &[5, 5, 6, 6, 0, 0]);
+        assert_eq!(
+            winning_moves(&threatened, Player::One),
+            [Move::Drop(4), Move::Drop(1)]
+        );
+        let flat = game(Variant::Standard, &[5, 5, 6, 6, 0, 0]);
+        assert!(winning_moves(&flat, Player::One).is_empty());
+    }
+
+    #[cfg(any(feature = "capi", feature = "wasm"))]
+    #[test]
+    fn a_search_on_a_budget_still_finds_a_move_and_takes_a_win() {
+        use std::time::Duration;
+
+        let opening = game(Variant::Standard, &[]);
+        let mv = move_within(&opening, Duration::ZERO).expect("there are moves");
+        assert!(opening.can_play(mv.column()));
+        // Player one has three up the first column
+        let winning = game(Variant::Standard, &[0, 1, 0, 1, 0, 2]);
+        assert_eq!(
+            move_within(&winning, Duration::from_millis(200)),
+            Some(Move::Drop(0))
+        );
+        assert_eq!(
+            move_within(
+                &game(Variant::Standard, &[0, 1, 0, 1, 0, 1, 0]),
+                Duration::ZERO
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+use crate::Player;
+
+// What a cell of the board holds: a player's piece, or None while it's empty
+pub type Cell = Option<Player>;
+
+// The grid of cells, indexed by row (top first) and then column
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Board {
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Board {
+    // An empty board
+    pub fn new(width: usize, height: usize) -> Board {
+        Board {
+            rows: vec![vec![None; width]; height],
+        }
+    }
+
+    // Rows from the top of the board down
+    pub fn rows(&self) -> impl Iterator<Item = &[Cell]> {
+        self.rows.iter().map(Vec::as_slice)
+    }
+}
+
+impl Index<usize> for Board {
+    type Output = [Cell];
+
+    fn index(&self, row: usize) -> &[Cell] {
+        &self.rows[row]
+    }
+}
+
+impl IndexMut<usize> for Board {
+    fn index_mut(&mut self, row: usize) -> &mut [Cell] {
+        &mut self.rows[row]
+    }
+}
+
+// Plain ASCII grid, one line per row from the top: X for player one, O for player two, and
+// . for empty cells, with no trailing newline. The format is stable and never colored, so
+// it can be asserted on in tests and read back with `parse`.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, row) in self.rows.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            for &cell in row {
+                let symbol = match cell {
+                    Some(Player::One) => 'X',
+                    Some(Player::Two) => 'O',
+                    // The grid only has symbols for a two-player game
+                    Some(Player::Three | Player::Four) | None => '.',
+                };
+                write!(f, "{}", symbol)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Why a text board couldn't be read; lines are counted from 1 after skipping blank ones
+#[derive(Debug, PartialEq)]
+pub enum ParseBoardError {
+    Empty,
+    RaggedRow { line: usize },
+    InvalidCell { line: usize, symbol: char },
+    FloatingPiece { line: usize, column: usize },
+}
+
+impl fmt::Display for ParseBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseBoardError::Empty => write!(f, "board has no rows"),
+            ParseBoardError::RaggedRow { line } => {
+                write!(f, "row {} is not as wide as the first row", line)
+            }
+            ParseBoardError::InvalidCell { line, symbol } => {
+                write!(f, "row {} has '{}'; expected X, O, or .", line, symbol)
+            }
+            ParseBoardError::FloatingPiece { line, column } => {
+                write!(
+                    f,
+                    "piece in row {} column {} has nothing under it",
+                    line, column
+                )
+            }
+        }
+    }
+}
+
+// Read the grid written by `Display`. Surrounding whitespace and blank lines are ignored and
+// pieces may be lowercase, but every piece must rest on the bottom or on another piece.
+impl FromStr for Board {
+    type Err = ParseBoardError;
+
+    fn from_str(text: &str) -> Result<Board, ParseBoardError> {
+        let mut rows: Vec<Vec<Cell>> = Vec::new();
+        for (index, line) in text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .enumerate()
+        {
+            let row = line
+                .chars()
+                .map(|symbol| match symbol {
+                    'X' | 'x' => Ok(Some(Player::One)),
+                    'O' | 'o' => Ok(Some(Player::Two)),
+                    '.' => Ok(None),
+                    _ => Err(ParseBoardError::InvalidCell {
+                        line: index + 1,
+                        symbol,
+                    }),
+                })
+                .collect::<Result<Vec<Cell>, ParseBoardError>>()?;
+            if rows.first().is_some_and(|first| first.len() != row.len()) {
+                return Err(ParseBoardError::RaggedRow { line: index + 1 });
+            }
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err(ParseBoardError::Empty);
+        }
+
+        for (index, pair) in rows.windows(2).enumerate() {
+            if let Some(column) = (0..pair[0].len())
+                .find(|&column| pair[0][column].is_some() && pair[1][column].is_none())
+            {
+                return Err(ParseBoardError::FloatingPiece {
+                    line: index + 1,
+                    column: column + 1,
+                });
+            }
+        }
+        Ok(Board { rows })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_and_parse_round_trip() {
+        let text = [
+            ".......", ".......", "...O...", "...X...", "..OX...", ".XOXO.X",
+        ]
+        .join("\n");
+        let board: Board = text.parse().expect("the board is well formed");
+        assert_eq!(board.to_string(), text);
+        assert_eq!(board.to_string().parse::<Board>(), Ok(board));
+    }
+
+    #[test]
+    fn an_empty_board_round_trips() {
+        let board = Board::new(9, 7);
+        assert_eq!(board.to_string().parse::<Board>(), Ok(board));
+    }
+
+    #[test]
+    fn parsing_forgives_case_blank_lines_and_indentation() {
+        let board: Board = "\n  ..\n\n  xo\n"
+            .parse()
+            .expect("the board is well formed");
+        assert_eq!(board.to_string(), "..\nXO");
+    }
+
+    #[test]
+    fn malformed_boards_are_rejected() {
+        let cases = [
+            ("", ParseBoardError::Empty),
+            ("\n  \n", ParseBoardError::Empty),
+            ("...\n..\n", ParseBoardError::RaggedRow { line: 2 }),
+            (
+                "...\n.Y.\n",
+                ParseBoardError::InvalidCell {
+                    line: 2,
+                    symbol: 'Y',
+                },
+            ),
+            (
+                "X..\n...\n",
+                ParseBoardError::FloatingPiece { line: 1, column: 1 },
+            ),
+            (
+                "...\n.O.\n...\n",
+                ParseBoardError::FloatingPiece { line: 2, column: 2 },
+            ),
+        ];
+        for (text, error) in cases {
+            assert_eq!(text.parse::<Board>(), Err(error), "{:?}", text);
+        }
+    }
+}
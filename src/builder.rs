@@ -0,0 +1,273 @@
+use std::fmt;
+
+use crate::handicap::Placement;
+use crate::rules::GameRules;
+use crate::session::First;
+use crate::settings::Settings;
+use crate::variant::Variant;
+use crate::{start, Game, Player};
+
+// A game set up from the settings with some of them changed a call at a time, like
+// Game::builder(settings).size(9, 7).connect(5).build(), rather than by changing a copy of
+// the settings and going by whatever it ends up holding
+pub struct GameBuilder {
+    settings: Settings,
+    // Whether the game is played from its first move here, with the players seated the way
+    // the settings say, the handicap given, and any random start played, rather than read
+    // back from a record that already has all of that
+    opening: bool,
+    // How many names were given when there are more than seats for them
+    too_many_names: Option<usize>,
+}
+
+// Why a builder couldn't set up its game
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RulesError {
+    // Every reason the board, win length, and variant can't be played, not just the first
+    Rules(Vec<String>),
+    // Why the players, handicap, and random start can't be played together
+    Setup(String),
+    // The number of names given, more than a game has seats
+    TooManyNames(usize),
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RulesError::Rules(problems) => write!(f, "{}", problems.join("; ")),
+            RulesError::Setup(problem) => write!(f, "{}", problem),
+            RulesError::TooManyNames(given) => write!(
+                f,
+                "a game is for at most {} players, not {}",
+                Player::SEATS.len(),
+                given
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+impl Game {
+    // Classic Connect Four between two people, as Game::default() is
+    pub fn new() -> Game {
+        Game::default()
+    }
+
+    // A game between two people by `rules`, or why it can't be played by them, for the C
+    // and JavaScript bindings
+    #[cfg_attr(not(any(test, feature = "capi", feature = "wasm")), allow(dead_code))]
+    pub fn with_rules(rules: GameRules) -> Result<Game, RulesError> {
+        Game::builder(&Settings::default()).rules(rules).build()
+    }
+
+    pub fn builder(settings: &Settings) -> GameBuilder {
+        GameBuilder {
+            settings: settings.clone(),
+            opening: true,
+            too_many_names: None,
+        }
+    }
+}
+
+impl GameBuilder {
+    // Every rule at once, as a save gives them
+    pub fn rules(mut self, rules: GameRules) -> GameBuilder {
+        self.settings.set_rules(rules);
+        self
+    }
+
+    pub fn size(self, width: usize, height: usize) -> GameBuilder {
+        let rules = self.settings.rules().with_size(width, height);
+        self.rules(rules)
+    }
+
+    pub fn connect(self, connect: usize) -> GameBuilder {
+        let rules = self.settings.rules().with_connect(connect);
+        self.rules(rules)
+    }
+
+    // As with the rules themselves, a 3D game takes the cube's board and win length too
+    pub fn variant(self, variant: Variant) -> GameBuilder {
+        let rules = self.settings.rules().with_variant(variant);
+        self.rules(rules)
+    }
+
+    // Games are set up from settings that say who moves first and what handicap there is,
+    // so only tests change them here so far
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn first_player(mut self, first: First) -> GameBuilder {
+        self.settings.first = first;
+        self
+    }
+
+    // Pieces of the second player's put down before the first move, where `placement` says
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn handicap(mut self, pieces: usize, placement: Placement) -> GameBuilder {
+        self.settings.handicap = pieces;
+        self.settings.placement = placement;
+        self
+    }
+
+    // The seed the random start and the computer's choices are played from
+    pub fn seed(mut self, seed: u32) -> GameBuilder {
+        self.settings.seed = Some(seed);
+        self
+    }
+
+    // The players by name, in their seats, as many as there are names
+    pub fn names(mut self, names: &[String]) -> GameBuilder {
+        match self.settings.names.get_mut(..names.len()) {
+            Some(seats) => {
+                seats.clone_from_slice(names);
+                self.settings.players = names.len();
+                self.too_many_names = None;
+            }
+            None => self.too_many_names = Some(names.len()),
+        }
+        self
+    }
+
+    // A game read back from a save, the log, or an import: nobody's played by the computer,
+    // there's no clock, and the players, handicap, and random start are left to the record
+    pub fn recorded(mut self) -> GameBuilder {
+        self.settings.computer = false;
+        self.settings.time = None;
+        self.opening = false;
+        self
+    }
+
+    // The game, or why it can't be played
+    pub fn build(self) -> Result<Game, RulesError> {
+        let settings = &self.settings;
+        settings.rules().validate().map_err(RulesError::Rules)?;
+        if let Some(given) = self.too_many_names {
+            return Err(RulesError::TooManyNames(given));
+        }
+        let mut game = Game::from_settings(settings);
+        if self.opening {
+            settings.check_setup().map_err(RulesError::Setup)?;
+            if settings.first.swap_first() {
+                game.rotate_seats();
+            }
+            game.give_handicap(settings.handicap, settings.placement);
+            game.start_randomly(
+                settings.random_start,
+                settings.seed.unwrap_or_else(start::fresh_seed),
+            )
+            .map_err(RulesError::Setup)?;
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn every_setter_is_used_by_the_game_built() {
+        let game = Game::builder(&Settings::default())
+            .size(9, 7)
+            .connect(5)
+            .first_player(First::P2)
+            .handicap(2, Placement::Fixed)
+            .seed(7)
+            .names(&names(&["Ada", "Grace"]))
+            .build()
+            .expect("the rules are valid");
+        assert_eq!(
+            game.rules,
+            GameRules {
+                first: First::P2,
+                ..GameRules::CLASSIC.with_size(9, 7).with_connect(5)
+            }
+        );
+        assert_eq!(
+            game.name(Player::One),
+            "Grace",
+            "the second player moves first"
+        );
+        assert_eq!(game.handicap_size(), 2);
+        assert_eq!(game.engine_seed, 7);
+
+        let cube = Game::builder(&Settings::default())
+            .variant(Variant::Cube)
+            .build()
+            .expect("the cube takes its own board");
+        assert_eq!(cube.rules, GameRules::CLASSIC.with_variant(Variant::Cube));
+    }
+
+    #[test]
+    fn games_can_be_made_without_a_builder() {
+        assert_eq!(Game::new().rules, GameRules::CLASSIC);
+        let game = Game::with_rules(GameRules::CLASSIC.with_size(9, 7).with_connect(5))
+            .expect("the rules are valid");
+        assert_eq!(
+            game.rules,
+            GameRules::CLASSIC.with_size(9, 7).with_connect(5)
+        );
+        assert_eq!(game.players(), 2);
+        let err = Game::with_rules(GameRules::CLASSIC.with_connect(2))
+            .map(|_| ())
+            .expect_err("2 in a row is too few");
+        assert_eq!(
+            err,
+            RulesError::Rules(vec![
+                "connect must be between 3 and 7 on a 7x6 board".to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn every_combination_that_cant_be_played_is_rejected() {
+        let default = Settings::default();
+        let mut random_start = Settings::default();
+        random_start.random_start = 4;
+        let mut pie_rule = Settings::default();
+        pie_rule.pie_rule = true;
+        let three = names(&["Ada", "Grace", "Alan"]);
+        let rejected = [
+            (Game::builder(&default).size(3, 30), "the board must be 4 to 20 columns wide, not 3; the board must be 4 to 20 rows high, not 30"),
+            (Game::builder(&default).connect(8), "connect must be between 3 and 7 on a 7x6 board"),
+            (Game::builder(&default).variant(Variant::Cube).size(7, 6), "a 3D game is kept on a 16x4 board and played to 4 in a row"),
+            (Game::builder(&default).names(&names(&["Ada"])), "a game is for 2 to 4 players"),
+            (Game::builder(&default).handicap(4, Placement::Fixed), "a handicap can be at most 3 pieces when 4 in a row wins"),
+            (Game::builder(&pie_rule).handicap(1, Placement::Fixed), "the pie rule can't be used with a handicap"),
+            (Game::builder(&default).first_player(First::Random).handicap(1, Placement::Fixed), "with a handicap the stronger player always moves first, so who moves first can't be random or alternate"),
+            (Game::builder(&random_start).handicap(1, Placement::Fixed), "a random start can't be used with a handicap"),
+            (Game::builder(&random_start).size(4, 1), "the board must be 4 to 20 rows high, not 1"),
+            (Game::builder(&default).variant(Variant::PopOut).handicap(1, Placement::Fixed), "PopOut can't be played with a handicap"),
+            (Game::builder(&default).variant(Variant::Cube).handicap(1, Placement::Fixed), "3D games can't be played with a handicap"),
+            (Game::builder(&default).handicap(1, Placement::Fixed).names(&three), "games of 3 players can't be played with a handicap"),
+        ];
+        for (builder, expected) in rejected {
+            let err = builder.build().map(|_| ()).expect_err(expected);
+            assert_eq!(err.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn more_names_than_seats_are_an_error_rather_than_a_panic() {
+        let five = names(&["Ada", "Grace", "Alan", "Edsger", "Barbara"]);
+        let err = Game::builder(&Settings::default())
+            .names(&five)
+            .build()
+            .map(|_| ())
+            .expect_err("there are four seats");
+        assert_eq!(err, RulesError::TooManyNames(5));
+        assert_eq!(err.to_string(), "a game is for at most 4 players, not 5");
+
+        // Naming the players again with few enough names makes the game playable
+        let game = Game::builder(&Settings::default())
+            .names(&five)
+            .names(&five[..4])
+            .build()
+            .expect("four names fit");
+        assert_eq!(game.players(), 4);
+    }
+}
@@ -0,0 +1,463 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::ai::Difficulty;
+use crate::clock::{self, TimeControl, TimeoutAction};
+use crate::handicap::Placement;
+use crate::i18n::LANGUAGES;
+use crate::leaderboard::{Format, Sort};
+use crate::logging::LogFormat;
+use crate::session::{First, MatchFormat};
+use crate::render::Ruler;
+use crate::settings::{Digits, Input, Ui};
+use crate::style::ColorChoice;
+use crate::theme::THEME_NAMES;
+use crate::variant::Variant;
+
+// Command-line options
+#[derive(Parser)]
+#[command(version, about = "Terminal Connect Four for two players")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub options: Options,
+}
+
+// Flags shared by every subcommand; anything left unset falls through to the config file
+#[derive(Args)]
+pub struct Options {
+    /// Read settings from this file instead of ~/.config/connect_four/config.toml
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Keep every position in the scrollback instead of redrawing on the alternate screen
+    #[arg(long, global = true)]
+    pub no_altscreen: bool,
+
+    /// Place pieces immediately instead of animating them down the column
+    #[arg(long, global = true)]
+    pub no_animation: bool,
+
+    /// When to use colors; auto disables them when the output is not a terminal
+    #[arg(long, value_enum, global = true)]
+    pub color: Option<ColorChoice>,
+
+    /// Disable colors; same as --color never
+    #[arg(long, conflicts_with = "color", global = true)]
+    pub no_color: bool,
+
+    /// Draw the board with plain ASCII borders and X/O cells; same as --theme ascii
+    #[arg(long, conflicts_with = "theme", global = true)]
+    pub ascii: bool,
+
+    /// Interface to play in: line prompts, or a full-screen terminal UI
+    #[arg(long, value_enum, global = true)]
+    pub ui: Option<Ui>,
+
+    /// Board appearance: classic, emoji, ascii, minimal, high-contrast, or colorblind
+    #[arg(long, value_parser = clap::builder::PossibleValuesParser::new(THEME_NAMES), global = true)]
+    pub theme: Option<String>,
+
+    /// Terminal columns per board cell, for terminals that disagree about emoji width
+    #[arg(long, value_parser = clap::value_parser!(u8).range(1..=2), global = true)]
+    pub cell_width: Option<u8>,
+
+    /// Large print: draw each cell as an NxN block with thick borders (3 when N is left out)
+    #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "3",
+          value_parser = clap::value_parser!(u8).range(2..=5), global = true)]
+    pub big: Option<u8>,
+
+    /// Where to draw the column numbers
+    #[arg(long, value_enum, global = true)]
+    pub ruler: Option<Ruler>,
+
+    /// Always space the cells out, even when the terminal is too narrow for it
+    #[arg(long, conflicts_with = "compact", global = true)]
+    pub wide: bool,
+
+    /// Always pack the cells together, even when there's room to space them out
+    #[arg(long, global = true)]
+    pub compact: bool,
+
+    /// Label columns with letters and rows with numbers, and list moves as coordinates
+    #[arg(long, global = true)]
+    pub coords: bool,
+
+    /// Mark the column of the last move with a caret under the board
+    #[arg(long, global = true)]
+    pub marker: bool,
+
+    /// Describe the board in words after each move instead of drawing it, for screen readers
+    #[arg(long, global = true)]
+    pub accessible: bool,
+
+    /// Language of the game's messages, en or es; taken from the locale when unset
+    #[arg(long, value_parser = clap::builder::PossibleValuesParser::new(LANGUAGES), global = true)]
+    pub lang: Option<String>,
+
+    /// Play by a named set of rules: classic (7x6, connect 4), five (9x7, connect 5), mini
+    /// (5x4, connect 3), a preset from the config file, or custom to go by the options below
+    #[arg(long, value_name = "NAME", global = true)]
+    pub preset: Option<String>,
+
+    /// Number of columns on the board
+    #[arg(long, global = true)]
+    pub width: Option<usize>,
+
+    /// Number of rows on the board
+    #[arg(long, global = true)]
+    pub height: Option<usize>,
+
+    /// Pieces in a row needed to win
+    #[arg(long, global = true)]
+    pub connect: Option<usize>,
+
+    /// Players taking turns, 2 to 4; more than two get a bigger board unless a size is given
+    #[arg(long, global = true)]
+    pub players: Option<usize>,
+
+    /// Play against the computer, which takes Player 2
+    #[arg(long, global = true)]
+    pub computer: bool,
+
+    /// How hard the computer plays
+    #[arg(long, value_enum, global = true)]
+    pub difficulty: Option<Difficulty>,
+
+    /// Play a match instead of single games: best-of-N or first-to-N, e.g. best-of-5
+    #[arg(long = "match", value_name = "FORMAT", global = true)]
+    pub match_format: Option<MatchFormat>,
+
+    /// Who moves first: p1, p2, random (a coin flip before every game), or alternate (the
+    /// other player every rematch)
+    #[arg(long, value_enum, global = true)]
+    pub first: Option<First>,
+
+    /// Let the second player answer the first move by swapping seats, taking it over as
+    /// their own
+    #[arg(long, global = true)]
+    pub pie_rule: bool,
+
+    /// Give the second player this many pieces on the board before the first move, for
+    /// games between unequal players
+    #[arg(long, value_name = "PIECES", global = true)]
+    pub handicap: Option<usize>,
+
+    /// Where the handicap goes: the bottom of the center columns, or wherever the player
+    /// given it drops it
+    #[arg(long, value_enum, global = true)]
+    pub placement: Option<Placement>,
+
+    /// Start from a random position this many moves in, where nobody can force a quick win
+    #[arg(long, value_name = "MOVES", global = true)]
+    pub random_start: Option<usize>,
+
+    /// Seed of the random start and of the computer's chance moves, to play a game shown
+    /// before again
+    #[arg(long, global = true)]
+    pub seed: Option<u32>,
+
+    /// The rules: standard; popout, where a player may pop one of their own pieces out of
+    /// the bottom row instead of dropping one; powerup, where each player also has an
+    /// anvil and a bomb (unrated, so use it with --casual); cylinder, where the board
+    /// wraps around from its right edge to its left; misere, where connecting a line
+    /// loses; score, where the board is filled and the most lines win; flip, where each
+    /// player may once turn the board upside down instead of dropping a piece; or 3d, four
+    /// in a row on a 4x4x4 cube of pegs, typed like b3
+    #[arg(long, value_enum, global = true)]
+    pub variant: Option<Variant>,
+
+    /// Play blind: the board is hidden once the first move is made, until the game ends or
+    /// a player looks with :peek
+    #[arg(long, global = true)]
+    pub blind: bool,
+
+    /// How many times a blind game lets the players peek at the board
+    #[arg(long, value_name = "COUNT", global = true)]
+    pub peeks: Option<usize>,
+
+    /// Play on a clock: each player's time, e.g. 5m, with an increment per move (3m+2s) or
+    /// a delay before the clock runs ("5m d3")
+    #[arg(long, value_name = "TIME", global = true)]
+    pub time: Option<TimeControl>,
+
+    /// Play on a named time control: blitz (3m+2s), rapid (10m), classical (30m), a preset
+    /// from the config file, or custom to go by --time
+    #[arg(long, value_name = "PRESET", global = true)]
+    pub tc: Option<String>,
+
+    /// Give every move this long, e.g. 30s; a player who goes over loses unless
+    /// --timeout-action says otherwise
+    #[arg(long, value_name = "TIME", value_parser = clock::parse_limit, global = true)]
+    pub move_timeout: Option<Duration>,
+
+    /// What happens when a move's time runs out: the player forfeits, or a random column is
+    /// played for them
+    #[arg(long, value_enum, global = true)]
+    pub timeout_action: Option<TimeoutAction>,
+
+    /// Play unrated games that leave the players' ratings alone
+    #[arg(long, global = true)]
+    pub casual: bool,
+
+    /// How moves are entered: a column per line, or arrow keys and single key presses
+    #[arg(long, value_enum, global = true)]
+    pub input: Option<Input>,
+
+    /// With key input, whether a column's key selects it or drops the piece right away
+    #[arg(long, value_enum, global = true)]
+    pub digits: Option<Digits>,
+
+    /// Play with single key presses, a column's key dropping the piece right away: short for
+    /// --input keys --digits drop
+    #[arg(long, conflicts_with_all = ["input", "digits"], global = true)]
+    pub quick_input: bool,
+
+    /// Play as this profile: its name for Player 1, and the settings saved with it
+    #[arg(long, value_name = "NAME", global = true)]
+    pub profile: Option<String>,
+
+    /// Name for Player 1
+    #[arg(long, global = true)]
+    pub p1_name: Option<String>,
+
+    /// Name for Player 2
+    #[arg(long, global = true)]
+    pub p2_name: Option<String>,
+
+    /// Name for Player 3, in games of three or four players
+    #[arg(long, global = true)]
+    pub p3_name: Option<String>,
+
+    /// Name for Player 4, in games of four players
+    #[arg(long, global = true)]
+    pub p4_name: Option<String>,
+
+    /// Piece symbol for Player 1, replacing the theme's
+    #[arg(long, global = true)]
+    pub p1_symbol: Option<String>,
+
+    /// Piece symbol for Player 2, replacing the theme's
+    #[arg(long, global = true)]
+    pub p2_symbol: Option<String>,
+
+    /// Piece color for Player 1: a named color or #rrggbb
+    #[arg(long, global = true)]
+    pub p1_color: Option<String>,
+
+    /// Piece color for Player 2: a named color or #rrggbb
+    #[arg(long, global = true)]
+    pub p2_color: Option<String>,
+
+    /// Append the timestamped transcript of the session's games to this file instead of
+    /// the daily one
+    #[arg(long, value_name = "FILE", global = true)]
+    pub log_file: Option<PathBuf>,
+
+    /// Keep no transcript of the session
+    #[arg(long, global = true, conflicts_with = "log_file")]
+    pub no_transcript: bool,
+
+    /// Write diagnostic logs to stderr: -v for info, -vv for debug, -vvv for trace
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// How diagnostic logs are written
+    #[arg(long, value_enum, default_value = "text", global = true)]
+    pub log_format: LogFormat,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Start a game right away, without the menu
+    Play,
+    /// Show a player's lifetime statistics, or figures over every recorded game with --all
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Stats {
+        #[command(subcommand)]
+        view: Option<StatsView>,
+        /// Name of the player
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        name: Option<String>,
+        /// Figures over the log of every finished game
+        #[arg(long)]
+        all: bool,
+        /// With --all, only the games this player took part in, from their side
+        #[arg(long, value_name = "NAME", requires = "all")]
+        player: Option<String>,
+        /// With --all, only the games played on or after this date
+        #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date, requires = "all")]
+        since: Option<String>,
+        /// With --all, print JSON for export
+        #[arg(long, requires = "all")]
+        json: bool,
+    },
+    /// List every rated player, highest rating first
+    Ratings,
+    /// Export the recorded games
+    Export {
+        #[command(subcommand)]
+        format: ExportFormat,
+    },
+    /// Read games written by export pgn into the saves, each under its id
+    Import {
+        /// File of games in the PGN-like format
+        file: PathBuf,
+        /// Only check the file, without saving anything
+        #[arg(long)]
+        check: bool,
+    },
+    /// Step through a finished or saved game move by move
+    Replay {
+        /// A save slot or file, a game's number in the log as export csv lists it, or a
+        /// game id
+        game: String,
+        /// Start playing the moves by themselves; without a terminal, print every position
+        #[arg(long)]
+        autoplay: bool,
+        /// Seconds between moves when playing by themselves
+        #[arg(long, value_name = "SECONDS", default_value = "1", value_parser = parse_speed)]
+        speed: Duration,
+    },
+    /// Play a saved game against the computer again, checking it makes every move it made
+    Reproduce {
+        /// A save slot or file
+        game: String,
+    },
+    /// Learn the rules and the basic tactics a lesson at a time, carrying on from the last
+    /// lesson finished
+    Tutorial {
+        /// Start at this lesson instead: drop, win, block, double, or center
+        lesson: Option<String>,
+    },
+    /// Rank every known player
+    Leaderboard {
+        /// What to rank players by
+        #[arg(long, value_enum, default_value = "rating")]
+        sort: Sort,
+        /// Leave out players with fewer games than this
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        min_games: u32,
+        /// List the computer's levels at their fixed ratings for comparison
+        #[arg(long)]
+        include_computer: bool,
+        /// Print an aligned table, or CSV or JSON for export
+        #[arg(long, value_enum, default_value = "table")]
+        format: Format,
+    },
+    /// Manage the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// List the rule presets, built-in and from the config file
+    Presets,
+}
+
+#[derive(Subcommand)]
+pub enum ExportFormat {
+    /// Write the recorded games as CSV, a row per game
+    Csv {
+        /// File to write, instead of standard output
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        /// A row per move instead of per game
+        #[arg(long)]
+        per_move: bool,
+        /// Only the games this player took part in
+        #[arg(long, value_name = "NAME")]
+        player: Option<String>,
+        /// Only the games played on or after this date
+        #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+        since: Option<String>,
+    },
+    /// Write one game as a JSON report: players, rules, result, every move, and the board
+    Json {
+        /// A save slot or file, a game's number in the log as export csv lists it, or a
+        /// game id
+        game: String,
+        /// File to write, instead of standard output
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+    },
+    /// Write games in a PGN-like text format, with tags, comments, and variations
+    Pgn {
+        /// Save slots or files, numbers in the log, or game ids; every recorded game if
+        /// none are given
+        #[arg(value_name = "GAME")]
+        games: Vec<String>,
+        /// File to write, instead of standard output
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        /// Only the recorded games this player took part in, without GAME
+        #[arg(long, value_name = "NAME", conflicts_with = "games")]
+        player: Option<String>,
+        /// Only the recorded games played on or after this date, without GAME
+        #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date, conflicts_with = "games")]
+        since: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StatsView {
+    /// Show how often Player 1 won by the first move's column, from the recorded games
+    Heatmap {
+        /// Also show every cell, by whether Player 1 held it when the game ended
+        #[arg(long)]
+        cells: bool,
+        /// Count mirror-image columns together
+        #[arg(long)]
+        mirror: bool,
+        /// Leave spots with fewer games than this blank
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        min_games: u32,
+        /// Print the raw counts as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Write a commented default config file
+    Init {
+        /// Overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print every resolved setting and where its value came from
+    Show,
+}
+
+// A calendar date as YYYY-MM-DD
+fn parse_date(text: &str) -> Result<String, String> {
+    let parts: Vec<&str> = text.split('-').collect();
+    let valid = match parts[..] {
+        [year, month, day] => {
+            year.len() == 4
+                && month.len() == 2
+                && day.len() == 2
+                && year.parse::<u32>().is_ok()
+                && month.parse::<u32>().is_ok_and(|month| (1..=12).contains(&month))
+                && day.parse::<u32>().is_ok_and(|day| (1..=31).contains(&day))
+        }
+        _ => false,
+    };
+    if valid {
+        Ok(text.to_string())
+    } else {
+        Err(format!("expected a date like 2024-05-31, got '{}'", text))
+    }
+}
+
+// Seconds for --speed: a positive number of them, up to a minute
+fn parse_speed(text: &str) -> Result<Duration, String> {
+    match text.parse::<f64>() {
+        Ok(seconds) if seconds > 0.0 && seconds <= 60.0 => Ok(Duration::from_secs_f64(seconds)),
+        _ => Err(format!("expected a number of seconds up to 60, got '{}'", text)),
+    }
+}
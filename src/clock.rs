@@ -0,0 +1,786 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::ai;
+use crate::outcome::{Outcome, WinKind};
+use crate::{Game, Player};
+
+// What a player gets on top of their main time for every move
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bonus {
+    None,
+    // Fischer increment: added to the clock once the move is made
+    Increment(Duration),
+    // Simple delay: the clock only starts going down this long into the move, and time the
+    // player doesn't use of it is lost rather than saved up
+    Delay(Duration),
+}
+
+// How long a game is played for: the same main time for each player, and the bonus per move
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeControl {
+    pub main: Duration,
+    pub bonus: Bonus,
+}
+
+// "5m" for plain countdown, "3m+2s" for an increment of 2 seconds a move, or "5m d3" for a
+// delay of 3 seconds. Times are minutes and seconds like "90s" or "1m30s", or bare numbers:
+// minutes for the main time, seconds for the bonus.
+impl FromStr for TimeControl {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<TimeControl, String> {
+        let error = || {
+            format!(
+                "expected a time control like 5m, 3m+2s, or 5m d3, got '{}'",
+                text
+            )
+        };
+        let text = text.trim();
+        let (main, bonus) = if let Some((main, increment)) = text.split_once('+') {
+            let increment = duration(increment.trim(), 1).ok_or_else(error)?;
+            (main.trim(), Bonus::Increment(increment))
+        } else if let Some((main, delay)) = text.split_once(char::is_whitespace) {
+            let delay = delay
+                .trim()
+                .strip_prefix('d')
+                .and_then(|delay| duration(delay, 1))
+                .ok_or_else(error)?;
+            (main, Bonus::Delay(delay))
+        } else {
+            (text, Bonus::None)
+        };
+        let main = duration(main, 60).ok_or_else(error)?;
+        Ok(TimeControl { main, bonus })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format(self.main, "m"))?;
+        match self.bonus {
+            Bonus::None => Ok(()),
+            Bonus::Increment(increment) => write!(f, "+{}", format(increment, "m")),
+            Bonus::Delay(delay) => write!(f, " d{}", format(delay, "s")),
+        }
+    }
+}
+
+// The named time controls that come built in, for --tc; the config file's [presets] section
+// can change them and add its own
+pub const PRESETS: [(&str, &str); 3] = [
+    ("blitz", "3m+2s"),
+    ("rapid", "10m"),
+    ("classical", "30m"),
+];
+
+// Most a preset can give a player for every move
+const MAX_BONUS: Duration = Duration::from_secs(60);
+
+// The time control of preset `name`, as long as a game can be played on it: some main time,
+// and a bonus under both the main time and a minute
+pub fn preset(name: &str, text: &str) -> Result<TimeControl, String> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "preset names are letters, digits, dashes, and underscores, got '{}'",
+            name
+        ));
+    }
+    if name == "custom" {
+        return Err("'custom' means no preset and can't be one".to_string());
+    }
+    let main = text.trim().split(['+', ' ']).next().unwrap_or("");
+    if !main.is_empty() && main.chars().all(|c| matches!(c, '0' | 'h' | 'm' | 's')) {
+        return Err(format!("preset '{}' has no main time", name));
+    }
+    let control: TimeControl = text.parse()?;
+    let bonus = match control.bonus {
+        Bonus::None => Duration::ZERO,
+        Bonus::Increment(bonus) | Bonus::Delay(bonus) => bonus,
+    };
+    if bonus > MAX_BONUS {
+        return Err(format!(
+            "preset '{}' gives {} a move, more than the most of {}",
+            name,
+            format(bonus, "m"),
+            format(MAX_BONUS, "m")
+        ));
+    }
+    if bonus >= control.main {
+        return Err(format!(
+            "preset '{}' gives {} a move, not less than its main time of {}",
+            name,
+            format(bonus, "m"),
+            format(control.main, "m")
+        ));
+    }
+    Ok(control)
+}
+
+// A time limit for every move, like "30s" or "1m", or a bare number of seconds
+pub fn parse_limit(text: &str) -> Result<Duration, String> {
+    duration(text, 1).ok_or_else(|| format!("expected a time like 30s or 1m, got '{}'", text))
+}
+
+// A time limit for every move the way `parse_limit` reads it
+pub fn format_limit(limit: Duration) -> String {
+    format(limit, "m")
+}
+
+// A time like "5m", "90s", "1m30s", or "1h"; a bare number counts `unit` seconds each. None
+// for anything else, and for no time at all.
+fn duration(text: &str, unit: u64) -> Option<Duration> {
+    if let Ok(count) = text.parse::<u64>() {
+        return count
+            .checked_mul(unit)
+            .map(Duration::from_secs)
+            .filter(|time| !time.is_zero());
+    }
+    let mut total = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'h' | 'm' | 's' if !number.is_empty() => {
+                let value: u64 = number.parse().ok()?;
+                let unit = match c {
+                    'h' => 3600,
+                    'm' => 60,
+                    _ => 1,
+                };
+                total = value.checked_mul(unit)?.checked_add(total)?;
+                number.clear();
+            }
+            _ => return None,
+        }
+    }
+    (number.is_empty() && total > 0).then(|| Duration::from_secs(total))
+}
+
+// A time the way `duration` reads it back, e.g. "5m" or "1m30s". Under a minute it's a bare
+// number of seconds when `bare` is "s".
+fn format(time: Duration, bare: &str) -> String {
+    let seconds = time.as_secs();
+    match (seconds / 60, seconds % 60) {
+        (0, seconds) if bare == "s" => seconds.to_string(),
+        (0, seconds) => format!("{}s", seconds),
+        (minutes, 0) => format!("{}m", minutes),
+        (minutes, seconds) => format!("{}m{}s", minutes, seconds),
+    }
+}
+
+// A chess clock for both players: only the clock of the player to move runs, and the time
+// control's bonus applies to every move. Every reading takes the time it's for, so a game can
+// be driven by a real clock or a made-up one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Clock {
+    pub control: TimeControl,
+    // The computer's clock is stopped while it works out its move
+    pub pause_computer: bool,
+    // Time each player has left, not counting the stretch that's running now
+    left: [Duration; 2],
+    // Time each player has spent thinking, delays and increments aside
+    used: [Duration; 2],
+    // The player whose time is running, since when, and how many moves had been played then
+    running: Option<(Player, Instant, usize)>,
+    // When the clock was paused, if it is; no time passes for anyone until it's resumed
+    paused: Option<Instant>,
+}
+
+fn seat(player: Player) -> usize {
+    usize::from(player == Player::Two)
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, pause_computer: bool) -> Clock {
+        Clock {
+            control,
+            pause_computer,
+            left: [control.main; 2],
+            used: [Duration::ZERO; 2],
+            running: None,
+            paused: None,
+        }
+    }
+
+    // The clock with the time left and used that a save has, stopped
+    pub fn restored(self, left: [Duration; 2], used: [Duration; 2]) -> Clock {
+        Clock {
+            left,
+            used,
+            running: None,
+            paused: None,
+            ..self
+        }
+    }
+
+    // Freeze both players' time at `now`, partway through a delay included
+    pub fn pause(&mut self, now: Instant) {
+        self.paused.get_or_insert(now);
+    }
+
+    // Let time pass again from `now`. The running player picks up where they were paused, with
+    // whatever was left of a delay.
+    pub fn resume(&mut self, now: Instant) {
+        let Some(at) = self.paused.take() else {
+            return;
+        };
+        if let Some((_, since, _)) = &mut self.running {
+            *since += now.saturating_duration_since(at);
+        }
+    }
+
+    pub fn paused_since(&self) -> Option<Instant> {
+        self.paused
+    }
+
+    // How much of a stretch of `elapsed` thinking comes off the clock
+    fn charged(&self, elapsed: Duration) -> Duration {
+        match self.control.bonus {
+            Bonus::Delay(delay) => elapsed.saturating_sub(delay),
+            _ => elapsed,
+        }
+    }
+
+    // How long `player`'s clock has been running by `now`, if it is, leaving out a pause
+    fn elapsed(&self, player: Player, now: Instant) -> Option<Duration> {
+        let now = self.paused.unwrap_or(now);
+        match self.running {
+            Some((running, since, _)) if running == player => {
+                Some(now.saturating_duration_since(since))
+            }
+            _ => None,
+        }
+    }
+
+    // Time `player` has spent thinking by `now`
+    pub fn used(&self, player: Player, now: Instant) -> Duration {
+        self.used[seat(player)] + self.elapsed(player, now).unwrap_or_default()
+    }
+
+    // Time `player` has left at `now`, zero once their flag has fallen
+    pub fn remaining(&self, player: Player, now: Instant) -> Duration {
+        let charged = self
+            .elapsed(player, now)
+            .map_or(Duration::ZERO, |elapsed| self.charged(elapsed));
+        self.left[seat(player)].saturating_sub(charged)
+    }
+
+    pub fn running(&self) -> Option<Player> {
+        self.running.map(|(player, _, _)| player)
+    }
+
+    // Charge the running player for their time so far and stop the clock. `ply` is the number
+    // of moves played by now: if it's gone up since the clock started, the player completed a
+    // move and earns the increment, the game's last move included. Returns how long that move
+    // took, if one was made.
+    pub fn stop(&mut self, now: Instant, ply: usize) -> Option<Duration> {
+        let (player, since, started) = self.running.take()?;
+        let elapsed = self.paused.unwrap_or(now).saturating_duration_since(since);
+        let index = seat(player);
+        self.used[index] += elapsed;
+        self.left[index] = self.left[index].saturating_sub(self.charged(elapsed));
+        if let (Bonus::Increment(increment), true) = (self.control.bonus, ply > started) {
+            if !self.left[index].is_zero() {
+                self.left[index] += increment;
+            }
+        }
+        (ply > started).then_some(elapsed)
+    }
+
+    // Stop the clock at `now` and give each player the other's time, for players trading
+    // seats; the next tick starts it again for whoever is to move
+    pub fn swap_seats(&mut self, now: Instant, ply: usize) {
+        self.stop(now, ply);
+        self.left.swap(0, 1);
+        self.used.swap(0, 1);
+    }
+
+    // Run `player`'s clock from `now`, after `ply` moves, stopping the other one. Returns the
+    // time of the move that stopped it, as `stop` does.
+    pub fn start(&mut self, player: Player, now: Instant, ply: usize) -> Option<Duration> {
+        if self.running() == Some(player) {
+            return None;
+        }
+        let moved = self.stop(now, ply);
+        self.running = Some((player, now, ply));
+        moved
+    }
+
+    // The running player, if their time has run out by `now`
+    pub fn flagged(&self, now: Instant) -> Option<Player> {
+        self.running()
+            .filter(|&player| self.remaining(player, now).is_zero())
+    }
+
+    // How long the running player has until their flag falls, a delay still to come included
+    pub fn until_flag(&self, now: Instant) -> Option<Duration> {
+        let player = self.running()?;
+        let elapsed = self.elapsed(player, now).unwrap_or_default();
+        let delay = match self.control.bonus {
+            Bonus::Delay(delay) => delay.saturating_sub(elapsed),
+            _ => Duration::ZERO,
+        };
+        Some(self.remaining(player, now) + delay)
+    }
+}
+
+// Pauses a rated game may have
+pub const RATED_PAUSES: usize = 3;
+
+// What happens to a player who runs out of time for a move
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum TimeoutAction {
+    // They lose the game
+    Forfeit,
+    // A random open column is played for them
+    Random,
+}
+
+// A limit on every move, with nothing carried over from one move to the next. Only people's
+// moves are timed; the computer takes as long as it takes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MoveTimer {
+    pub limit: Duration,
+    pub action: TimeoutAction,
+    // The player whose move is being timed, how many moves had been played then, and since
+    // when
+    turn: Option<(Player, usize, Instant)>,
+    // When the timer was paused, if it is
+    paused: Option<Instant>,
+}
+
+impl MoveTimer {
+    pub fn new(limit: Duration, action: TimeoutAction) -> MoveTimer {
+        MoveTimer {
+            limit,
+            action,
+            turn: None,
+            paused: None,
+        }
+    }
+
+    // Freeze the move's time at `now`
+    pub fn pause(&mut self, now: Instant) {
+        self.paused.get_or_insert(now);
+    }
+
+    // Let the move's time run again from `now`
+    pub fn resume(&mut self, now: Instant) {
+        let Some(at) = self.paused.take() else {
+            return;
+        };
+        if let Some((_, _, since)) = &mut self.turn {
+            *since += now.saturating_duration_since(at);
+        }
+    }
+
+    pub fn paused_since(&self) -> Option<Instant> {
+        self.paused
+    }
+
+    // Time the move `player` makes after `ply` moves from `now`, unless it already is
+    pub fn start(&mut self, player: Player, ply: usize, now: Instant) {
+        if self
+            .turn
+            .is_some_and(|(timed, started, _)| timed == player && started == ply)
+        {
+            return;
+        }
+        self.turn = Some((player, ply, now));
+    }
+
+    pub fn stop(&mut self) {
+        self.turn = None;
+    }
+
+    // Time left at `now` for the move being timed, if one is
+    pub fn remaining(&self, now: Instant) -> Option<Duration> {
+        let (_, _, since) = self.turn?;
+        let now = self.paused.unwrap_or(now);
+        Some(
+            self.limit
+                .saturating_sub(now.saturating_duration_since(since)),
+        )
+    }
+}
+
+impl Game {
+    // Bring the clock and the move timer up to `now`: they run for the player to move and
+    // stop once the game is over. A player whose time has run out loses on time, or has a
+    // move played for them when that's what the move timer does. Returns whether either just
+    // happened.
+    pub fn tick(&mut self, now: Instant) -> bool {
+        self.tick_clock(now) || self.tick_move_timer(now)
+    }
+
+    fn tick_clock(&mut self, now: Instant) -> bool {
+        let thinking = self.computer_to_move().is_some();
+        let placing = self.placing();
+        let ply = self.history.len();
+        let finished = self.is_finished();
+        let Some(clock) = &mut self.clock else {
+            return false;
+        };
+        // Placing a handicap isn't on the clock
+        let stopped = finished || placing || (thinking && clock.pause_computer);
+        let moved = if stopped {
+            clock.stop(now, ply)
+        } else {
+            clock.start(self.current_player, now, ply)
+        };
+        // The move that stopped the clock is the last one played
+        if let Some(time) = moved {
+            self.move_times.resize(ply - 1, Duration::ZERO);
+            self.move_times.push(time);
+        }
+        if stopped || clock.flagged(now).is_none() {
+            return false;
+        }
+        clock.stop(now, ply);
+        self.lose_on_time();
+        true
+    }
+
+    fn tick_move_timer(&mut self, now: Instant) -> bool {
+        let thinking = self.computer_to_move().is_some() || self.placing();
+        let (player, ply) = (self.current_player, self.history.len());
+        let finished = self.is_finished();
+        let Some(timer) = &mut self.move_timer else {
+            return false;
+        };
+        if finished || thinking {
+            timer.stop();
+            return false;
+        }
+        timer.start(player, ply, now);
+        if !timer.remaining(now).is_some_and(|left| left.is_zero()) {
+            return false;
+        }
+        timer.stop();
+        match timer.action {
+            TimeoutAction::Forfeit => self.lose_on_time(),
+            TimeoutAction::Random => {
+                let mv = ai::random_move(self).expect("a game going on has a legal move");
+                self.play(mv).expect("random moves are legal");
+                self.timeouts.push(self.history.len());
+            }
+        }
+        true
+    }
+
+    // Whether the players may pause the game now: only timed games have anything to pause,
+    // and rated games allow a few pauses each
+    pub fn can_pause(&self, rated: bool) -> Result<(), String> {
+        if self.clock.is_none() && self.move_timer.is_none() {
+            return Err("only timed games can be paused".to_string());
+        }
+        if self.paused_since().is_some() {
+            return Err("the game is already paused".to_string());
+        }
+        if rated && self.pauses.len() >= RATED_PAUSES {
+            return Err(format!(
+                "rated games can be paused {} times, and this one has been",
+                RATED_PAUSES
+            ));
+        }
+        Ok(())
+    }
+
+    // Freeze the clock and the move timer at `now`
+    pub fn pause(&mut self, now: Instant) {
+        if let Some(clock) = &mut self.clock {
+            clock.pause(now);
+        }
+        if let Some(timer) = &mut self.move_timer {
+            timer.pause(now);
+        }
+    }
+
+    // When the game was paused, if it is
+    pub fn paused_since(&self) -> Option<Instant> {
+        self.clock
+            .and_then(|clock| clock.paused_since())
+            .or(self.move_timer.and_then(|timer| timer.paused_since()))
+    }
+
+    // Let time run again from `now`, keeping how long the pause lasted with the game.
+    // Returns that.
+    pub fn resume(&mut self, now: Instant) -> Duration {
+        let Some(at) = self.paused_since() else {
+            return Duration::ZERO;
+        };
+        if let Some(clock) = &mut self.clock {
+            clock.resume(now);
+        }
+        if let Some(timer) = &mut self.move_timer {
+            timer.resume(now);
+        }
+        let length = now.saturating_duration_since(at);
+        self.pauses.push(length);
+        length
+    }
+
+    // Time until the player to move runs out, on the clock or for the move, whichever comes
+    // first
+    pub fn time_left(&self, now: Instant) -> Option<Duration> {
+        let clock = self.clock.and_then(|clock| clock.until_flag(now));
+        let timer = self.move_timer.and_then(|timer| timer.remaining(now));
+        match (clock, timer) {
+            (Some(clock), Some(timer)) => Some(clock.min(timer)),
+            (clock, timer) => clock.or(timer),
+        }
+    }
+
+    // Who the last move was played for and where, if it was played at random because their
+    // time for it ran out
+    pub fn timeout_note(&self) -> Option<String> {
+        if self.timeouts.last() != Some(&self.history.len()) {
+            return None;
+        }
+        let coord = self.last_move()?;
+        let seat = if self.history.len() % 2 == 1 {
+            Player::One
+        } else {
+            Player::Two
+        };
+        Some(format!(
+            "Time's up for {}: column {} was played for them",
+            self.name(seat),
+            coord.column + 1
+        ))
+    }
+
+    // The player to move loses because their time ran out
+    pub fn lose_on_time(&mut self) {
+        let player = self.current_player.other();
+        self.outcome = Some(Outcome::Win {
+            player,
+            line: None,
+            by: WinKind::Time,
+        });
+    }
+
+    // The time control and both players' time left, the one whose clock is running marked,
+    // e.g. "Clock (blitz, 3m+2s): Alice 2:31 *  Bob 3:00"
+    pub fn clock_line(&self, now: Instant) -> Option<String> {
+        let clock = self.clock.as_ref()?;
+        let side = |player: Player| {
+            let mark = if clock.running() == Some(player) && !self.is_finished() {
+                " *"
+            } else {
+                ""
+            };
+            format!(
+                "{} {}{}",
+                self.name(player),
+                display(clock.remaining(player, now)),
+                mark
+            )
+        };
+        let control = match &self.preset {
+            Some(name) => format!("{}, {}", name, clock.control),
+            None => clock.control.to_string(),
+        };
+        Some(format!(
+            "Clock ({}): {}  {}",
+            control,
+            side(Player::One),
+            side(Player::Two)
+        ))
+    }
+
+    // The player to move's time left on the clock and for the move, to go in the prompt,
+    // e.g. " (4:31 left, 0:27 for this move)"
+    pub fn time_note(&self, now: Instant) -> String {
+        let clock = self
+            .clock
+            .map(|clock| format!("{} left", display(clock.remaining(self.current_player, now))));
+        let timer = self
+            .move_timer
+            .and_then(|timer| timer.remaining(now))
+            .map(|left| format!("{} for this move", display(left)));
+        let notes: Vec<String> = clock.into_iter().chain(timer).collect();
+        if notes.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", notes.join(", "))
+        }
+    }
+
+    // How much time each player spent thinking, for after the game
+    pub fn time_used(&self, now: Instant) -> Option<String> {
+        let clock = self.clock.as_ref()?;
+        let side = |player: Player| {
+            format!(
+                "{} {}",
+                self.name(player),
+                display(clock.used(player, now))
+            )
+        };
+        Some(format!(
+            "Time used: {}, {}",
+            side(Player::One),
+            side(Player::Two)
+        ))
+    }
+}
+
+// A time on the clock as minutes and seconds, with tenths under ten seconds: "4:31", "0:09.4"
+pub fn display(time: Duration) -> String {
+    let seconds = time.as_secs();
+    if seconds < 10 {
+        format!("0:0{}.{}", seconds, time.subsec_millis() / 100)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    fn control(text: &str) -> TimeControl {
+        text.parse().expect("the time control is valid")
+    }
+
+    // A clock for `text` and play through `moves`, each the seconds a move took, the players
+    // taking turns from player one. Returns the clock, stopped after the last move, and the
+    // moment it was stopped.
+    fn scripted(text: &str, moves: &[u64]) -> (Clock, Instant) {
+        let mut clock = Clock::new(control(text), false);
+        let mut now = Instant::now();
+        clock.start(Player::One, now, 0);
+        for (ply, &seconds) in moves.iter().enumerate() {
+            now += secs(seconds);
+            let next = if ply % 2 == 0 { Player::Two } else { Player::One };
+            assert_eq!(clock.start(next, now, ply + 1), Some(secs(seconds)));
+        }
+        clock.stop(now, moves.len());
+        (clock, now)
+    }
+
+    fn left(clock: &Clock, now: Instant) -> [Duration; 2] {
+        [clock.remaining(Player::One, now), clock.remaining(Player::Two, now)]
+    }
+
+    #[test]
+    fn time_controls_parse() {
+        let cases = [
+            ("5m", secs(300), Bonus::None),
+            ("5", secs(300), Bonus::None),
+            ("1m30s", secs(90), Bonus::None),
+            ("1h", secs(3600), Bonus::None),
+            ("3m+2s", secs(180), Bonus::Increment(secs(2))),
+            ("3+2", secs(180), Bonus::Increment(secs(2))),
+            (" 3m + 2s ", secs(180), Bonus::Increment(secs(2))),
+            ("5m d3", secs(300), Bonus::Delay(secs(3))),
+            ("5m d3s", secs(300), Bonus::Delay(secs(3))),
+        ];
+        for (text, main, bonus) in cases {
+            assert_eq!(text.parse(), Ok(TimeControl { main, bonus }), "{:?}", text);
+        }
+        for text in ["", "0", "0m", "5m+", "+2s", "5m d", "5m x3", "5x", "m5", "1m30"] {
+            assert!(text.parse::<TimeControl>().is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn time_controls_display_as_they_are_parsed() {
+        for text in ["5m", "1m30s", "3m+2s", "10m+30s", "5m d3", "45s"] {
+            assert_eq!(control(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn times_too_long_to_count_are_errors() {
+        // Each overflows the seconds a Duration can hold somewhere along the way
+        for text in [
+            "999999999999999999",
+            "9999999999999999h",
+            "18446744073709551616s",
+            "18446744073709551615s1s",
+            "5m+99999999999999999999",
+            "5m d9999999999999999h",
+        ] {
+            assert!(text.parse::<TimeControl>().is_err(), "{:?}", text);
+        }
+        assert!(parse_limit("9999999999999999h").is_err());
+        // The most there is still counts
+        assert_eq!(
+            "18446744073709551615s".parse::<TimeControl>().map(|control| control.main),
+            Ok(secs(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn plain_clocks_count_down() {
+        let (clock, now) = scripted("1m", &[5, 10, 3]);
+        assert_eq!(left(&clock, now), [secs(52), secs(50)]);
+        assert_eq!(clock.used(Player::One, now), secs(8));
+    }
+
+    #[test]
+    fn an_increment_is_added_after_every_move() {
+        // 60 - 5 + 2 - 3 + 2 for player one, 60 - 10 + 2 - 7 + 2 for player two, the last
+        // move, which could have won the game, earning its increment too
+        let (clock, now) = scripted("1m+2s", &[5, 10, 3, 7]);
+        assert_eq!(left(&clock, now), [secs(56), secs(47)]);
+        assert_eq!(clock.used(Player::Two, now), secs(17));
+    }
+
+    #[test]
+    fn no_increment_without_a_move_or_after_the_flag() {
+        let mut clock = Clock::new(control("1m+2s"), false);
+        let start = Instant::now();
+        clock.start(Player::One, start, 0);
+        assert_eq!(clock.stop(start + secs(4), 0), None);
+        assert_eq!(clock.remaining(Player::One, start), secs(56));
+
+        clock.start(Player::One, start, 0);
+        assert_eq!(clock.flagged(start + secs(56)), Some(Player::One));
+        clock.stop(start + secs(70), 1);
+        assert_eq!(clock.remaining(Player::One, start), Duration::ZERO);
+    }
+
+    #[test]
+    fn a_delay_is_free_and_does_not_accumulate() {
+        // Moves inside the delay cost nothing, and the unused part of one isn't saved for the
+        // next: 60 - 0 - 0 - (4 - 3) for player one, 60 - (5 - 3) for player two
+        let (clock, now) = scripted("1m d3", &[2, 5, 3, 0, 4]);
+        assert_eq!(left(&clock, now), [secs(59), secs(58)]);
+        assert_eq!(clock.used(Player::One, now), secs(9));
+    }
+
+    #[test]
+    fn a_running_delay_holds_the_clock() {
+        let mut clock = Clock::new(control("1m d3"), false);
+        let start = Instant::now();
+        clock.start(Player::One, start, 0);
+        assert_eq!(clock.remaining(Player::One, start + secs(1)), secs(60));
+        assert_eq!(clock.until_flag(start + secs(1)), Some(secs(62)));
+        assert_eq!(clock.remaining(Player::One, start + secs(10)), secs(53));
+        assert_eq!(clock.until_flag(start + secs(10)), Some(secs(53)));
+    }
+
+    #[test]
+    fn a_pause_stops_the_running_clock() {
+        let mut clock = Clock::new(control("1m"), false);
+        let start = Instant::now();
+        clock.start(Player::One, start, 0);
+        clock.pause(start + secs(5));
+        assert_eq!(clock.remaining(Player::One, start + secs(30)), secs(55));
+        clock.resume(start + secs(30));
+        assert_eq!(clock.remaining(Player::One, start + secs(32)), secs(53));
+        assert_eq!(clock.start(Player::Two, start + secs(32), 1), Some(secs(7)));
+    }
+}
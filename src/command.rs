@@ -0,0 +1,999 @@
+use std::fmt;
+
+use crate::coord::Coord;
+use crate::cube;
+use crate::i18n;
+use crate::variant::Move;
+
+// What a line typed at the move prompt asks for
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    // Drop a piece into this column, counted from 0
+    Move(usize),
+    // A move other than a drop: under PopOut a pop of your own piece out of the bottom of a
+    // column, e.g. "p4", under Power Up an anvil ("a4") or a bomb ("b4"), and under Flip a
+    // flip of the board ("flip")
+    Special(Move),
+    // Show where a piece would land without playing it, e.g. "4?"
+    Preview(usize),
+    // Save the game under a name or at a path, or in the default slot
+    Save(Option<String>),
+    Hint,
+    // Take back this many moves
+    Undo(usize),
+    // Note on the last move played; no text removes it
+    Comment(String),
+    // List every command, or explain one
+    Help(Option<String>),
+    Resign,
+    // Leave the session, in the middle of a game after asking
+    Quit,
+    // Offer the opponent a draw, or accept theirs
+    Draw,
+    Accept,
+    // Under the pie rule, take the first move over as your own
+    Swap,
+    // Freeze a timed game's clocks and hide the board until both players are back
+    Pause,
+    Resume,
+    // In a blind game, show the board for a moment
+    Peek,
+    // Read out the board in words, or one column of it
+    Describe(Option<usize>),
+    // Show the session's tally, or start it over
+    Score { reset: bool },
+    // Show a player's lifetime statistics, the player to move's by default
+    Stats(Option<String>),
+    // List a player's earned and locked achievements, the player to move's by default
+    Achievements(Option<String>),
+    // Switch to a theme, or list them all
+    Theme(Option<String>),
+}
+
+// A colon command as :help describes it
+#[derive(Debug, PartialEq)]
+pub struct Spec {
+    pub name: &'static str,
+    pub args: &'static str,
+    // Whether it can still be used once the game has ended
+    pub after_game: bool,
+}
+
+impl Spec {
+    pub fn usage(&self) -> String {
+        format!(":{} {}", self.name, self.args)
+            .trim_end()
+            .to_string()
+    }
+
+    pub fn available(&self, context: Context) -> bool {
+        context == Context::Turn || self.after_game
+    }
+
+    // What it does, in a few words for the list of commands
+    pub fn summary(&self) -> String {
+        i18n::text(&format!("help.{}.summary", self.name))
+    }
+
+    // The longer explanation shown by :help <command>
+    pub fn details(&self) -> String {
+        i18n::text(&format!("help.{}.details", self.name))
+    }
+}
+
+// Every colon command; the help screen and the suggestions for typos are built from this
+pub const COMMANDS: [Spec; 18] = [
+    Spec {
+        name: "save",
+        args: "[name or path]",
+        after_game: true,
+    },
+    Spec {
+        name: "hint",
+        args: "",
+        after_game: false,
+    },
+    Spec {
+        name: "undo",
+        args: "[N]",
+        after_game: true,
+    },
+    Spec {
+        name: "comment",
+        args: "[text]",
+        after_game: true,
+    },
+    Spec {
+        name: "resign",
+        args: "",
+        after_game: false,
+    },
+    Spec {
+        name: "quit",
+        args: "",
+        after_game: true,
+    },
+    Spec {
+        name: "draw",
+        args: "",
+        after_game: false,
+    },
+    Spec {
+        name: "accept",
+        args: "",
+        after_game: false,
+    },
+    Spec {
+        name: "swap",
+        args: "",
+        after_game: false,
+    },
+    Spec {
+        name: "pause",
+        args: "",
+        after_game: false,
+    },
+    Spec {
+        name: "resume",
+        args: "",
+        after_game: false,
+    },
+    Spec {
+        name: "peek",
+        args: "",
+        after_game: false,
+    },
+    Spec {
+        name: "describe",
+        args: "[col N]",
+        after_game: true,
+    },
+    Spec {
+        name: "score",
+        args: "[reset]",
+        after_game: true,
+    },
+    Spec {
+        name: "stats",
+        args: "[name]",
+        after_game: true,
+    },
+    Spec {
+        name: "achievements",
+        args: "[name]",
+        after_game: true,
+    },
+    Spec {
+        name: "theme",
+        args: "[name]",
+        after_game: true,
+    },
+    Spec {
+        name: "help",
+        args: "[command]",
+        after_game: true,
+    },
+];
+
+// Look up a command by name, with or without its colon
+pub fn spec(name: &str) -> Option<&'static Spec> {
+    let name = name.strip_prefix(':').unwrap_or(name);
+    COMMANDS.iter().find(|spec| spec.name == name)
+}
+
+fn usage(name: &str) -> CommandError {
+    CommandError::Usage(spec(name).expect("commands are registered"))
+}
+
+// Every command with its arguments and summary, marking the ones that can't be used here
+pub fn help(context: Context) -> String {
+    let width = COMMANDS
+        .iter()
+        .map(|spec| spec.usage().len())
+        .max()
+        .unwrap_or(0);
+    let mut lines: Vec<String> = [
+        "help.columns",
+        "help.preview",
+        "help.popout",
+        "help.powerup",
+        "help.flip",
+    ]
+    .into_iter()
+    .map(i18n::text)
+    .collect();
+    for spec in &COMMANDS {
+        let note = if spec.available(context) {
+            String::new()
+        } else {
+            format!(" {}", i18n::text("help.not_now"))
+        };
+        lines.push(format!(
+            "  {:<width$}  {}{}",
+            spec.usage(),
+            spec.summary(),
+            note,
+            width = width
+        ));
+    }
+    lines.push(i18n::text("help.more"));
+    lines.join("\n")
+}
+
+// Usage and explanation of one command
+pub fn help_for(spec: &Spec, context: Context) -> String {
+    let note = if spec.available(context) {
+        String::new()
+    } else {
+        format!("\n{}", i18n::text("help.only_in_play"))
+    };
+    format!(
+        "{}\n  {}\n{}{}",
+        spec.usage(),
+        spec.summary(),
+        spec.details(),
+        note
+    )
+}
+
+// Split text into pages of at most `lines` lines
+pub fn pages(text: &str, lines: usize) -> Vec<String> {
+    text.lines()
+        .collect::<Vec<&str>>()
+        .chunks(lines.max(1))
+        .map(|page| page.join("\n"))
+        .collect()
+}
+
+// The registered command closest to a mistyped name, if it's only a typo or two away
+pub fn suggestion(name: &str) -> Option<&'static str> {
+    COMMANDS
+        .iter()
+        .map(|spec| (edit_distance(name, spec.name), spec.name))
+        .filter(|&(distance, _)| distance <= 2 && distance < name.chars().count())
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, name)| name)
+}
+
+// Levenshtein distance: single-character insertions, deletions, and substitutions
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+// Where a command was typed, which decides whether it may be used
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Context {
+    // A player is to move
+    Turn,
+    // The game has ended and the restart prompt is showing
+    GameOver,
+}
+
+// Why a line couldn't be turned into a command
+#[derive(Debug, PartialEq)]
+pub enum CommandError {
+    // Neither a move nor a command, on a board this many columns wide
+    NotUnderstood { text: String, width: usize },
+    // A column the board doesn't have
+    OffBoard { text: String, width: usize },
+    NotAPeg(String),
+    Unknown(String),
+    // The command's arguments were wrong
+    Usage(&'static Spec),
+    UnterminatedQuote,
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            CommandError::NotUnderstood { text, width } => i18n::fill(
+                "command.not_understood",
+                &[("text", text), ("width", width)],
+            ),
+            CommandError::OffBoard { text, width } => {
+                i18n::fill("command.off_board", &[("text", text), ("width", width)])
+            }
+            CommandError::NotAPeg(text) => i18n::fill("command.not_a_peg", &[("text", text)]),
+            CommandError::Unknown(name) => match suggestion(name) {
+                Some(suggestion) => {
+                    i18n::fill("command.unknown_suggest", &[("suggestion", &suggestion)])
+                }
+                None => i18n::text("command.unknown"),
+            },
+            CommandError::Usage(spec) => i18n::fill("command.usage", &[("usage", &spec.usage())]),
+            CommandError::UnterminatedQuote => i18n::text("command.unterminated_quote"),
+        };
+        write!(f, "{}", text)
+    }
+}
+
+// Parse a line from the move prompt of a board `width` columns wide: a bare column ("4" or
+// "d", also "col 4", "c4", or "4."), a preview ("4?"), a pop, anvil, bomb, or flip ("p4",
+// "a4", "b4", "flip"), or a command with arguments, its colon optional (":undo 2",
+// "save \"my game\"")
+pub fn parse(line: &str, width: usize) -> Result<Command, CommandError> {
+    let line = tidy(line);
+    let line = line.as_str();
+    let Some(command) = line.strip_prefix(':').or_else(|| bare_command(line)) else {
+        if let Some(column) = line.strip_suffix('?') {
+            return column_on_board(column, width).map(Command::Preview);
+        }
+        return match Move::parse(&move_word(line), Coord::parse_column) {
+            Some(mv) if mv.column() >= width => Err(CommandError::OffBoard {
+                text: line.to_string(),
+                width,
+            }),
+            Some(Move::Drop(column)) => Ok(Command::Move(column)),
+            Some(mv) => Ok(Command::Special(mv)),
+            None if column_zero(&move_word(line)) => Err(CommandError::OffBoard {
+                text: line.to_string(),
+                width,
+            }),
+            None => Err(not_understood(line, width)),
+        };
+    };
+
+    let words = split_words(command)?;
+    let Some((name, args)) = words.split_first() else {
+        return Err(CommandError::Unknown(String::new()));
+    };
+    match name.as_str() {
+        "save" => match args {
+            [] => Ok(Command::Save(None)),
+            [slot] => Ok(Command::Save(Some(slot.clone()))),
+            _ => Err(usage("save")),
+        },
+        "hint" => match args {
+            [] => Ok(Command::Hint),
+            _ => Err(usage("hint")),
+        },
+        "undo" => match args {
+            [] => Ok(Command::Undo(1)),
+            [count] => match count.parse::<usize>() {
+                Ok(count) if count > 0 => Ok(Command::Undo(count)),
+                _ => Err(usage("undo")),
+            },
+            _ => Err(usage("undo")),
+        },
+        "comment" => Ok(Command::Comment(args.join(" "))),
+        "help" => match args {
+            [] => Ok(Command::Help(None)),
+            [command] => Ok(Command::Help(Some(command.clone()))),
+            _ => Err(usage("help")),
+        },
+        "quit" => match args {
+            [] => Ok(Command::Quit),
+            _ => Err(usage("quit")),
+        },
+        "resign" => match args {
+            [] => Ok(Command::Resign),
+            _ => Err(usage("resign")),
+        },
+        "draw" => match args {
+            [] => Ok(Command::Draw),
+            _ => Err(usage("draw")),
+        },
+        "accept" => match args {
+            [] => Ok(Command::Accept),
+            _ => Err(usage("accept")),
+        },
+        "swap" => match args {
+            [] => Ok(Command::Swap),
+            _ => Err(usage("swap")),
+        },
+        "pause" => match args {
+            [] => Ok(Command::Pause),
+            _ => Err(usage("pause")),
+        },
+        "resume" => match args {
+            [] => Ok(Command::Resume),
+            _ => Err(usage("resume")),
+        },
+        "peek" => match args {
+            [] => Ok(Command::Peek),
+            _ => Err(usage("peek")),
+        },
+        "describe" => match args {
+            [] => Ok(Command::Describe(None)),
+            [kind, column] if kind == "col" || kind == "column" => Coord::parse_column(column)
+                .map(|column| Command::Describe(Some(column)))
+                .ok_or_else(|| usage("describe")),
+            [kind, peg] if kind == "peg" => cube::parse_peg(peg)
+                .map(|column| Command::Describe(Some(column)))
+                .ok_or_else(|| usage("describe")),
+            _ => Err(usage("describe")),
+        },
+        "score" => match args {
+            [] => Ok(Command::Score { reset: false }),
+            [reset] if reset == "reset" => Ok(Command::Score { reset: true }),
+            _ => Err(usage("score")),
+        },
+        "stats" => match args {
+            [] => Ok(Command::Stats(None)),
+            [name] => Ok(Command::Stats(Some(name.clone()))),
+            _ => Err(usage("stats")),
+        },
+        "achievements" => match args {
+            [] => Ok(Command::Achievements(None)),
+            [name] => Ok(Command::Achievements(Some(name.clone()))),
+            _ => Err(usage("achievements")),
+        },
+        // ":themes" lists them, as it always has
+        "theme" | "themes" => match args {
+            [] => Ok(Command::Theme(None)),
+            [theme] if name == "theme" => Ok(Command::Theme(Some(theme.clone()))),
+            _ => Err(usage("theme")),
+        },
+        _ => Err(CommandError::Unknown(name.clone())),
+    }
+}
+
+// Parse a line from the move prompt of a 3D game, where a move is a peg ("b3"), a preview
+// is a peg and ? ("b3?"), and colon commands are as in any other game
+pub fn parse_3d(line: &str) -> Result<Command, CommandError> {
+    let line = tidy(line);
+    let line = line.as_str();
+    if line.starts_with(':') || bare_command(line).is_some() {
+        return parse(line, cube::COLUMNS);
+    }
+    if let Some(peg) = line.strip_suffix('?') {
+        return cube::parse_peg(peg)
+            .map(Command::Preview)
+            .ok_or_else(|| CommandError::NotAPeg(peg.trim().to_string()));
+    }
+    cube::parse_peg(line)
+        .map(Command::Move)
+        .ok_or_else(|| CommandError::NotAPeg(line.to_string()))
+}
+
+// The line with full-width letters, digits, and punctuation, as some keyboards type them,
+// made plain, and trimmed
+fn tidy(line: &str) -> String {
+    let line: String = line
+        .chars()
+        .map(|c| match c {
+            '\u{ff01}'..='\u{ff5e}' => char::from_u32(c as u32 - 0xfee0).unwrap_or(c),
+            '\u{3000}' => ' ',
+            c => c,
+        })
+        .collect();
+    line.trim().to_string()
+}
+
+// A command typed without its colon, like "undo 2". No command is a single letter, so a lone
+// letter is always read as a column, whatever the width of the board.
+fn bare_command(line: &str) -> Option<&str> {
+    let name = line.split_whitespace().next()?;
+    COMMANDS
+        .iter()
+        .any(|spec| spec.name == name)
+        .then_some(line)
+}
+
+// A move with what it tends to be typed with taken off: spaces anywhere in it, a "col",
+// "column", or "c" before a number, and a full stop after it
+fn move_word(text: &str) -> String {
+    let word: String = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_lowercase();
+    let word = word.trim_end_matches('.');
+    let number = ["column", "col", "c"].iter().find_map(|prefix| {
+        word.strip_prefix(prefix)
+            .filter(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()))
+    });
+    number.unwrap_or(word).to_string()
+}
+
+// The column named by `text`, if a board `width` columns wide has it
+fn column_on_board(text: &str, width: usize) -> Result<usize, CommandError> {
+    let text = text.trim();
+    match Coord::parse_column(&move_word(text)) {
+        Some(column) if column < width => Ok(column),
+        Some(_) => Err(CommandError::OffBoard {
+            text: text.to_string(),
+            width,
+        }),
+        None if column_zero(&move_word(text)) => Err(CommandError::OffBoard {
+            text: text.to_string(),
+            width,
+        }),
+        None => Err(not_understood(text, width)),
+    }
+}
+
+// Whether a move word names column 0, as in "0" or "p0": columns are counted from 1, so it's
+// off the board as much as one past the last is
+fn column_zero(word: &str) -> bool {
+    let number = word.strip_prefix(['p', 'a', 'b']).unwrap_or(word);
+    !number.is_empty() && number.chars().all(|c| c == '0')
+}
+
+// Why `text` isn't a move: a command missing its colon, misspelled, or nothing at all
+fn not_understood(text: &str, width: usize) -> CommandError {
+    let name = text.to_lowercase();
+    if name.chars().all(|c| c.is_ascii_alphabetic()) && suggestion(&name).is_some() {
+        CommandError::Unknown(name)
+    } else {
+        CommandError::NotUnderstood {
+            text: text.to_string(),
+            width,
+        }
+    }
+}
+
+impl Command {
+    // Refuse commands that make no sense where they were typed, saying why
+    pub fn check(&self, context: Context) -> Result<(), String> {
+        let name = match self {
+            Command::Move(_) | Command::Special(_) | Command::Preview(_)
+                if context == Context::GameOver =>
+            {
+                return Err(i18n::text("command.game_over"));
+            }
+            Command::Move(_) | Command::Special(_) | Command::Preview(_) => return Ok(()),
+            Command::Save(_) => "save",
+            Command::Hint => "hint",
+            Command::Undo(_) => "undo",
+            Command::Comment(_) => "comment",
+            Command::Help(_) => "help",
+            Command::Resign => "resign",
+            Command::Quit => "quit",
+            Command::Draw => "draw",
+            Command::Accept => "accept",
+            Command::Swap => "swap",
+            Command::Pause => "pause",
+            Command::Resume => "resume",
+            Command::Peek => "peek",
+            Command::Describe(_) => "describe",
+            Command::Score { .. } => "score",
+            Command::Stats(_) => "stats",
+            Command::Achievements(_) => "achievements",
+            Command::Theme(_) => "theme",
+        };
+        if spec(name).is_some_and(|spec| spec.available(context)) {
+            Ok(())
+        } else {
+            Err(i18n::fill("command.only_in_play", &[("name", &name)]))
+        }
+    }
+}
+
+// Split arguments at whitespace. Single or double quotes keep spaces in a word, and inside
+// double quotes a backslash escapes the next character.
+fn split_words(text: &str) -> Result<Vec<String>, CommandError> {
+    let mut words = Vec::new();
+    let mut chars = text.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return Ok(words);
+        }
+
+        let mut word = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+            match c {
+                '\'' => loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(CommandError::UnterminatedQuote),
+                    }
+                },
+                '"' => loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => word.push(c),
+                            None => return Err(CommandError::UnterminatedQuote),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(CommandError::UnterminatedQuote),
+                    }
+                },
+                c => word.push(c),
+            }
+        }
+        words.push(word);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(line: &str) -> Result<Command, CommandError> {
+        parse(line, 7)
+    }
+
+    #[test]
+    fn columns_are_read_however_they_are_typed() {
+        for line in [
+            "4", "d", "D", " 4 ", "col 4", "column4", "c4", "C 4", "4.", "４",
+        ] {
+            assert_eq!(parsed(line), Ok(Command::Move(3)), "{:?}", line);
+        }
+        assert_eq!(parsed("1"), Ok(Command::Move(0)));
+        assert_eq!(parsed("g"), Ok(Command::Move(6)));
+    }
+
+    #[test]
+    fn previews_and_special_moves() {
+        let cases = [
+            ("4?", Command::Preview(3)),
+            ("d ?", Command::Preview(3)),
+            ("col 2?", Command::Preview(1)),
+            ("p4", Command::Special(Move::Pop(3))),
+            ("P 4", Command::Special(Move::Pop(3))),
+            ("a4", Command::Special(Move::Anvil(3))),
+            ("b7", Command::Special(Move::Bomb(6))),
+            ("flip", Command::Special(Move::Flip)),
+            ("FLIP", Command::Special(Move::Flip)),
+        ];
+        for (line, command) in cases {
+            assert_eq!(parsed(line), Ok(command), "{:?}", line);
+        }
+        // A lone letter is a column, never a pop, anvil, or bomb
+        assert_eq!(parsed("b"), Ok(Command::Move(1)));
+    }
+
+    #[test]
+    fn columns_past_the_edge_are_off_the_board() {
+        for (line, text) in [
+            ("8", "8"),
+            ("h", "h"),
+            ("p9", "p9"),
+            ("9?", "9"),
+            ("col 12", "col 12"),
+        ] {
+            assert_eq!(
+                parsed(line),
+                Err(CommandError::OffBoard {
+                    text: text.to_string(),
+                    width: 7
+                }),
+                "{:?}",
+                line
+            );
+        }
+        assert_eq!(parse("h", 8), Ok(Command::Move(7)));
+        assert_eq!(
+            parsed("8").unwrap_err().to_string(),
+            "'8' is not a column on this 7-wide board"
+        );
+    }
+
+    #[test]
+    fn commands_with_and_without_their_colon() {
+        let cases = [
+            (":save", Command::Save(None)),
+            (":save slot1", Command::Save(Some("slot1".to_string()))),
+            ("save slot1", Command::Save(Some("slot1".to_string()))),
+            (":hint", Command::Hint),
+            ("hint", Command::Hint),
+            (":undo", Command::Undo(1)),
+            (":undo 2", Command::Undo(2)),
+            ("undo 3", Command::Undo(3)),
+            (":help", Command::Help(None)),
+            (":help undo", Command::Help(Some("undo".to_string()))),
+            (":resign", Command::Resign),
+            (":quit", Command::Quit),
+            (":draw", Command::Draw),
+            (":accept", Command::Accept),
+            (":swap", Command::Swap),
+            (":pause", Command::Pause),
+            (":resume", Command::Resume),
+            (":peek", Command::Peek),
+            (":describe", Command::Describe(None)),
+            (":describe col 4", Command::Describe(Some(3))),
+            (":describe column d", Command::Describe(Some(3))),
+            (":score", Command::Score { reset: false }),
+            (":score reset", Command::Score { reset: true }),
+            (":stats", Command::Stats(None)),
+            (
+                ":achievements Ada",
+                Command::Achievements(Some("Ada".to_string())),
+            ),
+            (":theme ascii", Command::Theme(Some("ascii".to_string()))),
+            (":theme", Command::Theme(None)),
+            (":themes", Command::Theme(None)),
+            (":comment", Command::Comment(String::new())),
+            (
+                ":comment  a  fine move ",
+                Command::Comment("a fine move".to_string()),
+            ),
+            ("  :undo   2  ", Command::Undo(2)),
+            ("：ｕｎｄｏ　２", Command::Undo(2)),
+        ];
+        for (line, command) in cases {
+            assert_eq!(parsed(line), Ok(command), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn quotes_keep_spaces_in_names() {
+        let cases = [
+            (r#":save "my game""#, "my game"),
+            (r#"save "my game""#, "my game"),
+            (":save 'my game'", "my game"),
+            (r#":save "games/last night.toml""#, "games/last night.toml"),
+            (r#":save "say \"hi\" back""#, r#"say "hi" back"#),
+            (r#":save "back\\slash""#, r"back\slash"),
+            (r":save 'no \escapes'", r"no \escapes"),
+            (r#":save half" way""#, "half way"),
+            (":save ''", ""),
+        ];
+        for (line, slot) in cases {
+            assert_eq!(
+                parsed(line),
+                Ok(Command::Save(Some(slot.to_string()))),
+                "{:?}",
+                line
+            );
+        }
+        assert_eq!(
+            parsed(r#":stats "Ada Lovelace""#),
+            Ok(Command::Stats(Some("Ada Lovelace".to_string())))
+        );
+        // Unquoted, the same name is two arguments
+        assert_eq!(parsed(":save my game"), Err(usage("save")));
+    }
+
+    #[test]
+    fn unterminated_quotes_are_reported() {
+        for line in [r#":save "my game"#, ":save 'my game", r#":save "trailing\"#] {
+            assert_eq!(
+                parsed(line),
+                Err(CommandError::UnterminatedQuote),
+                "{:?}",
+                line
+            );
+        }
+        assert_eq!(
+            CommandError::UnterminatedQuote.to_string(),
+            "unterminated quote"
+        );
+    }
+
+    #[test]
+    fn malformed_arguments_get_the_commands_usage() {
+        let cases = [
+            (":undo 0", "undo"),
+            (":undo x", "undo"),
+            (":undo -1", "undo"),
+            (":undo 1 2", "undo"),
+            (":hint now", "hint"),
+            (":resign please", "resign"),
+            (":save a b", "save"),
+            (":help undo save", "help"),
+            (":score forever", "score"),
+            (":describe row 2", "describe"),
+            (":describe col", "describe"),
+            (":describe col zz", "describe"),
+            (":themes ascii", "theme"),
+            (":theme ascii emoji", "theme"),
+        ];
+        for (line, name) in cases {
+            assert_eq!(parsed(line), Err(usage(name)), "{:?}", line);
+        }
+        assert_eq!(
+            parsed(":undo x").unwrap_err().to_string(),
+            "usage: :undo [N]"
+        );
+        assert_eq!(parsed(":hint now").unwrap_err().to_string(), "usage: :hint");
+    }
+
+    #[test]
+    fn unknown_commands_point_to_help_or_the_nearest_name() {
+        assert_eq!(
+            parsed(":frobnicate"),
+            Err(CommandError::Unknown("frobnicate".to_string()))
+        );
+        assert_eq!(parsed(":"), Err(CommandError::Unknown(String::new())));
+        assert_eq!(
+            parsed(":frobnicate").unwrap_err().to_string(),
+            "unknown command, try :help"
+        );
+        assert_eq!(
+            parsed(":udno").unwrap_err().to_string(),
+            "unknown command, did you mean :undo?"
+        );
+        // A typo without its colon is taken for a command rather than a column
+        assert_eq!(
+            parsed("hnit"),
+            Err(CommandError::Unknown("hnit".to_string()))
+        );
+    }
+
+    #[test]
+    fn anything_else_is_not_understood() {
+        for line in ["", "zz", "4x", "col"] {
+            assert_eq!(
+                parsed(line),
+                Err(CommandError::NotUnderstood {
+                    text: line.to_string(),
+                    width: 7
+                }),
+                "{:?}",
+                line
+            );
+        }
+        assert_eq!(
+            parsed("zz").unwrap_err().to_string(),
+            "I didn't understand 'zz'; type a column number 1-7 or :help"
+        );
+    }
+
+    #[test]
+    fn pegs_in_3d_games() {
+        let peg = cube::parse_peg("b3").expect("b3 is a peg");
+        assert_eq!(parse_3d("b3"), Ok(Command::Move(peg)));
+        assert_eq!(parse_3d("b3?"), Ok(Command::Preview(peg)));
+        assert_eq!(parse_3d(":undo 2"), Ok(Command::Undo(2)));
+        assert_eq!(parse_3d("e9"), Err(CommandError::NotAPeg("e9".to_string())));
+        assert_eq!(parse_3d("4?"), Err(CommandError::NotAPeg("4".to_string())));
+    }
+
+    #[test]
+    fn commands_are_refused_where_they_make_no_sense() {
+        assert_eq!(Command::Move(3).check(Context::Turn), Ok(()));
+        assert_eq!(Command::Hint.check(Context::Turn), Ok(()));
+        assert_eq!(
+            Command::Move(3).check(Context::GameOver),
+            Err("the game is over; press R to play again".to_string())
+        );
+        assert_eq!(
+            Command::Hint.check(Context::GameOver),
+            Err("the game is over; :hint only works during a game".to_string())
+        );
+        for command in [Command::Save(None), Command::Undo(1), Command::Theme(None)] {
+            assert_eq!(command.check(Context::GameOver), Ok(()), "{:?}", command);
+        }
+    }
+
+    #[test]
+    fn help_lists_every_command() {
+        let during = help(Context::Turn);
+        let after = help(Context::GameOver);
+        for spec in &COMMANDS {
+            assert!(during.contains(&spec.usage()), "{}", spec.name);
+        }
+        assert!(!during.contains("(not now)"));
+        let hint = after
+            .lines()
+            .find(|line| line.contains(":hint"))
+            .expect("hint is listed");
+        assert!(hint.ends_with("(not now)"));
+        assert!(
+            help_for(spec(":undo").expect("undo is registered"), Context::Turn)
+                .starts_with(":undo [N]\n")
+        );
+    }
+
+    #[test]
+    fn suggestions_are_only_for_near_misses() {
+        assert_eq!(suggestion("udno"), Some("undo"));
+        assert_eq!(suggestion("thme"), Some("theme"));
+        assert_eq!(suggestion("xyzzy"), None);
+        assert_eq!(suggestion("h"), None);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn long_text_is_split_into_pages() {
+        assert_eq!(pages("a\nb\nc", 2), ["a\nb", "c"]);
+        assert_eq!(pages("a\nb", 0), ["a", "b"]);
+    }
+
+    #[test]
+    fn column_zero_is_off_the_board() {
+        for line in ["0", "00", "col 0", "c0", "0.", "p0", "a0", "b0", "０"] {
+            let text = tidy(line);
+            assert_eq!(
+                parsed(line),
+                Err(CommandError::OffBoard { text, width: 7 }),
+                "{:?}",
+                line
+            );
+        }
+        assert_eq!(
+            parsed("0?"),
+            Err(CommandError::OffBoard {
+                text: "0".to_string(),
+                width: 7
+            })
+        );
+        assert_eq!(
+            parsed("0").unwrap_err().to_string(),
+            "'0' is not a column on this 7-wide board"
+        );
+    }
+
+    // Every kind of input at the move prompt, on the classic board and a wide one
+    #[test]
+    fn move_input_table() {
+        let off = |text: &str, width| {
+            Err(CommandError::OffBoard {
+                text: text.to_string(),
+                width,
+            })
+        };
+        let unclear = |text: &str, width| {
+            Err(CommandError::NotUnderstood {
+                text: text.to_string(),
+                width,
+            })
+        };
+        let cases = [
+            ("1", 7, Ok(Command::Move(0))),
+            ("7", 7, Ok(Command::Move(6))),
+            ("8", 7, off("8", 7)),
+            ("0", 7, off("0", 7)),
+            ("007", 7, Ok(Command::Move(6))),
+            ("a", 7, Ok(Command::Move(0))),
+            ("G", 7, Ok(Command::Move(6))),
+            ("h", 7, off("h", 7)),
+            ("z", 7, off("z", 7)),
+            ("4 ", 7, Ok(Command::Move(3))),
+            ("\t4\n", 7, Ok(Command::Move(3))),
+            ("4\n", 7, Ok(Command::Move(3))),
+            ("1\n2", 12, Ok(Command::Move(11))),
+            ("col 4", 7, Ok(Command::Move(3))),
+            ("column 4", 7, Ok(Command::Move(3))),
+            ("COL4", 7, Ok(Command::Move(3))),
+            ("c4", 7, Ok(Command::Move(3))),
+            ("c", 7, Ok(Command::Move(2))),
+            ("4.", 7, Ok(Command::Move(3))),
+            ("4..", 7, Ok(Command::Move(3))),
+            ("４", 7, Ok(Command::Move(3))),
+            ("１２", 12, Ok(Command::Move(11))),
+            ("10", 12, Ok(Command::Move(9))),
+            ("12", 12, Ok(Command::Move(11))),
+            ("13", 12, off("13", 12)),
+            ("l", 12, Ok(Command::Move(11))),
+            (
+                "99999999999999999999999",
+                7,
+                unclear("99999999999999999999999", 7),
+            ),
+            ("-1", 7, unclear("-1", 7)),
+            ("+4", 7, Ok(Command::Move(3))),
+            ("4x", 7, unclear("4x", 7)),
+            ("xyz", 7, unclear("xyz", 7)),
+            ("col", 7, unclear("col", 7)),
+            ("4?", 7, Ok(Command::Preview(3))),
+            ("10?", 12, Ok(Command::Preview(9))),
+            ("p10", 12, Ok(Command::Special(Move::Pop(9)))),
+            ("hint", 7, Ok(Command::Hint)),
+            (":hint", 7, Ok(Command::Hint)),
+            ("hnit", 7, Err(CommandError::Unknown("hnit".to_string()))),
+            (":xyz", 7, Err(CommandError::Unknown("xyz".to_string()))),
+        ];
+        for (line, width, expected) in cases {
+            let result = parse(line, width);
+            assert_eq!(result, expected, "{:?} on a board {} wide", line, width);
+            // The messages never pass on a parser's own wording
+            if let Err(err) = result {
+                let message = err.to_string();
+                assert!(
+                    !message.contains("invalid digit"),
+                    "{:?}: {}",
+                    line,
+                    message
+                );
+                assert!(!message.contains("too large"), "{:?}: {}", line, message);
+            }
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::BTreeMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::export::json_string;
+
+// Longest comment, in characters
+pub const MAX_LENGTH: usize = 500;
+
+// Notes on a game's moves, by the number of the move they're on, from 1
+pub type Comments = BTreeMap<usize, String>;
+
+// A comment as typed, with surrounding space trimmed and control characters turned into
+// spaces so they can't upset the terminal. None means no text, which removes the comment.
+pub fn clean(text: &str) -> Result<Option<String>, String> {
+    let text: String = text
+        .trim()
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    match text.chars().count() {
+        0 => Ok(None),
+        length if length > MAX_LENGTH => Err(format!(
+            "comments can be up to {} characters; that one has {}",
+            MAX_LENGTH, length
+        )),
+        _ => Ok(Some(text)),
+    }
+}
+
+// Set or, with no text, remove the comment on move `ply`. Returns what was done, for the
+// player.
+pub fn set(comments: &mut Comments, ply: usize, text: &str) -> Result<String, String> {
+    if ply == 0 {
+        return Err("no move has been played yet to comment on".to_string());
+    }
+    Ok(match clean(text)? {
+        Some(text) => {
+            let verb = if comments.insert(ply, text).is_some() {
+                "Changed"
+            } else {
+                "Added"
+            };
+            format!("{} the comment on move {}", verb, ply)
+        }
+        None => match comments.remove(&ply) {
+            Some(_) => format!("Removed the comment on move {}", ply),
+            None => format!("Move {} has no comment to remove", ply),
+        },
+    })
+}
+
+// `text` broken at spaces into lines of at most `width` terminal columns. A word too long
+// for a line is broken where it has to be, between graphemes so no character is split.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let gap = usize::from(!line.is_empty());
+        if line.width() + gap + word.width() <= width {
+            if gap == 1 {
+                line.push(' ');
+            }
+            line.push_str(word);
+            continue;
+        }
+        if !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+        }
+        for grapheme in word.graphemes(true) {
+            if !line.is_empty() && line.width() + grapheme.width() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            line.push_str(grapheme);
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+// Comments as a save stores them: a table from move numbers to text
+pub fn to_toml(comments: &Comments) -> toml::Value {
+    toml::Value::Table(
+        comments
+            .iter()
+            .map(|(ply, text)| (ply.to_string(), toml::Value::String(text.clone())))
+            .collect(),
+    )
+}
+
+// Comments read back from a save, for a game of `moves` moves
+pub fn from_toml(value: &toml::Value, moves: usize) -> Result<Comments, String> {
+    let table = value
+        .as_table()
+        .ok_or("'comments' must be a table of move numbers")?;
+    table
+        .iter()
+        .map(|(ply, text)| {
+            let ply = ply
+                .parse::<usize>()
+                .ok()
+                .filter(|ply| (1..=moves).contains(ply))
+                .ok_or_else(|| format!("comment on '{}', which isn't a move of the game", ply))?;
+            let text = text
+                .as_str()
+                .ok_or_else(|| format!("the comment on move {} must be text", ply))?;
+            let text =
+                clean(text)?.ok_or_else(|| format!("the comment on move {} is empty", ply))?;
+            Ok((ply, text))
+        })
+        .collect()
+}
+
+// The comment on move `ply` as a JSON value, null without one
+pub fn json(comments: &Comments, ply: usize) -> String {
+    comments
+        .get(&ply)
+        .map_or("null".to_string(), |text| json_string(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_comments_are_trimmed_and_kept_plain() {
+        assert_eq!(
+            clean("  a good move \n"),
+            Ok(Some("a good move".to_string()))
+        );
+        assert_eq!(
+            clean("threat\tin\x1b[31m red"),
+            Ok(Some("threat in [31m red".to_string()))
+        );
+        assert_eq!(clean(""), Ok(None));
+        assert_eq!(clean(" \t\n"), Ok(None));
+        let longest = "é".repeat(MAX_LENGTH);
+        assert_eq!(clean(&longest), Ok(Some(longest.clone())));
+        assert_eq!(
+            clean(&format!("{}!", longest)),
+            Err("comments can be up to 500 characters; that one has 501".to_string())
+        );
+    }
+
+    #[test]
+    fn comments_are_added_changed_and_removed_by_move() {
+        let mut comments = Comments::new();
+        assert_eq!(
+            set(&mut comments, 0, "too soon"),
+            Err("no move has been played yet to comment on".to_string())
+        );
+        assert_eq!(
+            set(&mut comments, 3, " blunder "),
+            Ok("Added the comment on move 3".to_string())
+        );
+        assert_eq!(
+            set(&mut comments, 3, "only a mistake"),
+            Ok("Changed the comment on move 3".to_string())
+        );
+        assert_eq!(comments.get(&3).map(String::as_str), Some("only a mistake"));
+        assert_eq!(
+            set(&mut comments, 3, "  "),
+            Ok("Removed the comment on move 3".to_string())
+        );
+        assert_eq!(
+            set(&mut comments, 3, ""),
+            Ok("Move 3 has no comment to remove".to_string())
+        );
+        assert!(comments.is_empty());
+        assert!(set(&mut comments, 1, &"x".repeat(MAX_LENGTH + 1)).is_err());
+        assert!(comments.is_empty(), "a comment too long isn't kept");
+    }
+
+    #[test]
+    fn comments_read_back_only_for_moves_of_the_game() {
+        let mut comments = Comments::new();
+        comments.insert(1, "opening".to_string());
+        comments.insert(7, "the win".to_string());
+        assert_eq!(from_toml(&to_toml(&comments), 7), Ok(comments.clone()));
+
+        let table = |text: &str| {
+            text.parse::<toml::Table>()
+                .map(toml::Value::Table)
+                .expect("the TOML parses")
+        };
+        let rejected = [
+            (
+                "8 = \"late\"",
+                "comment on '8', which isn't a move of the game",
+            ),
+            (
+                "0 = \"early\"",
+                "comment on '0', which isn't a move of the game",
+            ),
+            (
+                "first = \"text\"",
+                "comment on 'first', which isn't a move of the game",
+            ),
+            ("2 = 5", "the comment on move 2 must be text"),
+            ("2 = \"  \"", "the comment on move 2 is empty"),
+        ];
+        for (text, err) in rejected {
+            assert_eq!(from_toml(&table(text), 7), Err(err.to_string()), "{}", text);
+        }
+        assert_eq!(
+            from_toml(&toml::Value::Integer(1), 7),
+            Err("'comments' must be a table of move numbers".to_string())
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+use crate::variant::{Move, Specials};
+use crate::MoveError;
+
+// A board square in chess-like notation: columns are letters from the left (a, b, ...),
+// rows are numbers from the bottom (1, 2, ...)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Coord {
+    pub column: usize,
+    // Counted from the bottom, starting at 0
+    pub row: usize,
+}
+
+impl Coord {
+    // Letter naming a column
+    pub fn column_letter(column: usize) -> char {
+        (b'a' + column as u8) as char
+    }
+
+    // Parse a column given as a letter ("d") or a 1-based number ("4") into a 0-based index;
+    // range checking against the board is left to the caller
+    pub fn parse_column(text: &str) -> Option<usize> {
+        let text = text.trim();
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(letter), None) if letter.is_ascii_alphabetic() => {
+                Some((letter.to_ascii_lowercase() as u8 - b'a') as usize)
+            }
+            _ => text.parse::<usize>().ok()?.checked_sub(1),
+        }
+    }
+}
+
+// A column checked against the width of the board it's for when it's made, so nothing
+// taking one needs to check it again
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Column(usize);
+
+impl Column {
+    // The 0-based `column` of a board `width` columns wide, if it has one
+    pub fn new(column: usize, width: usize) -> Result<Column, MoveError> {
+        if column < width {
+            Ok(Column(column))
+        } else {
+            Err(MoveError::InvalidColumn {
+                attempted: column,
+                width,
+            })
+        }
+    }
+
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", Coord::column_letter(self.column), self.row + 1)
+    }
+}
+
+// Move list in numbered pairs, e.g. "1. d1 d2 2. c1", with the move numbers in `specials`
+// shown as the letter of what they did and the column they did it to, e.g. "pd" for a pop
+pub fn format_history(history: &[Coord], specials: &Specials) -> String {
+    let moves: Vec<String> = history
+        .iter()
+        .enumerate()
+        .map(
+            |(index, coord)| match specials.at(index + 1, coord.column) {
+                Move::Drop(_) => coord.to_string(),
+                mv => mv.notation(|column| Coord::column_letter(column).to_string()),
+            },
+        )
+        .collect();
+    moves
+        .chunks(2)
+        .enumerate()
+        .map(|(turn, moves)| format!("{}. {}", turn + 1, moves.join(" ")))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
@@ -0,0 +1,355 @@
+use std::ops::Index;
+use std::sync::OnceLock;
+
+use crate::board::{Board, Cell};
+use crate::i18n;
+use crate::render::{Emphasis, Layout, Renderer};
+use crate::style::DIM;
+use crate::Game;
+
+// Pegs along each side of the 3D board, and levels up each peg
+pub const SIDE: usize = 4;
+
+// The flat board a 3D game is kept on: a column for every peg, row by row of pegs, and a row
+// for every level
+pub const COLUMNS: usize = SIDE * SIDE;
+
+// Space between the levels when they're drawn side by side
+const GAP: &str = "  ";
+
+// A cell of the cube as (x, y, level), each counted from 0: x is the peg's letter, y its
+// number, and the level is counted from the bottom
+pub type Point = (usize, usize, usize);
+
+// The cube seen through the flat board it's kept on: peg (x, y) is column y * SIDE + x, and
+// its levels are the rows from the bottom up
+pub struct Board3<'a> {
+    board: &'a Board,
+}
+
+impl Index<Point> for Board3<'_> {
+    type Output = Cell;
+
+    fn index(&self, (x, y, level): Point) -> &Cell {
+        &self.board[SIDE - 1 - level][peg(x, y)]
+    }
+}
+
+// Column of the flat board that peg (x, y) is kept in
+pub fn peg(x: usize, y: usize) -> usize {
+    y * SIDE + x
+}
+
+// Where a cell of the flat board is in the cube
+fn point(row: usize, column: usize) -> Point {
+    (column % SIDE, column / SIDE, SIDE - 1 - row)
+}
+
+// Row and column of the flat board a cell of the cube is kept at
+fn cell(&(x, y, level): &Point) -> (usize, usize) {
+    (SIDE - 1 - level, peg(x, y))
+}
+
+// The peg kept in `column` as it's typed, its letter and number, like "b3"
+pub fn peg_name(column: usize) -> String {
+    format!(
+        "{}{}",
+        (b'a' + (column % SIDE) as u8) as char,
+        column / SIDE + 1
+    )
+}
+
+// The column of a peg typed as its letter and number, like "b3"
+pub fn parse_peg(text: &str) -> Option<usize> {
+    let mut chars = text.trim().chars();
+    let x = chars.next()?.to_ascii_lowercase();
+    let y = chars.as_str().parse::<usize>().ok()?;
+    let x = (x as usize).checked_sub('a' as usize)?;
+    (x < SIDE && (1..=SIDE).contains(&y)).then(|| peg(x, y - 1))
+}
+
+// A cell of the flat board named as a peg and a level, like "b3 level 2"
+pub fn cell_name(row: usize, column: usize) -> String {
+    let (_, _, level) = point(row, column);
+    i18n::fill("describe.cell_level", &[("peg", &peg_name(column)), ("level", &(level + 1))])
+}
+
+// Every line of four in the cube, 76 of them: along each of the three axes, across the
+// diagonals of every face and slice, and through the four space diagonals corner to corner
+pub fn lines() -> &'static [[Point; SIDE]] {
+    static LINES: OnceLock<Vec<[Point; SIDE]>> = OnceLock::new();
+    LINES.get_or_init(|| {
+        let steps = [-1isize, 0, 1];
+        // Each direction once, the way whose first step that moves at all goes up
+        let directions: Vec<(isize, isize, isize)> = steps
+            .iter()
+            .flat_map(|&dx| {
+                steps
+                    .iter()
+                    .flat_map(move |&dy| steps.map(|dl| (dx, dy, dl)))
+            })
+            .filter(|&(dx, dy, dl)| [dx, dy, dl].into_iter().find(|&step| step != 0) == Some(1))
+            .collect();
+        let side = SIDE as isize;
+        let mut lines = Vec::new();
+        for x in 0..side {
+            for y in 0..side {
+                for level in 0..side {
+                    for &(dx, dy, dl) in &directions {
+                        let end = |start: isize, step: isize| start + step * (side - 1);
+                        if ![end(x, dx), end(y, dy), end(level, dl)]
+                            .iter()
+                            .all(|end| (0..side).contains(end))
+                        {
+                            continue;
+                        }
+                        let mut line = [(0, 0, 0); SIDE];
+                        for (i, point) in line.iter_mut().enumerate() {
+                            let i = i as isize;
+                            *point = (
+                                (x + i * dx) as usize,
+                                (y + i * dy) as usize,
+                                (level + i * dl) as usize,
+                            );
+                        }
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+        lines
+    })
+}
+
+impl Game {
+    // The board as the cube it stands for, in a 3D game
+    pub fn cube(&self) -> Board3<'_> {
+        Board3 { board: &self.board }
+    }
+
+    // Cells of a line of four through the last move, as (row, column) of the flat board, in
+    // a 3D game
+    pub fn cube_line(&self) -> Option<Vec<(usize, usize)>> {
+        let last = self.last_move()?;
+        let played = point(self.rules.height - 1 - last.row, last.column);
+        let cube = self.cube();
+        let piece = cube[played];
+        lines()
+            .iter()
+            .find(|line| line.contains(&played) && line.iter().all(|&point| cube[point] == piece))
+            .map(|line| line.iter().map(cell).collect())
+    }
+
+    // The cells of every line of four in the cube, for the engine to weigh
+    pub fn cube_windows(&self) -> Vec<Vec<Cell>> {
+        let cube = self.cube();
+        lines()
+            .iter()
+            .map(|line| line.iter().map(|&point| cube[point]).collect())
+            .collect()
+    }
+
+    // The four levels side by side, bottom level first, each with its pegs lettered across
+    // and numbered up, the last move and any winning line picked out, and the piece to
+    // come where it would land on `selected`
+    pub fn render_levels(&self, renderer: &Renderer, selected: Option<usize>) -> Vec<String> {
+        let renderer = &level_renderer(renderer);
+        let layout = renderer.layout;
+        let width = layout.width(SIDE);
+        let last = self
+            .last_move()
+            .map(|coord| (self.rules.height - 1 - coord.row, coord.column));
+        let ghost = selected
+            .filter(|&column| !self.is_finished() && column < self.rules.width)
+            .filter(|&column| self.column_height(column) < self.rules.height)
+            .map(|column| (self.rules.height - 1 - self.column_height(column), column));
+        let cube = self.cube();
+
+        let level = |level: usize| {
+            let mut lines = vec![renderer.accent(&format!(
+                "{:<width$}",
+                format!("Level {}", level + 1),
+                width = width
+            ))];
+            lines.extend(layout.edge(SIDE));
+            for y in (0..SIDE).rev() {
+                let cells: Vec<Cell> = (0..SIDE).map(|x| cube[(x, y, level)]).collect();
+                let emphasis: Vec<Emphasis> = (0..SIDE)
+                    .map(|x| {
+                        let at = cell(&(x, y, level));
+                        let winning = self.winning_line().is_some_and(|line| line.contains(&at));
+                        match (winning, last == Some(at), ghost == Some(at)) {
+                            (true, _, _) => Emphasis::Winning,
+                            (false, true, _) => Emphasis::LastMove,
+                            (false, false, true) => Emphasis::Ghost(self.current_player),
+                            _ => Emphasis::None,
+                        }
+                    })
+                    .collect();
+                lines.push(renderer.row(&cells, y + 1, &emphasis));
+            }
+            lines.extend(layout.edge(SIDE));
+            let letters: Vec<String> = (0..SIDE)
+                .map(|x| ((b'a' + x as u8) as char).to_string())
+                .collect();
+            let ruler = layout.ruler(&letters, |_, label| label.to_string());
+            lines.push(
+                renderer
+                    .style
+                    .paint(DIM, &format!("{:<width$}", ruler, width = width)),
+            );
+            lines
+        };
+        let levels: Vec<Vec<String>> = (0..SIDE).map(level).collect();
+        (0..levels[0].len())
+            .map(|line| {
+                levels
+                    .iter()
+                    .map(|level| level[line].as_str())
+                    .collect::<Vec<&str>>()
+                    .join(GAP)
+            })
+            .collect()
+    }
+}
+
+// Width of the levels drawn side by side
+pub fn levels_width(renderer: &Renderer) -> usize {
+    let width = level_renderer(renderer).layout.width(SIDE);
+    width * SIDE + GAP.len() * (SIDE - 1)
+}
+
+// The renderer a level is drawn with: normal-sized cells, with the pegs' numbers beside
+// each level
+fn level_renderer(renderer: &Renderer) -> Renderer {
+    let mut level = renderer.clone();
+    level.layout = Layout {
+        cell_width: renderer.layout.cell_width / renderer.layout.scale.max(1),
+        scale: 1,
+        margin: 2,
+        ..renderer.layout
+    };
+    level.coords = true;
+    level
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::variant::{Move, Variant};
+    use crate::Player;
+
+    // Pegs to drop into when nothing under the line needs filling, in an order that keeps
+    // the pieces there apart
+    const SPARE: [usize; COLUMNS] = [0, 10, 7, 13, 5, 15, 2, 8, 12, 1, 11, 6, 3, 9, 14, 4];
+
+    // Whether dropping into `peg` is a move that doesn't end the game
+    fn quiet(game: &Game, peg: usize) -> bool {
+        let mut next = game.clone();
+        next.play(Move::Drop(peg)).is_ok() && !next.is_finished()
+    }
+
+    // A 3D game where the first player fills `line`, either player filling in under it or
+    // playing out of the way in between, ending with the move that completes the line
+    fn won_on(line: &[Point; SIDE]) -> Game {
+        let mut game = Game::builder(&Settings::default())
+            .variant(Variant::Cube)
+            .build()
+            .expect("the 3D rules are valid");
+        let mut left: Vec<(usize, usize)> = line
+            .iter()
+            .map(|&(x, y, level)| (peg(x, y), level))
+            .collect();
+        // Pegs where the next piece goes under a point of the line rather than onto one, then
+        // the spare ones, leaving out any that would end the game
+        let fillers = |game: &Game, left: &[(usize, usize)]| -> Vec<usize> {
+            let pegs: Vec<usize> = left.iter().map(|&(peg, _)| peg).collect();
+            let under = pegs.iter().copied().filter(|&peg| {
+                let height = game.column_height(peg);
+                left.iter()
+                    .any(|&(target, level)| target == peg && level > height)
+                    && !left.contains(&(peg, height))
+            });
+            let spare = SPARE.iter().copied().filter(|peg| !pegs.contains(peg));
+            under.chain(spare).filter(|&peg| quiet(game, peg)).collect()
+        };
+        while !left.is_empty() {
+            assert!(!game.is_finished(), "the game ended early on {:?}", line);
+            let playable = left.iter().position(|&(peg, level)| {
+                game.column_height(peg) == level && (left.len() == 1 || quiet(&game, peg))
+            });
+            let peg = match playable {
+                Some(index) if game.current_player == Player::One => left.remove(index).0,
+                _ => *fillers(&game, &left)
+                    .first()
+                    .expect("there is a peg to play"),
+            };
+            game.play(Move::Drop(peg)).expect("the peg has room");
+        }
+        game
+    }
+
+    // Along an axis, across a face or slice, or corner to corner through the cube
+    fn class(line: &[Point; SIDE]) -> usize {
+        let (a, b) = (line[0], line[1]);
+        [a.0 != b.0, a.1 != b.1, a.2 != b.2]
+            .iter()
+            .filter(|&&moves| moves)
+            .count()
+    }
+
+    #[test]
+    fn there_are_76_lines() {
+        let lines = lines();
+        let count = |moving: usize| lines.iter().filter(|line| class(line) == moving).count();
+        assert_eq!(lines.len(), 76);
+        assert_eq!((count(1), count(2), count(3)), (48, 24, 4));
+    }
+
+    #[test]
+    fn the_space_diagonals_run_corner_to_corner() {
+        let mut ends: Vec<(Point, Point)> = lines()
+            .iter()
+            .filter(|line| class(line) == 3)
+            .map(|line| (line[0], line[SIDE - 1]))
+            .collect();
+        ends.sort();
+        assert_eq!(
+            ends,
+            [
+                ((0, 0, 0), (3, 3, 3)),
+                ((0, 0, 3), (3, 3, 0)),
+                ((0, 3, 0), (3, 0, 3)),
+                ((0, 3, 3), (3, 0, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_line_wins() {
+        for line in lines() {
+            let game = won_on(line);
+            assert_eq!(game.winner(), Some(Player::One), "{:?}", line);
+            let mut won: Vec<(usize, usize)> = game
+                .winning_line()
+                .expect("the game was won on the board")
+                .to_vec();
+            let mut expected: Vec<(usize, usize)> = line.iter().map(cell).collect();
+            won.sort();
+            expected.sort();
+            assert_eq!(won, expected, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn pegs_are_named_by_letter_and_number() {
+        assert_eq!(peg_name(peg(1, 2)), "b3");
+        assert_eq!(parse_peg("B3"), Some(peg(1, 2)));
+        assert_eq!(parse_peg("d4"), Some(COLUMNS - 1));
+        for text in ["e1", "a5", "a0", "b", "3b", ""] {
+            assert_eq!(parse_peg(text), None, "{:?}", text);
+        }
+        assert_eq!(cell_name(SIDE - 1, peg(0, 0)), "a1 level 1");
+    }
+}
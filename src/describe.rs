@@ -0,0 +1,454 @@
+use crate::ai;
+use crate::cube;
+use crate::i18n;
+use crate::outcome::{DrawKind, WinKind};
+use crate::render::{self, Renderer};
+use crate::variant::{Move, Variant};
+use crate::Game;
+
+// The board in words, for playing by screen reader or braille display: plain text with no
+// colors or glyphs, one short sentence to a line
+
+impl Game {
+    // What the last move did and how the game stands, shown after every move instead of the
+    // board. A blind game's hidden board stays out of it, as it does out of the drawing.
+    pub fn describe_move(&self, renderer: &Renderer) -> Vec<String> {
+        let hidden = self.hidden(renderer);
+        let ply = self.history.len();
+        let mut lines = Vec::new();
+        if ply == 0 {
+            lines.push(i18n::text("describe.new_game"));
+        } else {
+            lines.push(i18n::fill("describe.move", &[("number", &ply)]));
+            lines.push(self.describe_ply(ply, hidden));
+        }
+        if hidden {
+            lines.push(i18n::text("describe.hidden"));
+        } else {
+            lines.extend(self.heights());
+        }
+
+        if self.is_finished() {
+            lines.extend(self.describe_result(renderer));
+            return lines;
+        }
+        if !hidden && !self.placing() {
+            // Whoever moved last is named first, as the one whose threat is new. In misère a
+            // line loses, so the moves that would make one are the ones to stay away from.
+            let mover = self.previous_player(self.current_player);
+            let mut seats = vec![mover];
+            seats.extend(self.seats().into_iter().filter(|&seat| seat != mover));
+            for seat in seats {
+                let mut wins = if self.rules.variant.misere() {
+                    ai::losing_moves(self, seat)
+                } else {
+                    ai::winning_moves(self, seat)
+                };
+                if wins.is_empty() {
+                    continue;
+                }
+                wins.sort_by_key(|mv| mv.column());
+                let wins: Vec<String> = wins.into_iter().map(|mv| self.threat_name(mv)).collect();
+                let key = if self.rules.variant.misere() {
+                    "describe.must_avoid"
+                } else if seat == self.current_player {
+                    "describe.can_win"
+                } else {
+                    "describe.threatens"
+                };
+                let or = format!(" {} ", i18n::text("describe.or"));
+                lines.push(i18n::fill(
+                    key,
+                    &[("name", &self.name(seat)), ("moves", &wins.join(&or))],
+                ));
+            }
+        }
+        lines.push(i18n::fill(
+            "describe.to_move",
+            &[("name", &self.name(self.current_player))],
+        ));
+        lines
+    }
+
+    // Move number `ply` as a sentence, like "Alice played column 4, landing on row 3", without
+    // where it landed when the board is hidden
+    fn describe_ply(&self, ply: usize, hidden: bool) -> String {
+        let coord = self.history[ply - 1];
+        let name = self.name(self.seat_of(ply));
+        let (key, column) = match self.specials.at(ply, coord.column) {
+            Move::Drop(column) if hidden => ("describe.played", self.column_name(column)),
+            Move::Drop(column) if self.rules.variant == Variant::Cube => {
+                ("describe.landed_level", self.column_name(column))
+            }
+            Move::Drop(column) => ("describe.landed", self.column_name(column)),
+            Move::Pop(column) => ("describe.popped", (column + 1).to_string()),
+            Move::Anvil(column) => ("describe.anvil", (column + 1).to_string()),
+            Move::Bomb(column) => ("describe.bomb", (column + 1).to_string()),
+            Move::Flip => ("describe.flipped", String::new()),
+        };
+        i18n::fill(
+            key,
+            &[
+                ("name", &name),
+                ("column", &column),
+                ("row", &(coord.row + 1)),
+            ],
+        )
+    }
+
+    // How many pieces each column holds, in one line, or a line for each row of pegs in 3D
+    fn heights(&self) -> Vec<String> {
+        let heights: Vec<String> = (0..self.rules.width)
+            .map(|column| self.column_height(column).to_string())
+            .collect();
+        if self.rules.variant != Variant::Cube {
+            return vec![i18n::fill(
+                "describe.heights",
+                &[("heights", &heights.join(" "))],
+            )];
+        }
+        heights
+            .chunks(cube::SIDE)
+            .enumerate()
+            .map(|(y, row)| {
+                let first = cube::peg_name(cube::peg(0, y));
+                let last = cube::peg_name(cube::peg(cube::SIDE - 1, y));
+                i18n::fill(
+                    "describe.pegs",
+                    &[
+                        ("first", &first),
+                        ("last", &last),
+                        ("heights", &row.join(" ")),
+                    ],
+                )
+            })
+            .collect()
+    }
+
+    // A winning move as the threat names it, like "at column 6" or "with a pop from column 2"
+    fn threat_name(&self, mv: Move) -> String {
+        let (key, column) = match mv {
+            Move::Drop(column) => ("describe.threat.drop", self.column_name(column)),
+            Move::Pop(column) => ("describe.threat.pop", (column + 1).to_string()),
+            Move::Anvil(column) => ("describe.threat.anvil", (column + 1).to_string()),
+            Move::Bomb(column) => ("describe.threat.bomb", (column + 1).to_string()),
+            Move::Flip => ("describe.threat.flip", String::new()),
+        };
+        i18n::fill(key, &[("column", &column)])
+    }
+
+    // Who won and how, and where the line is from one end to the other
+    fn describe_result(&self, renderer: &Renderer) -> Vec<String> {
+        let (key, player) = match (self.winner(), self.connected_loser()) {
+            (None, _) if self.drawn_by() == Some(DrawKind::Agreement) => {
+                ("describe.agreement", None)
+            }
+            (None, _) => ("describe.draw", None),
+            (_, Some(loser)) => ("describe.lost", Some(loser)),
+            (Some(winner), None) => match self.won_by() {
+                Some(WinKind::Time) => ("describe.won_on_time", Some(winner)),
+                Some(WinKind::Resignation) => ("describe.won_by_resignation", Some(winner)),
+                Some(WinKind::Lines) => ("describe.won_on_lines", Some(winner)),
+                _ => ("describe.won", Some(winner)),
+            },
+        };
+        let name = player.map_or("", |player| self.name(player));
+        let mut lines = vec![i18n::fill(
+            key,
+            &[
+                ("name", &name),
+                ("count", &render::count(self.rules.connect)),
+            ],
+        )];
+        if let Some(line) = self.winning_line() {
+            let key = if self.rules.variant.misere() {
+                "describe.losing_line"
+            } else {
+                "describe.winning_line"
+            };
+            let (first, last) = (line[0], line[line.len() - 1]);
+            lines.push(i18n::fill(
+                key,
+                &[
+                    ("first", &self.cell_name(renderer, first.0, first.1)),
+                    ("last", &self.cell_name(renderer, last.0, last.1)),
+                ],
+            ));
+        }
+        lines.push(i18n::text("describe.over"));
+        lines
+    }
+
+    // The whole board, a row at a time from the bottom, naming the columns each player has a
+    // piece in; a 3D game goes level by level and names the pegs
+    pub fn describe_board(&self) -> Vec<String> {
+        let cube = self.rules.variant == Variant::Cube;
+        let Some(top) = (0..self.rules.height)
+            .rev()
+            .find(|&row| (0..self.rules.width).any(|column| self.cell(row, column).is_some()))
+        else {
+            return vec![i18n::text("describe.empty")];
+        };
+
+        let mut lines = Vec::new();
+        for row in 0..=top {
+            let pieces: Vec<String> = self
+                .seats()
+                .into_iter()
+                .filter_map(|seat| {
+                    let columns: Vec<String> = (0..self.rules.width)
+                        .filter(|&column| self.cell(row, column) == Some(seat))
+                        .map(|column| {
+                            if cube {
+                                cube::peg_name(column)
+                            } else {
+                                (column + 1).to_string()
+                            }
+                        })
+                        .collect();
+                    (!columns.is_empty()).then(|| {
+                        i18n::fill(
+                            "describe.pieces",
+                            &[("name", &self.name(seat)), ("columns", &columns.join(" "))],
+                        )
+                    })
+                })
+                .collect();
+            let pieces = if pieces.is_empty() {
+                i18n::text("describe.row_empty")
+            } else {
+                pieces.join(", ")
+            };
+            let key = if cube {
+                "describe.level"
+            } else {
+                "describe.row"
+            };
+            lines.push(i18n::fill(key, &[("row", &(row + 1)), ("pieces", &pieces)]));
+        }
+        let key = match (self.rules.height - (top + 1), cube) {
+            (0, _) => return lines,
+            (1, false) => "describe.last_row_empty",
+            (1, true) => "describe.last_level_empty",
+            (_, false) => "describe.rows_empty",
+            (_, true) => "describe.levels_empty",
+        };
+        lines.push(i18n::fill(
+            key,
+            &[("first", &(top + 2)), ("last", &self.rules.height)],
+        ));
+        lines
+    }
+
+    // One column, or peg in 3D, from the bottom up, and how full it is
+    pub fn describe_column(&self, column: usize) -> Vec<String> {
+        let name = self.column_name(column);
+        let mut chars = name.chars();
+        let name: String = chars
+            .next()
+            .into_iter()
+            .flat_map(char::to_uppercase)
+            .chain(chars)
+            .collect();
+        let height = self.column_height(column);
+        let fill = match height {
+            0 => return vec![i18n::fill("describe.column_empty", &[("column", &name)])],
+            height if height == self.rules.height => i18n::text("describe.full"),
+            height => i18n::fill(
+                "describe.filled",
+                &[("count", &height), ("height", &self.rules.height)],
+            ),
+        };
+        let pieces: Vec<&str> = (0..height)
+            .filter_map(|row| self.cell(row, column))
+            .map(|player| self.name(player))
+            .collect();
+        vec![
+            i18n::fill("describe.column", &[("column", &name), ("fill", &fill)]),
+            i18n::fill("describe.from_bottom", &[("pieces", &pieces.join(", "))]),
+        ]
+    }
+
+    // A column as the descriptions name it, "column 4", or its peg in 3D, "peg b3"
+    fn column_name(&self, column: usize) -> String {
+        match self.rules.variant {
+            Variant::Cube => i18n::fill("describe.peg", &[("peg", &cube::peg_name(column))]),
+            _ => i18n::fill("describe.column_name", &[("column", &(column + 1))]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::style::Style;
+    use crate::theme::Theme;
+
+    // A game of `variant` on a `width` by `height` board with these moves played, columns
+    // counted from 1 and pops negative
+    fn played(variant: Variant, width: usize, height: usize, moves: &[i32]) -> Game {
+        let mut game = Game::builder(&Settings::default())
+            .variant(variant)
+            .size(width, height)
+            .build()
+            .expect("the rules are valid");
+        for &column in moves {
+            let mv = match column {
+                0 => Move::Flip,
+                column if column < 0 => Move::Pop((-column - 1) as usize),
+                column => Move::Drop((column - 1) as usize),
+            };
+            game.play(mv).expect("the moves are legal");
+        }
+        game
+    }
+
+    fn renderer() -> Renderer {
+        let style = Style {
+            color: false,
+            truecolor: false,
+        };
+        Renderer::new(
+            Theme::builtin("classic").expect("the theme is built in"),
+            style,
+            None,
+        )
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn each_kind_of_move_is_told_with_the_threats_it_leaves() {
+        let renderer = renderer();
+        let cases = [
+            (
+                played(Variant::Standard, 7, 6, &[1, 2, 1, 2, 1]),
+                lines(&[
+                    "Move 5.",
+                    "Player 1 played column 1, landing on row 3.",
+                    "Column heights: 3 2 0 0 0 0 0.",
+                    "Player 1 threatens a win at column 1.",
+                    "Player 2 to move.",
+                ]),
+            ),
+            (
+                played(Variant::PopOut, 7, 6, &[1, 2, -1]),
+                lines(&[
+                    "Move 3.",
+                    "Player 1 popped a piece out of column 1.",
+                    "Column heights: 0 1 0 0 0 0 0.",
+                    "Player 2 to move.",
+                ]),
+            ),
+            (
+                played(Variant::Flip, 7, 6, &[1, 2, 0]),
+                lines(&[
+                    "Move 3.",
+                    "Player 1 flipped the board.",
+                    "Column heights: 1 1 0 0 0 0 0.",
+                    "Player 2 to move.",
+                ]),
+            ),
+            (
+                played(Variant::Standard, 7, 6, &[]),
+                lines(&[
+                    "New game.",
+                    "Column heights: 0 0 0 0 0 0 0.",
+                    "Player 1 to move.",
+                ]),
+            ),
+        ];
+        for (game, expected) in cases {
+            assert_eq!(game.describe_move(&renderer), expected);
+        }
+    }
+
+    #[test]
+    fn in_misere_the_moves_that_make_a_line_are_to_be_avoided() {
+        let game = played(Variant::Misere, 7, 6, &[1, 2, 1, 2, 1, 2]);
+        assert_eq!(
+            game.describe_move(&renderer()),
+            lines(&[
+                "Move 6.",
+                "Player 2 played column 2, landing on row 3.",
+                "Column heights: 3 3 0 0 0 0 0.",
+                "Player 2 must not play at column 2.",
+                "Player 1 must not play at column 1.",
+                "Player 1 to move.",
+            ])
+        );
+    }
+
+    #[test]
+    fn a_finished_game_says_how_it_ended() {
+        let renderer = renderer();
+        // The board filled with no line of four: rows of two and two, turned over each row
+        let drawn = [1, 3, 2, 4, 3, 1, 4, 2, 1, 3, 2, 4, 3, 1, 4, 2];
+        let cases = [
+            (
+                played(Variant::Standard, 7, 6, &[1, 2, 1, 2, 1, 2, 1]),
+                lines(&[
+                    "Player 1 wins.",
+                    "Winning line from column 1 row 4 to column 1 row 1.",
+                    "The game is over.",
+                ]),
+            ),
+            (
+                played(Variant::Misere, 7, 6, &[1, 2, 1, 2, 1, 2, 1]),
+                lines(&[
+                    "Player 1 loses by connecting four.",
+                    "Losing line from column 1 row 4 to column 1 row 1.",
+                    "The game is over.",
+                ]),
+            ),
+            (
+                played(Variant::Standard, 4, 4, &drawn),
+                lines(&["The game is a draw.", "The game is over."]),
+            ),
+        ];
+        for (game, expected) in cases {
+            assert_eq!(game.describe_result(&renderer), expected);
+            assert!(game.describe_move(&renderer).ends_with(&expected));
+        }
+    }
+
+    #[test]
+    fn the_board_is_read_out_a_row_at_a_time() {
+        assert_eq!(
+            played(Variant::Standard, 7, 6, &[]).describe_board(),
+            lines(&["The board is empty."])
+        );
+        assert_eq!(
+            played(Variant::Standard, 7, 6, &[4, 4, 5, 4, 3]).describe_board(),
+            lines(&[
+                "Row 1: Player 1 3 4 5.",
+                "Row 2: Player 2 4.",
+                "Row 3: Player 2 4.",
+                "Rows 4 to 6 are empty.",
+            ])
+        );
+    }
+
+    #[test]
+    fn a_column_is_read_out_from_the_bottom() {
+        let game = played(Variant::Standard, 7, 6, &[4, 4, 5, 4, 1, 1, 1, 1, 1, 1]);
+        assert_eq!(game.describe_column(6), lines(&["Column 7 is empty."]));
+        assert_eq!(
+            game.describe_column(3),
+            lines(&[
+                "Column 4: 3 of 6 filled.",
+                "From the bottom: Player 1, Player 2, Player 2.",
+            ])
+        );
+        assert_eq!(
+            game.describe_column(0),
+            lines(&[
+                "Column 1: full.",
+                "From the bottom: Player 1, Player 2, Player 1, Player 2, Player 1, Player 2.",
+            ])
+        );
+    }
+}
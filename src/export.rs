@@ -0,0 +1,495 @@
+use std::time::Duration;
+
+use crate::ai;
+use crate::builder::GameBuilder;
+use crate::comment;
+use crate::cube;
+use crate::history::{self, Entry, Filter};
+use crate::settings::Settings;
+use crate::timing;
+use crate::validate;
+use crate::variant::{Move, Specials, Variant};
+use crate::variation;
+use crate::{Game, Player};
+
+// Columns of the per-game export, in order. The set is kept stable so spreadsheets built on
+// it keep working:
+//   game        position of the game in the log, from 1, the same whatever the filters
+//   date        YYYY-MM-DD
+//   player_one  name of the player who moved first, or who took the first move over with a
+//               swap under the pie rule
+//   player_two  name of the player who moved second
+//   computer    seat and level of the computer, like "two:hard", or empty
+//   result      player_one, player_two, player_three, player_four, or draw
+//   winner      name of the winner, or empty for a draw
+//   ending      connect, resignation, agreement, full board, or lines; empty for old log
+//               lines
+//   moves       number of moves played
+//   board       columns x rows, like 7x6
+//   move_list   1-based columns in the order they were played, separated by spaces, with
+//               a p before the column of a pop under PopOut, and an a or a b before that
+//               of an anvil or a bomb under Power Up, and "flip" for a flip under Flip
+//   game_id     the game's ULID, or empty for games logged before games had one
+//   swapped     "yes" when the second player swapped under the pie rule, or empty
+//   handicap    1-based columns of the pieces player_two was given before the first move,
+//               separated by spaces, or empty
+//   variant     standard, popout, powerup, cylinder, misere, score, flip, or 3d
+//   player_three, player_four
+//               names of the third and fourth players in games of more than two, or empty
+//   lines       lines each player made under the scoring variant, like 3-2, or empty
+//   connect     pieces in a row needed to win, or empty for old log lines
+//   rule_preset name of the rule preset the game was played by, like five, or empty
+pub const GAME_COLUMNS: [&str; 20] = [
+    "game",
+    "date",
+    "player_one",
+    "player_two",
+    "computer",
+    "result",
+    "winner",
+    "ending",
+    "moves",
+    "board",
+    "move_list",
+    "game_id",
+    "swapped",
+    "handicap",
+    "variant",
+    "player_three",
+    "player_four",
+    "lines",
+    "connect",
+    "rule_preset",
+];
+
+// Columns of the per-move export, in order, kept stable like the per-game ones:
+//   game    position of the game in the log, matching the per-game export
+//   date    YYYY-MM-DD
+//   ply     number of the move in the game, from 1
+//   seat    1 to 4, by the order the players take turns
+//   player  name of the player who moved
+//   column  1-based column the piece was dropped in
+//   row     1-based row it landed in, counted from the bottom, above any handicap pieces
+//   game_id the game's ULID, matching the per-game export
+//   popped  "yes" when the move popped the player's piece out of the bottom of the column
+//           under PopOut, so row is 1, or empty
+//   power   "anvil" or "bomb" for those moves under Power Up, "flip" for a flip under Flip,
+//           or empty. An anvil's row is 1, a bomb's is the row of the piece it blew up, and
+//           a flip has no column or row.
+pub const MOVE_COLUMNS: [&str; 10] = [
+    "game", "date", "ply", "seat", "player", "column", "row", "game_id", "popped", "power",
+];
+
+// A CSV field as RFC 4180 has it: quoted when it holds a comma, quote, or line break, with
+// quotes doubled
+pub fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn record(fields: &[String]) -> String {
+    let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+// How a result names the seat that won
+fn seat_result(seat: Player) -> &'static str {
+    match seat {
+        Player::One => "player_one",
+        Player::Two => "player_two",
+        Player::Three => "player_three",
+        Player::Four => "player_four",
+    }
+}
+
+fn game_record(id: usize, entry: &Entry) -> String {
+    let (winner, result) = match entry.winner {
+        None => (String::new(), "draw"),
+        Some(winner) => (entry.names[winner.index()].clone(), seat_result(winner)),
+    };
+    let columns = |columns: &[usize], specials: &Specials| {
+        let columns: Vec<String> = specials
+            .list(columns)
+            .iter()
+            .map(|mv| mv.notation(|column| (column + 1).to_string()))
+            .collect();
+        columns.join(" ")
+    };
+    record(&[
+        id.to_string(),
+        entry.date.clone(),
+        entry.names[0].clone(),
+        entry.names[1].clone(),
+        match entry.computer.as_str() {
+            "-" => String::new(),
+            computer => computer.to_string(),
+        },
+        result.to_string(),
+        winner,
+        entry.ending.clone(),
+        entry.moves.len().to_string(),
+        format!("{}x{}", entry.width, entry.height),
+        columns(&entry.moves, &entry.specials),
+        entry.id.clone(),
+        if entry.swapped { "yes" } else { "" }.to_string(),
+        columns(&entry.handicap, &Specials::default()),
+        entry.variant.name().to_string(),
+        entry.names.get(2).cloned().unwrap_or_default(),
+        entry.names.get(3).cloned().unwrap_or_default(),
+        entry
+            .lines
+            .map_or(String::new(), |[one, two]| format!("{}-{}", one, two)),
+        entry
+            .connect
+            .map_or(String::new(), |connect| connect.to_string()),
+        entry.rule_preset.clone().unwrap_or_default(),
+    ])
+}
+
+fn move_records(id: usize, entry: &Entry) -> String {
+    let mut filled = vec![0usize; entry.width];
+    for &column in &entry.handicap {
+        if let Some(height) = filled.get_mut(column) {
+            *height += 1;
+        }
+    }
+    let mut records = String::new();
+    for (ply, mv) in entry.specials.list(&entry.moves).into_iter().enumerate() {
+        let column = mv.column();
+        let Some(height) = filled.get_mut(column) else {
+            break;
+        };
+        let row = match mv {
+            Move::Drop(_) => {
+                *height += 1;
+                *height
+            }
+            Move::Pop(_) => {
+                *height = (*height).saturating_sub(1);
+                1
+            }
+            Move::Anvil(_) => {
+                *height = 1;
+                1
+            }
+            Move::Bomb(_) => {
+                let row = *height;
+                *height = (*height).saturating_sub(1);
+                row
+            }
+            // Turning the board over leaves every column as full as it was
+            Move::Flip => 0,
+        };
+        let (column, row) = match mv {
+            Move::Flip => (String::new(), String::new()),
+            _ => ((column + 1).to_string(), row.to_string()),
+        };
+        let power = match mv {
+            Move::Anvil(_) => "anvil",
+            Move::Bomb(_) => "bomb",
+            Move::Flip => "flip",
+            _ => "",
+        };
+        let seat = entry.seat_of(ply + 1);
+        records.push_str(&record(&[
+            id.to_string(),
+            entry.date.clone(),
+            (ply + 1).to_string(),
+            (seat as u8).to_string(),
+            entry.names[seat.index()].clone(),
+            column,
+            row,
+            entry.id.clone(),
+            if matches!(mv, Move::Pop(_)) {
+                "yes"
+            } else {
+                ""
+            }
+            .to_string(),
+            power.to_string(),
+        ]));
+    }
+    records
+}
+
+// The logged games the filter lets through as CSV with a header record, a record per game
+// or with `per_move` per move. Also returns how many log lines were unreadable.
+pub fn csv(filter: &Filter, per_move: bool) -> Result<(String, u32), String> {
+    let header: Vec<String> = if per_move {
+        MOVE_COLUMNS
+            .iter()
+            .map(|column| column.to_string())
+            .collect()
+    } else {
+        GAME_COLUMNS
+            .iter()
+            .map(|column| column.to_string())
+            .collect()
+    };
+    let mut text = record(&header);
+    let mut id = 0;
+    let skipped = history::scan(|entry| {
+        id += 1;
+        if filter.side(entry).is_none() {
+            return;
+        }
+        if per_move {
+            text.push_str(&move_records(id, entry));
+        } else {
+            text.push_str(&game_record(id, entry));
+        }
+    })?;
+    Ok((text, skipped))
+}
+
+// A JSON string literal
+pub fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c if u32::from(c) < 0x20 => quoted.push_str(&format!("\\u{:04x}", u32::from(c))),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Version of the game report's layout, raised whenever a field changes meaning or goes away
+pub const REPORT_VERSION: u32 = 1;
+
+// Play `moves` in 0-based columns on a fresh board of the game `builder` sets up, after
+// dropping the pieces of a `handicap` into their columns, for reporting on games read back
+// from a save or the log
+pub fn replay(builder: GameBuilder, handicap: &[usize], moves: &[Move]) -> Result<Game, String> {
+    let game = builder.recorded().build();
+    let mut game = game.map_err(|err| err.to_string())?;
+    game.set_handicap(handicap)?;
+    for (ply, &mv) in moves.iter().enumerate() {
+        game.play(mv)
+            .map_err(|err| format!("move {} in column {}: {}", ply + 1, mv.column() + 1, err))?;
+    }
+    Ok(game)
+}
+
+// A logged game as a playable one, ending the way the log says it did
+pub fn from_entry(entry: &Entry, settings: &Settings) -> Result<Game, String> {
+    let builder = Game::builder(settings)
+        .size(entry.width, entry.height)
+        .connect(entry.connect.unwrap_or(settings.connect))
+        .variant(entry.variant)
+        .names(&entry.names);
+    let moves = entry.specials.list(&entry.moves);
+    let mut game = replay(builder, &entry.handicap, &moves)?;
+    if !entry.id.is_empty() {
+        game.id = entry.id.clone();
+    }
+    game.rule_preset = entry.rule_preset.clone();
+    game.swapped = entry.swapped && !entry.moves.is_empty();
+    game.rules.pie_rule = game.swapped;
+    if let Some((plies, seed)) = entry.random_start {
+        game.random_start = plies;
+        game.seed = Some(seed);
+    }
+    match entry.ending.as_str() {
+        "time" if !game.is_finished() => game.lose_on_time(),
+        "resignation" if !game.is_finished() => game.resign(),
+        "agreement" if !game.is_finished() => game.agree_to_draw(),
+        _ => {}
+    }
+    game.validate()
+        .map_err(|errors| validate::summary(&errors))?;
+    Ok(game)
+}
+
+impl Game {
+    // Everything about the game as one self-describing JSON document: who played, the
+    // rules, how it ended and under the scoring variant each player's lines, every move with
+    // where it landed, its square being the peg in a 3D game, whether it was a pop, an
+    // anvil, a bomb, or a flip, which lands nowhere, and any comment on it, and the final
+    // board, rows from the top with the seat number, "1" to "4", for the players' pieces and
+    // "." for empty cells, then the variations kept with it. `date` is when it was played,
+    // if known.
+    pub fn to_json_report(&self, date: Option<&str>) -> String {
+        let name = |seat: Player| json_string(self.name(seat));
+        let (result, winner) = match (self.is_finished(), self.winner()) {
+            (false, _) => ("in_progress", "null".to_string()),
+            (true, None) => ("draw", "null".to_string()),
+            (true, Some(winner)) => (seat_result(winner), name(winner)),
+        };
+        let termination = if self.is_finished() {
+            json_string(history::ending(self))
+        } else {
+            "null".to_string()
+        };
+        let lines = match self.rules.variant {
+            Variant::Score => {
+                let [one, two] = self.line_tallies();
+                format!("[{}, {}]", one, two)
+            }
+            _ => "null".to_string(),
+        };
+        let times = self.move_times();
+        // The square a move landed on, or in a 3D game the peg it went onto
+        let square = |ply: usize| {
+            let coord = self.history[ply - 1];
+            if self.flipped(ply) {
+                "null".to_string()
+            } else if self.rules.variant == Variant::Cube {
+                json_string(&cube::peg_name(coord.column))
+            } else {
+                json_string(&coord.to_string())
+            }
+        };
+        let milliseconds = |time: Duration| time.as_millis().to_string();
+        let moves: Vec<String> = self
+            .history
+            .iter()
+            .enumerate()
+            .map(|(ply, coord)| {
+                let seat = self.seat_of(ply + 1);
+                let think = times
+                    .as_ref()
+                    .map_or("null".to_string(), |times| milliseconds(times[ply]));
+                let power = match self.specials.at(ply + 1, coord.column) {
+                    Move::Anvil(_) => json_string("anvil"),
+                    Move::Bomb(_) => json_string("bomb"),
+                    Move::Flip => json_string("flip"),
+                    _ => "null".to_string(),
+                };
+                // A flip moves every piece, so it has no square of its own
+                let (column, row) = if self.flipped(ply + 1) {
+                    ("null".to_string(), "null".to_string())
+                } else {
+                    ((coord.column + 1).to_string(), (coord.row + 1).to_string())
+                };
+                format!(
+                    "    {{\"ply\": {}, \"seat\": {}, \"player\": {}, \"column\": {}, \"row\": {}, \"square\": {}, \"pop\": {}, \"power\": {}, \"think_ms\": {}, \"comment\": {}}}",
+                    ply + 1,
+                    seat as u8,
+                    name(seat),
+                    column,
+                    row,
+                    square(ply + 1),
+                    self.popped(ply + 1),
+                    power,
+                    think,
+                    comment::json(&self.comments, ply + 1)
+                )
+            })
+            .collect();
+        // How many of the moves were played at random to start the game, and from what seed
+        let random_start = self.seed.map_or("null".to_string(), |seed| {
+            format!("{{\"moves\": {}, \"seed\": {}}}", self.random_start, seed)
+        });
+        let handicap: Vec<String> = self
+            .handicap
+            .iter()
+            .map(|column| (column + 1).to_string())
+            .collect();
+        // Top row first, the way the board is drawn
+        let board: Vec<String> = (0..self.rules.height)
+            .rev()
+            .map(|row| {
+                let cells: String = (0..self.rules.width)
+                    .map(|column| match self.cell(row, column) {
+                        None => '.',
+                        Some(player) => char::from(b'0' + player as u8),
+                    })
+                    .collect();
+                format!("    {}", json_string(&cells))
+            })
+            .collect();
+        let players: Vec<String> = self.seats().into_iter().map(name).collect();
+        let computer = match self.computer {
+            Some((seat, difficulty)) => format!(
+                "{{\"seat\": {}, \"difficulty\": {}, \"depth\": {}, \"seed\": {}, \"version\": {}}}",
+                seat as u8,
+                json_string(&format!("{:?}", difficulty).to_lowercase()),
+                difficulty.depth(),
+                self.engine_seed,
+                json_string(ai::VERSION)
+            ),
+            None => "null".to_string(),
+        };
+        // Where the time went in a game on a clock: by player, then the longest thinks
+        let time_usage = times.as_ref().map_or("null".to_string(), |times| {
+            let shares = timing::shares(times);
+            let both = |part: fn(&timing::Share) -> Duration| {
+                format!(
+                    "[{}, {}]",
+                    milliseconds(part(&shares[0])),
+                    milliseconds(part(&shares[1]))
+                )
+            };
+            let longest: Vec<String> = timing::longest(times, timing::LONGEST)
+                .iter()
+                .map(|&(ply, time)| {
+                    format!(
+                        "{{\"ply\": {}, \"square\": {}, \"think_ms\": {}}}",
+                        ply,
+                        square(ply),
+                        milliseconds(time)
+                    )
+                })
+                .collect();
+            format!(
+                "{{\"total_ms\": {}, \"average_ms\": {}, \"median_ms\": {}, \"longest\": [{}], \"sparkline\": {}}}",
+                both(|share| share.total),
+                both(|share| share.average),
+                both(|share| share.median),
+                longest.join(", "),
+                json_string(&timing::sparkline(times))
+            )
+        });
+
+        format!(
+            "{{\n  \"format\": \"connect_four-game\",\n  \"version\": {},\n  \"metadata\": {{\n    \
+             \"id\": {},\n    \"previous_game\": {},\n    \
+             \"players\": [{}],\n    \"computer\": {},\n    \"date\": {},\n    \
+             \"width\": {},\n    \"height\": {},\n    \"connect\": {},\n    \"rule_preset\": {},\n    \
+             \"variant\": {},\n    \"time_control\": {},\n    \"preset\": {},\n    \"pie_rule\": {},\n    \
+             \"swapped\": {},\n    \"handicap\": [{}],\n    \"random_start\": {},\n    \
+             \"result\": {},\n    \
+             \"winner\": {},\n    \"termination\": {},\n    \"lines\": {},\n    \"moves\": {}\n  }},\n  \
+             \"moves\": [\n{}\n  ],\n  \"final_board\": [\n{}\n  ],\n  \"time_usage\": {},\n  \
+             \"variations\": {}\n}}",
+            REPORT_VERSION,
+            json_string(&self.id),
+            self.previous_game
+                .as_deref()
+                .map_or("null".to_string(), json_string),
+            players.join(", "),
+            computer,
+            date.map_or("null".to_string(), json_string),
+            self.rules.width,
+            self.rules.height,
+            self.rules.connect,
+            self.rule_preset.as_deref().map_or("null".to_string(), json_string),
+            json_string(self.rules.variant.name()),
+            self.clock
+                .as_ref()
+                .map_or("null".to_string(), |clock| json_string(&clock.control.to_string())),
+            self.preset.as_deref().map_or("null".to_string(), json_string),
+            self.rules.pie_rule,
+            self.swapped,
+            handicap.join(", "),
+            random_start,
+            json_string(result),
+            winner,
+            termination,
+            lines,
+            self.history.len(),
+            moves.join(",\n"),
+            board.join(",\n"),
+            time_usage,
+            variation::json(&self.variations)
+        )
+    }
+}
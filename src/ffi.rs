@@ -0,0 +1,371 @@
+// The game for C and anything else that can call it, declared in capi/include/connect_four.h.
+// A game is an opaque c4_game pointer from c4_new, which every other call takes and c4_free
+// gives back. Columns are counted from 0.
+//
+// Every call returns a C4_* code, or a count or column that is never negative, rather than
+// letting a Rust panic cross into C; a game whose call panicked is marked broken and refuses
+// every later call but c4_free.
+//
+// A game may be moved to another thread between calls but never used from two at once: a
+// call made while another is still running on the same game returns C4_ERR_BUSY instead of
+// touching it. Separate games are independent.
+//
+// Pointers must be null, which is reported as C4_ERR_NULL, or what the header says: a game
+// from c4_new not yet freed, and buffers with room for at least `len` elements.
+#![allow(clippy::missing_safety_doc)]
+
+use std::ffi::c_char;
+use std::marker::PhantomData;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::Duration;
+
+use crate::rules::GameRules;
+use crate::variant::Move;
+use crate::{ai, Game, MoveError};
+
+pub const C4_OK: i32 = 0;
+pub const C4_ERR_NULL: i32 = -1;
+pub const C4_ERR_GAME_FINISHED: i32 = -2;
+pub const C4_ERR_INVALID_COLUMN: i32 = -3;
+pub const C4_ERR_COLUMN_FULL: i32 = -4;
+pub const C4_ERR_ILLEGAL: i32 = -5;
+pub const C4_ERR_NOTHING_TO_UNDO: i32 = -6;
+pub const C4_ERR_BUFFER: i32 = -7;
+pub const C4_ERR_BUSY: i32 = -8;
+pub const C4_ERR_PANIC: i32 = -9;
+
+// Least score c4_evaluate gives a position the player to move can force a win from
+pub const C4_WIN: i32 = ai::WIN;
+
+// What a game is doing: waiting for a call, in the middle of one, or broken by a panic
+const IDLE: u8 = 0;
+const IN_CALL: u8 = 1;
+const BROKEN: u8 = 2;
+
+// A game behind a c4_game pointer. It's Send but not Sync, like the C side's promise: it may
+// change threads, and the state turns away a second caller rather than sharing it.
+pub struct C4Game {
+    game: Game,
+    state: AtomicU8,
+    unshared: PhantomData<std::cell::Cell<()>>,
+}
+
+// Run `call` on the game behind `handle`, turning a null pointer, a call already running,
+// a game a panic broke, and a panic now into their codes
+unsafe fn with_game(handle: *mut C4Game, call: impl FnOnce(&mut Game) -> i32) -> i32 {
+    if handle.is_null() {
+        return C4_ERR_NULL;
+    }
+    // Only the state is looked at before the call has the game to itself
+    let state = &(*handle).state;
+    match state.compare_exchange(IDLE, IN_CALL, Ordering::Acquire, Ordering::Relaxed) {
+        Ok(_) => {}
+        Err(BROKEN) => return C4_ERR_PANIC,
+        Err(_) => return C4_ERR_BUSY,
+    }
+    let game = &mut *ptr::addr_of_mut!((*handle).game);
+    match panic::catch_unwind(AssertUnwindSafe(|| call(game))) {
+        Ok(code) => {
+            state.store(IDLE, Ordering::Release);
+            code
+        }
+        Err(_) => {
+            state.store(BROKEN, Ordering::Release);
+            C4_ERR_PANIC
+        }
+    }
+}
+
+// The code for a move the game turned down
+fn move_code(err: &MoveError) -> i32 {
+    match err {
+        MoveError::GameFinished => C4_ERR_GAME_FINISHED,
+        MoveError::InvalidColumn { .. } => C4_ERR_INVALID_COLUMN,
+        MoveError::ColumnFull { .. } => C4_ERR_COLUMN_FULL,
+        _ => C4_ERR_ILLEGAL,
+    }
+}
+
+// A count or column as a code, which can't be negative
+fn count(value: usize) -> i32 {
+    i32::try_from(value).unwrap_or(i32::MAX)
+}
+
+// A new two-player game on a board `width` columns by `height` rows, played to `connect` in
+// a row, or null when those aren't rules a game can be played by
+#[no_mangle]
+pub extern "C" fn c4_new(width: u32, height: u32, connect: u32) -> *mut C4Game {
+    let built = panic::catch_unwind(|| {
+        Game::with_rules(
+            GameRules::CLASSIC
+                .with_size(width as usize, height as usize)
+                .with_connect(connect as usize),
+        )
+    });
+    match built {
+        Ok(Ok(game)) => Box::into_raw(Box::new(C4Game {
+            game,
+            state: AtomicU8::new(IDLE),
+            unshared: PhantomData,
+        })),
+        _ => ptr::null_mut(),
+    }
+}
+
+// Free a game from c4_new; null is ignored
+#[no_mangle]
+pub unsafe extern "C" fn c4_free(handle: *mut C4Game) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+// Drop a piece in `column` for the player to move
+#[no_mangle]
+pub unsafe extern "C" fn c4_play(handle: *mut C4Game, column: i32) -> i32 {
+    with_game(handle, |game| {
+        let Ok(column) = usize::try_from(column) else {
+            return C4_ERR_INVALID_COLUMN;
+        };
+        match game.play(Move::Drop(column)) {
+            Ok(()) => C4_OK,
+            Err(err) => move_code(&err),
+        }
+    })
+}
+
+// Take back the last move
+#[no_mangle]
+pub unsafe extern "C" fn c4_undo(handle: *mut C4Game) -> i32 {
+    with_game(handle, |game| {
+        if game.undo() {
+            C4_OK
+        } else {
+            C4_ERR_NOTHING_TO_UNDO
+        }
+    })
+}
+
+// The columns a piece can be dropped in, from the left, written to `buffer`; returns how
+// many there are, or C4_ERR_BUFFER without writing any when `len` is too few for them all
+#[no_mangle]
+pub unsafe extern "C" fn c4_legal_moves(handle: *mut C4Game, buffer: *mut i32, len: usize) -> i32 {
+    with_game(handle, |game| {
+        let columns: Vec<usize> = game.legal_moves().collect();
+        if columns.is_empty() {
+            return 0;
+        }
+        if buffer.is_null() {
+            return C4_ERR_NULL;
+        }
+        if len < columns.len() {
+            return C4_ERR_BUFFER;
+        }
+        for (index, &column) in columns.iter().enumerate() {
+            *buffer.add(index) = count(column);
+        }
+        count(columns.len())
+    })
+}
+
+// 1 once the game is over, 0 while it goes on
+#[no_mangle]
+pub unsafe extern "C" fn c4_is_finished(handle: *mut C4Game) -> i32 {
+    with_game(handle, |game| i32::from(game.is_finished()))
+}
+
+// The player who won, 1 or 2; 0 while the game goes on and for a draw
+#[no_mangle]
+pub unsafe extern "C" fn c4_winner(handle: *mut C4Game) -> i32 {
+    with_game(handle, |game| {
+        game.winner().map_or(0, |player| i32::from(player as u8))
+    })
+}
+
+// The column the computer would play for the player to move, searching for about `millis`
+#[no_mangle]
+pub unsafe extern "C" fn c4_best_move(handle: *mut C4Game, millis: u32) -> i32 {
+    with_game(handle, |game| {
+        match ai::move_within(game, Duration::from_millis(u64::from(millis))) {
+            Some(mv) => count(mv.column()),
+            None => C4_ERR_GAME_FINISHED,
+        }
+    })
+}
+
+// The column the computer would play for the player to move looking `depth` moves ahead
+#[no_mangle]
+pub unsafe extern "C" fn c4_best_move_at_depth(handle: *mut C4Game, depth: u32) -> i32 {
+    with_game(handle, |game| {
+        match ai::move_at_depth(game, depth as usize) {
+            Some(mv) => count(mv.column()),
+            None => C4_ERR_GAME_FINISHED,
+        }
+    })
+}
+
+// How the position stands for the player to move as the hard level sees it, written to
+// `score`: above 0 is better for them, and C4_WIN or more a win they can force. Returns the
+// column the search likes best.
+#[no_mangle]
+pub unsafe extern "C" fn c4_evaluate(handle: *mut C4Game, score: *mut i32) -> i32 {
+    with_game(handle, |game| {
+        if score.is_null() {
+            return C4_ERR_NULL;
+        }
+        match ai::evaluation(game) {
+            Some((mv, value)) => {
+                *score = value;
+                count(mv.column())
+            }
+            None => C4_ERR_GAME_FINISHED,
+        }
+    })
+}
+
+// The board as rows of X, O, and ., top row first, written to `buffer` with a terminating
+// NUL. Returns the length of the text without the NUL; when `len` has no room for all of it,
+// as much as fits is written, NUL-terminated, so a caller can ask again with more room.
+#[no_mangle]
+pub unsafe extern "C" fn c4_render_ascii(
+    handle: *mut C4Game,
+    buffer: *mut c_char,
+    len: usize,
+) -> i32 {
+    with_game(handle, |game| {
+        let text = game.board.to_string();
+        if len > 0 {
+            if buffer.is_null() {
+                return C4_ERR_NULL;
+            }
+            let written = text.len().min(len - 1);
+            ptr::copy_nonoverlapping(text.as_ptr().cast::<c_char>(), buffer, written);
+            *buffer.add(written) = 0;
+        }
+        count(text.len())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    // A classic game with these columns played through the API
+    fn played(columns: &[i32]) -> *mut C4Game {
+        let handle = c4_new(7, 6, 4);
+        for &column in columns {
+            assert_eq!(
+                unsafe { c4_play(handle, column) },
+                C4_OK,
+                "column {}",
+                column
+            );
+        }
+        handle
+    }
+
+    #[test]
+    fn a_game_plays_through_to_a_win() {
+        let handle = played(&[0, 1, 0, 1, 0, 1]);
+        unsafe {
+            assert_eq!(c4_is_finished(handle), 0);
+            assert_eq!(c4_best_move(handle, 50), 0, "the computer takes the win");
+            assert_eq!(c4_play(handle, 0), C4_OK);
+            assert_eq!(c4_is_finished(handle), 1);
+            assert_eq!(c4_winner(handle), 1);
+            assert_eq!(c4_play(handle, 3), C4_ERR_GAME_FINISHED);
+            assert_eq!(c4_best_move(handle, 50), C4_ERR_GAME_FINISHED);
+            assert_eq!(c4_legal_moves(handle, ptr::null_mut(), 0), 0);
+            assert_eq!(c4_undo(handle), C4_OK);
+            assert_eq!(c4_winner(handle), 0);
+            c4_free(handle);
+        }
+    }
+
+    #[test]
+    fn the_computer_searches_to_a_depth_and_scores_the_position() {
+        let handle = played(&[0, 1, 0, 1, 0]);
+        unsafe {
+            assert_eq!(c4_best_move_at_depth(handle, 2), 0, "the threat is blocked");
+            let mut score = 0;
+            assert_eq!(c4_evaluate(handle, &mut score), 0);
+            assert!(score < 0 && score > -C4_WIN, "{}", score);
+            assert_eq!(c4_play(handle, 3), C4_OK);
+            assert_eq!(c4_evaluate(handle, &mut score), 0);
+            assert!(score >= C4_WIN, "{}", score);
+            assert_eq!(c4_evaluate(handle, ptr::null_mut()), C4_ERR_NULL);
+            c4_free(handle);
+        }
+    }
+
+    #[test]
+    fn bad_moves_and_arguments_are_codes() {
+        assert!(c4_new(3, 6, 4).is_null(), "too narrow");
+        assert!(c4_new(7, 6, 9).is_null(), "longer than the board");
+        let handle = played(&[2, 2, 2, 2, 2, 2]);
+        unsafe {
+            assert_eq!(c4_play(handle, 2), C4_ERR_COLUMN_FULL);
+            assert_eq!(c4_play(handle, 7), C4_ERR_INVALID_COLUMN);
+            assert_eq!(c4_play(handle, -1), C4_ERR_INVALID_COLUMN);
+            let mut columns = [0; 7];
+            assert_eq!(
+                c4_legal_moves(handle, columns.as_mut_ptr(), 5),
+                C4_ERR_BUFFER
+            );
+            assert_eq!(c4_legal_moves(handle, columns.as_mut_ptr(), 7), 6);
+            assert_eq!(columns[..6], [0, 1, 3, 4, 5, 6]);
+            c4_free(handle);
+
+            let empty = c4_new(7, 6, 4);
+            assert_eq!(c4_undo(empty), C4_ERR_NOTHING_TO_UNDO);
+            c4_free(empty);
+            assert_eq!(c4_play(ptr::null_mut(), 0), C4_ERR_NULL);
+            assert_eq!(c4_winner(ptr::null_mut()), C4_ERR_NULL);
+            c4_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn the_board_renders_into_a_buffer_of_any_size() {
+        let handle = played(&[3, 3, 2]);
+        let board = [
+            ".......", ".......", ".......", ".......", "...O...", "..XX...",
+        ]
+        .join("\n");
+        unsafe {
+            let mut buffer = [0 as c_char; 64];
+            assert_eq!(
+                c4_render_ascii(handle, buffer.as_mut_ptr(), buffer.len()),
+                count(board.len())
+            );
+            assert_eq!(CStr::from_ptr(buffer.as_ptr()).to_str(), Ok(board.as_str()));
+            let mut short = [0 as c_char; 5];
+            assert_eq!(
+                c4_render_ascii(handle, short.as_mut_ptr(), short.len()),
+                count(board.len())
+            );
+            assert_eq!(CStr::from_ptr(short.as_ptr()).to_str(), Ok("...."));
+            assert_eq!(
+                c4_render_ascii(handle, ptr::null_mut(), 0),
+                count(board.len())
+            );
+            c4_free(handle);
+        }
+    }
+
+    #[test]
+    fn a_game_in_use_or_broken_turns_callers_away() {
+        let handle = played(&[]);
+        unsafe {
+            (*handle).state.store(IN_CALL, Ordering::Relaxed);
+            assert_eq!(c4_play(handle, 0), C4_ERR_BUSY);
+            (*handle).state.store(IDLE, Ordering::Relaxed);
+            assert_eq!(with_game(handle, |_| panic!("a bug")), C4_ERR_PANIC);
+            assert_eq!(c4_play(handle, 0), C4_ERR_PANIC);
+            c4_free(handle);
+        }
+    }
+}
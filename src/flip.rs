@@ -0,0 +1,112 @@
+use crate::board::Cell;
+use crate::coord::Coord;
+use crate::powerup::{plies_of, seat_of};
+use crate::profile;
+use crate::variant::{Move, Variant};
+use crate::{Game, MoveError, Player};
+
+// Flips each player has for a game
+pub const FLIPS: usize = 1;
+
+impl Game {
+    // Flips `player` has yet to make
+    pub fn flips_left(&self, player: Player) -> usize {
+        FLIPS.saturating_sub(plies_of(&self.specials.flips, player))
+    }
+
+    // Whether the player to move could turn the board over
+    pub fn can_flip(&self) -> bool {
+        !self.is_finished() && !self.placing() && self.can_flip_any(self.current_player)
+    }
+
+    // Whether `player` still has their flip, and there's anything on the board to flip
+    pub fn can_flip_any(&self, player: Player) -> bool {
+        self.rules.variant == Variant::Flip
+            && self.flips_left(player) > 0
+            && self.board[self.rules.height - 1]
+                .iter()
+                .any(|cell| cell.is_some())
+    }
+
+    // Whether move number `ply`, from 1, turned the board over
+    pub fn flipped(&self, ply: usize) -> bool {
+        self.specials.flips.contains(&ply)
+    }
+
+    // The flips each player has left, and who flipped the board if that was the last move,
+    // for showing under the board
+    pub fn flip_line(&self) -> Option<String> {
+        if self.rules.variant != Variant::Flip {
+            return None;
+        }
+        let seat = |player: Player| {
+            format!(
+                "{} {}",
+                self.name(player),
+                profile::plural(self.flips_left(player) as u32, "flip")
+            )
+        };
+        let last = self.history.len();
+        let flipped = if last > 0 && self.flipped(last) {
+            format!("{} flipped the board. ", self.name(seat_of(last)))
+        } else {
+            String::new()
+        };
+        Some(format!(
+            "{}Still to play: {}; {}",
+            flipped,
+            seat(Player::One),
+            seat(Player::Two)
+        ))
+    }
+
+    // Under Flip, turn the board upside down and let the pieces fall back to the bottom,
+    // each column keeping its pieces in the reverse order. Lines can turn up anywhere, and
+    // the flipper wins when both players get one.
+    pub fn flip(&mut self) -> Result<(), MoveError> {
+        if self.is_finished() {
+            return Err(MoveError::GameFinished);
+        }
+        if self.rules.variant != Variant::Flip || self.placing() {
+            return Err(MoveError::NoFlips);
+        }
+        if self.flips_left(self.current_player) == 0 {
+            return Err(MoveError::NoneLeft);
+        }
+        if self.draw_offered() {
+            self.draw_offer = None;
+        }
+
+        self.boards.push(self.board.clone());
+        for column in 0..self.rules.width {
+            let pieces: Vec<Cell> = (0..self.rules.height)
+                .map(|row| self.board[row][column])
+                .filter(|cell| cell.is_some())
+                .collect();
+            for row in 0..self.rules.height {
+                self.board[row][column] = None;
+            }
+            for (count, piece) in pieces.into_iter().enumerate() {
+                self.board[self.rules.height - 1 - count][column] = piece;
+            }
+        }
+        self.current_move += 1;
+        self.history.push(Coord { column: 0, row: 0 });
+        self.specials.record(self.history.len(), Move::Flip);
+        self.settle();
+        Ok(())
+    }
+
+    // Take back the flip that was the last move, putting the board back as it was
+    pub fn unflip(&mut self) {
+        let (Some(_), Some(board)) = (self.history.pop(), self.boards.pop()) else {
+            return;
+        };
+        let ply = self.history.len() + 1;
+        self.specials.truncate(ply - 1);
+        self.current_player = self.last_mover();
+        self.board = board;
+        self.current_move -= 1;
+        self.outcome = None;
+    }
+}
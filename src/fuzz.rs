@@ -0,0 +1,185 @@
+// The readers of text from outside, fed damaged copies of what they usually read. None of
+// them may panic, and a game read back from a damaged file must still hold together. The
+// damage comes from fixed seeds, so a failure names the seed that makes it again.
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::board::Board;
+use crate::clock::TimeControl;
+use crate::command;
+use crate::pgn::{self, Record};
+use crate::session::Session;
+use crate::settings::Settings;
+use crate::start::Rng;
+use crate::variant::{Move, Variant};
+use crate::{save, Game};
+
+// Damaged inputs tried for each reader
+const CASES: u32 = 500;
+
+// Characters damage brings in: ones the readers give meaning to, and some they never see
+const NOISE: &[char] = &[
+    '0', '1', '4', '9', '-', '+', ':', '.', ' ', '\n', '\t', '"', '\'', '\\', '[', ']', '{', '}',
+    '(', ')', '=', '#', 'd', 'h', 'm', 's', 'p', 'X', 'O', 'é', '●', '\0',
+];
+
+// A run of digits too long for any number the readers keep
+const HUGE: &str = "99999999999999999999";
+
+// `text` with one to four bits of damage picked by `rng`: a character changed, a run of
+// them cut out or repeated, a huge number put in, or the end torn off
+fn damaged(text: &str, rng: &mut Rng) -> String {
+    let mut chars: Vec<char> = text.chars().collect();
+    for _ in 0..1 + rng.below(4) {
+        let at = rng.below(chars.len() + 1);
+        let end = (at + 1 + rng.below(8)).min(chars.len());
+        match rng.below(5) {
+            0 if at < chars.len() => chars[at] = NOISE[rng.below(NOISE.len())],
+            1 if at < end => {
+                chars.drain(at..end);
+            }
+            2 if at < end => {
+                let run: Vec<char> = chars[at..end].to_vec();
+                chars.splice(at..at, run);
+            }
+            3 => {
+                chars.splice(at..at, HUGE.chars());
+            }
+            _ => chars.truncate(at),
+        }
+    }
+    chars.into_iter().collect()
+}
+
+// Run `read` on damaged copies of each of the `samples`, failing with the input that made
+// it panic
+fn fuzz(samples: &[String], mut read: impl FnMut(&str)) {
+    for seed in 0..CASES {
+        let mut rng = Rng::new(seed);
+        let input = damaged(&samples[rng.below(samples.len())], &mut rng);
+        if panic::catch_unwind(AssertUnwindSafe(|| read(&input))).is_err() {
+            panic!("seed {}: reading {:?} panicked", seed, input);
+        }
+    }
+}
+
+// Games to copy from: one of each variant on the default board, played a few moves in with
+// a pop, an anvil, or a flip where the variant has them, and one played out to a win
+fn games() -> Vec<Game> {
+    let variants = [
+        (
+            Variant::Standard,
+            vec![Move::Drop(3), Move::Drop(3), Move::Drop(2)],
+        ),
+        (
+            Variant::PopOut,
+            vec![Move::Drop(0), Move::Drop(1), Move::Pop(0)],
+        ),
+        (
+            Variant::PowerUp,
+            vec![Move::Drop(0), Move::Anvil(0), Move::Bomb(0)],
+        ),
+        (
+            Variant::Flip,
+            vec![Move::Drop(0), Move::Drop(0), Move::Flip],
+        ),
+        (Variant::Cube, vec![Move::Drop(5), Move::Drop(6)]),
+    ];
+    let mut games: Vec<Game> = variants
+        .into_iter()
+        .map(|(variant, moves)| {
+            let mut game = Game::builder(&Settings::default())
+                .variant(variant)
+                .build()
+                .expect("the rules are valid");
+            for mv in moves {
+                game.play(mv).expect("the move is legal");
+            }
+            game
+        })
+        .collect();
+    let mut won = Game::builder(&Settings::default())
+        .build()
+        .expect("the default rules are valid");
+    for column in [0, 1, 0, 1, 0, 1, 0] {
+        won.play(Move::Drop(column)).expect("the move is legal");
+    }
+    games.push(won);
+    games
+}
+
+#[test]
+fn damaged_pgn_reads_without_panicking() {
+    let samples: Vec<String> = games()
+        .into_iter()
+        .map(|game| pgn::write(&Record::new(game, Some("2024-05-04".to_string()))))
+        .collect();
+    fuzz(&samples, |text| {
+        let (records, _) = pgn::read(text, &Settings::default());
+        for record in records {
+            assert_eq!(record.game.validate(), Ok(()), "{:?}", text);
+        }
+    });
+}
+
+#[test]
+fn damaged_saves_load_without_panicking() {
+    let path = std::env::temp_dir().join(format!("fuzz-{}.toml", std::process::id()));
+    let slot = path
+        .to_str()
+        .expect("the temporary directory has a UTF-8 path");
+    let samples: Vec<String> = games()
+        .iter()
+        .map(|game| {
+            save::write(game, &Settings::default(), &Session::default(), slot)
+                .expect("the save can be written");
+            fs::read_to_string(&path).expect("the save can be read")
+        })
+        .collect();
+    fuzz(&samples, |text| {
+        fs::write(&path, text).expect("the save can be written");
+        if let Ok(game) = save::load(&path, &Settings::default()) {
+            assert_eq!(game.validate(), Ok(()), "{:?}", text);
+        }
+    });
+    fs::remove_file(&path).expect("the save can be removed");
+}
+
+#[test]
+fn damaged_commands_parse_without_panicking() {
+    let samples: Vec<String> = [
+        "4",
+        "d",
+        "p3",
+        "a12",
+        "b 7",
+        "undo 2",
+        ":save \"my game\"",
+        "name 2 'Ann Lee'",
+        "hint",
+        "offer draw",
+        "time 5m+2s",
+        "help save",
+        "b2c",
+    ]
+    .map(str::to_string)
+    .to_vec();
+    fuzz(&samples, |line| {
+        for width in [4, 7, 20] {
+            let _ = command::parse(line, width);
+        }
+        let _ = command::parse_3d(line);
+    });
+}
+
+#[test]
+fn damaged_boards_and_time_controls_parse_without_panicking() {
+    let boards: Vec<String> = games().iter().map(|game| game.board.to_string()).collect();
+    fuzz(&boards, |text| {
+        let _ = text.parse::<Board>();
+    });
+    let controls = ["5m", "1m30s", "3m+2s", "5m d3", "1h", "45"].map(str::to_string);
+    fuzz(&controls, |text| {
+        let _ = text.parse::<TimeControl>();
+    });
+}
@@ -0,0 +1,138 @@
+use crate::coord::{Column, Coord};
+use crate::{Game, MoveError, Player};
+
+// How the pieces of a handicap go on the board before the first move
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Placement {
+    // On the bottom row, from the center column outward
+    Fixed,
+    // Dropped one at a time by the player given them, wherever they like
+    Choose,
+}
+
+// The most pieces a handicap may give when `connect` in a row wins: any more could make a
+// line before anyone has moved
+pub fn most(connect: usize) -> usize {
+    connect - 1
+}
+
+// Columns of a fixed handicap of `count` pieces on a board `width` wide: the center column,
+// then the ones either side of it, nearest and leftmost first, stacking onto the bottom row
+// once it's full
+pub fn fixed(width: usize, count: usize) -> Vec<usize> {
+    let center = (width - 1) / 2;
+    let mut order: Vec<usize> = (0..width).collect();
+    order.sort_by_key(|&column| (column.abs_diff(center), column));
+    (0..count).map(|index| order[index % width]).collect()
+}
+
+impl Game {
+    // Whether the player given a handicap still has pieces of it to place
+    pub fn placing(&self) -> bool {
+        self.handicap_left > 0
+    }
+
+    // Pieces in the game's handicap, placed or not
+    pub fn handicap_size(&self) -> usize {
+        self.handicap.len() + self.handicap_left
+    }
+
+    // Give the second player `count` pieces before the first move. Fixed ones, and any the
+    // computer is given, go down at once; otherwise the player drops them one at a time,
+    // and the first player moves once they're all down.
+    pub fn give_handicap(&mut self, count: usize, placement: Placement) {
+        self.placement = placement;
+        if count == 0 {
+            return;
+        }
+        let computer = self.computer.is_some_and(|(seat, _)| seat == Player::Two);
+        if placement == Placement::Fixed || computer {
+            let columns = fixed(self.rules.width, count);
+            self.set_handicap(&columns)
+                .expect("a fixed handicap fits the board");
+        } else {
+            self.handicap_left = count;
+            self.current_player = Player::Two;
+        }
+    }
+
+    // Drop the next piece of the handicap into `column`
+    pub fn place_handicap(&mut self, column: Column) -> Result<Coord, MoveError> {
+        let column = column.index();
+        let height = self.column_height(column);
+        if height == self.rules.height {
+            return Err(MoveError::ColumnFull { column });
+        }
+        self.board[self.rules.height - 1 - height][column] = Some(Player::Two);
+        self.handicap.push(column);
+        self.handicap_left -= 1;
+        if self.handicap_left == 0 {
+            self.current_player = Player::One;
+        }
+        Ok(Coord {
+            column,
+            row: height,
+        })
+    }
+
+    // Put a recorded handicap on the empty board of a game read back, rejecting one the
+    // game's rules don't allow or whose pieces don't fit
+    pub fn set_handicap(&mut self, columns: &[usize]) -> Result<(), String> {
+        if columns.len() > most(self.rules.connect) {
+            return Err(format!(
+                "a handicap can be at most {} pieces when {} in a row wins",
+                most(self.rules.connect),
+                self.rules.connect
+            ));
+        }
+        self.handicap_left = columns.len();
+        for (index, &column) in columns.iter().enumerate() {
+            let placed = self
+                .column(column)
+                .and_then(|column| self.place_handicap(column));
+            placed.map_err(|err| {
+                format!(
+                    "handicap piece {} in column {}: {}",
+                    index + 1,
+                    column + 1,
+                    err
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    // Leave `left` more pieces of the handicap for the second player to place, for a game
+    // saved before they were all down
+    pub fn leave_handicap(&mut self, left: usize) -> Result<(), String> {
+        if left == 0 {
+            return Ok(());
+        }
+        if !self.history.is_empty() {
+            return Err(
+                "handicap pieces can only be left to place before the first move".to_string(),
+            );
+        }
+        if self.handicap.len() + left > most(self.rules.connect) {
+            return Err(format!(
+                "a handicap can be at most {} pieces when {} in a row wins",
+                most(self.rules.connect),
+                self.rules.connect
+            ));
+        }
+        self.handicap_left = left;
+        self.current_player = Player::Two;
+        Ok(())
+    }
+
+    // Who places the next handicap piece, and what comes after
+    pub fn placement_note(&self) -> String {
+        format!(
+            "{} places handicap piece {} of {}; {} moves first once they're down",
+            self.name(Player::Two),
+            self.handicap.len() + 1,
+            self.handicap_size(),
+            self.name(Player::One)
+        )
+    }
+}
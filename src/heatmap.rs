@@ -0,0 +1,240 @@
+use crate::history::{self, Entry};
+use crate::style::{Color, Style};
+use crate::Player;
+
+// Games counted at one spot of the heatmap, and how many of them Player 1 won
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Count {
+    pub games: u32,
+    pub wins: u32,
+}
+
+impl Count {
+    fn add(&mut self, other: Count) {
+        self.games += other.games;
+        self.wins += other.wins;
+    }
+
+    fn win_rate(&self) -> f64 {
+        match self.games {
+            0 => 0.0,
+            games => f64::from(self.wins) / f64::from(games),
+        }
+    }
+}
+
+// How often Player 1 won, by the column of the game's first move and by every cell Player 1
+// held when the game ended, over the logged games on one board size
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heatmap {
+    pub width: usize,
+    pub height: usize,
+    pub columns: Vec<Count>,
+    // Rows from the top, as the board is drawn
+    pub cells: Vec<Vec<Count>>,
+}
+
+// Shade and color for each bucket, from Player 1 rarely winning to nearly always
+const SHADES: [&str; 5] = [" ", "░", "▒", "▓", "█"];
+const BUCKET_COLORS: [&str; 5] = ["red", "yellow", "white", "cyan", "green"];
+
+// Bucket of a win rate from 0 to 1: below 20% is 0, 80% and up is 4
+pub fn bucket(rate: f64) -> usize {
+    ((rate.clamp(0.0, 1.0) * 5.0) as usize).min(4)
+}
+
+impl Heatmap {
+    pub fn new(width: usize, height: usize) -> Heatmap {
+        Heatmap {
+            width,
+            height,
+            columns: vec![Count::default(); width],
+            cells: vec![vec![Count::default(); width]; height],
+        }
+    }
+
+    // Count one logged game, if it was played on this board size
+    pub fn add(&mut self, entry: &Entry) {
+        if entry.width != self.width || entry.height != self.height {
+            return;
+        }
+        let won = Count {
+            games: 1,
+            wins: u32::from(entry.winner == Some(Player::One)),
+        };
+        // Replay the moves to find where Player 1's pieces landed
+        let mut filled = vec![0; self.width];
+        let mut held = vec![vec![false; self.width]; self.height];
+        for (index, &column) in entry.moves.iter().enumerate() {
+            if column >= self.width || filled[column] >= self.height {
+                return;
+            }
+            if entry.seat_of(index + 1) == Player::One {
+                held[self.height - 1 - filled[column]][column] = true;
+            }
+            filled[column] += 1;
+        }
+
+        if let Some(&first) = entry.moves.first() {
+            self.columns[first].add(won);
+        }
+        for (row, held) in held.iter().enumerate() {
+            for (column, _) in held.iter().enumerate().filter(|(_, &held)| held) {
+                self.cells[row][column].add(won);
+            }
+        }
+    }
+
+    // Count mirror-image spots together, since the board is symmetric left to right
+    pub fn merge_mirrors(&mut self) {
+        let width = self.width;
+        for column in 0..width / 2 {
+            let mirror = width - 1 - column;
+            let merged = |counts: &mut Vec<Count>| {
+                let mut sum = counts[column];
+                sum.add(counts[mirror]);
+                counts[column] = sum;
+                counts[mirror] = sum;
+            };
+            merged(&mut self.columns);
+            for row in &mut self.cells {
+                merged(row);
+            }
+        }
+    }
+
+    // The first-move row, and with `cells` the board-shaped grid, as shaded win rates over
+    // game counts. Spots with fewer than `min_games` games are left blank.
+    pub fn render(&self, style: Style, min_games: u32, cells: bool) -> String {
+        let spot = |count: &Count| {
+            if count.games == 0 || count.games < min_games {
+                return "    · ".to_string();
+            }
+            let rate = count.win_rate();
+            let level = bucket(rate);
+            let text = format!("{}{:>4.0}%", SHADES[level], rate * 100.0);
+            match Color::parse(BUCKET_COLORS[level]) {
+                Ok(color) => style.paint_color(color, &text),
+                Err(_) => text,
+            }
+        };
+        let games = |count: &Count| format!("{:>6}", count.games);
+        let labels: String = (1..=self.width)
+            .map(|column| format!("{:>6}", column))
+            .collect();
+
+        let mut lines = vec![
+            "Player 1 win rate by first move".to_string(),
+            format!("Column   {}", labels),
+            format!(
+                "Win%     {}",
+                self.columns.iter().map(spot).collect::<String>()
+            ),
+            format!(
+                "Games    {}",
+                self.columns.iter().map(games).collect::<String>()
+            ),
+        ];
+        if cells {
+            lines.push(String::new());
+            lines.push("Player 1 win rate by cell held at the end".to_string());
+            for (index, row) in self.cells.iter().enumerate() {
+                lines.push(format!(
+                    "Row {:<4} {}",
+                    self.height - index,
+                    row.iter().map(spot).collect::<String>()
+                ));
+            }
+            lines.push(format!("Column   {}", labels));
+            lines.push(String::new());
+            lines.push("Games by cell".to_string());
+            for (index, row) in self.cells.iter().enumerate() {
+                lines.push(format!(
+                    "Row {:<4} {}",
+                    self.height - index,
+                    row.iter().map(games).collect::<String>()
+                ));
+            }
+        }
+        lines.push(String::new());
+        lines.push(format!(
+            "Shading: {}",
+            SHADES
+                .iter()
+                .enumerate()
+                .map(|(level, shade)| format!("'{}' {}-{}%", shade, level * 20, level * 20 + 20))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+        lines.join("\n")
+    }
+
+    // The raw counts as JSON
+    pub fn json(&self) -> String {
+        let counts = |counts: &[Count]| {
+            let items: Vec<String> = counts
+                .iter()
+                .map(|count| format!("{{\"games\": {}, \"wins\": {}}}", count.games, count.wins))
+                .collect();
+            format!("[{}]", items.join(", "))
+        };
+        let rows: Vec<String> = self
+            .cells
+            .iter()
+            .map(|row| format!("    {}", counts(row)))
+            .collect();
+        format!(
+            "{{\n  \"width\": {},\n  \"height\": {},\n  \"first_move\": {},\n  \"cells\": [\n{}\n  ]\n}}",
+            self.width,
+            self.height,
+            counts(&self.columns),
+            rows.join(",\n")
+        )
+    }
+}
+
+// The heatmap of the logged games on a board of this size, and how many log lines were
+// unreadable
+pub fn build(width: usize, height: usize) -> Result<(Heatmap, u32), String> {
+    let mut heatmap = Heatmap::new(width, height);
+    let skipped = history::scan(|entry| heatmap.add(entry))?;
+    Ok((heatmap, skipped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_bucket_starts_at_its_own_edge() {
+        let edges = [
+            (0.0, 0),
+            (0.1999, 0),
+            (0.2, 1),
+            (0.3999, 1),
+            (0.4, 2),
+            (0.5999, 2),
+            (0.6, 3),
+            (0.7999, 3),
+            (0.8, 4),
+            (1.0, 4),
+        ];
+        for (rate, expected) in edges {
+            assert_eq!(bucket(rate), expected, "{}", rate);
+        }
+        // Rates worked out from counts land on the same edges
+        for wins in 0..=5 {
+            let count = Count { games: 5, wins };
+            assert_eq!(
+                bucket(count.win_rate()),
+                (wins as usize).min(4),
+                "{} of 5",
+                wins
+            );
+        }
+        // Out of range rates are kept in it
+        assert_eq!(bucket(-0.5), 0);
+        assert_eq!(bucket(1.5), 4);
+        assert_eq!(bucket(f64::NAN), 0);
+    }
+}
@@ -0,0 +1,649 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use crate::outcome::Outcome;
+use crate::profile;
+use crate::variant::{Move, Specials, Variant};
+use crate::{Game, Player};
+
+// Column titles of the game log
+const HEADER: &str =
+    "date\tplayer_one\tplayer_two\tcomputer\tresult\tmoves\tsize\tending\tid\tswap\thandicap\trandom_start\tvariant\tplayer_three\tplayer_four\tlines\tconnect\trule_preset";
+
+// Log of every finished game, a tab-separated line each. Names can't hold tabs, since
+// control characters are stripped from them.
+pub fn log_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("connect_four").join("games.tsv"))
+}
+
+// Add a finished game to the end of the log
+pub fn append(game: &Game, date: &str) -> Result<(), String> {
+    let path = log_path().ok_or("no data directory found")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|err| format!("could not create {}: {}", dir.display(), err))?;
+    }
+    let new = !path.exists();
+    let computer = match game.computer {
+        Some((seat, difficulty)) => format!(
+            "{}:{}",
+            seat_name(seat),
+            format!("{:?}", difficulty).to_lowercase()
+        ),
+        None => "-".to_string(),
+    };
+    let result = match game.winner() {
+        None => "draw",
+        Some(winner) => seat_name(winner),
+    };
+    let moves: Vec<String> = game
+        .history
+        .iter()
+        .enumerate()
+        .map(|(index, coord)| {
+            let mv = game.specials.at(index + 1, coord.column);
+            mv.notation(|column| (column + 1).to_string())
+        })
+        .collect();
+    let handicap: Vec<String> = game
+        .handicap
+        .iter()
+        .map(|column| (column + 1).to_string())
+        .collect();
+
+    // Players are logged in the seats a swap under the pie rule left them in, and "swap"
+    // marks that one happened. A handicap is the columns of its pieces, like the moves, and
+    // a random start how many of the moves it played and its seed, like "8:42". Under
+    // PopOut a pop is the column it popped after a p, like "p4", and under Power Up an anvil
+    // or a bomb the column after an a or a b. The names of a third and fourth player come
+    // next, left empty for the seats nobody sat in, then under the scoring variant each
+    // player's lines, like "3-2". Last come the pieces in a row needed to win and the name
+    // of the rule preset the game was played by.
+    let lines = match game.rules.variant {
+        Variant::Score => {
+            let [one, two] = game.line_tallies();
+            format!("{}-{}", one, two)
+        }
+        _ => "-".to_string(),
+    };
+    let mut line = format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}x{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+        date,
+        game.names[0],
+        game.names[1],
+        computer,
+        result,
+        moves.join(","),
+        game.rules.width,
+        game.rules.height,
+        ending(game),
+        game.id,
+        if game.swapped { "swap" } else { "-" },
+        if handicap.is_empty() {
+            "-".to_string()
+        } else {
+            handicap.join(",")
+        },
+        game.seed.map_or("-".to_string(), |seed| {
+            format!("{}:{}", game.random_start, seed)
+        }),
+        game.rules.variant.name(),
+        game.names.get(2).map_or("", String::as_str),
+        game.names.get(3).map_or("", String::as_str),
+        lines,
+        game.rules.connect,
+        game.rule_preset.as_deref().unwrap_or("-")
+    );
+    if new {
+        line = format!("{}\n{}", HEADER, line);
+    }
+    log::debug!("appending the game to {}", path.display());
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|err| format!("could not write {}: {}", path.display(), err))
+}
+
+// How a finished game ended: connect, time, resignation, agreement, full board, or lines
+// (more of them on a full board, under the scoring variant); empty while it goes on
+pub fn ending(game: &Game) -> &'static str {
+    game.outcome.as_ref().map_or("", Outcome::ending)
+}
+
+fn seat_name(seat: Player) -> &'static str {
+    match seat {
+        Player::One => "one",
+        Player::Two => "two",
+        Player::Three => "three",
+        Player::Four => "four",
+    }
+}
+
+// One game read back from the log
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub date: String,
+    // Names of the players in seat order, two to four of them
+    pub names: Vec<String>,
+    // None for a draw
+    pub winner: Option<Player>,
+    // 0-based columns in the order they were played, and the numbers of the moves, from 1,
+    // that weren't drops into them
+    pub moves: Vec<usize>,
+    pub specials: Specials,
+    // Columns and rows of the board
+    pub width: usize,
+    pub height: usize,
+    // Seat and level of the computer, like "two:hard", or "-"
+    pub computer: String,
+    // How the game ended: by connecting, resigning, agreeing to a draw, or filling the
+    // board; empty in logs from before it was recorded
+    pub ending: String,
+    // The game's ULID; empty in logs from before games had one
+    pub id: String,
+    // The second player took over the first move under the pie rule
+    pub swapped: bool,
+    // Columns of the pieces the second player was given before the first move
+    pub handicap: Vec<usize>,
+    // How many of the moves were played at random to start the game, and their seed
+    pub random_start: Option<(usize, u32)>,
+    // The rules the game was played by
+    pub variant: Variant,
+    // Each player's lines, under the scoring variant
+    pub lines: Option<[usize; 2]>,
+    // Pieces in a row needed to win, and the rule preset the game was played by; the
+    // first is None in logs from before it was recorded
+    pub connect: Option<usize>,
+    pub rule_preset: Option<String>,
+}
+
+impl Entry {
+    fn parse(line: &str) -> Option<Entry> {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [date, one, two, computer, result, moves, ref later @ ..] = fields[..] else {
+            return None;
+        };
+        // The first logs didn't give the board size, and were all played on the standard
+        // one, nor how the game ended, its id, whether there was a swap, the handicap, the
+        // random start, the variant, any players past the second, the scoring variant's
+        // lines, or the connect and rule preset
+        let (size, ending, id, swap, handicap, start, variant, others) = match later {
+            [] => ("7x6", "", "", "-", "-", "-", "standard", &[][..]),
+            [size] => (*size, "", "", "-", "-", "-", "standard", &[][..]),
+            [size, ending] => (*size, *ending, "", "-", "-", "-", "standard", &[][..]),
+            [size, ending, id] => (*size, *ending, *id, "-", "-", "-", "standard", &[][..]),
+            [size, ending, id, swap] => (*size, *ending, *id, *swap, "-", "-", "standard", &[][..]),
+            [size, ending, id, swap, handicap] => (
+                *size,
+                *ending,
+                *id,
+                *swap,
+                *handicap,
+                "-",
+                "standard",
+                &[][..],
+            ),
+            [size, ending, id, swap, handicap, start] => (
+                *size,
+                *ending,
+                *id,
+                *swap,
+                *handicap,
+                *start,
+                "standard",
+                &[][..],
+            ),
+            [size, ending, id, swap, handicap, start, variant] => (
+                *size,
+                *ending,
+                *id,
+                *swap,
+                *handicap,
+                *start,
+                *variant,
+                &[][..],
+            ),
+            [size, ending, id, swap, handicap, start, variant, ref others @ ..]
+                if (2..=5).contains(&others.len()) =>
+            {
+                (
+                    *size, *ending, *id, *swap, *handicap, *start, *variant, others,
+                )
+            }
+            _ => return None,
+        };
+        let (width, height) = size.split_once('x')?;
+        let (width, height) = (width.parse().ok()?, height.parse().ok()?);
+        let mut names = vec![one.to_string(), two.to_string()];
+        names.extend(
+            others
+                .iter()
+                .take(2)
+                .take_while(|name| !name.is_empty())
+                .map(|name| name.to_string()),
+        );
+        let lines = match others.get(2) {
+            None | Some(&"-") => None,
+            Some(lines) => {
+                let (one, two) = lines.split_once('-')?;
+                Some([one.parse().ok()?, two.parse().ok()?])
+            }
+        };
+        let connect = match others.get(3) {
+            None => None,
+            Some(connect) => Some(connect.parse().ok()?),
+        };
+        let rule_preset = match others.get(4) {
+            None | Some(&"-") => None,
+            Some(preset) => Some(preset.to_string()),
+        };
+        let winner = match result {
+            "one" => Some(Player::One),
+            "two" => Some(Player::Two),
+            "three" if names.len() > 2 => Some(Player::Three),
+            "four" if names.len() > 3 => Some(Player::Four),
+            "draw" => None,
+            _ => return None,
+        };
+        let columns = |text: &str| -> Option<Vec<usize>> {
+            if text.is_empty() || text == "-" {
+                return Some(Vec::new());
+            }
+            text.split(',')
+                .map(|column| column.parse::<usize>().ok()?.checked_sub(1))
+                .collect()
+        };
+        // Pops, anvils, and bombs are their column after a letter, and flips are "flip"
+        let mut specials = Specials::default();
+        let moves = match moves {
+            "" | "-" => Vec::new(),
+            moves => moves
+                .split(',')
+                .enumerate()
+                .map(|(index, word)| {
+                    let mv =
+                        Move::parse(word, |column| column.parse::<usize>().ok()?.checked_sub(1))?;
+                    specials.record(index + 1, mv);
+                    Some(mv.column())
+                })
+                .collect::<Option<Vec<usize>>>()?,
+        };
+        let handicap = columns(handicap)?;
+        let variant = Variant::parse(variant)?;
+        let random_start = match start.split_once(':') {
+            None if start == "-" => None,
+            None => return None,
+            Some((plies, seed)) => Some((plies.parse().ok()?, seed.parse().ok()?))
+                .filter(|&(plies, _)| plies <= moves.len()),
+        };
+        Some(Entry {
+            date: date.to_string(),
+            names,
+            winner,
+            moves,
+            specials,
+            width,
+            height,
+            computer: computer.to_string(),
+            ending: ending.to_string(),
+            id: id.to_string(),
+            swapped: swap == "swap",
+            handicap,
+            random_start,
+            variant,
+            lines,
+            connect,
+            rule_preset,
+        })
+    }
+
+    // The seat `name` played in, if they played
+    fn seat(&self, name: &str) -> Option<Player> {
+        let name = name.to_lowercase();
+        self.names
+            .iter()
+            .position(|seat| seat.to_lowercase() == name)
+            .map(Player::from_index)
+    }
+
+    // The seat that played move number `ply`, from 1, going round the players in turn
+    pub fn seat_of(&self, ply: usize) -> Player {
+        Player::from_index((ply + self.names.len() - 1) % self.names.len())
+    }
+}
+
+// Which logged games to count
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Filter {
+    // Only games this player took part in, reported from their side
+    pub player: Option<String>,
+    // Only games on or after this date, as YYYY-MM-DD
+    pub since: Option<String>,
+}
+
+impl Filter {
+    // Whether to count `entry`, and if so the side its results are counted from: the
+    // filtered player's seat, or Player 1
+    pub fn side(&self, entry: &Entry) -> Option<Player> {
+        if self.since.as_ref().is_some_and(|since| entry.date < *since) {
+            return None;
+        }
+        match &self.player {
+            Some(name) => entry.seat(name),
+            None => Some(Player::One),
+        }
+    }
+}
+
+// Wins, losses, and draws as counted from one side
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tally {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Tally {
+    fn add(&mut self, winner: Option<Player>, side: Player) {
+        match winner {
+            None => self.draws += 1,
+            Some(winner) if winner == side => self.wins += 1,
+            Some(_) => self.losses += 1,
+        }
+    }
+
+    fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    fn win_rate(&self) -> f64 {
+        match self.games() {
+            0 => 0.0,
+            games => f64::from(self.wins) / f64::from(games),
+        }
+    }
+}
+
+// Figures over the logged games, built up one game at a time
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub player: Option<String>,
+    pub games: u32,
+    pub moves: u32,
+    // Results from the first player's side, or from the filtered player's
+    pub overall: Tally,
+    // Results as counted by the first move's column, from the first player's side
+    pub first_columns: Vec<Tally>,
+    // The filtered player's results from each seat, Player 1 first
+    pub by_seat: [Tally; 4],
+    // Results by month, YYYY-MM, from the same side as `overall`
+    pub by_month: BTreeMap<String, Tally>,
+    // Games under the scoring variant, and the lines made in them from the same side
+    pub scored_games: u32,
+    pub lines: u32,
+}
+
+impl Summary {
+    fn add(&mut self, entry: &Entry, filter: &Filter) {
+        let Some(side) = filter.side(entry) else {
+            return;
+        };
+
+        self.games += 1;
+        self.moves += entry.moves.len() as u32;
+        self.overall.add(entry.winner, side);
+        if let Some(&first) = entry.moves.first() {
+            if self.first_columns.len() <= first {
+                self.first_columns.resize(first + 1, Tally::default());
+            }
+            self.first_columns[first].add(entry.winner, Player::One);
+        }
+        if filter.player.is_some() {
+            let seat = side.index();
+            self.by_seat[seat].add(entry.winner, side);
+            // Named as they last played, whatever case the filter was typed in
+            self.player = Some(entry.names[seat].clone());
+        }
+        if let Some(&lines) = entry
+            .lines
+            .as_ref()
+            .and_then(|lines| lines.get(side.index()))
+        {
+            self.scored_games += 1;
+            self.lines += lines as u32;
+        }
+        let month = entry.date.get(..7).unwrap_or(&entry.date).to_string();
+        self.by_month
+            .entry(month)
+            .or_default()
+            .add(entry.winner, side);
+    }
+
+    // Whose side the results are counted from
+    fn side(&self) -> &str {
+        self.player.as_deref().unwrap_or("Player 1")
+    }
+
+    // The figures as tables
+    pub fn report(&self) -> String {
+        if self.games == 0 {
+            return "No games recorded".to_string();
+        }
+        let percent = |tally: &Tally| format!("{:.0}%", tally.win_rate() * 100.0);
+        let mut lines = vec![
+            format!("Games                  {}", self.games),
+            format!(
+                "Average game length    {:.1} moves",
+                f64::from(self.moves) / f64::from(self.games)
+            ),
+            format!(
+                "{:<22} {} won, {} lost, {} drawn ({})",
+                format!("{} results", self.side()),
+                self.overall.wins,
+                self.overall.losses,
+                self.overall.draws,
+                percent(&self.overall)
+            ),
+        ];
+        if self.scored_games > 0 {
+            lines.push(format!(
+                "{:<22} {} in {} ({:.1} a game)",
+                "Lines, scoring games",
+                self.lines,
+                profile::plural(self.scored_games, "game"),
+                f64::from(self.lines) / f64::from(self.scored_games)
+            ));
+        }
+        if self.player.is_some() {
+            lines.push(String::new());
+            lines.push("Seat        Games  Won  Lost  Drawn  Win%".to_string());
+            // The third and fourth seats only when the player has sat in them
+            for (index, (seat, tally)) in Player::SEATS.iter().zip(&self.by_seat).enumerate() {
+                if index >= 2 && tally.games() == 0 {
+                    continue;
+                }
+                lines.push(format!(
+                    "{:<10}  {:>5}  {:>3}  {:>4}  {:>5}  {:>4}",
+                    seat,
+                    tally.games(),
+                    tally.wins,
+                    tally.losses,
+                    tally.draws,
+                    percent(tally)
+                ));
+            }
+        }
+        lines.push(String::new());
+        lines.push("First move  Games  Player 1 win%".to_string());
+        for (column, tally) in self.first_columns.iter().enumerate() {
+            if tally.games() > 0 {
+                lines.push(format!(
+                    "Column {:<3}  {:>5}  {:>13}",
+                    column + 1,
+                    tally.games(),
+                    percent(tally)
+                ));
+            }
+        }
+        lines.push(String::new());
+        lines.push(format!(
+            "Month       Games  Won  Lost  Drawn  ({})",
+            self.side()
+        ));
+        for (month, tally) in &self.by_month {
+            lines.push(format!(
+                "{:<10}  {:>5}  {:>3}  {:>4}  {:>5}",
+                month,
+                tally.games(),
+                tally.wins,
+                tally.losses,
+                tally.draws
+            ));
+        }
+        lines.join("\n")
+    }
+
+    // A few headline figures, short enough for the TUI's side panel
+    pub fn panel(&self) -> Vec<String> {
+        if self.games == 0 {
+            return vec!["No games recorded".to_string()];
+        }
+        let share =
+            |count: u32| format!("{:.0}%", f64::from(count) * 100.0 / f64::from(self.games));
+        let mut lines = vec![
+            format!("Games       {}", self.games),
+            format!(
+                "Avg length  {:.1}",
+                f64::from(self.moves) / f64::from(self.games)
+            ),
+            format!("P1 wins     {}", share(self.overall.wins)),
+            format!("P2 wins     {}", share(self.overall.losses)),
+            format!("Draws       {}", share(self.overall.draws)),
+        ];
+        let best = self
+            .first_columns
+            .iter()
+            .enumerate()
+            .filter(|(_, tally)| tally.games() > 0)
+            .max_by(|(_, a), (_, b)| a.win_rate().total_cmp(&b.win_rate()));
+        if let Some((column, tally)) = best {
+            lines.push(format!(
+                "Best opener {} ({:.0}%)",
+                column + 1,
+                tally.win_rate() * 100.0
+            ));
+        }
+        lines
+    }
+
+    // The figures as a JSON object
+    pub fn json(&self) -> String {
+        let tally = |tally: &Tally| {
+            format!(
+                "{{\"games\": {}, \"wins\": {}, \"losses\": {}, \"draws\": {}}}",
+                tally.games(),
+                tally.wins,
+                tally.losses,
+                tally.draws
+            )
+        };
+        let columns: Vec<String> = self
+            .first_columns
+            .iter()
+            .enumerate()
+            .filter(|(_, counts)| counts.games() > 0)
+            .map(|(column, counts)| format!("    \"{}\": {}", column + 1, tally(counts)))
+            .collect();
+        let months: Vec<String> = self
+            .by_month
+            .iter()
+            .map(|(month, counts)| format!("    \"{}\": {}", month, tally(counts)))
+            .collect();
+        let mut fields = vec![
+            format!("  \"games\": {}", self.games),
+            format!("  \"moves\": {}", self.moves),
+            format!("  \"results\": {}", tally(&self.overall)),
+            format!("  \"first_move\": {{\n{}\n  }}", columns.join(",\n")),
+            format!("  \"by_month\": {{\n{}\n  }}", months.join(",\n")),
+        ];
+        if self.scored_games > 0 {
+            fields.push(format!(
+                "  \"scoring\": {{\"games\": {}, \"lines\": {}}}",
+                self.scored_games, self.lines
+            ));
+        }
+        if self.player.is_some() {
+            fields.push(format!(
+                "  \"as_player_one\": {},\n  \"as_player_two\": {}",
+                tally(&self.by_seat[0]),
+                tally(&self.by_seat[1])
+            ));
+            for (seat, counts) in ["three", "four"].iter().zip(&self.by_seat[2..]) {
+                if counts.games() > 0 {
+                    fields.push(format!("  \"as_player_{}\": {}", seat, tally(counts)));
+                }
+            }
+        }
+        format!("{{\n{}\n}}", fields.join(",\n"))
+    }
+}
+
+// The game at this position of the log, from 1
+pub fn find(number: usize) -> Result<Option<Entry>, String> {
+    let mut found = None;
+    let mut position = 0;
+    scan(|entry| {
+        position += 1;
+        if position == number {
+            found = Some(entry.clone());
+        }
+    })?;
+    Ok(found)
+}
+
+// The logged game with this id
+pub fn find_id(id: &str) -> Result<Option<Entry>, String> {
+    let mut found = None;
+    scan(|entry| {
+        if found.is_none() && entry.id.eq_ignore_ascii_case(id) {
+            found = Some(entry.clone());
+        }
+    })?;
+    Ok(found)
+}
+
+// Count the games the filter lets through
+pub fn summarize(filter: &Filter) -> Result<(Summary, u32), String> {
+    let mut summary = Summary {
+        player: filter.player.clone(),
+        ..Summary::default()
+    };
+    let skipped = scan(|entry| summary.add(entry, filter))?;
+    Ok((summary, skipped))
+}
+
+// Go through the log a line at a time, handing each game to `visit`, so even a long log
+// is never read into memory whole. Returns how many lines couldn't be read and were skipped.
+pub fn scan(mut visit: impl FnMut(&Entry)) -> Result<u32, String> {
+    let Some(path) = log_path().filter(|path| path.exists()) else {
+        return Ok(0);
+    };
+    let file = fs::File::open(&path)
+        .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+    let mut skipped = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+        if line.starts_with("date\t") || line.is_empty() {
+            continue;
+        }
+        match Entry::parse(&line) {
+            Some(entry) => visit(&entry),
+            None => skipped += 1,
+        }
+    }
+    Ok(skipped)
+}
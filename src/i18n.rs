@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+// Languages the messages come in, by the code --lang takes; English comes first, as the one
+// every other falls back to
+pub const LANGUAGES: [&str; 2] = ["en", "es"];
+
+// The messages of each language in LANGUAGES, in the format described at the top of en.txt
+const CATALOGS: [&str; 2] = [include_str!("i18n/en.txt"), include_str!("i18n/es.txt")];
+
+// Messages in the language picked for the session, and in English for any it lacks
+struct Catalog {
+    chosen: HashMap<&'static str, &'static str>,
+    english: HashMap<&'static str, &'static str>,
+}
+
+// The session's messages, in English until `init` picks a language
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(|| Catalog {
+        chosen: parse(CATALOGS[0]),
+        english: parse(CATALOGS[0]),
+    })
+}
+
+// Show messages in `language` from here on. Returns what's wrong with its messages, on one
+// line for a warning at startup: texts it lacks, which are shown in English, and names in
+// braces nothing fills in.
+pub fn init(language: &str) -> Option<String> {
+    let index = LANGUAGES
+        .iter()
+        .position(|&known| known == language)
+        .unwrap_or(0);
+    let catalog = Catalog {
+        chosen: parse(CATALOGS[index]),
+        english: parse(CATALOGS[0]),
+    };
+    let problems = catalog.problems();
+    let warning = (!problems.is_empty()).then(|| {
+        catalog.fill(
+            "i18n.problems",
+            &[("language", &language), ("problems", &problems.join("; "))],
+        )
+    });
+    if CATALOG.set(catalog).is_err() {
+        log::warn!("messages were shown before the language was picked");
+    }
+    warning
+}
+
+impl Catalog {
+    // What's wrong with the chosen language's messages, in that language
+    fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let missing = self
+            .english
+            .keys()
+            .filter(|key| !self.chosen.contains_key(*key))
+            .count();
+        if missing > 0 {
+            problems.push(self.fill("i18n.missing", &[("count", &missing)]));
+        }
+        let mut keys: Vec<&&str> = self.chosen.keys().collect();
+        keys.sort();
+        for key in keys {
+            let Some(english) = self.english.get(key) else {
+                problems.push(self.fill("i18n.unknown", &[("key", key)]));
+                continue;
+            };
+            match (placeholders(self.chosen[key]), placeholders(english)) {
+                (Some(names), Some(known)) => {
+                    if let Some(name) = names.iter().find(|name| !known.contains(name)) {
+                        problems.push(self.fill("i18n.unfilled", &[("key", key), ("name", name)]));
+                    }
+                }
+                _ => problems.push(self.fill("i18n.unclosed", &[("key", key)])),
+            }
+        }
+        problems
+    }
+
+    // The message `key` with each {name} in it replaced by the value given for it
+    fn fill(&self, key: &str, values: &[(&str, &dyn fmt::Display)]) -> String {
+        let text = self
+            .chosen
+            .get(key)
+            .or_else(|| self.english.get(key))
+            .copied()
+            .unwrap_or(key);
+        let mut filled = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('{') {
+            filled.push_str(&rest[..start]);
+            let Some((name, after)) = rest[start + 1..].split_once('}') else {
+                rest = &rest[start..];
+                break;
+            };
+            match values.iter().find(|(known, _)| *known == name) {
+                Some((_, value)) => filled.push_str(&value.to_string()),
+                None => filled.push_str(&rest[start..start + name.len() + 2]),
+            }
+            rest = after;
+        }
+        filled.push_str(rest);
+        filled
+    }
+}
+
+// The entries of a catalog: a key, "=", and its text on every line that isn't blank or a
+// comment
+fn parse(text: &'static str) -> HashMap<&'static str, &'static str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, text)| (key.trim(), text.trim()))
+        .collect()
+}
+
+// The names in braces in `text`, or None when a brace isn't closed
+fn placeholders(text: &str) -> Option<Vec<&str>> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let (name, after) = rest[start + 1..].split_once('}')?;
+        names.push(name);
+        rest = after;
+    }
+    Some(names)
+}
+
+// The message `key` in the session's language
+pub fn fill(key: &str, values: &[(&str, &dyn fmt::Display)]) -> String {
+    catalog().fill(key, values)
+}
+
+// The message `key` as it stands, with nothing to fill in
+pub fn text(key: &str) -> String {
+    fill(key, &[])
+}
+
+// The form of the message `key` for `count` of something, "key.one" or "key.other", with
+// {count} filled in. English and Spanish both take the singular for one alone.
+pub fn plural(key: &str, count: usize) -> String {
+    let form = if count == 1 { "one" } else { "other" };
+    fill(&format!("{}.{}", key, form), &[("count", &count)])
+}
+
+// The language of a locale like "es_ES.UTF-8", when there are messages in it
+pub fn from_locale(locale: &str) -> Option<&'static str> {
+    let code = locale.split(['_', '.', '@', '-']).next()?.to_lowercase();
+    LANGUAGES.iter().find(|&&known| known == code).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_in_braces_are_filled_in() {
+        // A key with no message is shown as it stands, so these fill in the key itself
+        let cases = [
+            ("{name} has won", "Ann has won"),
+            ("{name} and {name}", "Ann and Ann"),
+            ("{other} stays", "{other} stays"),
+            ("abc{def", "abc{def"),
+            ("{name} then {", "Ann then {"),
+            ("no braces", "no braces"),
+        ];
+        for (text, filled) in cases {
+            assert_eq!(fill(text, &[("name", &"Ann")]), filled, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn every_language_has_the_messages_english_has_and_no_others() {
+        let english = parse(CATALOGS[0]);
+        for (language, text) in LANGUAGES.iter().zip(CATALOGS).skip(1) {
+            let chosen = parse(text);
+            let mut missing: Vec<&str> = english
+                .keys()
+                .filter(|key| !chosen.contains_key(*key))
+                .copied()
+                .collect();
+            let mut unknown: Vec<&str> = chosen
+                .keys()
+                .filter(|key| !english.contains_key(*key))
+                .copied()
+                .collect();
+            missing.sort();
+            unknown.sort();
+            assert_eq!(missing, Vec::<&str>::new(), "missing from {}", language);
+            assert_eq!(unknown, Vec::<&str>::new(), "only in {}", language);
+            let catalog = Catalog {
+                chosen,
+                english: english.clone(),
+            };
+            assert_eq!(catalog.problems(), Vec::<String>::new(), "{}", language);
+        }
+    }
+
+    #[test]
+    fn errors_are_shown_in_the_language_chosen() {
+        let spanish = Catalog {
+            chosen: parse(CATALOGS[1]),
+            english: parse(CATALOGS[0]),
+        };
+        let unknown = spanish.fill("command.unknown", &[]);
+        assert_eq!(
+            spanish.fill("error", &[("message", &unknown)]),
+            "Error: comando desconocido, prueba :help"
+        );
+        let off_board = spanish.fill("command.off_board", &[("text", &"9"), ("width", &7)]);
+        assert_eq!(
+            spanish.fill("warning", &[("message", &off_board)]),
+            "Aviso: '9' no es una columna de este tablero de 7 de ancho"
+        );
+    }
+}
@@ -0,0 +1,32 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+
+use crate::now::{self, TimeSource};
+
+// Crockford's base 32, which leaves out I, L, O, and U so ids can't be misread
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+// Characters in an id: 10 for the time, 16 for the random part
+pub const LENGTH: usize = 26;
+
+// A new ULID: the milliseconds since 1970 followed by 80 random bits, 26 characters long.
+// Ids made later sort after earlier ones.
+pub fn ulid() -> String {
+    let millis = now::System.wall().as_millis();
+    let state = RandomState::new();
+    let random = (u128::from(state.hash_one(millis)) << 64 | u128::from(state.hash_one(!millis)))
+        & ((1 << 80) - 1);
+    let value = (millis & ((1 << 48) - 1)) << 80 | random;
+    (0..LENGTH)
+        .rev()
+        .map(|index| char::from(ALPHABET[(value >> (index * 5)) as usize & 31]))
+        .collect()
+}
+
+// Whether `text` could be an id: the right length, in the id alphabet, either case
+pub fn is_ulid(text: &str) -> bool {
+    text.len() == LENGTH
+        && text
+            .bytes()
+            .all(|byte| ALPHABET.contains(&byte.to_ascii_uppercase()))
+}
@@ -0,0 +1,460 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::execute;
+
+use crate::coord::Coord;
+use crate::i18n;
+use crate::render::Layout;
+use crate::settings::Digits;
+use crate::variant::Move;
+
+// What a key press or mouse event asks for
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    // Move the cursor one column left (-1) or right (1)
+    Step(isize),
+    Select(usize),
+    // The mouse moved over a column; unlike selecting, full columns are passed over quietly
+    Hover(usize),
+    Drop(usize),
+    // A move other than a drop: a pop under PopOut, or an anvil or a bomb under Power Up
+    Special(Move),
+    // A click that missed the board
+    Outside,
+    Undo,
+    // Pause a timed game
+    Pause,
+    // Take over the first move under the pie rule
+    Swap,
+    // Look at the hidden board of a blind game
+    Peek,
+    // List the keys
+    Help,
+    // Leave the game, after asking
+    Quit,
+    // Leave at once, on Ctrl-C
+    Interrupt,
+    Ignore,
+}
+
+// How long a digit waits for a second one on a board more than nine columns wide, so 1 then 2
+// is column 12
+pub const SECOND_DIGIT: Duration = Duration::from_millis(500);
+
+// Longest a wait for input is counted out. A clock read from a save or settings can give a
+// player more time than an Instant can be moved on by, and a year is as good as forever.
+const LONGEST_WAIT: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+// When a wait of `timeout` from now runs out
+fn deadline(timeout: Duration) -> Instant {
+    Instant::now() + timeout.min(LONGEST_WAIT)
+}
+
+// Map a key press to an action given the column under the cursor
+pub fn action(key: KeyEvent, selected: Option<usize>, digits: Digits) -> Action {
+    if key.kind == KeyEventKind::Release {
+        return Action::Ignore;
+    }
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Action::Interrupt,
+        KeyCode::Left | KeyCode::Char('h') => Action::Step(-1),
+        KeyCode::Right | KeyCode::Char('l') => Action::Step(1),
+        KeyCode::Enter | KeyCode::Char(' ') => selected.map_or(Action::Ignore, Action::Drop),
+        KeyCode::Char('o') => selected.map_or(Action::Ignore, |column| {
+            Action::Special(Move::Pop(column))
+        }),
+        KeyCode::Char('A') => selected.map_or(Action::Ignore, |column| {
+            Action::Special(Move::Anvil(column))
+        }),
+        KeyCode::Char('B') => selected.map_or(Action::Ignore, |column| {
+            Action::Special(Move::Bomb(column))
+        }),
+        KeyCode::Char('F') => Action::Special(Move::Flip),
+        KeyCode::Char('u') => Action::Undo,
+        KeyCode::Char('p') => Action::Pause,
+        KeyCode::Char('s') => Action::Swap,
+        KeyCode::Char('v') => Action::Peek,
+        KeyCode::Char('?') => Action::Help,
+        KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+        // Every other digit or letter is a column. The keys above keep their actions on a
+        // board wide enough to have a column h, l, or o, which is picked by moving to it.
+        KeyCode::Char(symbol) => match Coord::parse_column(&symbol.to_string()) {
+            Some(column) => column_action(column, selected, digits),
+            None => Action::Ignore,
+        },
+        _ => Action::Ignore,
+    }
+}
+
+// What naming `column` with its key does: drop into it with digits set to drop or when it's
+// already selected, and otherwise select it
+pub fn column_action(column: usize, selected: Option<usize>, digits: Digits) -> Action {
+    if digits == Digits::Drop || selected == Some(column) {
+        Action::Drop(column)
+    } else {
+        Action::Select(column)
+    }
+}
+
+// Whether `key` is a digit that a second one could follow to name a column of a board
+// `width` columns wide
+pub fn starts_number(key: &KeyEvent, width: usize) -> bool {
+    key.kind != KeyEventKind::Release
+        && matches!(key.code, KeyCode::Char(first) if first
+            .to_digit(10)
+            .is_some_and(|first| first > 0 && first as usize * 10 <= width))
+}
+
+// The 0-based column that the digit `first` then `second` name together, if the board has it
+pub fn two_digit_column(first: &KeyEvent, second: &Event, width: usize) -> Option<usize> {
+    let (KeyCode::Char(first), Event::Key(second)) = (first.code, second) else {
+        return None;
+    };
+    let KeyCode::Char(second) = second.code else {
+        return None;
+    };
+    let number = first.to_digit(10)? * 10 + second.to_digit(10)?;
+    (1..=width).contains(&(number as usize)).then(|| number as usize - 1)
+}
+
+// The keys of key input as ? lists them: the message naming each set of keys and the one
+// saying what they do, with no keys for a line that carries on from the one before
+const KEY_HELP: [(&str, &str); 13] = [
+    ("keys.cursor", "keys.cursor.does"),
+    ("keys.drop", "keys.drop.does"),
+    ("keys.column", "keys.column.does"),
+    ("", "keys.column.wide"),
+    ("keys.pop", "keys.pop.does"),
+    ("keys.powers", "keys.powers.does"),
+    ("keys.flip", "keys.flip.does"),
+    ("keys.undo", "keys.undo.does"),
+    ("keys.swap", "keys.swap.does"),
+    ("keys.pause", "keys.pause.does"),
+    ("keys.peek", "keys.peek.does"),
+    ("keys.quit", "keys.quit.does"),
+    ("keys.help", "keys.help.does"),
+];
+
+// The keys of key input, for ? to list
+pub fn help() -> String {
+    let lines: Vec<(String, String)> = KEY_HELP
+        .iter()
+        .map(|&(keys, does)| {
+            let keys = if keys.is_empty() { String::new() } else { i18n::text(keys) };
+            (keys, i18n::text(does))
+        })
+        .collect();
+    let width = lines.iter().map(|(keys, _)| keys.chars().count()).max().unwrap_or(0);
+    lines
+        .iter()
+        .map(|(keys, does)| format!("{:<width$}  {}", keys, does, width = width))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Map a mouse event to an action: a left click drops into the column under the pointer, a
+// right click pops it, and moving the pointer selects it. `x` is measured from the left edge
+// of the board's rows using the board's layout.
+pub fn mouse_action(
+    mouse: MouseEvent,
+    x: Option<usize>,
+    layout: &Layout,
+    columns: usize,
+) -> Action {
+    let column = x.and_then(|x| layout.column_at(x, columns));
+    match (mouse.kind, column) {
+        (MouseEventKind::Down(MouseButton::Left), Some(column)) => Action::Drop(column),
+        (MouseEventKind::Down(MouseButton::Right), Some(column)) => {
+            Action::Special(Move::Pop(column))
+        }
+        (MouseEventKind::Down(MouseButton::Left), None) => Action::Outside,
+        (MouseEventKind::Moved, Some(column)) => Action::Hover(column),
+        _ => Action::Ignore,
+    }
+}
+
+// Raw mode with mouse reporting for as long as the guard lives, so both are also turned off
+// when a panic unwinds and the terminal's own selection works again
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> io::Result<RawMode> {
+        terminal::enable_raw_mode()?;
+        let raw = RawMode;
+        execute!(io::stdout(), EnableMouseCapture)?;
+        Ok(raw)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), DisableMouseCapture);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+// Wait for the next key press or mouse event. The terminal is only raw while waiting, so
+// everything printed in between keeps its normal line endings.
+pub fn read_input() -> io::Result<Event> {
+    let _raw = RawMode::enable()?;
+    loop {
+        let event = event::read()?;
+        if matches!(event, Event::Key(_) | Event::Mouse(_)) {
+            return Ok(event);
+        }
+    }
+}
+
+// Wait up to `timeout` for the next key press or mouse event, or with None for as long as it
+// takes. None when the time ran out first.
+pub fn poll_input(timeout: Option<Duration>) -> io::Result<Option<Event>> {
+    let Some(timeout) = timeout else {
+        return read_input().map(Some);
+    };
+    let deadline = deadline(timeout);
+    let _raw = RawMode::enable()?;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if !event::poll(left)? {
+            return Ok(None);
+        }
+        let event = event::read()?;
+        if matches!(event, Event::Key(_) | Event::Mouse(_)) {
+            return Ok(Some(event));
+        }
+    }
+}
+
+// Read a line typed at the terminal within `timeout`, echoing it after `status`, which is
+// redrawn every second so a countdown in it stays current. Like a read from stdin the line
+// ends in a newline, and is empty when the player ended the input with Ctrl-D or Ctrl-C.
+// None when the time ran out first.
+pub fn read_line(timeout: Duration, status: impl Fn() -> String) -> io::Result<Option<String>> {
+    let deadline = deadline(timeout);
+    let _raw = RawMode::enable()?;
+    let mut line = String::new();
+    let mut out = io::stdout();
+    loop {
+        write!(out, "\r{}{}{}", Clear(ClearType::CurrentLine), status(), line)?;
+        out.flush()?;
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            write!(out, "\r\n")?;
+            return Ok(None);
+        }
+        if !event::poll(left.min(Duration::from_secs(1)))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('c' | 'd') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                write!(out, "\r\n")?;
+                return Ok(Some(String::new()));
+            }
+            KeyCode::Enter => {
+                write!(out, "\r\n")?;
+                line.push('\n');
+                return Ok(Some(line));
+            }
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            KeyCode::Char(c) => line.push(c),
+            _ => {}
+        }
+    }
+}
+
+// Wait for the next key press, ignoring the mouse
+pub fn read_key() -> io::Result<KeyEvent> {
+    loop {
+        if let Event::Key(key) = read_input()? {
+            return Ok(key);
+        }
+    }
+}
+
+// Wait up to `timeout` for a key press, or with None for as long as it takes. None when the
+// time ran out first.
+pub fn poll_key(timeout: Option<Duration>) -> io::Result<Option<KeyEvent>> {
+    let Some(timeout) = timeout else {
+        return read_key().map(Some);
+    };
+    let deadline = deadline(timeout);
+    let _raw = RawMode::enable()?;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if !event::poll(left)? {
+            return Ok(None);
+        }
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Release {
+                return Ok(Some(key));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyEventState, MouseEvent};
+
+    // A key pressed with no modifiers
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    // A character key pressed on its own
+    fn key(symbol: char) -> KeyEvent {
+        press(KeyCode::Char(symbol))
+    }
+
+    #[test]
+    fn keys_map_to_actions() {
+        let cases = [
+            (press(KeyCode::Left), Action::Step(-1)),
+            (key('h'), Action::Step(-1)),
+            (press(KeyCode::Right), Action::Step(1)),
+            (key('l'), Action::Step(1)),
+            (press(KeyCode::Enter), Action::Drop(2)),
+            (key(' '), Action::Drop(2)),
+            (key('o'), Action::Special(Move::Pop(2))),
+            (key('A'), Action::Special(Move::Anvil(2))),
+            (key('B'), Action::Special(Move::Bomb(2))),
+            (key('F'), Action::Special(Move::Flip)),
+            (key('u'), Action::Undo),
+            (key('p'), Action::Pause),
+            (key('s'), Action::Swap),
+            (key('v'), Action::Peek),
+            (key('?'), Action::Help),
+            (key('q'), Action::Quit),
+            (press(KeyCode::Esc), Action::Quit),
+            (KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL), Action::Interrupt),
+            (key('c'), Action::Drop(2)),
+            (key('4'), Action::Select(3)),
+            (key('d'), Action::Select(3)),
+            (key('D'), Action::Select(3)),
+            (key('0'), Action::Ignore),
+            (key('!'), Action::Ignore),
+            (press(KeyCode::Tab), Action::Ignore),
+            (press(KeyCode::F(1)), Action::Ignore),
+        ];
+        for (key, expected) in cases {
+            assert_eq!(action(key, Some(2), Digits::Select), expected, "{:?}", key.code);
+        }
+    }
+
+    #[test]
+    fn nothing_selected_leaves_nothing_to_drop_or_pop() {
+        let specials = [KeyCode::Char('o'), KeyCode::Char('A'), KeyCode::Char('B')];
+        for code in [KeyCode::Enter, KeyCode::Char(' ')].into_iter().chain(specials) {
+            assert_eq!(action(press(code), None, Digits::Select), Action::Ignore, "{:?}", code);
+        }
+        assert_eq!(action(key('4'), None, Digits::Select), Action::Select(3));
+    }
+
+    #[test]
+    fn digits_select_or_drop() {
+        assert_eq!(action(key('4'), None, Digits::Drop), Action::Drop(3));
+        assert_eq!(action(key('4'), Some(3), Digits::Select), Action::Drop(3));
+        assert_eq!(action(key('4'), Some(1), Digits::Select), Action::Select(3));
+        assert_eq!(column_action(0, Some(0), Digits::Select), Action::Drop(0));
+    }
+
+    #[test]
+    fn key_releases_are_ignored() {
+        let release = KeyEvent {
+            code: KeyCode::Char('4'),
+            modifiers: KeyModifiers::NONE,
+            kind: KeyEventKind::Release,
+            state: KeyEventState::NONE,
+        };
+        assert_eq!(action(release, None, Digits::Drop), Action::Ignore);
+        assert!(!starts_number(&release, 12));
+    }
+
+    #[test]
+    fn a_first_digit_waits_only_when_a_second_could_follow() {
+        assert!(starts_number(&key('1'), 12));
+        assert!(starts_number(&key('1'), 10));
+        assert!(!starts_number(&key('1'), 9));
+        assert!(!starts_number(&key('2'), 12));
+        assert!(starts_number(&key('2'), 20));
+        assert!(!starts_number(&key('0'), 12));
+        assert!(!starts_number(&key('a'), 12));
+    }
+
+    #[test]
+    fn two_digits_name_one_column() {
+        let second = |symbol| Event::Key(key(symbol));
+        assert_eq!(two_digit_column(&key('1'), &second('2'), 12), Some(11));
+        assert_eq!(two_digit_column(&key('1'), &second('0'), 12), Some(9));
+        assert_eq!(two_digit_column(&key('2'), &second('0'), 20), Some(19));
+        assert_eq!(two_digit_column(&key('1'), &second('3'), 12), None);
+        assert_eq!(two_digit_column(&key('1'), &second('x'), 12), None);
+        assert_eq!(two_digit_column(&key('1'), &Event::Resize(80, 24), 12), None);
+        assert_eq!(two_digit_column(&key('x'), &second('1'), 12), None);
+    }
+
+    #[test]
+    fn waits_longer_than_an_instant_can_reach_end_in_a_year() {
+        let start = Instant::now();
+        let end = deadline(Duration::MAX);
+        assert!(end >= start + LONGEST_WAIT);
+        assert!(end <= Instant::now() + LONGEST_WAIT);
+        assert!(deadline(SECOND_DIGIT) <= Instant::now() + SECOND_DIGIT);
+    }
+
+    #[test]
+    fn mouse_events_map_to_columns() {
+        let layout = Layout {
+            cell_width: 1,
+            separator: " ",
+            borders: false,
+            margin: 0,
+            scale: 1,
+            open_sides: false,
+        };
+        let mouse = |kind| MouseEvent {
+            kind,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        };
+        let left = mouse(MouseEventKind::Down(MouseButton::Left));
+        assert_eq!(mouse_action(left, Some(0), &layout, 7), Action::Drop(0));
+        assert_eq!(mouse_action(left, Some(7), &layout, 7), Action::Drop(3));
+        assert_eq!(mouse_action(left, Some(13), &layout, 7), Action::Outside);
+        assert_eq!(mouse_action(left, None, &layout, 7), Action::Outside);
+        let right = mouse(MouseEventKind::Down(MouseButton::Right));
+        assert_eq!(mouse_action(right, Some(2), &layout, 7), Action::Special(Move::Pop(1)));
+        assert_eq!(mouse_action(mouse(MouseEventKind::Moved), Some(12), &layout, 7), Action::Hover(6));
+        assert_eq!(mouse_action(mouse(MouseEventKind::ScrollUp), Some(0), &layout, 7), Action::Ignore);
+
+        // Double-width cells inside borders, where a click on the border is outside the board
+        let wide = Layout { cell_width: 2, borders: true, ..layout };
+        let clicks = [(1, Action::Outside), (2, Action::Drop(0)), (4, Action::Drop(0)), (5, Action::Drop(1))];
+        for (x, action) in clicks.into_iter().chain([(21, Action::Drop(6)), (22, Action::Outside)]) {
+            assert_eq!(mouse_action(left, Some(x), &wide, 7), action, "x {}", x);
+        }
+        // Large print, three times the size, with labeled rows as well as borders
+        let big = Layout { cell_width: 6, borders: true, margin: 2, scale: 3, ..layout };
+        let clicks = [(3, Action::Outside), (4, Action::Drop(0)), (10, Action::Drop(0)), (11, Action::Drop(1))];
+        for (x, action) in clicks.into_iter().chain([(51, Action::Drop(6)), (52, Action::Outside)]) {
+            assert_eq!(mouse_action(left, Some(x), &big, 7), action, "x {}", x);
+        }
+        assert_eq!(mouse_action(mouse(MouseEventKind::Moved), Some(30), &big, 7), Action::Hover(3));
+    }
+}
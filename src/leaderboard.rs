@@ -0,0 +1,353 @@
+use std::cmp::Ordering;
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::ai::Difficulty;
+use crate::export::{csv_field, json_string};
+use crate::profile::{self, Profile};
+
+// What the leaderboard is ordered by, best first
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Sort {
+    Rating,
+    Wins,
+    Games,
+    WinRate,
+}
+
+// How the leaderboard is printed
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+// One row of the leaderboard
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub rating: Option<i32>,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    // A computer level listed for comparison, not a player
+    pub reference: bool,
+}
+
+impl Entry {
+    fn from_profile(profile: &Profile) -> Entry {
+        let (wins, losses, draws) =
+            profile
+                .records
+                .iter()
+                .fold((0, 0, 0), |(wins, losses, draws), (_, record)| {
+                    (
+                        wins + record.wins,
+                        losses + record.losses,
+                        draws + record.draws,
+                    )
+                });
+        Entry {
+            name: profile.name.clone(),
+            rating: profile.rating,
+            wins,
+            losses,
+            draws,
+            reference: false,
+        }
+    }
+
+    fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    // Share of games won, from 0 to 1
+    fn win_rate(&self) -> f64 {
+        match self.games() {
+            0 => 0.0,
+            games => f64::from(self.wins) / f64::from(games),
+        }
+    }
+}
+
+// The rows for these profiles, leaving out players with fewer than `min_games` games and
+// any profile still under a seat's default name, which could be anyone's, and adding the
+// computer levels at their fixed ratings when `computer` is on. Ties on the sort key go to
+// the higher rating, then to more games, then alphabetically.
+pub fn entries(profiles: &[Profile], sort: Sort, min_games: u32, computer: bool) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = profiles
+        .iter()
+        .filter(|profile| !profile::anonymous(&profile.name))
+        .map(Entry::from_profile)
+        .filter(|entry| entry.games() >= min_games)
+        .collect();
+    if computer {
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            entries.push(Entry {
+                name: format!("Computer ({})", format!("{:?}", difficulty).to_lowercase()),
+                rating: Some(profile::computer_rating(difficulty)),
+                wins: 0,
+                losses: 0,
+                draws: 0,
+                reference: true,
+            });
+        }
+    }
+
+    let rating = |entry: &Entry| entry.rating.unwrap_or(profile::START_RATING);
+    entries.sort_by(|a, b| {
+        let key = match sort {
+            Sort::Rating => Ordering::Equal,
+            Sort::Wins => b.wins.cmp(&a.wins),
+            Sort::Games => b.games().cmp(&a.games()),
+            Sort::WinRate => b.win_rate().total_cmp(&a.win_rate()),
+        };
+        key.then_with(|| rating(b).cmp(&rating(a)))
+            .then_with(|| b.games().cmp(&a.games()))
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    entries
+}
+
+// Each row's place, counting players only; computer levels have none
+fn ranks(entries: &[Entry]) -> Vec<Option<usize>> {
+    let mut rank = 0;
+    entries
+        .iter()
+        .map(|entry| {
+            (!entry.reference).then(|| {
+                rank += 1;
+                rank
+            })
+        })
+        .collect()
+}
+
+fn rating_text(entry: &Entry) -> String {
+    entry
+        .rating
+        .map_or("-".to_string(), |rating| rating.to_string())
+}
+
+fn win_rate_text(entry: &Entry) -> String {
+    if entry.reference || entry.games() == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.0}%", entry.win_rate() * 100.0)
+    }
+}
+
+// Cut `text` down to `width` terminal columns, ending in an ellipsis when anything was cut
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut cut = String::new();
+    for grapheme in text.graphemes(true) {
+        if cut.width() + grapheme.width() + 1 > width {
+            break;
+        }
+        cut.push_str(grapheme);
+    }
+    cut.push('…');
+    cut
+}
+
+// The rows as an aligned table fitting in `width` terminal columns when it's known; long
+// names are shortened first
+pub fn table(entries: &[Entry], width: Option<usize>) -> String {
+    const HEADER: [&str; 7] = ["#", "Rating", "Games", "W", "L", "D", "Win%"];
+    let cells: Vec<[String; 7]> = entries
+        .iter()
+        .zip(ranks(entries))
+        .map(|(entry, rank)| {
+            [
+                rank.map_or("-".to_string(), |rank| rank.to_string()),
+                rating_text(entry),
+                entry.games().to_string(),
+                entry.wins.to_string(),
+                entry.losses.to_string(),
+                entry.draws.to_string(),
+                win_rate_text(entry),
+            ]
+        })
+        .collect();
+    let widths: Vec<usize> = (0..HEADER.len())
+        .map(|column| {
+            cells
+                .iter()
+                .map(|row| row[column].len())
+                .chain([HEADER[column].len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    // The name column gets whatever the numbers leave, but never less than a few letters
+    let numbers: usize = widths.iter().map(|width| width + 2).sum();
+    let longest = entries
+        .iter()
+        .map(|entry| entry.name.width())
+        .chain(["Player".width()])
+        .max()
+        .unwrap_or(0);
+    let name_width = match width {
+        Some(width) => longest.min(width.saturating_sub(numbers)).max(4),
+        None => longest,
+    };
+
+    let line = |row: &[String], name: &str| {
+        let name = truncate(name, name_width);
+        let padding = name_width - name.width();
+        let mut text = format!(
+            "{:>w$}  {}{}",
+            row[0],
+            name,
+            " ".repeat(padding),
+            w = widths[0]
+        );
+        for (column, cell) in row.iter().enumerate().skip(1) {
+            text.push_str(&format!("  {:>w$}", cell, w = widths[column]));
+        }
+        text
+    };
+    let header: Vec<String> = HEADER.iter().map(|title| title.to_string()).collect();
+    let mut lines = vec![line(&header, "Player")];
+    for (entry, row) in entries.iter().zip(&cells) {
+        lines.push(line(row, &entry.name));
+    }
+    lines.join("\n")
+}
+
+// The rows as CSV with a header line
+pub fn csv(entries: &[Entry]) -> String {
+    let mut lines = vec!["rank,name,rating,games,wins,losses,draws,win_rate,computer".to_string()];
+    for (entry, rank) in entries.iter().zip(ranks(entries)) {
+        lines.push(format!(
+            "{},{},{},{},{},{},{},{:.3},{}",
+            rank.map_or(String::new(), |rank| rank.to_string()),
+            csv_field(&entry.name),
+            entry
+                .rating
+                .map_or(String::new(), |rating| rating.to_string()),
+            entry.games(),
+            entry.wins,
+            entry.losses,
+            entry.draws,
+            entry.win_rate(),
+            entry.reference
+        ));
+    }
+    lines.join("\n")
+}
+
+// The rows as a JSON array of objects
+pub fn json(entries: &[Entry]) -> String {
+    let objects: Vec<String> = entries
+        .iter()
+        .zip(ranks(entries))
+        .map(|(entry, rank)| {
+            format!(
+                "  {{\"rank\": {}, \"name\": {}, \"rating\": {}, \"games\": {}, \"wins\": {}, \
+                 \"losses\": {}, \"draws\": {}, \"win_rate\": {:.3}, \"computer\": {}}}",
+                rank.map_or("null".to_string(), |rank| rank.to_string()),
+                json_string(&entry.name),
+                entry
+                    .rating
+                    .map_or("null".to_string(), |rating| rating.to_string()),
+                entry.games(),
+                entry.wins,
+                entry.losses,
+                entry.draws,
+                entry.win_rate(),
+                entry.reference
+            )
+        })
+        .collect();
+    if objects.is_empty() {
+        return "[]".to_string();
+    }
+    format!("[\n{}\n]", objects.join(",\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::Record;
+
+    fn profile(name: &str, rating: Option<i32>, wins: u32, losses: u32, draws: u32) -> Profile {
+        Profile {
+            name: name.to_string(),
+            records: vec![(
+                "human".to_string(),
+                Record {
+                    wins,
+                    losses,
+                    draws,
+                },
+            )],
+            rating,
+            ..Profile::default()
+        }
+    }
+
+    fn names(entries: &[Entry]) -> Vec<String> {
+        entries.iter().map(|entry| entry.name.clone()).collect()
+    }
+
+    #[test]
+    fn players_are_ordered_by_the_sort_key_then_rating_games_and_name() {
+        let profiles = [
+            profile("ada", Some(1300), 2, 2, 0),
+            profile("Grace", Some(1300), 2, 2, 0),
+            profile("Alan", Some(1300), 2, 3, 0),
+            profile("Edsger", Some(1450), 1, 0, 0),
+            profile("Barbara", None, 6, 4, 0),
+        ];
+        let sorted = |sort| names(&entries(&profiles, sort, 0, false));
+        // Unrated players count as the starting rating
+        assert_eq!(
+            sorted(Sort::Rating),
+            ["Edsger", "Alan", "ada", "Grace", "Barbara"]
+        );
+        assert_eq!(
+            sorted(Sort::Wins),
+            ["Barbara", "Alan", "ada", "Grace", "Edsger"]
+        );
+        assert_eq!(
+            sorted(Sort::Games),
+            ["Barbara", "Alan", "ada", "Grace", "Edsger"]
+        );
+        assert_eq!(
+            sorted(Sort::WinRate),
+            ["Edsger", "Barbara", "ada", "Grace", "Alan"]
+        );
+    }
+
+    #[test]
+    fn few_games_and_default_names_are_left_out_and_the_computer_is_unranked() {
+        let profiles = [
+            profile("Ada", Some(1500), 3, 0, 0),
+            profile("Grace", Some(1000), 2, 0, 0),
+            profile("Player 1", Some(1700), 9, 0, 0),
+            profile("Player 3", None, 9, 0, 0),
+        ];
+        let board = entries(&profiles, Sort::Rating, 3, true);
+        assert_eq!(
+            names(&board),
+            [
+                "Computer (hard)",
+                "Ada",
+                "Computer (medium)",
+                "Computer (easy)"
+            ]
+        );
+        assert_eq!(ranks(&board), [None, Some(1), None, None]);
+        assert_eq!(
+            names(&entries(&profiles, Sort::Rating, 0, false)),
+            ["Ada", "Grace"]
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use crate::start::Rng;
+use crate::Game;
+
+// Which columns a piece can be dropped in, asked the same way play_move decides it, so a
+// column these call legal is never turned down
+impl Game {
+    // Whether play_move takes a piece in `column`: the game goes on, and the column is on the
+    // board and has room
+    pub fn can_play(&self, column: usize) -> bool {
+        !self.is_finished()
+            && self.column(column).is_ok()
+            && self.column_height(column) < self.rules.height
+    }
+
+    // Columns a piece can be dropped in, from the left; none once the game is over
+    pub fn legal_moves(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.rules.width).filter(|&column| self.can_play(column))
+    }
+
+    // One of the legal moves picked by `rng`, or None when there's none
+    pub fn random_move(&self, rng: &mut Rng) -> Option<usize> {
+        let moves: Vec<usize> = self.legal_moves().collect();
+        (!moves.is_empty()).then(|| moves[rng.below(moves.len())])
+    }
+
+    // Pieces the board has room for before it's full
+    pub fn moves_remaining(&self) -> usize {
+        self.cells().filter(|(_, cell)| cell.is_none()).count()
+    }
+}
@@ -0,0 +1,2864 @@
+// Built without the terminal, only the engine is called on, so the rest goes unused
+#![cfg_attr(not(feature = "terminal"), allow(dead_code, unused_imports))]
+
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use clap::Parser;
+use connect_four_core::Grid;
+#[cfg(feature = "terminal")]
+use crossterm::event::{Event, KeyCode, KeyEvent};
+
+mod achievements;
+mod ai;
+mod board;
+mod builder;
+mod cli;
+mod clock;
+mod command;
+mod comment;
+mod coord;
+mod cube;
+mod describe;
+mod export;
+#[cfg(feature = "capi")]
+pub mod ffi;
+mod flip;
+#[cfg(test)]
+mod fuzz;
+mod handicap;
+mod heatmap;
+mod history;
+mod i18n;
+mod id;
+#[cfg(feature = "terminal")]
+mod keys;
+mod leaderboard;
+mod legal;
+mod logging;
+#[cfg(feature = "terminal")]
+mod menu;
+mod now;
+mod outcome;
+mod pgn;
+mod popout;
+mod position;
+mod powerup;
+mod presets;
+mod profile;
+#[cfg(test)]
+mod properties;
+mod render;
+#[cfg(feature = "terminal")]
+mod replay;
+mod reproduce;
+mod rules;
+mod save;
+mod scoring;
+mod session;
+mod settings;
+mod start;
+mod style;
+#[cfg(feature = "terminal")]
+mod terminal;
+mod theme;
+mod timing;
+mod transcript;
+#[cfg(feature = "terminal")]
+mod tutorial;
+#[cfg(feature = "terminal")]
+mod tui;
+mod validate;
+mod variant;
+mod variation;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+use ai::{Difficulty, Opponent};
+use board::{Board, Cell};
+use cli::{Cli, Command, ConfigAction, ExportFormat, StatsView};
+use clock::{Clock, MoveTimer};
+use command::Context;
+use comment::Comments;
+use coord::{Column, Coord};
+use handicap::Placement;
+#[cfg(feature = "terminal")]
+use keys::Action;
+use outcome::{DrawKind, Outcome, WinKind};
+use render::{Blind, Emphasis, Renderer};
+use profile::Profile;
+use rules::GameRules;
+use session::{First, Session};
+use settings::{Input, Settings, Ui};
+#[cfg(feature = "terminal")]
+use terminal::{Capabilities, Screen};
+use transcript::Transcript;
+use variant::{Move, Specials, Variant};
+use variation::Variation;
+
+// Time a dropping piece spends on each row, and the longest a whole drop may take
+const DROP_FRAME: Duration = Duration::from_millis(40);
+const MAX_DROP: Duration = Duration::from_millis(300);
+
+// How long a peek shows the board of a blind game
+const PEEK: Duration = Duration::from_secs(2);
+
+// Moves that must be played before a player may offer another draw
+const DRAW_OFFER_GAP: usize = 4;
+
+// Enum representing players; the third and fourth only play in games of more than two
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Player {
+    One = 1,
+    Two = 2,
+    Three = 3,
+    Four = 4,
+}
+
+impl Player {
+    // The seats in turn order
+    const SEATS: [Player; 4] = [Player::One, Player::Two, Player::Three, Player::Four];
+
+    // The seat at position `index`, from 0, the way `index` counts them
+    fn from_index(index: usize) -> Player {
+        Player::SEATS[index]
+    }
+
+    // The other side of a two-player game; in a bigger one every other seat faces the first
+    fn other(self) -> Player {
+        match self {
+            Player::One => Player::Two,
+            Player::Two | Player::Three | Player::Four => Player::One,
+        }
+    }
+
+    // Position of the seat among the players, from 0, for anything kept by seat
+    fn index(self) -> usize {
+        self as usize - 1
+    }
+}
+
+// The seat as players see it numbered, like "Player 1"
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Player {}", self.index() + 1)
+    }
+}
+
+// What a dropped piece did: where it landed and whose it was, the move count after it, and
+// the result when it ended the game. A handicap piece is reported the same way, with the
+// move count it went down before.
+#[derive(Clone, Debug, PartialEq)]
+struct PlayedMove {
+    landing: Coord,
+    player: Player,
+    number: usize,
+    outcome: Option<Outcome>,
+}
+
+// Enum for possible move errors, each carrying what its message needs
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+enum MoveError {
+    GameFinished,
+    // The 0-based column tried, and the width of the board it's not on
+    InvalidColumn { attempted: usize, width: usize },
+    // The 0-based column with no room left
+    ColumnFull { column: usize },
+    NoPops,
+    NotYourPiece,
+    NoPowers,
+    NoneLeft,
+    NothingToBomb,
+    NoFlips,
+}
+
+impl std::fmt::Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            MoveError::ColumnFull { column } => i18n::fill("move.column_full", &[("column", &(column + 1))]),
+            MoveError::InvalidColumn { attempted, width } => {
+                i18n::fill("move.invalid_column", &[("column", &(attempted + 1)), ("width", width)])
+            }
+            MoveError::GameFinished => i18n::text("move.game_finished"),
+            MoveError::NoPops => i18n::text("move.no_pops"),
+            MoveError::NotYourPiece => i18n::text("move.not_your_piece"),
+            MoveError::NoPowers => i18n::text("move.no_powers"),
+            MoveError::NoneLeft => i18n::text("move.none_left"),
+            MoveError::NothingToBomb => i18n::text("move.nothing_to_bomb"),
+            MoveError::NoFlips => i18n::text("move.no_flips"),
+        };
+        write!(f, "{}", text)
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+// Struct representing the game state
+#[derive(Clone, Debug)]
+struct Game {
+    // ULID shared by the game's save, log lines, transcript, and exports
+    id: String,
+    // Id of the game this one is a rematch of
+    previous_game: Option<String>,
+    // Day it was played, YYYY-MM-DD, for an imported game that came with one; the log keeps
+    // the day of games played here
+    date: Option<String>,
+    current_move: usize,
+    current_player: Player,
+    board: Board,
+    // The rules played by: the board, the win length, the variant, who moves first, and
+    // whether the pie rule is on
+    rules: GameRules,
+    // Names of the players in turn order, one for each seat
+    names: Vec<String>,
+    // How the game ended, once it has
+    outcome: Option<Outcome>,
+    // Landing square of every move played, in order; a pop or an anvil is recorded at the
+    // bottom of its column, a bomb where the piece it blew up was, and a flip at the bottom
+    // of the first column
+    history: Vec<Coord>,
+    // The numbers of the moves that weren't plain drops, and what was in the column of each
+    // anvil or bomb, and on the board before each flip, for taking them back
+    specials: Specials,
+    cleared: Vec<Vec<Cell>>,
+    boards: Vec<Board>,
+    // Name of the rule preset the game is played by, when its rules are all the preset's
+    rule_preset: Option<String>,
+    // The second player took the first move over as their own under the pie rule
+    swapped: bool,
+    // Pieces the second player is given before the first move: the columns they went into,
+    // in order, how many are still to be placed, and how they're placed
+    handicap: Vec<usize>,
+    handicap_left: usize,
+    placement: Placement,
+    // Moves played at random for the players before they took over, and the seed they came
+    // from
+    random_start: usize,
+    seed: Option<u32>,
+    // Player whose draw offer is waiting for the other's answer
+    draw_offer: Option<Player>,
+    // Move number of each player's latest draw offer, for spacing them out
+    last_offers: [Option<usize>; 2],
+    // Seat the computer plays and how hard, if one of the players is the computer
+    computer: Option<(Player, Difficulty)>,
+    // Seed the computer's chance moves come from, so the game can be played again move for
+    // move
+    engine_seed: u32,
+    // Hints asked for and moves taken back by each player
+    hints: Vec<u32>,
+    takebacks: Vec<u32>,
+    // Lines branching off the game, kept from the replay viewer
+    variations: Vec<Variation>,
+    // Notes on the game's moves, by move number
+    comments: Comments,
+    // Both players' time, when the game is played on a clock, and the name of the preset its
+    // time control came from
+    clock: Option<Clock>,
+    preset: Option<String>,
+    // The limit on every move, when there is one
+    move_timer: Option<MoveTimer>,
+    // Numbers of the moves played at random for a player whose time for the move ran out
+    timeouts: Vec<usize>,
+    // How long each pause of a timed game lasted
+    pauses: Vec<Duration>,
+    // How long each move took on the clock, as far as it's run for them
+    move_times: Vec<Duration>,
+}
+
+impl Game {
+    // Initialize an empty game with the configured board and players
+    fn from_settings(settings: &Settings) -> Game {
+        // The computer goes by "Computer", in the session's language, unless it was given a name
+        let mut names = settings.names[..settings.players].to_vec();
+        let computer = match settings.opponent() {
+            Opponent::Human => None,
+            Opponent::Computer(difficulty) => Some((Player::Two, difficulty)),
+        };
+        if computer.is_some() && names[1] == Settings::default().names[1] {
+            names[1] = i18n::text("player.computer");
+        }
+
+        Game {
+            id: id::ulid(),
+            previous_game: None,
+            date: None,
+            current_move: 0,
+            current_player: Player::One,
+            board: Board::new(settings.width, settings.height),
+            rules: settings.rules(),
+            names,
+            outcome: None,
+            history: Vec::new(),
+            specials: Specials::default(),
+            cleared: Vec::new(),
+            boards: Vec::new(),
+            rule_preset: settings.preset_name(),
+            swapped: false,
+            handicap: Vec::new(),
+            handicap_left: 0,
+            placement: settings.placement,
+            random_start: 0,
+            seed: None,
+            draw_offer: None,
+            last_offers: [None, None],
+            computer,
+            engine_seed: settings.seed.unwrap_or_else(start::fresh_seed),
+            hints: vec![0; settings.players],
+            takebacks: vec![0; settings.players],
+            variations: Vec::new(),
+            comments: Comments::new(),
+            clock: settings
+                .time
+                .map(|control| Clock::new(control, settings.pause_computer)),
+            preset: settings.time.and(settings.preset.clone()),
+            move_timer: settings
+                .move_timeout
+                .map(|limit| MoveTimer::new(limit, settings.timeout_action)),
+            timeouts: Vec::new(),
+            pauses: Vec::new(),
+            move_times: Vec::new(),
+        }
+    }
+
+    // The session's first game, with the players seated the way `settings.first` says, the
+    // second one given the handicap, and any random start played from the seed asked for
+    fn opening(settings: &Settings) -> Result<Game, String> {
+        Game::builder(settings).build().map_err(|err| err.to_string())
+    }
+
+    // Who the coin flip picked to move first, when a coin flip picks
+    fn coin_flip_note(&self, settings: &Settings) -> Option<String> {
+        (settings.first == First::Random).then(|| i18n::fill("note.coin_flip", &[("name", &self.name(Player::One))]))
+    }
+
+    // Move every player up a seat and the first one to the back, so the second player moves
+    // first; with two players they trade colors
+    fn rotate_seats(&mut self) {
+        self.names.rotate_left(1);
+        self.computer = self.computer.map(|(seat, difficulty)| (self.previous_player(seat), difficulty));
+    }
+
+    // A new game between the same players on the same board. With `swap` they move up a
+    // seat, so the previous second player moves first, except under a handicap, which stays
+    // with the player given it.
+    fn rematch(&self, swap: bool) -> Game {
+        let mut game = Game {
+            id: id::ulid(),
+            previous_game: Some(self.id.clone()),
+            date: None,
+            current_move: 0,
+            current_player: Player::One,
+            board: Board::new(self.rules.width, self.rules.height),
+            outcome: None,
+            history: Vec::new(),
+            specials: Specials::default(),
+            cleared: Vec::new(),
+            boards: Vec::new(),
+            swapped: false,
+            handicap: Vec::new(),
+            handicap_left: 0,
+            random_start: 0,
+            seed: None,
+            draw_offer: None,
+            last_offers: [None, None],
+            engine_seed: start::fresh_seed(),
+            hints: vec![0; self.players()],
+            takebacks: vec![0; self.players()],
+            variations: Vec::new(),
+            comments: Comments::new(),
+            clock: self
+                .clock
+                .map(|clock| Clock::new(clock.control, clock.pause_computer)),
+            move_timer: self
+                .move_timer
+                .map(|timer| MoveTimer::new(timer.limit, timer.action)),
+            timeouts: Vec::new(),
+            pauses: Vec::new(),
+            move_times: Vec::new(),
+            ..self.clone()
+        };
+        if swap && self.handicap_size() == 0 {
+            game.rotate_seats();
+        }
+        game.give_handicap(self.handicap_size(), self.placement);
+        // A new seed each game; it's shown, so any start can be had again
+        if let Err(err) = game.start_randomly(self.random_start, start::fresh_seed()) {
+            log::warn!("starting the rematch from the empty board: {}", err);
+        }
+        game
+    }
+
+    // Difficulty of the computer if it's the one to move
+    fn computer_to_move(&self) -> Option<Difficulty> {
+        match self.computer {
+            Some((seat, difficulty)) if seat == self.current_player && !self.is_finished() => Some(difficulty),
+            _ => None,
+        }
+    }
+
+    // Name of a real player
+    fn name(&self, player: Player) -> &str {
+        &self.names[player.index()]
+    }
+
+    // How many players take turns
+    fn players(&self) -> usize {
+        self.names.len()
+    }
+
+    // Every seat, in turn order
+    fn seats(&self) -> Vec<Player> {
+        Player::SEATS[..self.players()].to_vec()
+    }
+
+    // The seat that plays move number `ply`, from 1: player 1 makes the first move of every
+    // round
+    fn seat_of(&self, ply: usize) -> Player {
+        Player::from_index((ply + self.players() - 1) % self.players())
+    }
+
+    // Landing square of the most recent move, if any has been played and it wasn't a flip,
+    // which moves every piece
+    fn last_move(&self) -> Option<Coord> {
+        if self.flipped(self.history.len()) {
+            return None;
+        }
+        self.history.last().copied()
+    }
+
+    // Every move played, in order, with columns counted from 1 and the prefix of any that
+    // wasn't a drop, like "4 4 p4 flip"
+    fn to_moves_string(&self) -> String {
+        let columns: Vec<usize> = self.history.iter().map(|coord| coord.column).collect();
+        let moves: Vec<String> = self.specials.list(&columns).iter().map(|mv| mv.notation(|column| (column + 1).to_string())).collect();
+        moves.join(" ")
+    }
+
+    // Whether a column has no room left for another piece
+    fn is_column_full(&self, column: usize) -> bool {
+        self.column_height(column) == self.rules.height
+    }
+
+    // Display the game board
+    #[cfg(feature = "terminal")]
+    fn display_board(&self, screen: &Screen) {
+        // Described in words for a screen reader, the position only adds on to what was said
+        // before, as a peek at a blind game's board does
+        if screen.renderer.accessible {
+            let mut lines = self.describe_move(&screen.renderer);
+            if screen.renderer.blind.is_some_and(|blind| blind.peeking) {
+                lines.extend(self.describe_board());
+            }
+            if let Some(clock) = self.clock_line(Instant::now()) {
+                lines.push(clock);
+            }
+            println!("\n{}", lines.join("\n"));
+            return;
+        }
+
+        // Clears the screen before displaying the board
+        screen.clear();
+        println!("\n");
+
+        // Fit the board to the terminal as it is now, in case it was resized
+        let mut renderer = screen.renderer.clone();
+        match screen.board_layout(self.rules.width) {
+            Ok(layout) => renderer.layout = layout,
+            Err(needed) => {
+                println!("{}", renderer.accent(&i18n::fill("board.too_narrow", &[("columns", &needed)])));
+                return;
+            }
+        }
+        println!("{}", self.render_board(&renderer, screen.selected_column));
+        if let Some(clock) = self.clock_line(Instant::now()) {
+            println!("{}", renderer.accent(&clock));
+        }
+    }
+
+    // The whole board display as text: header, grid with its highlights and rulers, move
+    // list, and the result once the game is over. Rendering never prints, so the same text
+    // can be shown anywhere.
+    fn render_board(&self, renderer: &Renderer, selected_column: Option<usize>) -> String {
+        // Open sides show that lines run off one edge of a cylinder onto the other
+        let mut renderer = renderer.clone();
+        renderer.layout.open_sides = self.rules.variant.wraps();
+        let renderer = &renderer;
+        let layout = renderer.layout;
+        let cube = self.rules.variant == Variant::Cube;
+        let rule = if cube { renderer.accent(&"-".repeat(cube::levels_width(renderer))) } else { renderer.accent(&layout.rule(self.rules.width)) };
+        let mut lines = vec![rule.clone()];
+        let tally = self.tally_line().map_or(String::new(), |tally| format!("  {}", tally));
+        let board = if cube { " IN 3D" } else { "" };
+        lines.push(renderer.accent(&format!("CONNECT {}{} (Move {}){}", self.rules.connect, board, self.current_move, tally)));
+        lines.push(rule.clone());
+
+        let hidden = self.hidden(renderer);
+
+        if cube {
+            lines.extend(self.render_levels(renderer, selected_column));
+        } else {
+            lines.extend(self.render_grid(renderer, selected_column, hidden));
+        }
+
+        // Move list in coordinate notation, or in columns when the board is hidden and the
+        // list is all there is to go on; a 3D game names its pegs
+        if cube && renderer.coords && !self.history.is_empty() {
+            let pegs: Vec<String> = self.history.iter().map(|coord| cube::peg_name(coord.column)).collect();
+            lines.push(format!("Moves: {}", pegs.join(" ")));
+        } else if renderer.coords && !self.history.is_empty() {
+            lines.push(format!("Moves: {}", coord::format_history(&self.history, &self.specials)));
+        } else if hidden {
+            lines.push(format!("Moves: {}", self.to_moves_string()));
+        }
+        match renderer.blind.map(|blind| blind.left()) {
+            Some(0) if hidden => lines.push(renderer.accent(&i18n::text("blind.hidden"))),
+            Some(left) if hidden => lines.push(renderer.accent(&i18n::fill("blind.peek", &[("count", &left)]))),
+            _ => {}
+        }
+        if let Some(stock) = self.stock_line() {
+            lines.push(stock);
+        }
+        if let Some(flips) = self.flip_line() {
+            lines.push(flips);
+        }
+        if self.rules.variant.wraps() {
+            lines.push("The board wraps around: lines can run off one side onto the other".to_string());
+        }
+        if cube {
+            lines.push("Name a peg by its letter and number to drop a piece onto it, like b3".to_string());
+        }
+        lines.push(rule.clone());
+
+        // Display the winner if the game is finished
+        if let Some(result) = self.render_result(renderer) {
+            lines.push(result);
+        }
+        if let (true, Some(blind)) = (self.is_finished(), renderer.blind) {
+            let played = i18n::fill("blind.played", &[("peeks", &i18n::plural("count.peeks", blind.used)), ("allowed", &blind.peeks)]);
+            lines.push(renderer.accent(&format!(" {}", played)));
+        }
+
+        lines.push(rule);
+        lines.join("\n")
+    }
+
+    // A blind game keeps the position out of sight once it's under way
+    fn hidden(&self, renderer: &Renderer) -> bool {
+        renderer.blind.is_some_and(|blind| !blind.peeking) && !self.is_finished() && self.history.len() > self.random_start
+    }
+
+    // A cell of the board, by row from the top and column, as results spell it out: "column 4
+    // row 1", its coordinate when the board is labeled with them, or its peg and level in 3D
+    fn cell_name(&self, renderer: &Renderer, row: usize, column: usize) -> String {
+        let coord = Coord { column, row: self.rules.height - 1 - row };
+        if self.rules.variant == Variant::Cube {
+            cube::cell_name(row, column)
+        } else if renderer.coords {
+            coord.to_string()
+        } else {
+            i18n::fill("describe.cell", &[("column", &(column + 1)), ("row", &(coord.row + 1))])
+        }
+    }
+
+    // The grid with its highlights, rulers, and marker, or its hidden rows in a blind game
+    fn render_grid(&self, renderer: &Renderer, selected_column: Option<usize>, hidden: bool) -> Vec<String> {
+        let layout = renderer.layout;
+        let mut lines = Vec::new();
+        // Column numbers, with full columns marked as unavailable
+        let full_columns: Vec<bool> = (0..self.rules.width).map(|column| self.is_column_full(column)).collect();
+        if renderer.ruler.above() {
+            lines.push(renderer.ruler_row(&full_columns));
+        }
+
+        // Where the current player's piece would land in the selected column, which is
+        // never a full one
+        let ghost = selected_column
+            .filter(|&column| !self.is_finished() && !hidden && column < self.rules.width)
+            .filter(|&column| self.column_height(column) < self.rules.height)
+            .map(|column| (self.rules.height - 1 - self.column_height(column), column));
+        if let Some((_, column)) = ghost {
+            lines.push(renderer.preview_row(column, self.current_player));
+        }
+
+        // Iterate over each row in the board and print it
+        if let Some(edge) = layout.edge(self.rules.width) {
+            lines.push(edge);
+        }
+        let last_move = self.last_move();
+        for (index, row) in self.board.rows().enumerate() {
+            if let (true, Some(spacer)) = (index > 0, layout.spacer(self.rules.width)) {
+                lines.push(spacer);
+            }
+            if hidden {
+                lines.push(renderer.hidden_row(self.rules.width, self.rules.height - index));
+                continue;
+            }
+            let emphasis: Vec<Emphasis> = (0..self.rules.width)
+                .map(|column| {
+                    let winning = self
+                        .winning_line()
+                        .is_some_and(|line| line.contains(&(index, column)));
+                    let last = last_move.is_some_and(|coord| {
+                        coord.column == column && coord.row == self.rules.height - 1 - index
+                    });
+                    match (winning, last) {
+                        (true, _) => Emphasis::Winning,
+                        (false, true) => Emphasis::LastMove,
+                        _ if ghost == Some((index, column)) => {
+                            Emphasis::Ghost(self.current_player)
+                        }
+                        _ => Emphasis::None,
+                    }
+                })
+                .collect();
+            lines.push(renderer.row(row, self.rules.height - index, &emphasis));
+        }
+        if let Some(edge) = layout.edge(self.rules.width) {
+            lines.push(edge);
+        }
+        if renderer.ruler.below() {
+            lines.push(renderer.ruler_row(&full_columns));
+        }
+        if let (true, Some(coord)) = (renderer.marker, last_move) {
+            lines.push(renderer.marker_row(coord.column));
+        }
+        lines
+    }
+
+    // Win or draw announcement, with the cells of a winning line spelled out
+    fn render_result(&self, renderer: &Renderer) -> Option<String> {
+        if !self.is_finished() {
+            return None;
+        }
+        let banner = match (self.winner(), self.connected_loser()) {
+            (None, _) => renderer.draw_banner(),
+            (_, Some(loser)) => renderer.loser_banner(self.name(loser), loser, self.rules.connect),
+            (Some(winner), None) => renderer.winner_banner(self.name(winner), winner),
+        };
+
+        let note = |text: &str| Some(format!("{}\n{}", banner, renderer.accent(text)));
+        let loser = self.winner().map(|winner| self.name(winner.other()).to_string());
+        match (self.won_by(), self.drawn_by(), loser) {
+            (_, Some(DrawKind::Agreement), _) => return note(&format!(" {}", i18n::text("banner.agreement"))),
+            (Some(WinKind::Time), _, Some(loser)) => {
+                return note(&format!(" {} lost on time on move {}", loser, self.current_move + 1))
+            }
+            (Some(WinKind::Resignation), _, Some(loser)) => {
+                return note(&format!(" {} resigned on move {}", loser, self.current_move + 1))
+            }
+            _ => {}
+        }
+
+        // Spell out where the line is so the result never depends on telling colors apart
+        let Some(line) = self.winning_line() else {
+            return Some(banner);
+        };
+        let cells: Vec<String> = line.iter().map(|&(row, column)| self.cell_name(renderer, row, column)).collect();
+        let label = if self.rules.variant.misere() { "Losing" } else { "Winning" };
+        let line = renderer.accent(&format!(" {} line: {}", label, cells.join(", ")));
+        Some(format!("{}\n{}", banner, line))
+    }
+
+    // Show the last piece played falling from the top of its column, one row per frame,
+    // finishing just above where it landed
+    #[cfg(feature = "terminal")]
+    fn animate_drop(&self, screen: &Screen, played: &PlayedMove) {
+        // The levels of a 3D game aren't drawn as columns
+        if self.rules.variant == Variant::Cube {
+            return;
+        }
+        let coord = played.landing;
+        let landing = self.rules.height - 1 - coord.row;
+        let frames = drop_frames(landing);
+        if frames.is_empty() {
+            return;
+        }
+        let delay = (DROP_FRAME * frames.len() as u32).min(MAX_DROP) / frames.len() as u32;
+
+        // The position before the move, without its result, is what the piece falls through
+        let mut frame = self.clone();
+        let piece = Some(played.player);
+        frame.board[landing][coord.column] = None;
+        frame.history.pop();
+        frame.outcome = None;
+
+        for row in frames {
+            frame.board[row][coord.column] = piece;
+            frame.display_board(screen);
+            thread::sleep(delay);
+            frame.board[row][coord.column] = None;
+        }
+    }
+
+    // Display an error message
+    #[cfg(feature = "terminal")]
+    fn display_error(&self, screen: &Screen, error: String) {
+        // Display the board and then the error message
+        self.display_board(screen);
+        screen.error(&error);
+    }
+
+    // How the game stands after the last move: won, drawn, or None while it goes on
+    fn calculate_outcome(&self) -> Option<Outcome> {
+        let drawn = Outcome::Draw { by: DrawKind::FullBoard };
+        // Under the scoring variant lines don't end the game, and the full board goes to
+        // whoever made more of them
+        if self.rules.variant == Variant::Score {
+            if self.stuck(self.next_player(self.current_player)) {
+                return Some(match self.scored_winner() {
+                    Some(player) => Outcome::Win { player, line: None, by: WinKind::Lines },
+                    None => drawn,
+                });
+            }
+            return None;
+        }
+
+        // Early return if not enough moves have been made to win; the pieces of a handicap
+        // count toward the second player's line
+        let needed = match self.handicap.len() {
+            0 => 2 * self.rules.connect - 1,
+            given => 2 * (self.rules.connect - given),
+        };
+        if self.current_move < needed {
+            return None;
+        }
+
+        // Under misère the line loses, so the game goes to the other player
+        if let Some(line) = self.find_winning_line() {
+            let (row, column) = line[0];
+            let connected = self.board[row][column].expect("a line is made of pieces");
+            let player = if self.rules.variant.misere() { connected.other() } else { connected };
+            return Some(Outcome::Win { player, line: Some(line), by: WinKind::Connect });
+        }
+
+        self.stuck(self.next_player(self.current_player)).then_some(drawn)
+    }
+
+    // Whether the game is drawn with `player` to move: the board is full, and under PopOut
+    // they have no piece of their own to pop out, under Power Up no bomb to clear a space,
+    // or under Flip no flip left
+    fn stuck(&self, player: Player) -> bool {
+        self.board[0].iter().all(|cell| cell.is_some())
+            && !self.can_pop_any(player)
+            && !self.can_bomb_any(player)
+            && !self.can_flip_any(player)
+    }
+
+    // The player who lost a misère game by completing the line on the board
+    fn connected_loser(&self) -> Option<Player> {
+        let winner = self.winner().filter(|_| self.rules.variant.misere() && self.winning_line().is_some())?;
+        Some(winner.other())
+    }
+
+    // Decide the game after pieces have moved or come off the board, when a new line can be
+    // anywhere on it: the player who moved wins when both players have one, and otherwise
+    // the turn passes, unless the next player is left without a move
+    fn settle(&mut self) {
+        let lines: Vec<Vec<(usize, usize)>> = (0..self.rules.height)
+            .flat_map(|row| (0..self.rules.width).map(move |column| (row, column)))
+            .filter_map(|(row, column)| self.line_through(row, column))
+            .collect();
+        let mover = Some(self.current_player);
+        let line = lines
+            .iter()
+            .find(|line| self.board[line[0].0][line[0].1] == mover)
+            .or(lines.first());
+        match line {
+            Some(line) => {
+                let (row, column) = line[0];
+                let player = self.board[row][column].expect("a line is made of pieces");
+                self.outcome = Some(Outcome::Win { player, line: Some(line.clone()), by: WinKind::Connect });
+            }
+            None if self.stuck(self.next_player(self.current_player)) => {
+                self.outcome = Some(Outcome::Draw { by: DrawKind::FullBoard });
+            }
+            None => self.current_player = self.next_player(self.current_player),
+        }
+    }
+
+    // Cells of a connected line through the last move, as (row, column), including every
+    // connected cell when there are more than needed. A move completing two lines at once
+    // reports the first one in the order the directions are listed.
+    fn find_winning_line(&self) -> Option<Vec<(usize, usize)>> {
+        if self.rules.variant == Variant::Cube {
+            return self.cube_line();
+        }
+        // Only the piece just played can have completed a line
+        let last_move = self.last_move()?;
+        self.line_through(self.rules.height - 1 - last_move.row, last_move.column)
+    }
+
+    // Cells of a connected line through the piece at (row, column), ordered as
+    // find_winning_line orders them, if one is long enough to win
+    fn line_through(&self, row: usize, col: usize) -> Option<Vec<(usize, usize)>> {
+        let grid = Grid {
+            width: self.rules.width,
+            height: self.rules.height,
+            connect: self.rules.connect,
+            wraps: self.rules.variant.wraps(),
+        };
+        connect_four_core::line_through(&grid, row, col, |row, column| self.board[row][column])
+    }
+
+    // Take back the last move, returning false when there is none
+    fn undo(&mut self) -> bool {
+        // Taking back a resignation, an agreed draw, or a loss on time leaves the moves alone
+        self.draw_offer = None;
+        if self.outcome.as_ref().is_some_and(Outcome::off_the_board) {
+            self.outcome = None;
+            return true;
+        }
+        // A swap is taken back on its own, before the move it took over
+        if self.swapped && self.history.len() == 1 {
+            self.trade_seats(Instant::now());
+            self.swapped = false;
+            return true;
+        }
+        if self.popped(self.history.len()) {
+            self.unpop();
+            return true;
+        }
+        if self.powered(self.history.len()) {
+            self.unpower();
+            return true;
+        }
+        if self.flipped(self.history.len()) {
+            self.unflip();
+            return true;
+        }
+        let Some(coord) = self.history.pop() else {
+            return false;
+        };
+        let row = self.rules.height - 1 - coord.row;
+        self.current_player = self.board[row][coord.column].take().expect("a dropped piece stays where it landed");
+        self.current_move -= 1;
+        self.outcome = None;
+        true
+    }
+
+    // Take back up to `count` moves, along with the computer's reply to the last of them so
+    // the human is to move again
+    fn take_back(&mut self, count: usize) {
+        self.takebacks[self.current_player.index()] += 1;
+        for _ in 0..count {
+            if !self.can_take_back() {
+                break;
+            }
+            self.undo();
+        }
+        while self.computer_to_move().is_some() && self.can_take_back() && self.undo() {}
+        // Notes on moves taken back would end up on whatever is played instead
+        let moves = self.history.len();
+        self.comments.retain(|&ply, _| ply <= moves);
+        self.timeouts.retain(|&ply| ply <= moves);
+        self.move_times.truncate(moves);
+    }
+
+    // Whether there's anything a player may take back: an ending, or a move they played
+    // rather than one of a random start
+    fn can_take_back(&self) -> bool {
+        self.outcome.as_ref().is_some_and(Outcome::off_the_board) || self.history.len() > self.random_start
+    }
+
+    // Why there's no move to take back
+    fn nothing_to_undo(&self) -> String {
+        match self.random_start {
+            0 => i18n::text("undo.nothing"),
+            _ => i18n::text("undo.random_start"),
+        }
+    }
+
+    // Step to the position after the first `ply` of `moves`, the game's whole move list,
+    // taking moves back or playing them as needed. The move numbers in `specials` weren't
+    // plain drops into their column.
+    fn seek(&mut self, moves: &[usize], specials: &Specials, ply: usize) -> Result<(), MoveError> {
+        let ply = ply.min(moves.len());
+        while self.history.len() > ply || self.outcome.as_ref().is_some_and(Outcome::off_the_board) {
+            self.undo();
+        }
+        for mv in &specials.list(moves)[self.history.len()..ply] {
+            self.play(*mv)?;
+        }
+        Ok(())
+    }
+
+    // The player whose turn comes after `player`'s, going back to the first after the last
+    fn next_player(&self, player: Player) -> Player {
+        Player::from_index((player.index() + 1) % self.players())
+    }
+
+    // The player whose turn comes before `player`'s
+    fn previous_player(&self, player: Player) -> Player {
+        Player::from_index((player.index() + self.players() - 1) % self.players())
+    }
+
+    // The player who made the last move: still the one to move when it ended the game, and
+    // otherwise the one before. Moves a search plays out of turn don't follow the count of
+    // moves, so this goes by whose turn it is instead.
+    fn last_mover(&self) -> Player {
+        if self.is_finished() {
+            self.current_player
+        } else {
+            self.previous_player(self.current_player)
+        }
+    }
+
+    // End the game with the player to move giving up, so the other one wins
+    fn resign(&mut self) {
+        let player = self.current_player.other();
+        self.outcome = Some(Outcome::Win { player, line: None, by: WinKind::Resignation });
+    }
+
+    // Offer the other player a draw, which they can accept on their next turn
+    fn offer_draw(&mut self) -> Result<(), String> {
+        let index = usize::from(self.current_player == Player::Two);
+        if self.draw_offer == Some(self.current_player) {
+            return Err(i18n::text("draw.already_offered"));
+        }
+        if let Some(last) = self.last_offers[index] {
+            if self.current_move < last + DRAW_OFFER_GAP {
+                return Err(i18n::fill("draw.too_soon", &[("last", &(last + 1)), ("next", &(last + DRAW_OFFER_GAP + 1))]));
+            }
+        }
+        self.draw_offer = Some(self.current_player);
+        self.last_offers[index] = Some(self.current_move);
+        Ok(())
+    }
+
+    // Whether the player to move has a draw offer to answer
+    fn draw_offered(&self) -> bool {
+        self.draw_offer == Some(self.current_player.other())
+    }
+
+    // Accept the standing draw offer, ending the game
+    fn accept_draw(&mut self) -> Result<(), String> {
+        if !self.draw_offered() {
+            return Err(i18n::text("draw.no_offer"));
+        }
+        self.draw_offer = None;
+        self.agree_to_draw();
+        Ok(())
+    }
+
+    // End the game in a draw both players agreed to
+    fn agree_to_draw(&mut self) {
+        self.outcome = Some(Outcome::Draw { by: DrawKind::Agreement });
+    }
+
+    // Whether the player to move may swap: under the pie rule, as the answer to the first move
+    fn can_swap(&self) -> bool {
+        self.rules.pie_rule && !self.swapped && !self.is_finished() && self.history.len() == 1
+    }
+
+    // Take the first move over as the second player's own, under the pie rule: the players
+    // trade seats with the board as it is, so the one who made the move is to play next
+    fn swap(&mut self, now: Instant) -> Result<(), String> {
+        if !self.can_swap() {
+            return Err(i18n::text("swap.not_allowed"));
+        }
+        self.trade_seats(now);
+        self.swapped = true;
+        Ok(())
+    }
+
+    // What happened when the second player swapped
+    fn swap_note(&self) -> String {
+        i18n::fill("swap.note", &[("first", &self.name(Player::One)), ("second", &self.name(Player::Two))])
+    }
+
+    // Exchange everything kept by seat between the players: names, the computer's seat,
+    // clocks, and the assists and draw offers counted against each
+    fn trade_seats(&mut self, now: Instant) {
+        let ply = self.history.len();
+        self.names.swap(0, 1);
+        self.computer = self.computer.map(|(seat, difficulty)| (seat.other(), difficulty));
+        self.hints.swap(0, 1);
+        self.takebacks.swap(0, 1);
+        self.last_offers.swap(0, 1);
+        self.draw_offer = None;
+        if let Some(clock) = &mut self.clock {
+            clock.swap_seats(now, ply);
+        }
+        if let Some(timer) = &mut self.move_timer {
+            timer.stop();
+        }
+    }
+
+    // The 0-based `column`, if the board has it
+    fn column(&self, column: usize) -> Result<Column, MoveError> {
+        Column::new(column, self.rules.width)
+    }
+
+    // What was wrong with a move, naming the column of a 3D game by its peg
+    fn move_error(&self, err: &MoveError) -> String {
+        match err {
+            MoveError::ColumnFull { column } if self.rules.variant == Variant::Cube => {
+                i18n::fill("move.peg_full", &[("peg", &cube::peg_name(*column))])
+            }
+            _ => err.to_string(),
+        }
+    }
+
+    // Play a move in the specified column
+    fn play_move(&mut self, column: Column) -> Result<PlayedMove, MoveError> {
+        // Check if the game is already finished
+        if self.is_finished() {
+            return Err(MoveError::GameFinished);
+        }
+
+        // Until the handicap is down, pieces go to it instead of being moves
+        if self.placing() {
+            let landing = self.place_handicap(column)?;
+            return Ok(PlayedMove { landing, player: Player::Two, number: self.current_move, outcome: None });
+        }
+
+        // The piece lands on top of the ones already in the column, unless it's full
+        let column = column.index();
+        let height = self.column_height(column);
+        if height == self.rules.height {
+            return Err(MoveError::ColumnFull { column });
+        }
+
+        // Moving instead of accepting turns down a draw offer
+        if self.draw_offered() {
+            self.draw_offer = None;
+        }
+
+        // Place the current player's piece in the cell and record where it landed
+        let player = self.current_player;
+        let landing = Coord { column, row: height };
+        self.board[self.rules.height - 1 - height][column] = Some(player);
+        self.current_move += 1;
+        self.history.push(landing);
+
+        // Decide the game after the move, and pass the turn on unless it was won
+        self.outcome = self.calculate_outcome();
+        if self.winner().is_none() {
+            self.current_player = self.next_player(self.current_player);
+        }
+
+        Ok(PlayedMove { landing, player, number: self.current_move, outcome: self.outcome.clone() })
+    }
+}
+
+// Classic Connect Four between two people, with every setting as it comes
+impl Default for Game {
+    fn default() -> Game {
+        Game::from_settings(&Settings::default())
+    }
+}
+
+// The plain board followed by the move count and whose turn it is, or the result once the
+// game is over. Like the board's, this never contains colors and is stable for tests.
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.board)?;
+        match &self.outcome {
+            None => write!(
+                f,
+                "move {}, {} to move",
+                self.current_move,
+                self.name(self.current_player)
+            ),
+            Some(Outcome::Draw { by: DrawKind::Agreement }) => {
+                write!(f, "draw by agreement after {} moves", self.current_move)
+            }
+            Some(Outcome::Draw { .. }) => write!(f, "draw after {} moves", self.current_move),
+            Some(Outcome::Win { player, by: WinKind::Time, .. }) => write!(
+                f,
+                "{} won on time after {} moves",
+                self.name(*player),
+                self.current_move
+            ),
+            Some(Outcome::Win { player, by: WinKind::Resignation, .. }) => write!(
+                f,
+                "{} won by resignation after {} moves",
+                self.name(*player),
+                self.current_move
+            ),
+            Some(Outcome::Win { player, .. }) => write!(f, "{} won on move {}", self.name(*player), self.current_move),
+        }
+    }
+}
+
+// Write each setting, its value, and where the value came from, one to a line
+fn show_config(out: &mut impl Write, settings: &Settings) -> io::Result<()> {
+    for (key, value, source) in settings.describe() {
+        writeln!(out, "{:<20} {:<12} ({})", key, value, source)?;
+    }
+    Ok(())
+}
+
+// Print `message` to stderr as an error, in the session's language
+fn print_error(message: impl fmt::Display) {
+    eprintln!("{}", i18n::fill("error", &[("message", &message)]));
+}
+
+// Print `message` to stderr as a warning
+fn print_warning(message: impl fmt::Display) {
+    eprintln!("{}", i18n::fill("warning", &[("message", &message)]));
+}
+
+// Exit once output can't be written. A reader that stopped reading, like `head`, isn't
+// an error, so that exits quietly.
+fn print_or_exit(written: io::Result<()>) {
+    match written {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        Err(err) => {
+            print_error(err);
+            process::exit(1);
+        }
+    }
+}
+
+// Print `text` and a newline the way print_or_exit writes
+fn print_out(text: &str) {
+    print_or_exit(writeln!(io::stdout().lock(), "{}", text));
+}
+
+// Run the program on its command line, exiting when it's done
+#[cfg(feature = "terminal")]
+pub fn run() {
+    let cli = Cli::parse();
+    logging::init(cli.options.verbose, cli.options.log_format);
+
+    // Writing the config file must work before there is one to read
+    if let Some(Command::Config {
+        action: ConfigAction::Init { force },
+    }) = cli.command
+    {
+        let path = settings::config_path(&cli.options).unwrap_or_else(|| {
+            print_error(i18n::text("config.no_dir"));
+            process::exit(2);
+        });
+        match settings::write_default_config(&path, force) {
+            Ok(()) => println!("Wrote {}", path.display()),
+            Err(err) => {
+                print_error(err);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let (settings, warnings) = match Settings::resolve(&cli.options) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            print_error(err);
+            process::exit(2);
+        }
+    };
+    for warning in warnings {
+        print_warning(warning);
+    }
+    if let Some(warning) = i18n::init(&settings.language) {
+        print_warning(warning);
+    }
+
+    match cli.command {
+        Some(Command::Config {
+            action: ConfigAction::Show,
+        }) => {
+            print_or_exit(show_config(&mut io::stdout().lock(), &settings));
+        }
+        Some(Command::Config {
+            action: ConfigAction::Init { .. },
+        }) => unreachable!("config init is handled before the settings are resolved"),
+        Some(Command::Play) => play(&settings),
+        Some(Command::Presets) => print_out(&presets::list(&settings.rule_presets)),
+        Some(Command::Stats {
+            view:
+                Some(StatsView::Heatmap {
+                    cells,
+                    mirror,
+                    min_games,
+                    json,
+                }),
+            ..
+        }) => match heatmap::build(settings.width, settings.height) {
+            Ok((mut heatmap, skipped)) => {
+                if skipped > 0 {
+                    print_warning(i18n::plural("log.skipped", skipped as usize));
+                }
+                if mirror {
+                    heatmap.merge_mirrors();
+                }
+                if json {
+                    print_out(&heatmap.json());
+                } else {
+                    let style = Capabilities::detect(settings.color).style;
+                    print_out(&heatmap.render(style, min_games, cells));
+                }
+            }
+            Err(err) => {
+                print_error(err);
+                process::exit(1);
+            }
+        },
+        Some(Command::Stats { name: Some(name), .. }) => match Profile::load(&name) {
+            Ok(Some(profile)) => print_out(&profile.report()),
+            Ok(None) => println!("No games recorded for {}", name),
+            Err(err) => {
+                print_error(err);
+                process::exit(1);
+            }
+        },
+        Some(Command::Stats {
+            name: None,
+            player,
+            since,
+            json,
+            ..
+        }) => match history::summarize(&history::Filter { player, since }) {
+            Ok((summary, skipped)) => {
+                if skipped > 0 {
+                    print_warning(i18n::plural("log.skipped", skipped as usize));
+                }
+                if json {
+                    print_out(&summary.json());
+                } else {
+                    print_out(&summary.report());
+                }
+            }
+            Err(err) => {
+                print_error(err);
+                process::exit(1);
+            }
+        },
+        Some(Command::Leaderboard {
+            sort,
+            min_games,
+            include_computer,
+            format,
+        }) => match profile::all() {
+            Ok(profiles) => {
+                let entries = leaderboard::entries(&profiles, sort, min_games, include_computer);
+                let width = io::stdout()
+                    .is_terminal()
+                    .then(|| crossterm::terminal::size().ok())
+                    .flatten()
+                    .map(|(columns, _)| usize::from(columns));
+                match format {
+                    leaderboard::Format::Table if entries.is_empty() => println!("No players yet"),
+                    leaderboard::Format::Table => print_out(&leaderboard::table(&entries, width)),
+                    leaderboard::Format::Csv => print_out(&leaderboard::csv(&entries)),
+                    leaderboard::Format::Json => print_out(&leaderboard::json(&entries)),
+                }
+            }
+            Err(err) => {
+                print_error(err);
+                process::exit(1);
+            }
+        },
+        Some(Command::Export {
+            format:
+                ExportFormat::Csv {
+                    out,
+                    per_move,
+                    player,
+                    since,
+                },
+        }) => {
+            let written = export::csv(&history::Filter { player, since }, per_move).and_then(|(text, skipped)| {
+                if skipped > 0 {
+                    print_warning(i18n::plural("log.skipped", skipped as usize));
+                }
+                match &out {
+                    Some(path) => std::fs::write(path, text)
+                        .map_err(|err| format!("could not write {}: {}", path.display(), err)),
+                    None => {
+                        print_or_exit(write!(io::stdout().lock(), "{}", text));
+                        Ok(())
+                    }
+                }
+            });
+            if let Err(err) = written {
+                print_error(err);
+                process::exit(1);
+            }
+        }
+        Some(Command::Export {
+            format: ExportFormat::Json { game, out },
+        }) => {
+            let report = game_report(&game, &settings).and_then(|report| match &out {
+                Some(path) => std::fs::write(path, format!("{}\n", report))
+                    .map_err(|err| format!("could not write {}: {}", path.display(), err)),
+                None => {
+                    print_out(&report);
+                    Ok(())
+                }
+            });
+            if let Err(err) = report {
+                print_error(err);
+                process::exit(1);
+            }
+        }
+        Some(Command::Export {
+            format: ExportFormat::Pgn { games, out, player, since },
+        }) => {
+            let written = pgn_games(&games, &history::Filter { player, since }, &settings).and_then(|text| match &out {
+                Some(path) => std::fs::write(path, text).map_err(|err| format!("could not write {}: {}", path.display(), err)),
+                None => {
+                    print_or_exit(write!(io::stdout().lock(), "{}", text));
+                    Ok(())
+                }
+            });
+            if let Err(err) = written {
+                print_error(err);
+                process::exit(1);
+            }
+        }
+        Some(Command::Import { file, check }) => {
+            let imported = std::fs::read_to_string(&file)
+                .map_err(|err| format!("could not read {}: {}", file.display(), err))
+                .and_then(|text| import_games(&text, &settings, check));
+            match imported {
+                Ok(0) => {}
+                Ok(failed) => {
+                    eprintln!("{}", i18n::fill("import.failed", &[("count", &failed), ("file", &file.display())]));
+                    process::exit(1);
+                }
+                Err(err) => {
+                    print_error(err);
+                    process::exit(1);
+                }
+            }
+        }
+        Some(Command::Replay { game, autoplay, speed }) => {
+            if let Err(err) = find_game(&game, &settings).and_then(|found| replay::run(found, &settings, autoplay, speed)) {
+                print_error(err);
+                process::exit(1);
+            }
+        }
+        Some(Command::Reproduce { game }) => match reproduce::run(&game, &settings) {
+            Ok(report) => {
+                for line in &report.lines {
+                    print_out(line);
+                }
+                if !report.reproduced {
+                    process::exit(1);
+                }
+            }
+            Err(err) => {
+                print_error(err);
+                process::exit(1);
+            }
+        },
+        Some(Command::Tutorial { lesson }) => {
+            if let Err(err) = tutorial::run(&settings, lesson.as_deref()) {
+                print_error(err);
+                process::exit(1);
+            }
+        }
+        Some(Command::Ratings) => match profile::ratings() {
+            Ok(profiles) if profiles.is_empty() => println!("No rated games yet"),
+            Ok(profiles) => {
+                for (rank, profile) in profiles.iter().enumerate() {
+                    print_out(&format!(
+                        "{:>3}. {:<20} {:>5}  ({})",
+                        rank + 1,
+                        profile.name,
+                        profile.rating.unwrap_or(profile::START_RATING),
+                        profile::plural(profile.rated_games, "game")
+                    ));
+                }
+            }
+            Err(err) => {
+                print_error(err);
+                process::exit(1);
+            }
+        },
+        // The menu is for people at a terminal; piped games start right away
+        None if io::stdin().is_terminal() && io::stdout().is_terminal() => {
+            let mut settings = settings;
+            if show_menu(&mut settings, &cli.options) {
+                play(&settings);
+            }
+        }
+        None => play(&settings),
+    }
+}
+
+// The JSON report of a game named as `find_game` takes it
+fn game_report(game: &str, settings: &Settings) -> Result<String, String> {
+    let found = find_game(game, settings)?;
+    Ok(found.game.to_json_report(found.date.as_deref()))
+}
+
+// The games named as `find_game` takes them as PGN, or without names every logged game the
+// filter lets through
+fn pgn_games(games: &[String], filter: &history::Filter, settings: &Settings) -> Result<String, String> {
+    let mut found = Vec::new();
+    if games.is_empty() {
+        let mut failed = None;
+        // PGN results are for two players, so games of more are left out
+        let mut crowded = 0;
+        let skipped = history::scan(|entry| {
+            if filter.side(entry).is_none() || failed.is_some() {
+                return;
+            }
+            if entry.names.len() > 2 {
+                crowded += 1;
+                return;
+            }
+            match export::from_entry(entry, settings) {
+                Ok(game) => found.push(pgn::Record::new(game, Some(entry.date.clone()))),
+                Err(err) => failed = Some(err),
+            }
+        })?;
+        if let Some(err) = failed {
+            return Err(err);
+        }
+        if skipped > 0 {
+            print_warning(i18n::plural("log.skipped", skipped as usize));
+        }
+        if crowded > 0 {
+            print_warning(i18n::plural("export.crowded", crowded));
+        }
+    } else {
+        for game in games {
+            let game = find_game(game, settings)?;
+            if game.game.players() > 2 {
+                return Err(format!("PGN can't hold a game of {} players; export it as json", game.game.players()));
+            }
+            found.push(pgn::Record::new(game.game, game.date));
+        }
+    }
+    Ok(pgn::write_all(&found))
+}
+
+// Save every game in `text`, written by export pgn, under its id, or with `check` only read
+// them. Games that can't be read are reported and left out; returns how many those were.
+fn import_games(text: &str, settings: &Settings, check: bool) -> Result<usize, String> {
+    let (records, errors) = pgn::read(text, settings);
+    for error in &errors {
+        print_error(error);
+    }
+    for record in &records {
+        if !check {
+            save::write(&record.game, settings, &Session::default(), &record.game.id)?;
+        }
+    }
+    let verb = if check { "Read" } else { "Imported" };
+    let noun = if records.len() == 1 { "game" } else { "games" };
+    println!("{} {} {}", verb, records.len(), noun);
+    Ok(errors.len())
+}
+
+// A game looked up by `find_game`
+struct Found {
+    game: Game,
+    // The date it was played, when the log or the save has it
+    date: Option<String>,
+    // The save it was read from, if it was
+    save: Option<PathBuf>,
+}
+
+// A game named by a save slot or file, its number in the log, or its id. A save that exists
+// goes first, then a number, then an id, looked for among the saves before the log.
+fn find_game(game: &str, settings: &Settings) -> Result<Found, String> {
+    let path = save::slot_path(game).ok_or("no data directory found; give a path instead")?;
+    let logged = |entry: Option<history::Entry>, missing: String| {
+        let entry = entry.ok_or(missing)?;
+        Ok(Found {
+            game: export::from_entry(&entry, settings)?,
+            date: Some(entry.date),
+            save: None,
+        })
+    };
+    let saved = |path: PathBuf| {
+        let game = save::load(&path, settings)?;
+        Ok(Found {
+            date: game.date.clone(),
+            game,
+            save: Some(path),
+        })
+    };
+    if path.exists() {
+        saved(path)
+    } else if let Ok(number) = game.parse::<usize>() {
+        logged(history::find(number)?, format!("no game {} in the log", number))
+    } else if id::is_ulid(game) {
+        match save::find(game) {
+            Some(path) => saved(path),
+            None => logged(history::find_id(game)?, format!("no game with id {}", game)),
+        }
+    } else {
+        saved(path)
+    }
+}
+
+// Let the player set up the session from the startup menu. Returns whether to start a game.
+#[cfg(feature = "terminal")]
+fn show_menu(settings: &mut Settings, options: &cli::Options) -> bool {
+    let capabilities = Capabilities::detect(settings.color);
+    let (theme, _) = settings
+        .theme()
+        .fallback(capabilities.unicode, capabilities.style.color);
+    let renderer = Renderer::new(theme, capabilities.style, settings.cell_width);
+    let keys = settings.input == Input::Keys || settings.ui == Ui::Tui;
+    let config = settings::config_path(options);
+
+    match menu::run(settings, &renderer, config.as_deref(), keys) {
+        Ok(start) => start,
+        Err(err) => {
+            print_error(err);
+            process::exit(1);
+        }
+    }
+}
+
+// Rows a piece passes through, top first, on its way down to the `landing` row
+fn drop_frames(landing: usize) -> Vec<usize> {
+    (0..landing).collect()
+}
+
+// Play one turn with single keys: the cursor starts on the selected column (or the middle
+// one) and skips full columns. Returns false when the player quits.
+#[cfg(feature = "terminal")]
+fn key_turn(game: &mut Game, screen: &mut Screen, settings: &Settings, session: &Session) -> io::Result<bool> {
+    let open = |game: &Game, column: usize| game.can_play(column) || game.can_pop(column) || game.can_bomb(column);
+    if !screen.selected_column.is_some_and(|column| open(game, column)) {
+        let middle = game.rules.width / 2;
+        screen.selected_column = (0..game.rules.width)
+            .filter(|&column| open(game, column))
+            .min_by_key(|&column| column.abs_diff(middle));
+    }
+    let blind = screen.renderer.blind.is_some();
+    let prompt = |game: &Game| {
+        let part = |shown: bool, key: &str| if shown { i18n::text(key) } else { String::new() };
+        let pause = part(game.clock.is_some() || game.move_timer.is_some(), "prompt.keys.pause");
+        let swap = part(game.can_swap(), "prompt.keys.swap");
+        let peek = part(blind, "prompt.keys.peek");
+        let pop = match game.rules.variant {
+            Variant::Standard | Variant::Cylinder | Variant::Misere | Variant::Score | Variant::Cube => String::new(),
+            Variant::PopOut => i18n::text("prompt.keys.pop"),
+            Variant::PowerUp => i18n::text("prompt.keys.powers"),
+            Variant::Flip => i18n::text("prompt.keys.flip"),
+        };
+        let placing = if game.placing() { format!("{}. ", game.placement_note()) } else { String::new() };
+        let time = game.time_note(Instant::now());
+        i18n::fill(
+            "prompt.keys",
+            &[("placing", &placing), ("pop", &pop), ("swap", &swap), ("pause", &pause), ("peek", &peek), ("time", &time)],
+        )
+    };
+    // The board with a note on a move just played for a player out of time
+    let redraw = |game: &Game, screen: &Screen| {
+        game.display_board(screen);
+        if let Some(note) = game.timeout_note() {
+            println!("{}", screen.renderer.accent(&note));
+        }
+        screen.prompt(&prompt(game));
+    };
+    redraw(game, screen);
+
+    // A key read while waiting for a second digit that turned out not to be one
+    let mut pending = None;
+    loop {
+        // On a clock or a move timer, stop waiting when the player's time runs out, and where
+        // the board is redrawn in place show it going down every second
+        let left = game.time_left(Instant::now());
+        let wait = match left {
+            Some(left) if screen.redraws_in_place() => Some(left.min(Duration::from_secs(1))),
+            left => left,
+        };
+        let event = match pending.take() {
+            Some(event) => Some(event),
+            None => keys::poll_input(wait)?,
+        };
+        let Some(event) = event else {
+            if !game.tick(Instant::now()) {
+                redraw(game, screen);
+                continue;
+            }
+            game.display_board(screen);
+            return Ok(true);
+        };
+        if game.tick(Instant::now()) {
+            game.display_board(screen);
+            return Ok(true);
+        }
+
+        // Board rows start at the left edge of the screen
+        let action = match event {
+            // On a board wider than nine columns a digit waits a moment for a second one
+            Event::Key(key) if keys::starts_number(&key, game.rules.width) => {
+                let second = keys::poll_input(Some(keys::SECOND_DIGIT))?;
+                match second.as_ref().and_then(|second| keys::two_digit_column(&key, second, game.rules.width)) {
+                    Some(column) => keys::column_action(column, screen.selected_column, settings.digits),
+                    None => {
+                        pending = second;
+                        keys::action(key, screen.selected_column, settings.digits)
+                    }
+                }
+            }
+            Event::Key(key) => keys::action(key, screen.selected_column, settings.digits),
+            Event::Mouse(mouse) => match screen.board_layout(game.rules.width) {
+                Ok(layout) => keys::mouse_action(mouse, Some(usize::from(mouse.column)), &layout, game.rules.width),
+                Err(_) => Action::Ignore,
+            },
+            _ => Action::Ignore,
+        };
+        match action {
+            Action::Step(step) => {
+                // Walk over full columns to the next open one, staying put at the edges
+                let Some(start) = screen.selected_column else {
+                    continue;
+                };
+                let mut column = start as isize + step;
+                while (0..game.rules.width as isize).contains(&column) && !open(game, column as usize) {
+                    column += step;
+                }
+                if (0..game.rules.width as isize).contains(&column) {
+                    screen.selected_column = Some(column as usize);
+                    game.display_board(screen);
+                }
+            }
+            Action::Select(column) if column >= game.rules.width => {
+                game.display_error(screen, MoveError::InvalidColumn { attempted: column, width: game.rules.width }.to_string());
+            }
+            Action::Select(column) if !open(game, column) => {
+                game.display_error(screen, game.move_error(&MoveError::ColumnFull { column }));
+            }
+            Action::Select(column) => {
+                screen.selected_column = Some(column);
+                game.display_board(screen);
+            }
+            Action::Hover(column) => {
+                // Only redraw when the pointer reaches another open column
+                if screen.selected_column == Some(column) || !open(game, column) {
+                    continue;
+                }
+                screen.selected_column = Some(column);
+                game.display_board(screen);
+            }
+            Action::Outside => {
+                game.display_error(screen, "Click on a column of the board to drop a piece".to_string());
+            }
+            Action::Drop(column) => match game.column(column).and_then(|column| game.play_move(column)) {
+                Ok(played) => {
+                    game.debug_validate();
+                    game.tick(Instant::now());
+                    if settings.animation && screen.redraws_in_place() {
+                        game.animate_drop(screen, &played);
+                    }
+                    game.display_board(screen);
+                    return Ok(true);
+                }
+                Err(err) => game.display_error(screen, game.move_error(&err)),
+            },
+            Action::Special(mv) => match game.play(mv) {
+                Ok(()) => {
+                    game.debug_validate();
+                    game.tick(Instant::now());
+                    game.display_board(screen);
+                    return Ok(true);
+                }
+                Err(err) => game.display_error(screen, err.to_string()),
+            },
+            Action::Undo => {
+                if game.history.len() > game.random_start {
+                    game.take_back(1);
+                    return Ok(true);
+                }
+                game.display_error(screen, game.nothing_to_undo());
+            }
+            Action::Pause => pause_game(game, screen, settings, true),
+            Action::Peek => peek_board(game, screen),
+            Action::Swap => match game.swap(Instant::now()) {
+                Ok(()) => {
+                    game.tick(Instant::now());
+                    game.display_board(screen);
+                    println!("{}", screen.renderer.accent(&game.swap_note()));
+                    return Ok(true);
+                }
+                Err(err) => game.display_error(screen, err),
+            },
+            Action::Help => {
+                game.display_board(screen);
+                println!("{}", keys::help());
+            }
+            Action::Quit => {
+                if confirm_quit(game, screen, settings, session, true) {
+                    return Ok(false);
+                }
+            }
+            Action::Interrupt => return Ok(false),
+            Action::Ignore => continue,
+        }
+        screen.prompt(&prompt(game));
+    }
+}
+
+// Renderer for the settings' theme and display options on this terminal, with the warning
+// to show if the theme had to be replaced by one the terminal can show
+#[cfg(feature = "terminal")]
+fn build_renderer(settings: &Settings, capabilities: &Capabilities) -> (Renderer, Option<String>) {
+    let (theme, warning) = settings.theme().fallback(capabilities.unicode, capabilities.style.color);
+    let mut renderer = Renderer::new(theme, capabilities.style, settings.cell_width);
+    renderer.enlarge(settings.scale);
+    renderer.ruler = settings.ruler;
+    renderer.coords = settings.coords;
+    renderer.marker = settings.marker;
+    renderer.density = settings.density;
+    renderer.accessible = settings.accessible;
+
+    // The levels of a 3D game are drawn peg by peg
+    if settings.variant == Variant::Cube {
+        renderer.fit_board(cube::SIDE, cube::SIDE);
+    } else {
+        renderer.fit_board(settings.width, settings.height);
+    }
+    (renderer, warning)
+}
+
+// Carry out a colon command typed at the move prompt or after the game
+#[cfg(feature = "terminal")]
+fn run_command(
+    game: &mut Game,
+    screen: &mut Screen,
+    settings: &Settings,
+    session: &mut Session,
+    command: command::Command,
+    context: Context,
+) {
+    if let Err(reason) = command.check(context) {
+        game.display_error(screen, reason);
+        return;
+    }
+
+    match command {
+        command::Command::Move(_) | command::Command::Special(_) => unreachable!("moves are played by the caller"),
+        command::Command::Quit => unreachable!("quitting is up to the caller"),
+        command::Command::Preview(column) if column >= game.rules.width => {
+            game.display_error(screen, MoveError::InvalidColumn { attempted: column, width: game.rules.width }.to_string());
+        }
+        command::Command::Preview(column) if game.board[0][column].is_some() => {
+            game.display_error(screen, game.move_error(&MoveError::ColumnFull { column }));
+        }
+        command::Command::Preview(column) => {
+            screen.selected_column = Some(column);
+            game.display_board(screen);
+        }
+        command::Command::Save(slot) => match save::write(game, settings, session, slot.as_deref().unwrap_or(save::DEFAULT_SLOT)) {
+            Ok(path) => {
+                game.display_board(screen);
+                println!("{}", screen.renderer.accent(&i18n::fill("quit.saved", &[("path", &path.display())])));
+            }
+            Err(err) => game.display_error(screen, err),
+        },
+        command::Command::Hint if game.placing() => {
+            game.display_error(screen, "hints start with the first move, once the handicap is down".to_string());
+        }
+        command::Command::Hint => match ai::choose_move(game, Difficulty::Hard) {
+            Some(mv) => {
+                game.hints[game.current_player.index()] += 1;
+                screen.selected_column = Some(mv.column());
+                game.display_board(screen);
+                println!("{}", screen.renderer.accent(&i18n::fill("hint.try", &[("move", &move_name(game, mv, &screen.renderer))])));
+            }
+            None => game.display_error(screen, "No moves left".to_string()),
+        },
+        command::Command::Undo(count) => {
+            if game.history.len() <= game.random_start {
+                game.display_error(screen, game.nothing_to_undo());
+                return;
+            }
+            if game.won_by() == Some(WinKind::Time) {
+                game.display_error(screen, "the game was lost on time; taking moves back won't give the time back".to_string());
+                return;
+            }
+            game.take_back(count);
+            screen.selected_column = None;
+            game.display_board(screen);
+        }
+        command::Command::Comment(text) => match comment::set(&mut game.comments, game.history.len(), &text) {
+            Ok(message) => {
+                game.display_board(screen);
+                println!("{}", screen.renderer.accent(&message));
+            }
+            Err(err) => game.display_error(screen, err),
+        },
+        command::Command::Help(None) => show_help(game, screen, &command::help(context)),
+        command::Command::Help(Some(name)) => match command::spec(&name) {
+            Some(spec) => show_help(game, screen, &command::help_for(spec, context)),
+            None => game.display_error(screen, format!("no command called '{}'; :help lists them", name)),
+        },
+        command::Command::Resign | command::Command::Draw | command::Command::Accept if game.players() > 2 => {
+            game.display_error(screen, "resigning and draws by agreement are only for two-player games".to_string());
+        }
+        command::Command::Resign => {
+            screen.prompt(&format!("{}, resign the game? (y/n)", game.name(game.current_player)));
+            let mut answer = String::new();
+            if io::stdin().read_line(&mut answer).unwrap_or(0) > 0 && answer.trim().eq_ignore_ascii_case("y") {
+                game.resign();
+            }
+            screen.selected_column = None;
+            game.display_board(screen);
+        }
+        command::Command::Draw => match game.offer_draw() {
+            Ok(()) => {
+                game.display_board(screen);
+                let offer = i18n::fill("draw.offered", &[("name", &game.name(game.current_player.other()))]);
+                println!("{}", screen.renderer.accent(&offer));
+            }
+            Err(err) => game.display_error(screen, err),
+        },
+        command::Command::Pause => pause_game(game, screen, settings, false),
+        command::Command::Resume => game.display_error(screen, "the game isn't paused".to_string()),
+        command::Command::Peek => peek_board(game, screen),
+        command::Command::Describe(_) if game.hidden(&screen.renderer) => {
+            game.display_error(screen, "the board is hidden; :peek shows it for a moment".to_string());
+        }
+        command::Command::Describe(Some(column)) if column >= game.rules.width => {
+            game.display_error(screen, MoveError::InvalidColumn { attempted: column, width: game.rules.width }.to_string());
+        }
+        command::Command::Describe(column) => {
+            // Read out under the board as it's drawn, or after what was said so far
+            if !screen.renderer.accessible {
+                game.display_board(screen);
+            }
+            let lines = match column {
+                None => game.describe_board(),
+                Some(column) => game.describe_column(column),
+            };
+            println!("{}", lines.join("\n"));
+        }
+        command::Command::Accept => match game.accept_draw() {
+            Ok(()) => {
+                screen.selected_column = None;
+                game.display_board(screen);
+            }
+            Err(err) => game.display_error(screen, err),
+        },
+        command::Command::Swap => match game.swap(Instant::now()) {
+            Ok(()) => {
+                screen.selected_column = None;
+                game.tick(Instant::now());
+                game.display_board(screen);
+                println!("{}", screen.renderer.accent(&game.swap_note()));
+            }
+            Err(err) => game.display_error(screen, err),
+        },
+        command::Command::Score { reset: false } => {
+            game.display_board(screen);
+            println!("{}", screen.renderer.accent(&session.with(game).summary()));
+        }
+        command::Command::Score { reset: true } => {
+            *session = Session::default();
+            game.display_board(screen);
+            println!("{}", screen.renderer.accent("Session tally reset"));
+        }
+        command::Command::Stats(name) => {
+            let name = name.unwrap_or_else(|| game.name(game.current_player).to_string());
+            match Profile::load(&name) {
+                Ok(Some(profile)) => {
+                    game.display_board(screen);
+                    println!("{}", profile.report());
+                }
+                Ok(None) => game.display_error(screen, format!("no games recorded for {}", name)),
+                Err(err) => game.display_error(screen, err),
+            }
+        }
+        command::Command::Achievements(name) => {
+            let name = name.unwrap_or_else(|| game.name(game.current_player).to_string());
+            // Someone who hasn't finished a game yet still sees what there is to earn
+            match Profile::load(&name) {
+                Ok(profile) => {
+                    let profile = profile.unwrap_or(Profile {
+                        name,
+                        ..Profile::default()
+                    });
+                    game.display_board(screen);
+                    println!("{}", profile.achievement_report());
+                }
+                Err(err) => game.display_error(screen, err),
+            }
+        }
+        command::Command::Theme(None) => {
+            game.display_board(screen);
+            println!("{}", screen.renderer.theme_list(settings.cell_width));
+        }
+        command::Command::Theme(Some(name)) => {
+            if !theme::THEME_NAMES.contains(&name.as_str()) {
+                game.display_error(screen, format!("unknown theme '{}'; :theme lists them", name));
+                return;
+            }
+            let mut themed = settings.clone();
+            themed.theme = name;
+            if let Err(err) = themed.theme().check_players_distinct(themed.players) {
+                game.display_error(screen, err);
+                return;
+            }
+            let (renderer, warning) = build_renderer(&themed, &screen.capabilities);
+            screen.renderer = Renderer { blind: screen.renderer.blind, ..renderer };
+            game.display_board(screen);
+            if let Some(warning) = warning {
+                println!("{}", warning);
+            }
+        }
+    }
+}
+
+// Stop a timed game's clocks and hide the board until both players are ready to go on, so
+// nobody studies the position on their opponent's time. With `keys` any key resumes, after
+// asking whether both players are ready; otherwise Enter does, or :resume without asking.
+#[cfg(feature = "terminal")]
+fn pause_game(game: &mut Game, screen: &Screen, settings: &Settings, keys: bool) {
+    if let Err(err) = game.can_pause(settings.rated) {
+        game.display_error(screen, err);
+        return;
+    }
+    game.pause(Instant::now());
+    screen.transcript.observe(game);
+
+    // A key, or a line with its newline trimmed; None at the end of the input
+    let read = || {
+        if keys {
+            return keys::read_key().ok().map(|key| match key.code {
+                KeyCode::Char(c) => c.to_string(),
+                _ => String::new(),
+            });
+        }
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(line.trim().to_string()),
+        }
+    };
+    loop {
+        screen.clear();
+        println!("\n");
+        println!("{}", screen.renderer.accent("Game paused — both clocks are stopped"));
+        screen.prompt(if keys {
+            "Press any key to resume"
+        } else {
+            "Press Enter to resume, or type :resume"
+        });
+        // A script that ends while paused just carries on
+        let Some(input) = read() else {
+            break;
+        };
+        if input == ":resume" {
+            break;
+        }
+        screen.prompt("Both players ready? (y/n)");
+        if read().is_none_or(|answer| answer.eq_ignore_ascii_case("y")) {
+            break;
+        }
+    }
+
+    let length = game.resume(Instant::now());
+    screen.transcript.observe(game);
+    game.display_board(screen);
+    println!("{}", screen.renderer.accent(&i18n::fill("pause.resumed", &[("length", &clock::display(length))])));
+}
+
+// Show a blind game's board for a moment, then hide it again
+#[cfg(feature = "terminal")]
+fn peek_board(game: &Game, screen: &mut Screen) {
+    let peeked = match &mut screen.renderer.blind {
+        None => Err(i18n::text("blind.not_blind")),
+        Some(_) if game.history.len() <= game.random_start => Err(i18n::text("blind.in_sight")),
+        Some(blind) => blind.peek().map(|()| blind.left()),
+    };
+    let left = match peeked {
+        Ok(left) => left,
+        Err(err) => {
+            game.display_error(screen, err);
+            return;
+        }
+    };
+    screen.transcript.peek(game, left);
+    game.display_board(screen);
+    println!("{}", screen.renderer.accent(&i18n::fill("blind.peeking", &[("peeks", &i18n::plural("count.peeks", left))])));
+    thread::sleep(PEEK);
+
+    // The board drawn next is the hidden one, over the top of the peek
+    if let Some(blind) = &mut screen.renderer.blind {
+        blind.end_peek();
+    }
+    game.display_board(screen);
+}
+
+// Show help under the board, or a page at a time on a screen of its own when it doesn't fit
+// in the terminal
+#[cfg(feature = "terminal")]
+fn show_help(game: &Game, screen: &Screen, text: &str) {
+    let pages = command::pages(text, screen.terminal_height().map_or(usize::MAX, |rows| rows.saturating_sub(2)));
+    if pages.len() <= 1 {
+        game.display_board(screen);
+        println!("{}", text);
+        return;
+    }
+
+    for (index, page) in pages.iter().enumerate() {
+        screen.clear();
+        println!("{}", page);
+        if index + 1 < pages.len() {
+            screen.prompt(&format!("-- page {} of {}, Enter for more --", index + 1, pages.len()));
+        } else {
+            screen.prompt("-- Enter to return to the game --");
+        }
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            break;
+        }
+    }
+    game.display_board(screen);
+}
+
+// Ask before leaving a game in the middle of it, offering to save it first when there's
+// somewhere to keep saves, and taking the answer as a key press with `keys`. Returns whether
+// to quit.
+#[cfg(feature = "terminal")]
+fn confirm_quit(game: &Game, screen: &Screen, settings: &Settings, session: &Session, keys: bool) -> bool {
+    let saves = save::slot_path(save::DEFAULT_SLOT).is_some();
+    screen.prompt(&i18n::text(if saves { "quit.ask_save" } else { "quit.ask" }));
+    let mut answer = String::new();
+    if keys {
+        if let Ok(KeyEvent { code: KeyCode::Char(key), .. }) = keys::read_key() {
+            answer.push(key);
+        }
+    } else if io::stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+        return true;
+    }
+    match answer.trim().to_lowercase().as_str() {
+        "y" => true,
+        "s" if saves => match save::write(game, settings, session, save::DEFAULT_SLOT) {
+            Ok(path) => {
+                println!("{}", screen.renderer.accent(&i18n::fill("quit.saved", &[("path", &path.display())])));
+                true
+            }
+            Err(err) => {
+                game.display_error(screen, err);
+                false
+            }
+        },
+        _ => {
+            game.display_board(screen);
+            false
+        }
+    }
+}
+
+// How the prompt names a column: its letter with coordinates on, otherwise its number
+fn column_name(column: usize, renderer: &Renderer) -> String {
+    if renderer.coords {
+        Coord::column_letter(column).to_string()
+    } else {
+        (column + 1).to_string()
+    }
+}
+
+// A move as hints describe it, e.g. "column 4", "popping column 4", or "peg b3"
+fn move_name(game: &Game, mv: Move, renderer: &Renderer) -> String {
+    if game.rules.variant == Variant::Cube {
+        return i18n::fill("hint.peg", &[("peg", &cube::peg_name(mv.column()))]);
+    }
+    let column = column_name(mv.column(), renderer);
+    let key = match mv {
+        Move::Drop(_) => "hint.drop",
+        Move::Pop(_) => "hint.pop",
+        Move::Anvil(_) => "hint.anvil",
+        Move::Bomb(_) => "hint.bomb",
+        Move::Flip => "hint.flip",
+    };
+    i18n::fill(key, &[("column", &column)])
+}
+
+// Count a finished game in its players' profiles, warning about any that couldn't be updated
+fn record_profiles(game: &Game, settings: &Settings) {
+    for warning in profile::record_game(game, settings) {
+        print_warning(warning);
+    }
+}
+
+// The session's transcript, or none if the file can't be opened
+fn open_transcript(settings: &Settings) -> Transcript {
+    Transcript::open(settings).unwrap_or_else(|err| {
+        print_warning(i18n::fill("transcript.off", &[("reason", &err)]));
+        Transcript::off()
+    })
+}
+
+// Ask for the names of the players at the terminal, keeping the defaults on an empty line.
+// The computer keeps its own name.
+fn ask_names(settings: &Settings) -> Settings {
+    let mut named = settings.clone();
+    // The computer's seat, the second, keeps its name
+    for index in (0..settings.players).filter(|&index| index != 1 || !settings.computer) {
+        loop {
+            print!("{} ", i18n::fill("names.ask", &[("number", &(index + 1)), ("name", &named.names[index])]));
+            let _ = io::Write::flush(&mut io::stdout());
+            let mut line = String::new();
+            if io::stdin().read_line(&mut line).unwrap_or(0) == 0 || line.trim().is_empty() {
+                break;
+            }
+            let taken = |name: &str| (0..settings.players).find(|&other| other != index && named.names[other].to_lowercase() == name.to_lowercase());
+            match settings::clean_name(&line) {
+                Ok(name) => match taken(&name) {
+                    Some(other) => print_error(i18n::fill("names.taken", &[("number", &(other + 1)), ("name", &name)])),
+                    None => {
+                        named.names[index] = name;
+                        break;
+                    }
+                },
+                Err(err) => print_error(err),
+            }
+        }
+    }
+    named
+}
+
+// Run the interactive game loop
+#[cfg(feature = "terminal")]
+fn play(settings: &Settings) {
+    // Players who weren't named anywhere get asked at the start of an interactive session
+    let named;
+    let settings = if settings.names == Settings::default().names && io::stdin().is_terminal() && io::stdout().is_terminal() {
+        named = ask_names(settings);
+        &named
+    } else {
+        settings
+    };
+
+    let capabilities = Capabilities::detect(settings.color);
+    let requested = settings.theme != Settings::default().theme;
+
+    // Fall back to a theme this terminal can show, explaining why only if one was asked for
+    let (mut renderer, warning) = build_renderer(settings, &capabilities);
+    renderer.blind = settings.blind.then(|| Blind::new(settings.peeks));
+    if let (Some(warning), true) = (warning, requested) {
+        eprintln!("{}", warning);
+    }
+
+    // The full-screen interface needs a terminal to draw on
+    if settings.ui == Ui::Tui && capabilities.interactive {
+        if let Err(err) = tui::run(settings, &renderer) {
+            print_error(err);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let mut game = match Game::opening(settings) {
+        Ok(game) => game,
+        Err(err) => {
+            print_error(err);
+            process::exit(1);
+        }
+    };
+    let mut screen = Screen::new(settings.altscreen, capabilities, renderer);
+    let mut session = Session::default();
+    // The tally last printed where it stays after the game, on a screen that isn't redrawn
+    let mut tally_shown = None;
+    screen.transcript = open_transcript(settings);
+
+    // Single-key input needs a terminal on both ends; scripts keep entering lines
+    let mut keys = settings.input == Input::Keys
+        && io::stdin().is_terminal()
+        && screen.capabilities.interactive;
+
+    game.tick(Instant::now());
+    game.display_board(&screen);
+    for note in game.coin_flip_note(settings).into_iter().chain(game.random_start_note()) {
+        println!("{}", screen.renderer.accent(&note));
+    }
+
+    'session: loop {
+        while !game.is_finished() {
+            // The clock and the move timer run for the player to move, whose time may have
+            // run out
+            if game.tick(Instant::now()) {
+                game.display_board(&screen);
+                if let Some(note) = game.timeout_note() {
+                    println!("{}", screen.renderer.accent(&note));
+                }
+                continue;
+            }
+            screen.transcript.observe(&game);
+            println!("\n");
+
+            // Display the current player's turn
+            println!("{}", game.name(game.current_player));
+
+            // The computer answers without waiting for input
+            if let Some(difficulty) = game.computer_to_move() {
+                if game.draw_offered() && ai::accepts_draw(&game, difficulty) {
+                    game.accept_draw().expect("a draw was offered");
+                    game.display_board(&screen);
+                    continue;
+                }
+                if game.can_swap() && ai::takes_swap(&game, difficulty) {
+                    game.swap(Instant::now()).expect("the computer may swap");
+                    game.tick(Instant::now());
+                    game.display_board(&screen);
+                    println!("{}", screen.renderer.accent(&game.swap_note()));
+                    continue;
+                }
+                let declined = game.draw_offered().then_some(game.current_player);
+                if let Some(mv) = ai::choose_move(&game, difficulty) {
+                    // A move found after the computer's time ran out is too late
+                    if game.tick(Instant::now()) {
+                        game.display_board(&screen);
+                        continue;
+                    }
+                    let played = match mv {
+                        Move::Drop(column) => game.column(column).and_then(|column| game.play_move(column)).map(Some),
+                        mv => game.play(mv).map(|()| None),
+                    }
+                    .expect("the computer only picks legal moves");
+                    game.debug_validate();
+                    game.tick(Instant::now());
+                    if let (Some(played), true) = (&played, settings.animation && screen.redraws_in_place()) {
+                        game.animate_drop(&screen, played);
+                    }
+                    game.display_board(&screen);
+                    if let Some(seat) = declined {
+                        println!("{}", screen.renderer.accent(&format!("{} declines the draw", game.name(seat))));
+                    }
+                }
+                continue;
+            }
+
+            // Once a column fills up, list the ones still open so nobody has to find out by trying
+            let open: Vec<String> = (0..game.rules.width)
+                .filter(|&column| !game.is_column_full(column))
+                .map(|column| {
+                    if game.rules.variant == Variant::Cube {
+                        cube::peg_name(column)
+                    } else if screen.renderer.coords {
+                        Coord::column_letter(column).to_string()
+                    } else {
+                        (column + 1).to_string()
+                    }
+                })
+                .collect();
+            if open.len() < game.rules.width {
+                println!("{}", i18n::fill("prompt.available", &[("columns", &open.join(" "))]));
+            }
+
+            // Steer a cursor with single keys instead of entering a line
+            if keys {
+                match key_turn(&mut game, &mut screen, settings, &session) {
+                    Ok(true) => continue,
+                    Ok(false) => break 'session,
+                    Err(err) => {
+                        game.display_error(&screen, format!("can't read keys ({}); enter columns instead", err));
+                        keys = false;
+                        continue;
+                    }
+                }
+            }
+
+            if game.draw_offered() {
+                let offerer = game.name(game.current_player.other());
+                let offer = format!("{} offers a draw: :accept to agree, or play a move to decline", offerer);
+                println!("{}", screen.renderer.accent(&offer));
+            }
+            if game.can_swap() {
+                let question = format!("Swap or play? :swap takes {}'s first move as your own", game.name(Player::One));
+                println!("{}", screen.renderer.accent(&question));
+            }
+            if game.placing() {
+                println!("{}", screen.renderer.accent(&game.placement_note()));
+            }
+
+            let time = game.time_note(Instant::now());
+            if game.rules.variant == Variant::Cube {
+                screen.prompt(&i18n::fill("prompt.peg", &[("time", &time)]));
+            } else if screen.renderer.coords {
+                screen.prompt(&i18n::fill(
+                    "prompt.column_letters",
+                    &[
+                        ("width", &game.rules.width),
+                        ("letter", &Coord::column_letter(game.rules.width - 1)),
+                        ("time", &time),
+                    ],
+                ));
+            } else {
+                screen.prompt(&i18n::fill("prompt.column", &[("width", &game.rules.width), ("time", &time)]));
+            }
+
+            // On a clock or a move timer at a terminal the wait for the line ends when the
+            // time runs out, and the time left counts down in front of what's typed
+            let left = game.time_left(Instant::now());
+            let typed = match left {
+                Some(left) if io::stdin().is_terminal() => {
+                    keys::read_line(left, || format!("{} > ", game.time_note(Instant::now()).trim())).ok()
+                }
+                _ => None,
+            };
+            let user_move = match typed {
+                Some(Some(line)) => line,
+                Some(None) => continue,
+                None => {
+                    let mut line = String::new();
+                    io::stdin().read_line(&mut line).expect("Failed to read line");
+                    line
+                }
+            };
+
+            // Stop at the end of a piped script, ending the session as quitting would
+            if user_move.is_empty() {
+                break 'session;
+            }
+
+            // A move entered after the time ran out is too late
+            if game.tick(Instant::now()) {
+                game.display_board(&screen);
+                if let Some(note) = game.timeout_note() {
+                    println!("{}", screen.renderer.accent(&note));
+                }
+                continue;
+            }
+
+            // Play the column or run the command on the line
+            let parsed = if game.rules.variant == Variant::Cube { command::parse_3d(&user_move) } else { command::parse(&user_move, game.rules.width) };
+            match parsed {
+                Ok(command::Command::Move(column)) => {
+                    screen.selected_column = None;
+                    match game.column(column).and_then(|column| game.play_move(column)) {
+                        Ok(played) => {
+                            game.debug_validate();
+                            game.tick(Instant::now());
+                            if settings.animation && screen.redraws_in_place() {
+                                game.animate_drop(&screen, &played);
+                            }
+                            game.display_board(&screen);
+                        }
+                        Err(err) => {
+                            game.display_error(&screen, game.move_error(&err));
+                        }
+                    }
+                }
+                Ok(command::Command::Special(mv)) => {
+                    screen.selected_column = None;
+                    match game.play(mv) {
+                        Ok(()) => {
+                            game.debug_validate();
+                            game.tick(Instant::now());
+                            game.display_board(&screen);
+                        }
+                        Err(err) => game.display_error(&screen, err.to_string()),
+                    }
+                }
+                Ok(command::Command::Quit) => {
+                    if confirm_quit(&game, &screen, settings, &session, false) {
+                        break 'session;
+                    }
+                }
+                Ok(command) => run_command(&mut game, &mut screen, settings, &mut session, command, Context::Turn),
+                Err(err) => game.display_error(&screen, err.to_string()),
+            }
+        }
+        
+        game.tick(Instant::now());
+        screen.transcript.observe(&game);
+
+        // The tally so far, this game included, and in a match whether it's been decided
+        let tally = session.with(&game);
+        let decided = match settings.match_format {
+            Some(format) => {
+                let score = format!("Match ({}): {}", format, tally.match_score(settings.half_point_draws));
+                println!("{}", screen.renderer.accent(&score));
+                let result = tally.match_result(format, settings.half_point_draws);
+                if let Some(result) = &result {
+                    println!("{}", screen.renderer.accent(&format!("*** {} ***", result)));
+                }
+                result.is_some()
+            }
+            None => {
+                println!("{}", screen.renderer.accent(&tally.summary()));
+                tally_shown = (!screen.redraws_in_place()).then(|| tally.summary());
+                false
+            }
+        };
+        for change in profile::rating_changes(&game, settings) {
+            println!("{}", screen.renderer.accent(&change));
+        }
+        for banner in profile::achievement_banners(&game) {
+            println!("{}", screen.renderer.accent(&format!("*** {} ***", banner)));
+        }
+        if let Some(used) = game.time_used(Instant::now()) {
+            println!("{}", screen.renderer.accent(&used));
+        }
+        for line in game.time_breakdown() {
+            println!("{}", screen.renderer.accent(&line));
+        }
+        println!("{}", i18n::fill("over.game_id", &[("id", &game.id)]));
+        screen.prompt(&i18n::text(match (settings.match_format, decided) {
+            (None, _) if settings.first == First::Random => "over.rematch_random",
+            (None, _) if settings.first == First::Alternate => "over.rematch_alternate",
+            (None, _) if game.handicap_size() > 0 => "over.rematch_handicap",
+            (None, _) => "over.rematch",
+            (Some(_), false) if game.handicap_size() > 0 => "over.next_game_handicap",
+            (Some(_), false) => "over.next_game",
+            (Some(_), true) => "over.new_match",
+        }));
+
+        let mut user_input = String::new();
+
+        // Read user input to restart or quit
+        if io::stdin().read_line(&mut user_input).expect("failed to read line") == 0 {
+            break;
+        }
+
+        // Handle the user input
+        match user_input.trim() {
+            // Colors alternate through a match
+            "" | "r" | "R" if settings.match_format.is_some() && !decided => {
+                session.record(&game);
+                record_profiles(&game, settings);
+                game = game.rematch(true);
+                screen.renderer.new_game();
+                game.tick(Instant::now());
+                game.display_board(&screen);
+            }
+            "r" | "R" if decided => {
+                session = Session::default();
+                record_profiles(&game, settings);
+                game = game.rematch(settings.first.swap_rematch(true));
+                screen.renderer.new_game();
+                game.tick(Instant::now());
+                game.display_board(&screen);
+                for note in game.coin_flip_note(settings).into_iter().chain(game.random_start_note()) {
+                    println!("{}", screen.renderer.accent(&note));
+                }
+            }
+            "r" | "R" => {
+                session.record(&game);
+                record_profiles(&game, settings);
+                game = game.rematch(settings.first.swap_rematch(user_input.trim() == "r"));
+                screen.renderer.new_game();
+                game.tick(Instant::now());
+                game.display_board(&screen);
+                for note in game.coin_flip_note(settings).into_iter().chain(game.random_start_note()) {
+                    println!("{}", screen.renderer.accent(&note));
+                }
+            }
+            "Q" | "q" => {
+                break;
+            }
+            "" => game.display_error(&screen, i18n::text("prompt.invalid")),
+            // The commands of the move prompt work here too, with or without their colon
+            line => match command::parse(line, game.rules.width) {
+                Ok(command::Command::Quit) => break,
+                Ok(command) => run_command(&mut game, &mut screen, settings, &mut session, command, Context::GameOver),
+                Err(err) => game.display_error(&screen, err.to_string()),
+            },
+        }
+    }
+
+    // Leave the alternate screen first so the goodbye and the tally stay on the main screen
+    screen.transcript.end(&game);
+    drop(screen);
+    record_profiles(&game, settings);
+    println!("{}", i18n::text("quit.quitting"));
+    if let Some(summary) = closing_tally(&session, &game, tally_shown.as_deref()) {
+        println!("{}", summary);
+    }
+}
+
+// The tally to print as the session ends, unless it's `shown` already and still reads the
+// same, as it does when quitting right after a game without the alternate screen
+fn closing_tally(session: &Session, game: &Game, shown: Option<&str>) -> Option<String> {
+    let tally = session.with(game);
+    let summary = tally.summary();
+    (tally.games() > 0 && shown != Some(summary.as_str())).then_some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::{ColorChoice, Style};
+    use crate::theme::{Theme, THEME_NAMES};
+
+    // A game under the default rules with these columns played, counted from 1
+    fn played(columns: &[usize]) -> Game {
+        let mut game = Game::builder(&Settings::default()).build().expect("the default rules are valid");
+        for &column in columns {
+            game.play(Move::Drop(column - 1)).expect("the moves are legal");
+        }
+        game
+    }
+
+    // A renderer for a built-in theme, laid out for the classic board
+    fn renderer(theme: &str, color: bool) -> Renderer {
+        let style = Style { color, truecolor: false };
+        let mut renderer = Renderer::new(Theme::builtin(theme).expect("the theme is built in"), style, None);
+        renderer.fit_board(7, 6);
+        renderer
+    }
+
+    #[test]
+    fn no_color_leaves_no_escapes_in_any_theme() {
+        let style = Style::resolve(ColorChoice::Auto, true, true, true, true);
+        let won = played(&[1, 2, 1, 2, 1, 2, 1]);
+        for name in THEME_NAMES {
+            let renderer = renderer(name, style.color);
+            let mut output = won.render_board(&renderer, Some(3));
+            output.push_str(&renderer.error(style, "Column 1 is full"));
+            output.push_str(&renderer.draw_banner());
+            assert!(!output.contains('\x1b'), "{} has escapes: {:?}", name, output);
+        }
+        // The same board colored, so the check above isn't passing for want of any color
+        assert!(won.render_board(&renderer("classic", true), None).contains('\x1b'));
+    }
+
+    #[test]
+    fn ascii_mid_game_golden() {
+        let game = played(&[4, 4, 3, 5]);
+        let expected = [
+            "-----------------",
+            "CONNECT 4 (Move 4)",
+            "-----------------",
+            "+---------------+",
+            "| . . . . . . . |",
+            "| . . . . . . . |",
+            "| . . . . . . . |",
+            "| . . . . . . . |",
+            "| . . . O . . . |",
+            "| . . X X[O]. . |",
+            "+---------------+",
+            "  1 2 3 4 5 6 7",
+            "-----------------",
+            "-----------------",
+        ];
+        assert_eq!(game.render_board(&renderer("ascii", false), None), expected.join("\n"));
+    }
+
+    #[test]
+    fn ascii_finished_game_golden() {
+        let game = played(&[4, 4, 3, 5, 2, 5, 1]);
+        let expected = [
+            "-----------------",
+            "CONNECT 4 (Move 7)",
+            "-----------------",
+            "+---------------+",
+            "| . . . . . . . |",
+            "| . . . . . . . |",
+            "| . . . . . . . |",
+            "| . . . . . . . |",
+            "| . . . O O . . |",
+            "|*X*X*X*X*O . . |",
+            "+---------------+",
+            "  1 2 3 4 5 6 7",
+            "-----------------",
+            "*** Player 1 (X) has won! ***",
+            " Winning line: column 1 row 1, column 2 row 1, column 3 row 1, column 4 row 1",
+            "-----------------",
+        ];
+        assert_eq!(game.render_board(&renderer("ascii", false), None), expected.join("\n"));
+    }
+
+    #[test]
+    fn a_twelve_column_board_has_a_ruler_over_every_cell() {
+        let mut game = Game::builder(&Settings::default()).size(12, 6).build().expect("the rules are valid");
+        for column in [12, 12, 12, 12, 12, 12, 1, 10] {
+            game.play(Move::Drop(column - 1)).expect("the moves are legal");
+        }
+        let mut renderer = renderer("ascii", false);
+        renderer.fit_board(12, 6);
+        renderer.ruler = render::Ruler::Both;
+        // Two-digit labels widen every cell, and the full last column is dashed out
+        let expected = [
+            "---------------------------------------",
+            "CONNECT 4 (Move 8)",
+            "---------------------------------------",
+            "  1  2  3  4  5  6  7  8  9  10 11 --",
+            "+-------------------------------------+",
+            "| .  .  .  .  .  .  .  .  .  .  .  O  |",
+            "| .  .  .  .  .  .  .  .  .  .  .  X  |",
+            "| .  .  .  .  .  .  .  .  .  .  .  O  |",
+            "| .  .  .  .  .  .  .  .  .  .  .  X  |",
+            "| .  .  .  .  .  .  .  .  .  .  .  O  |",
+            "| X  .  .  .  .  .  .  .  . [O ].  X  |",
+            "+-------------------------------------+",
+            "  1  2  3  4  5  6  7  8  9  10 11 --",
+            "---------------------------------------",
+            "---------------------------------------",
+        ];
+        assert_eq!(game.render_board(&renderer, None), expected.join("\n"));
+    }
+
+    #[test]
+    fn ascii_errors_and_draws_are_marked_in_plain_characters() {
+        let style = Style { color: false, truecolor: false };
+        let renderer = renderer("ascii", false);
+        assert_eq!(renderer.error(style, "Column 4 is full"), "!! Error: Column 4 is full");
+        assert_eq!(renderer.draw_banner(), "*** It's a draw! ***");
+    }
+
+    #[test]
+    fn theme_goldens() {
+        // The board after 4, 4, 3, without the title lines every theme shares
+        let game = played(&[4, 4, 3]);
+        let cases: [(&str, &[&str]); 3] = [
+            ("minimal", &[
+                "· · · · · · ·",
+                "· · · · · · ·",
+                "· · · · · · ·",
+                "· · · · · · ·",
+                "· · · ○ · · ·",
+                "· ·[●]● · · ·",
+                "1 2 3 4 5 6 7",
+                "-------------",
+                "-------------",
+            ]),
+            ("high-contrast", &[
+                "+---------------+",
+                "| . . . . . . . |",
+                "| . . . . . . . |",
+                "| . . . . . . . |",
+                "| . . . . . . . |",
+                "| . . . O . . . |",
+                "| . .[X]X . . . |",
+                "+---------------+",
+                "  1 2 3 4 5 6 7",
+                "-----------------",
+                "-----------------",
+            ]),
+            ("emoji", &[
+                "⚫ ⚫ ⚫ ⚫ ⚫ ⚫ ⚫",
+                "⚫ ⚫ ⚫ ⚫ ⚫ ⚫ ⚫",
+                "⚫ ⚫ ⚫ ⚫ ⚫ ⚫ ⚫",
+                "⚫ ⚫ ⚫ ⚫ ⚫ ⚫ ⚫",
+                "⚫ ⚫ ⚫ 🔵 ⚫ ⚫ ⚫",
+                "⚫ ⚫[🔴]🔴 ⚫ ⚫ ⚫",
+                "1  2  3  4  5  6  7",
+                "--------------------",
+                "--------------------",
+            ]),
+        ];
+        for (theme, expected) in cases {
+            let output = game.render_board(&renderer(theme, false), None);
+            let board: Vec<&str> = output.lines().skip(3).collect();
+            assert_eq!(board, expected, "{}", theme);
+        }
+    }
+
+    #[test]
+    fn classic_golden_colors_each_disc() {
+        let game = played(&[4, 4, 3]);
+        let output = game.render_board(&renderer("classic", true), None);
+        let bottom = output.lines().nth(8).expect("the board has six rows");
+        let empty = "\x1b[2m·\x1b[0m";
+        let red = "\x1b[;31m●\x1b[0m";
+        let last = format!("\x1b[1;4m{}\x1b[0m", red);
+        let expected = [empty, empty, &last, red, empty, empty, empty].join(" ");
+        assert_eq!(bottom, expected);
+        assert!(output.contains("\x1b[33m●\x1b[0m"), "the yellow disc is colored");
+    }
+
+    #[test]
+    fn winning_lines_list_every_cell_in_order() {
+        // Cells are (row, column), with row 0 at the top of the classic 7x6 board
+        type Case = (&'static str, &'static [usize], &'static [(usize, usize)]);
+        let cases: [Case; 5] = [
+            ("horizontal", &[1, 1, 2, 2, 3, 3, 4], &[(5, 0), (5, 1), (5, 2), (5, 3)]),
+            ("vertical", &[1, 2, 1, 2, 1, 2, 1], &[(2, 0), (3, 0), (4, 0), (5, 0)]),
+            ("rising diagonal", &[1, 2, 2, 3, 4, 3, 3, 4, 4, 6, 4], &[(5, 0), (4, 1), (3, 2), (2, 3)]),
+            ("falling diagonal", &[7, 6, 6, 5, 4, 5, 5, 4, 4, 2, 4], &[(2, 3), (3, 4), (4, 5), (5, 6)]),
+            ("five in a row", &[1, 1, 2, 2, 4, 4, 5, 5, 3], &[(5, 0), (5, 1), (5, 2), (5, 3), (5, 4)]),
+        ];
+        for (name, columns, line) in cases {
+            let game = played(columns);
+            assert_eq!(game.winner(), Some(Player::One), "{}", name);
+            assert_eq!(game.winning_line(), Some(line), "{}", name);
+        }
+    }
+
+    #[test]
+    fn a_double_win_reports_the_horizontal_line() {
+        // The last disc completes the bottom row and the diagonal up to the right at once
+        let game = played(&[1, 5, 5, 6, 2, 6, 6, 7, 3, 7, 1, 7, 7, 1, 4]);
+        assert_eq!(game.winner(), Some(Player::One));
+        assert_eq!(game.winning_line(), Some(&[(5, 0), (5, 1), (5, 2), (5, 3)][..]));
+    }
+
+    #[test]
+    fn game_display_shows_the_grid_and_whose_turn_it_is() {
+        let game = played(&[4, 4, 3]);
+        let expected = [".......", ".......", ".......", ".......", "...O...", "..XX...", "move 3, Player 2 to move"];
+        assert_eq!(game.to_string(), expected.join("\n"));
+        let grid = game.to_string().lines().take(6).collect::<Vec<_>>().join("\n");
+        assert_eq!(grid.parse::<Board>().map(|board| board == game.board), Ok(true));
+
+        let won = played(&[1, 2, 1, 2, 1, 2, 1]);
+        assert!(won.to_string().ends_with("\nPlayer 1 won on move 7"));
+    }
+
+    // A cylinder game of `width` columns connecting `connect` with these columns played,
+    // counted from 1
+    fn cylinder(width: usize, connect: usize, columns: &[usize]) -> Game {
+        let mut game = Game::builder(&Settings::default())
+            .size(width, 6)
+            .connect(connect)
+            .variant(Variant::Cylinder)
+            .build()
+            .expect("the rules are valid");
+        for &column in columns {
+            game.play(Move::Drop(column - 1)).expect("the moves are legal");
+        }
+        game
+    }
+
+    #[test]
+    fn cylinder_lines_run_across_the_seam() {
+        type Case = (&'static str, &'static [usize], &'static [(usize, usize)]);
+        let cases: [Case; 3] = [
+            ("horizontal", &[6, 6, 7, 7, 1, 1, 2], &[(5, 5), (5, 6), (5, 0), (5, 1)]),
+            ("rising diagonal", &[6, 7, 7, 1, 2, 1, 1, 2, 2, 4, 2], &[(5, 5), (4, 6), (3, 0), (2, 1)]),
+            ("falling diagonal", &[2, 1, 1, 7, 6, 7, 7, 6, 6, 4, 6], &[(2, 5), (3, 6), (4, 0), (5, 1)]),
+        ];
+        for (name, columns, line) in cases {
+            let game = cylinder(7, 4, columns);
+            assert_eq!(game.winner(), Some(Player::One), "{}", name);
+            assert_eq!(game.winning_line(), Some(line), "{}", name);
+            // On a flat board the same moves win nothing
+            assert!(!played(columns).is_finished(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn cylinder_lines_dont_count_a_cell_twice() {
+        // The whole bottom row of a board four wide is only four in a row, however far round
+        // it's followed
+        let game = cylinder(4, 5, &[1, 1, 2, 2, 3, 3, 4]);
+        assert_eq!(game.board.to_string().lines().last(), Some("XXXX"));
+        assert!(!game.is_finished());
+
+        // A line broken by the other player's piece on the far side of the seam
+        let game = cylinder(7, 4, &[6, 1, 7, 7, 2, 2, 3]);
+        assert_eq!(game.board.to_string().lines().last(), Some("OXX..XX"));
+        assert!(!game.is_finished());
+    }
+    #[test]
+    fn the_largest_board_plays_out_to_a_full_board_draw() {
+        let (width, height) = (20, 15);
+        let mut game = Game::builder(&Settings::default())
+            .size(width, height)
+            .build()
+            .expect("the largest board is allowed");
+        // Pieces in pairs up each column and alternating along each row never make four in a
+        // row: player one takes the cells where half the height plus the column is even.
+        // Filling the lowest column the player to move has the next cell of keeps the turns
+        // in step with the pattern.
+        let owner = |row: usize, column: usize| Player::from_index((row / 2 + column) % 2);
+        for ply in 0..width * height {
+            assert!(!game.is_finished(), "ended early at ply {}", ply);
+            let column = (0..width)
+                .filter(|&column| game.can_play(column))
+                .filter(|&column| owner(game.column_height(column), column) == game.current_player)
+                .min_by_key(|&column| game.column_height(column))
+                .expect("the pattern always leaves the player to move a column");
+            game.play(Move::Drop(column)).expect("the column is open");
+        }
+        assert_eq!(game.history.len(), width * height);
+        assert_eq!(game.outcome, Some(Outcome::Draw { by: DrawKind::FullBoard }));
+        assert_eq!(game.winner(), None);
+
+        let mut renderer = renderer("ascii", false);
+        renderer.fit_board(width, height);
+        let output = game.render_board(&renderer, None);
+        let border = output.lines().find(|line| line.starts_with('+')).expect("the board has a border");
+        let rows: Vec<&str> = output.lines().filter(|line| line.starts_with('|')).collect();
+        assert_eq!(rows.len(), height);
+        assert!(rows.iter().all(|row| row.len() == border.len()), "{}", output);
+        // Full columns are marked in the numbers row, each as wide as its number
+        let numbers = "  -  -  -  -  -  -  -  -  -  -- -- -- -- -- -- -- -- -- -- --";
+        assert!(output.lines().any(|line| line == numbers), "{}", output);
+    }
+
+    #[test]
+    fn the_computer_plays_the_largest_board() {
+        let mut game = Game::builder(&Settings::default())
+            .size(20, 15)
+            .build()
+            .expect("the largest board is allowed");
+        for column in [10, 10, 11] {
+            game.play(Move::Drop(column)).expect("the column is open");
+        }
+        let mv = ai::choose_move(&game, Difficulty::Hard).expect("there are moves to play");
+        assert!(game.can_play(mv.column()), "{:?}", mv);
+    }
+
+    // Output nobody reads any more, like a pipe into `head` that has closed
+    struct Closed {
+        lines: usize,
+    }
+
+    impl Write for Closed {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.lines == 0 {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            self.lines -= buf.iter().filter(|&&byte| byte == b'\n').count().min(self.lines);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn config_show_stops_at_a_closed_pipe_instead_of_panicking() {
+        let settings = Settings::default();
+        let mut shown = Vec::new();
+        show_config(&mut shown, &settings).expect("a Vec takes everything");
+        let shown = String::from_utf8(shown).expect("the settings are UTF-8");
+        assert_eq!(shown.lines().count(), settings.describe().len());
+        assert!(shown.lines().all(|line| line.ends_with(')')), "{}", shown);
+
+        let err = show_config(&mut Closed { lines: 2 }, &settings).expect_err("the pipe closes");
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn the_session_tally_is_printed_once_on_quit() {
+        let session = Session::default();
+        let unplayed = played(&[]);
+        assert_eq!(closing_tally(&session, &unplayed, None), None, "no games, no tally");
+
+        let won = played(&[1, 2, 1, 2, 1, 2, 1]);
+        let summary = session.with(&won).summary();
+        assert_eq!(closing_tally(&session, &won, None), Some(summary.clone()));
+        assert_eq!(closing_tally(&session, &won, Some(&summary)), None, "it's on the screen already");
+
+        // Taking the winning move back after the tally was shown changes it
+        let mut earlier = Session::default();
+        earlier.record(&won);
+        let shown = earlier.with(&won).summary();
+        let mut undone = won.clone();
+        assert!(undone.undo());
+        assert_eq!(closing_tally(&earlier, &undone, Some(&shown)), Some(earlier.summary()));
+    }
+
+    #[test]
+    fn a_dropped_piece_passes_every_empty_row_and_stops_above_its_own() {
+        assert_eq!(drop_frames(5), [0, 1, 2, 3, 4], "down an empty column of 6");
+        assert_eq!(drop_frames(1), [0]);
+        assert_eq!(drop_frames(0), Vec::<usize>::new(), "the top row has nothing above it");
+
+        // As animate_drop finds it: the third piece in a column lands on board row 3 of 6
+        let game = played(&[4, 4, 4]);
+        let coord = game.last_move().expect("a move was played");
+        let landing = game.rules.height - 1 - coord.row;
+        assert_eq!(game.board[landing][coord.column], Some(Player::One));
+        let frames = drop_frames(landing);
+        assert_eq!(frames.len(), 3);
+        assert_eq!(frames.last(), Some(&(landing - 1)));
+        assert!(frames.iter().all(|&row| game.board[row][coord.column].is_none()), "it only falls through empty rows");
+    }
+}
@@ -0,0 +1,67 @@
+use std::io::{self, Write};
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::export::json_string;
+use crate::transcript;
+
+// How diagnostic log lines are written to stderr
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum LogFormat {
+    // "2024-05-04T19:22:11Z DEBUG connect_four::ai: message"
+    Text,
+    // One JSON object a line, with time, level, target, and message
+    Json,
+}
+
+// Writes every record the level lets through to stderr
+struct Logger {
+    format: LogFormat,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = match self.format {
+            LogFormat::Text => format!(
+                "{} {:<5} {}: {}",
+                transcript::timestamp(),
+                record.level(),
+                record.target(),
+                record.args()
+            ),
+            LogFormat::Json => format!(
+                "{{\"time\": \"{}\", \"level\": \"{}\", \"target\": {}, \"message\": {}}}",
+                transcript::timestamp(),
+                record.level().as_str().to_lowercase(),
+                json_string(record.target()),
+                json_string(&record.args().to_string())
+            ),
+        };
+        let _ = writeln!(io::stderr(), "{}", line);
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+// Log at the level `-v` was repeated for: nothing without it, so the game's own output is
+// all there is, then info, debug, and trace
+pub fn init(verbosity: u8, format: LogFormat) {
+    let level = match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    if log::set_logger(Box::leak(Box::new(Logger { format }))).is_ok() {
+        log::set_max_level(level);
+    }
+}
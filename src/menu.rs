@@ -0,0 +1,611 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use crossterm::cursor::MoveTo;
+use crossterm::event::{KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{Clear, ClearType};
+
+use crate::ai::Difficulty;
+use crate::command::{self, Context};
+use crate::i18n;
+use crate::keys;
+use crate::render::Renderer;
+use crate::settings::{self, Settings};
+use crate::theme::THEME_NAMES;
+
+// Pages of the startup menu
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Page {
+    Main,
+    Difficulty,
+    Settings,
+    Theme,
+}
+
+// A key press or entered line, as far as the menu is concerned
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Choice {
+    Up,
+    Down,
+    // The item under the cursor
+    Confirm,
+    // An item by its position on the page, from 0
+    Pick(usize),
+    Back,
+    Help,
+}
+
+// Text the menu needs typed in before it can go on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    Name(usize),
+}
+
+// What the caller does after a choice
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    Stay,
+    Play,
+    Quit,
+    Ask(Field),
+    // Write the session's settings to the config file
+    Save,
+}
+
+// The main page's items, by their keys in the message catalogs
+const MAIN_ITEMS: [&str; 8] = [
+    "menu.new_human",
+    "menu.new_computer",
+    "menu.resume",
+    "menu.load",
+    "menu.replay",
+    "menu.puzzles",
+    "menu.settings",
+    "menu.quit",
+];
+
+const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+// Where the menu is and what it last had to say
+#[derive(Clone, Debug, PartialEq)]
+pub struct Menu {
+    page: Page,
+    cursor: usize,
+    pub notice: Option<String>,
+}
+
+impl Menu {
+    pub fn new() -> Menu {
+        Menu {
+            page: Page::Main,
+            cursor: 0,
+            notice: None,
+        }
+    }
+
+    // Labels of the current page's items
+    fn items(&self, settings: &Settings) -> Vec<String> {
+        let back = i18n::text("menu.back");
+        match self.page {
+            Page::Main => MAIN_ITEMS.iter().map(|item| i18n::text(item)).collect(),
+            Page::Difficulty => DIFFICULTIES
+                .iter()
+                .map(|&difficulty| difficulty_name(difficulty))
+                .chain([back])
+                .collect(),
+            Page::Settings => vec![
+                i18n::fill("menu.item.theme", &[("theme", &settings.theme)]),
+                i18n::fill(
+                    "menu.item.name",
+                    &[("number", &1), ("name", &settings.names[0])],
+                ),
+                i18n::fill(
+                    "menu.item.name",
+                    &[("number", &2), ("name", &settings.names[1])],
+                ),
+                i18n::fill(
+                    "menu.item.difficulty",
+                    &[("difficulty", &difficulty_name(settings.difficulty))],
+                ),
+                i18n::fill(
+                    "menu.item.time_control",
+                    &[("time", &time_control_name(settings))],
+                ),
+                i18n::fill("menu.item.rules", &[("rules", &rules_name(settings))]),
+                i18n::text("menu.item.save"),
+                back,
+            ],
+            Page::Theme => THEME_NAMES
+                .iter()
+                .map(|name| name.to_string())
+                .chain([back])
+                .collect(),
+        }
+    }
+
+    fn go(&mut self, page: Page) -> Step {
+        self.page = page;
+        self.cursor = 0;
+        Step::Stay
+    }
+
+    // Act on a choice, changing `settings` for the session as items are picked
+    pub fn press(&mut self, choice: Choice, settings: &mut Settings) -> Step {
+        let count = self.items(settings).len();
+        self.notice = None;
+        let item = match choice {
+            Choice::Up => {
+                self.cursor = (self.cursor + count - 1) % count;
+                return Step::Stay;
+            }
+            Choice::Down => {
+                self.cursor = (self.cursor + 1) % count;
+                return Step::Stay;
+            }
+            Choice::Back => return self.back(),
+            Choice::Help => {
+                self.notice = Some(help());
+                return Step::Stay;
+            }
+            Choice::Confirm => self.cursor,
+            Choice::Pick(item) if item < count => {
+                self.cursor = item;
+                item
+            }
+            Choice::Pick(_) => {
+                self.notice = Some(i18n::fill("menu.choose", &[("count", &count)]));
+                return Step::Stay;
+            }
+        };
+        // The last item of every page but the main one goes back
+        if self.page != Page::Main && item == count - 1 {
+            return self.back();
+        }
+
+        match (self.page, item) {
+            (Page::Main, 0) => {
+                settings.computer = false;
+                Step::Play
+            }
+            (Page::Main, 1) => self.go(Page::Difficulty),
+            (Page::Main, 2..=5) => {
+                let item = i18n::text(MAIN_ITEMS[item]);
+                self.notice = Some(i18n::fill("menu.unavailable", &[("item", &item)]));
+                Step::Stay
+            }
+            (Page::Main, 6) => self.go(Page::Settings),
+            (Page::Main, _) => Step::Quit,
+            (Page::Difficulty, _) => {
+                settings.computer = true;
+                settings.difficulty = DIFFICULTIES[item];
+                Step::Play
+            }
+            (Page::Settings, 0) => self.go(Page::Theme),
+            (Page::Settings, 1 | 2) => Step::Ask(Field::Name(item - 1)),
+            (Page::Settings, 3) => {
+                let next = DIFFICULTIES
+                    .iter()
+                    .position(|&difficulty| difficulty == settings.difficulty)
+                    .map_or(0, |index| (index + 1) % DIFFICULTIES.len());
+                settings.difficulty = DIFFICULTIES[next];
+                Step::Stay
+            }
+            (Page::Settings, 4) => {
+                next_time_control(settings);
+                Step::Stay
+            }
+            (Page::Settings, 5) => {
+                if let Err(err) = next_rules(settings) {
+                    self.notice = Some(capitalize(&err));
+                }
+                Step::Stay
+            }
+            (Page::Settings, _) => Step::Save,
+            (Page::Theme, _) => {
+                let previous =
+                    std::mem::replace(&mut settings.theme, THEME_NAMES[item].to_string());
+                if let Err(err) = settings.theme().check_players_distinct(settings.players) {
+                    settings.theme = previous;
+                    self.notice = Some(err);
+                    return Step::Stay;
+                }
+                self.go(Page::Settings)
+            }
+        }
+    }
+
+    // Up one page; the theme list returns to the settings page it came from
+    fn back(&mut self) -> Step {
+        match self.page {
+            Page::Main => Step::Stay,
+            Page::Theme => self.go(Page::Settings),
+            Page::Difficulty | Page::Settings => self.go(Page::Main),
+        }
+    }
+
+    // Take the text asked for with `Step::Ask`
+    pub fn answer(&mut self, field: Field, text: &str, settings: &mut Settings) {
+        match field {
+            Field::Name(index) => match settings::clean_name(text) {
+                Ok(name) if name.to_lowercase() == settings.names[1 - index].to_lowercase() => {
+                    self.notice = Some(i18n::fill("menu.name_taken", &[("name", &name)]));
+                }
+                Ok(name) => settings.names[index] = name,
+                Err(err) => self.notice = Some(capitalize(&err)),
+            },
+        }
+    }
+
+    // Prompt for a field's text
+    pub fn question(field: Field) -> String {
+        match field {
+            Field::Name(index) => i18n::fill("menu.ask_name", &[("number", &(index + 1))]),
+        }
+    }
+
+    // Title, numbered items, and the notice, with the cursor's item marked when `cursor` is on
+    pub fn render(&self, settings: &Settings, renderer: &Renderer, cursor: bool) -> String {
+        let title = match self.page {
+            Page::Main => "menu.title",
+            Page::Difficulty => "menu.new_computer",
+            Page::Settings => "menu.settings",
+            Page::Theme => "menu.theme",
+        };
+        let mut lines = vec![renderer.accent(&i18n::text(title)), String::new()];
+        for (index, item) in self.items(settings).iter().enumerate() {
+            let mark = if cursor && index == self.cursor {
+                ">"
+            } else {
+                " "
+            };
+            lines.push(format!("{} {}. {}", mark, index + 1, item));
+        }
+        if let Some(notice) = &self.notice {
+            lines.push(String::new());
+            lines.push(renderer.accent(notice));
+        }
+        lines.join("\n")
+    }
+}
+
+// How to get around the menu, and what can be typed once a game starts
+fn help() -> String {
+    format!(
+        "{}\n{}\n\n{}\n{}",
+        i18n::text("menu.help.pick"),
+        i18n::text("menu.help.keys"),
+        i18n::text("menu.help.during"),
+        command::help(Context::Turn)
+    )
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map_or(String::new(), |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+// A difficulty as the menu shows it
+fn difficulty_name(difficulty: Difficulty) -> String {
+    i18n::text(match difficulty {
+        Difficulty::Easy => "menu.easy",
+        Difficulty::Medium => "menu.medium",
+        Difficulty::Hard => "menu.hard",
+    })
+}
+
+// The time control as the settings page shows it: "off", the preset's name and time control,
+// or the time control alone when it's not a preset's
+fn time_control_name(settings: &Settings) -> String {
+    match (&settings.preset, settings.time) {
+        (_, None) => i18n::text("menu.time_off"),
+        (Some(name), Some(control)) => format!("{} ({})", name, control),
+        (None, Some(control)) => control.to_string(),
+    }
+}
+
+// Step through the presets in order, then untimed, then back to the first preset
+fn next_time_control(settings: &mut Settings) {
+    let current = settings
+        .preset
+        .as_ref()
+        .filter(|_| settings.time.is_some())
+        .and_then(|name| settings.presets.iter().position(|(known, _)| known == name));
+    let next = match current {
+        None if settings.time.is_none() => Some(0),
+        None => None,
+        Some(index) => Some(index + 1).filter(|&next| next < settings.presets.len()),
+    };
+    match next.and_then(|next| settings.presets.get(next)) {
+        Some((name, control)) => {
+            settings.preset = Some(name.clone());
+            settings.time = Some(*control);
+        }
+        None => {
+            settings.preset = None;
+            settings.time = None;
+        }
+    }
+}
+
+// The rules as the settings page shows them: the preset's name, or custom, then the rules
+// themselves
+fn rules_name(settings: &Settings) -> String {
+    let name = settings
+        .preset_name()
+        .unwrap_or_else(|| i18n::text("menu.custom"));
+    format!("{} ({})", name, settings.rules().describe())
+}
+
+// Step through the rule presets in order, back to the first after the last, passing over
+// any that can't be played with the other settings
+fn next_rules(settings: &mut Settings) -> Result<(), String> {
+    let count = settings.rule_presets.len();
+    let next = settings
+        .preset_name()
+        .and_then(|name| {
+            settings
+                .rule_presets
+                .iter()
+                .position(|(known, _)| *known == name)
+        })
+        .map_or(0, |index| (index + 1) % count);
+    let mut first_error = None;
+    for offset in 0..count {
+        let (name, rules) = settings.rule_presets[(next + offset) % count].clone();
+        match settings.use_preset(&name, rules) {
+            Ok(()) => return Ok(()),
+            Err(err) => {
+                first_error.get_or_insert(format!("{}: {}", name, err));
+            }
+        }
+    }
+    Err(first_error.unwrap_or_else(|| i18n::text("menu.no_presets")))
+}
+
+// The settings the menu can change, as config file entries
+pub fn config_values(settings: &Settings) -> Vec<(&'static str, String)> {
+    vec![
+        ("display.theme", settings.theme.clone()),
+        ("players.one", settings.names[0].clone()),
+        ("players.two", settings.names[1].clone()),
+        (
+            "gameplay.difficulty",
+            format!("{:?}", settings.difficulty).to_lowercase(),
+        ),
+        (
+            "clock.time",
+            settings
+                .time
+                .map_or("off".to_string(), |control| control.to_string()),
+        ),
+        (
+            "clock.preset",
+            settings.preset.clone().unwrap_or("custom".to_string()),
+        ),
+        (
+            "gameplay.preset",
+            settings.preset_name().unwrap_or("custom".to_string()),
+        ),
+        ("gameplay.width", settings.width.to_string()),
+        ("gameplay.height", settings.height.to_string()),
+        ("gameplay.connect", settings.connect.to_string()),
+        ("gameplay.variant", settings.variant.name().to_string()),
+        (
+            "gameplay.first",
+            format!("{:?}", settings.first).to_lowercase(),
+        ),
+    ]
+}
+
+// Show the menu until a game is started or the player quits. Returns whether to play. With
+// `keys` the arrows move the cursor; otherwise items are chosen by number, one line each.
+pub fn run(
+    settings: &mut Settings,
+    renderer: &Renderer,
+    config: Option<&Path>,
+    keys: bool,
+) -> io::Result<bool> {
+    let mut menu = Menu::new();
+    loop {
+        if keys {
+            execute!(io::stdout(), Clear(ClearType::All), MoveTo(0, 0))?;
+        }
+        println!("{}\n", menu.render(settings, renderer, keys));
+
+        let choice = if keys {
+            read_choice()?
+        } else {
+            read_line(&i18n::text("menu.prompt"))?.and_then(|line| match line.trim() {
+                "q" | "Q" => None,
+                "b" | "B" => Some(Choice::Back),
+                "h" | "H" => Some(Choice::Help),
+                text => match text.parse::<usize>() {
+                    Ok(number) if number > 0 => Some(Choice::Pick(number - 1)),
+                    _ => Some(Choice::Pick(usize::MAX)),
+                },
+            })
+        };
+        let Some(choice) = choice else {
+            return Ok(false);
+        };
+
+        match menu.press(choice, settings) {
+            Step::Stay => {}
+            Step::Play => return Ok(true),
+            Step::Quit => return Ok(false),
+            Step::Ask(field) => match read_line(&Menu::question(field))? {
+                None => return Ok(false),
+                Some(text) => menu.answer(field, &text, settings),
+            },
+            Step::Save => {
+                menu.notice = Some(match config {
+                    None => i18n::text("menu.no_config"),
+                    Some(path) => match settings::save_config(path, &config_values(settings)) {
+                        Ok(()) => i18n::fill("quit.saved", &[("path", &path.display())]),
+                        Err(err) => err,
+                    },
+                });
+            }
+        }
+    }
+}
+
+// One key press as a menu choice: arrows or j/k move, Enter picks, digits pick by number,
+// Esc or Backspace goes back, and h shows help. None when the player quits with q or Ctrl-C.
+fn read_choice() -> io::Result<Option<Choice>> {
+    loop {
+        let key = keys::read_key()?;
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        let choice = match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Char('q') => return Ok(None),
+            KeyCode::Up | KeyCode::Char('k') => Choice::Up,
+            KeyCode::Down | KeyCode::Char('j') => Choice::Down,
+            KeyCode::Enter | KeyCode::Char(' ') => Choice::Confirm,
+            KeyCode::Esc | KeyCode::Backspace => Choice::Back,
+            KeyCode::Char('h') => Choice::Help,
+            KeyCode::Char(digit @ '1'..='9') => Choice::Pick(digit as usize - '1' as usize),
+            _ => continue,
+        };
+        return Ok(Some(choice));
+    }
+}
+
+// Prompt and read a line; None at end of input
+fn read_line(prompt: &str) -> io::Result<Option<String>> {
+    println!("{}", prompt);
+    io::stdout().flush()?;
+    let mut line = String::new();
+    if io::stdin().read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Press each choice in turn, returning the step the last one led to
+    fn pressed(menu: &mut Menu, choices: &[Choice], settings: &mut Settings) -> Step {
+        choices
+            .iter()
+            .map(|&choice| menu.press(choice, settings))
+            .last()
+            .expect("a choice is pressed")
+    }
+
+    #[test]
+    fn the_cursor_wraps_around_each_page() {
+        let mut settings = Settings::default();
+        let mut menu = Menu::new();
+        assert_eq!(menu.press(Choice::Up, &mut settings), Step::Stay);
+        assert_eq!(
+            menu.cursor,
+            MAIN_ITEMS.len() - 1,
+            "up from the top is the bottom"
+        );
+        assert_eq!(menu.press(Choice::Down, &mut settings), Step::Stay);
+        assert_eq!(menu.cursor, 0, "down from the bottom is the top");
+        assert_eq!(menu.press(Choice::Back, &mut settings), Step::Stay);
+        assert_eq!(
+            menu.page,
+            Page::Main,
+            "the main page has nothing to go back to"
+        );
+
+        // Confirming takes the item under the cursor, here the last before Quit
+        pressed(&mut menu, &[Choice::Up, Choice::Up], &mut settings);
+        assert_eq!(menu.press(Choice::Confirm, &mut settings), Step::Stay);
+        assert_eq!((menu.page, menu.cursor), (Page::Settings, 0));
+        assert_eq!(menu.press(Choice::Up, &mut settings), Step::Stay);
+        assert_eq!(menu.cursor, 7, "the settings page has its own items");
+        assert_eq!(menu.press(Choice::Back, &mut settings), Step::Stay);
+        assert_eq!((menu.page, menu.cursor), (Page::Main, 0));
+
+        assert_eq!(menu.press(Choice::Pick(8), &mut settings), Step::Stay);
+        assert_eq!(menu.notice.as_deref(), Some("Choose 1-8"));
+        assert_eq!(menu.cursor, 0, "an item that isn't there leaves the cursor");
+        assert_eq!(menu.press(Choice::Down, &mut settings), Step::Stay);
+        assert_eq!(menu.notice, None, "a notice lasts one choice");
+    }
+
+    #[test]
+    fn picking_items_plays_asks_or_changes_the_settings() {
+        let mut settings = Settings::default();
+        settings.computer = true;
+        assert_eq!(
+            Menu::new().press(Choice::Pick(0), &mut settings),
+            Step::Play
+        );
+        assert!(!settings.computer);
+
+        let mut menu = Menu::new();
+        assert_eq!(
+            pressed(
+                &mut menu,
+                &[Choice::Pick(1), Choice::Pick(2)],
+                &mut settings
+            ),
+            Step::Play
+        );
+        assert!(settings.computer);
+        assert_eq!(settings.difficulty, Difficulty::Hard);
+
+        let mut menu = Menu::new();
+        assert_eq!(menu.press(Choice::Pick(3), &mut settings), Step::Stay);
+        assert_eq!(
+            menu.notice.as_deref(),
+            Some("Load saved game isn't available yet")
+        );
+        assert_eq!(menu.press(Choice::Pick(7), &mut settings), Step::Quit);
+
+        // On the settings page the difficulty goes round, a name is asked for, and a theme
+        // is chosen from a page of its own
+        let mut menu = Menu::new();
+        assert_eq!(
+            pressed(
+                &mut menu,
+                &[Choice::Pick(6), Choice::Pick(3)],
+                &mut settings
+            ),
+            Step::Stay
+        );
+        assert_eq!(settings.difficulty, Difficulty::Easy);
+        assert_eq!(
+            menu.press(Choice::Pick(2), &mut settings),
+            Step::Ask(Field::Name(1))
+        );
+        menu.answer(Field::Name(1), "Grace", &mut settings);
+        assert_eq!(settings.names[1], "Grace");
+        menu.answer(Field::Name(0), "grace", &mut settings);
+        assert_eq!(
+            menu.notice.as_deref(),
+            Some("The other player is already called 'grace'")
+        );
+        assert_ne!(settings.names[0], "grace");
+
+        let ascii = THEME_NAMES
+            .iter()
+            .position(|&name| name == "ascii")
+            .expect("ascii is built in");
+        assert_eq!(
+            pressed(
+                &mut menu,
+                &[Choice::Pick(0), Choice::Pick(ascii)],
+                &mut settings
+            ),
+            Step::Stay
+        );
+        assert_eq!(settings.theme, "ascii");
+        assert_eq!(menu.page, Page::Settings, "choosing a theme goes back");
+        assert_eq!(menu.press(Choice::Pick(6), &mut settings), Step::Save);
+        assert_eq!(menu.press(Choice::Pick(7), &mut settings), Step::Stay);
+        assert_eq!(menu.page, Page::Main, "the last item goes back");
+    }
+}
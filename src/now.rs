@@ -0,0 +1,51 @@
+// Where the engine gets the time from. A browser has no clock of the operating system's to
+// read, and asking std for one there panics, so everything the wasm build runs asks a
+// TimeSource instead: built for wasm32 with the `wasm` feature it's the page's clock, and
+// everywhere else the system's.
+use std::time::Duration;
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub trait TimeSource {
+    // Time since a moment of the source's own, for measuring how long something took
+    fn monotonic(&self) -> Duration;
+
+    // Time since the start of 1970, for ids and seeds
+    fn wall(&self) -> Duration;
+}
+
+// The clock of whatever the game runs on
+#[derive(Clone, Copy, Debug, Default)]
+pub struct System;
+
+#[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+impl TimeSource for System {
+    fn monotonic(&self) -> Duration {
+        static STARTED: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+        STARTED.get_or_init(Instant::now).elapsed()
+    }
+
+    fn wall(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+    }
+}
+
+// The page's Date.now(), in milliseconds. The page's clock can be changed while a search
+// runs, which throws that search's timing off but never stops it.
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+impl TimeSource for System {
+    fn monotonic(&self) -> Duration {
+        self.wall()
+    }
+
+    fn wall(&self) -> Duration {
+        Duration::from_secs_f64(js_sys::Date::now().max(0.0) / 1000.0)
+    }
+}
+
+// How long it's been since `started`, a reading of `time`'s monotonic clock
+pub fn since(time: &impl TimeSource, started: Duration) -> Duration {
+    time.monotonic().saturating_sub(started)
+}
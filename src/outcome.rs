@@ -0,0 +1,108 @@
+use crate::{Game, Player};
+
+// How a finished game ended
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    // `player` won. A game won on the board has the connected line, as (row, column), which
+    // under misère is the loser's.
+    Win {
+        player: Player,
+        line: Option<Vec<(usize, usize)>>,
+        by: WinKind,
+    },
+    Draw {
+        by: DrawKind,
+    },
+}
+
+// How a game was won
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WinKind {
+    // By connecting a line, or under misère by the other player connecting one
+    Connect,
+    // By making more lines on the full board, under the scoring variant
+    Lines,
+    Resignation,
+    // The other player's time ran out
+    Time,
+}
+
+// How a game was drawn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawKind {
+    // The board filled up, or the player to move was otherwise left without a move
+    FullBoard,
+    Agreement,
+}
+
+impl Outcome {
+    // How the game ended as the game log, saves, and exports name it: connect, lines, time,
+    // resignation, full board, or agreement
+    pub fn ending(&self) -> &'static str {
+        match self {
+            Outcome::Win { by, .. } => match by {
+                WinKind::Connect => "connect",
+                WinKind::Lines => "lines",
+                WinKind::Resignation => "resignation",
+                WinKind::Time => "time",
+            },
+            Outcome::Draw { by } => match by {
+                DrawKind::FullBoard => "full board",
+                DrawKind::Agreement => "agreement",
+            },
+        }
+    }
+
+    // Whether the game was ended by a player rather than by the moves on the board, so
+    // taking it back leaves the moves alone
+    pub fn off_the_board(&self) -> bool {
+        matches!(
+            self,
+            Outcome::Win {
+                by: WinKind::Resignation | WinKind::Time,
+                ..
+            } | Outcome::Draw {
+                by: DrawKind::Agreement
+            }
+        )
+    }
+}
+
+impl Game {
+    pub fn is_finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    // The player who won, once someone has; None while the game goes on and for a draw
+    pub fn winner(&self) -> Option<Player> {
+        match self.outcome {
+            Some(Outcome::Win { player, .. }) => Some(player),
+            _ => None,
+        }
+    }
+
+    // How the game was won or drawn, once it's over
+    pub fn won_by(&self) -> Option<WinKind> {
+        match self.outcome {
+            Some(Outcome::Win { by, .. }) => Some(by),
+            _ => None,
+        }
+    }
+
+    pub fn drawn_by(&self) -> Option<DrawKind> {
+        match self.outcome {
+            Some(Outcome::Draw { by }) => Some(by),
+            _ => None,
+        }
+    }
+
+    // Cells of the connected line, as (row, column), once the game is won on the board
+    pub fn winning_line(&self) -> Option<&[(usize, usize)]> {
+        match &self.outcome {
+            Some(Outcome::Win {
+                line: Some(line), ..
+            }) => Some(line),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,925 @@
+use std::fmt;
+
+use crate::clock::TimeControl;
+use crate::comment::{self, Comments};
+use crate::history;
+use crate::settings::Settings;
+use crate::validate;
+use crate::variant::{Move, Specials, Variant};
+use crate::variation::Variation;
+use crate::{Game, Player};
+
+// An archival text format for games modeled on chess PGN: a section of tags, one a line,
+// then the numbered moves. Columns are 1-based, comments go in braces after the move they're
+// on, and variations in parentheses after the move they're played instead of:
+//
+//   [Event "Casual game"]
+//   [Date "2024.05.04"]
+//   [Red "Alice"]
+//   [Blue "Bob"]
+//   [Result "1-0"]
+//   [Board "7x6"]
+//   [Connect "4"]
+//
+//   1. 4 4 2. 3 {had to block on 3} ({try 5} 2. 5 3 3. 2) 2... 5 3. 2 1-0
+//
+// A variation's name is a comment before its first move. Lines of moves wrap at 79
+// characters, inside comments too, where a line break stands for a space. Files hold any
+// number of games, a blank line apart. The reader expects exactly the tags and results
+// defined here, and keeps any other tags as they were.
+
+// Tag names, in the order they're written
+pub const EVENT: &str = "Event";
+pub const DATE: &str = "Date";
+// The players by the color of their pieces: red moves first
+pub const RED: &str = "Red";
+pub const BLUE: &str = "Blue";
+pub const RESULT: &str = "Result";
+// Width by height, e.g. "7x6"
+pub const BOARD: &str = "Board";
+pub const CONNECT: &str = "Connect";
+// Tags written only when they apply
+pub const ID: &str = "Id";
+pub const PREVIOUS_GAME: &str = "PreviousGame";
+// The rules when they aren't the standard ones, like "popout". Under PopOut a pop is written
+// as a p before its column, like "p4", and under Power Up an anvil or a bomb as an a or a b
+// before it.
+pub const VARIANT: &str = "Variant";
+// Name of the rule preset the game was played by, like "five"
+pub const RULE_PRESET: &str = "RulePreset";
+// "yes" when Blue answered the first move by swapping under the pie rule; Red and Blue name
+// the players in the seats they took after it
+pub const SWAP: &str = "Swap";
+// Columns of the pieces Blue was given before the first move, like "4 3"
+pub const HANDICAP: &str = "Handicap";
+// How many of the moves were played at random before the players took over, and the seed
+// that picked them
+pub const RANDOM_START: &str = "RandomStart";
+pub const SEED: &str = "Seed";
+// The clock's time control, like "3m+2s", and the name of the preset it came from
+pub const TIME_CONTROL: &str = "TimeControl";
+pub const PRESET: &str = "Preset";
+// How a finished game ended, as the game log has it: "connect", "full board", "lines",
+// "time", "resignation", or "agreement"
+pub const TERMINATION: &str = "Termination";
+
+// Results, for the Result tag and to end the moves
+pub const RED_WINS: &str = "1-0";
+pub const BLUE_WINS: &str = "0-1";
+pub const DRAW: &str = "1/2-1/2";
+pub const UNFINISHED: &str = "*";
+
+// Event of a game that wasn't part of one
+pub const DEFAULT_EVENT: &str = "Casual game";
+// Date of a game when it isn't known, in the YYYY.MM.DD form of the Date tag
+pub const UNKNOWN_DATE: &str = "????.??.??";
+
+// Longest line of moves before wrapping
+const LINE_WIDTH: usize = 79;
+
+// The result as the Result tag has it
+pub fn result(game: &Game) -> &'static str {
+    match game.winner() {
+        _ if !game.is_finished() => UNFINISHED,
+        Some(Player::One) => RED_WINS,
+        Some(Player::Two) => BLUE_WINS,
+        None => DRAW,
+        // Games of more than two players aren't written, having no result of their own
+        Some(_) => UNFINISHED,
+    }
+}
+
+// A game as the format has it: the game itself with its comments and variations, and the
+// tags that aren't part of it
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub game: Game,
+    pub event: String,
+    // YYYY-MM-DD, when it's known
+    pub date: Option<String>,
+    // Tags the format doesn't define, in the order they came
+    pub extras: Vec<(String, String)>,
+}
+
+impl Record {
+    pub fn new(game: Game, date: Option<String>) -> Record {
+        Record {
+            game,
+            event: DEFAULT_EVENT.to_string(),
+            date,
+            extras: Vec::new(),
+        }
+    }
+}
+
+// One game with its tags, comments, and variations, ending in a newline
+pub fn write(record: &Record) -> String {
+    let game = &record.game;
+    let mut tags = vec![
+        (EVENT.to_string(), record.event.clone()),
+        (
+            DATE.to_string(),
+            record
+                .date
+                .as_ref()
+                .map_or(UNKNOWN_DATE.to_string(), |date| date.replace('-', ".")),
+        ),
+        (RED.to_string(), game.names[0].clone()),
+        (BLUE.to_string(), game.names[1].clone()),
+        (RESULT.to_string(), result(game).to_string()),
+        (
+            BOARD.to_string(),
+            format!("{}x{}", game.rules.width, game.rules.height),
+        ),
+        (CONNECT.to_string(), game.rules.connect.to_string()),
+        (ID.to_string(), game.id.clone()),
+    ];
+    if let Some(previous) = &game.previous_game {
+        tags.push((PREVIOUS_GAME.to_string(), previous.clone()));
+    }
+    if game.rules.variant != Variant::Standard {
+        tags.push((VARIANT.to_string(), game.rules.variant.name().to_string()));
+    }
+    if let Some(preset) = &game.rule_preset {
+        tags.push((RULE_PRESET.to_string(), preset.clone()));
+    }
+    if game.swapped {
+        tags.push((SWAP.to_string(), "yes".to_string()));
+    }
+    if !game.handicap.is_empty() {
+        let columns: Vec<String> = game
+            .handicap
+            .iter()
+            .map(|column| (column + 1).to_string())
+            .collect();
+        tags.push((HANDICAP.to_string(), columns.join(" ")));
+    }
+    if let Some(seed) = game.seed {
+        tags.push((RANDOM_START.to_string(), game.random_start.to_string()));
+        tags.push((SEED.to_string(), seed.to_string()));
+    }
+    if let Some(clock) = &game.clock {
+        tags.push((TIME_CONTROL.to_string(), clock.control.to_string()));
+    }
+    if let Some(preset) = &game.preset {
+        tags.push((PRESET.to_string(), preset.clone()));
+    }
+    if game.is_finished() {
+        tags.push((TERMINATION.to_string(), history::ending(game).to_string()));
+    }
+    tags.extend(record.extras.iter().cloned());
+
+    let mut text = String::new();
+    for (name, value) in tags {
+        text.push_str(&format!("[{} \"{}\"]\n", name, escape(&value, '"')));
+    }
+    text.push('\n');
+
+    let moves: Vec<usize> = game.history.iter().map(|coord| coord.column).collect();
+    let mut tokens = Vec::new();
+    line_tokens(
+        &mut tokens,
+        &moves,
+        &game.specials,
+        0,
+        Some(&game.comments),
+        &game.variations,
+    );
+    tokens.push(result(game).to_string());
+    text.push_str(&wrap(&tokens));
+    text.push('\n');
+    text
+}
+
+// Several games, a blank line apart
+pub fn write_all(records: &[Record]) -> String {
+    let games: Vec<String> = records.iter().map(write).collect();
+    games.join("\n")
+}
+
+// Tokens for the moves of `line` from `start` on, the move numbers in `specials` as what
+// they did, with
+// `comments` on them when it's the main line and each of `variations` after the move it
+// replaces. A move number goes before every red move, and before a blue one that doesn't
+// follow its red move directly.
+fn line_tokens(
+    tokens: &mut Vec<String>,
+    line: &[usize],
+    specials: &Specials,
+    start: usize,
+    comments: Option<&Comments>,
+    variations: &[Variation],
+) {
+    let mut numbered = false;
+    for (index, &column) in line.iter().enumerate().skip(start) {
+        let ply = index + 1;
+        let number = ply.div_ceil(2);
+        let mv = specials
+            .at(ply, column)
+            .notation(|column| (column + 1).to_string());
+        if ply % 2 == 1 {
+            tokens.push(format!("{}. {}", number, mv));
+        } else if numbered {
+            tokens.push(mv);
+        } else {
+            tokens.push(format!("{}... {}", number, mv));
+        }
+        numbered = true;
+
+        if let Some(text) = comments.and_then(|comments| comments.get(&ply)) {
+            push_comment(tokens, text);
+            numbered = false;
+        }
+        for variation in variations.iter().filter(|variation| variation.ply == index) {
+            let mut inner = Vec::new();
+            push_comment(&mut inner, &variation.name);
+            // Variations are all drops from where they branch off
+            line_tokens(
+                &mut inner,
+                &variation.line(line),
+                &Specials::default(),
+                index,
+                None,
+                &variation.variations,
+            );
+            inner[0].insert(0, '(');
+            if let Some(last) = inner.last_mut() {
+                last.push(')');
+            }
+            tokens.append(&mut inner);
+            numbered = false;
+        }
+    }
+}
+
+// A comment in braces, split at spaces so long ones wrap
+fn push_comment(tokens: &mut Vec<String>, text: &str) {
+    let comment = format!("{{{}}}", escape(text, '}'));
+    tokens.extend(comment.split(' ').map(str::to_string));
+}
+
+// `text` with backslashes and `quote` escaped by a backslash
+fn escape(text: &str, quote: char) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        if c == '\\' || c == quote {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// Tokens joined by spaces into lines of up to LINE_WIDTH characters, where a token longer
+// than that gets a line of its own
+fn wrap(tokens: &[String]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for token in tokens {
+        if !line.is_empty() && line.chars().count() + 1 + token.chars().count() > LINE_WIDTH {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(token);
+    }
+    lines.push(line);
+    lines.join("\n")
+}
+
+// Why a game in a file couldn't be read, and where
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    // Position of the game in the file, from 1
+    pub game: usize,
+    // Line and column of the problem, from 1
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "game {}, line {}, column {}: {}",
+            self.game, self.line, self.column, self.message
+        )
+    }
+}
+
+// Every game in `text`, and what went wrong with the ones that couldn't be read. A game that
+// fails doesn't stop the rest being read: each starts at a tag line after a blank line.
+// Missing tags are filled from `settings`.
+pub fn read(text: &str, settings: &Settings) -> (Vec<Record>, Vec<ParseError>) {
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    for (number, (first_line, chunk)) in chunks(text).into_iter().enumerate() {
+        let mut parser = Parser {
+            chars: chunk.chars().collect(),
+            at: 0,
+            line: first_line,
+            column: 1,
+        };
+        match parser.game(settings) {
+            Ok(record) => records.push(record),
+            Err((line, column, message)) => errors.push(ParseError {
+                game: number + 1,
+                line,
+                column,
+                message,
+            }),
+        }
+    }
+    (records, errors)
+}
+
+// The games of a file, each with the number of its first line: a game starts at a line
+// opening with '[' at the start of the file or after a blank one
+fn chunks(text: &str) -> Vec<(usize, String)> {
+    let mut chunks: Vec<(usize, String)> = Vec::new();
+    let mut after_blank = true;
+    for (index, line) in text.lines().enumerate() {
+        let trimmed = line.trim();
+        let starts =
+            after_blank && trimmed.starts_with('[') || chunks.is_empty() && !trimmed.is_empty();
+        if starts && chunks.last().is_none_or(|(_, chunk)| has_moves(chunk)) {
+            chunks.push((index + 1, String::new()));
+        }
+        if let Some((_, chunk)) = chunks.last_mut() {
+            chunk.push_str(line);
+            chunk.push('\n');
+        }
+        after_blank = trimmed.is_empty();
+    }
+    chunks
+}
+
+// Whether a game read so far has got past its tags, so a tag line starts the next game
+fn has_moves(chunk: &str) -> bool {
+    chunk
+        .lines()
+        .any(|line| !line.trim().is_empty() && !line.trim().starts_with('['))
+}
+
+// Where reading a game went wrong: line, column, and what happened
+type Failure = (usize, usize, String);
+
+// Reads one game, keeping track of the line and column
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+    line: usize,
+    column: usize,
+}
+
+// A line of moves being read: the main line, or a variation inside parentheses
+struct Line {
+    game: Game,
+    // Moves before the variation starts, 0 for the main line
+    start: usize,
+    name: Option<String>,
+    variations: Vec<Variation>,
+}
+
+impl Line {
+    fn moves(&self) -> Vec<usize> {
+        self.game.history.iter().map(|coord| coord.column).collect()
+    }
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.at += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_space(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    fn fail<T>(&self, message: impl Into<String>) -> Result<T, Failure> {
+        Err((self.line, self.column, message.into()))
+    }
+
+    // Text up to an unescaped `end`, which is taken too, with line breaks read as spaces
+    fn quoted(&mut self, end: char, what: &str) -> Result<String, Failure> {
+        let (line, column) = (self.line, self.column);
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                None => return Err((line, column, format!("unterminated {}", what))),
+                Some('\\') => match self.next() {
+                    Some(c) => text.push(c),
+                    None => return Err((line, column, format!("unterminated {}", what))),
+                },
+                Some(c) if c == end => return Ok(text),
+                Some('\n') => text.push(' '),
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    // A word of letters, digits, and the punctuation of move numbers and results
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self
+            .peek()
+            .filter(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '-' | '/' | '*'))
+        {
+            word.push(c);
+            self.next();
+        }
+        word
+    }
+
+    fn tags(&mut self) -> Result<Vec<(String, String, usize, usize)>, Failure> {
+        let mut tags = Vec::new();
+        loop {
+            self.skip_space();
+            if self.peek() != Some('[') {
+                return Ok(tags);
+            }
+            let (line, column) = (self.line, self.column);
+            self.next();
+            self.skip_space();
+            let name = self.word();
+            if name.is_empty() {
+                return self.fail("expected a tag name after '['");
+            }
+            self.skip_space();
+            if self.next() != Some('"') {
+                return self.fail(format!("expected the quoted value of tag {}", name));
+            }
+            let value = self.quoted('"', "tag value")?;
+            self.skip_space();
+            if self.next() != Some(']') {
+                return self.fail(format!("expected ']' to close tag {}", name));
+            }
+            tags.push((name, value, line, column));
+        }
+    }
+
+    fn game(&mut self, settings: &Settings) -> Result<Record, Failure> {
+        let mut record = Record::new(Game::new(), None);
+        let mut builder = Game::builder(settings).recorded();
+        let mut names = settings.names[..settings.players].to_vec();
+        let mut result_tag = None;
+        let mut termination = None;
+        let mut id = None;
+        let mut previous = None;
+        let mut rule_preset = None;
+        let mut swapped = false;
+        let mut handicap = None;
+        let mut random_start = None;
+        let mut seed = None;
+        for (name, value, line, column) in self.tags()? {
+            let bad = |what: &str| Err((line, column, format!("{} must be {}", name, what)));
+            match name.as_str() {
+                EVENT => record.event = value,
+                DATE if value == UNKNOWN_DATE => record.date = None,
+                DATE => {
+                    let date = value.replace('.', "-");
+                    let parts: Vec<&str> = date.split('-').collect();
+                    let digits = |part: &str, length: usize| {
+                        part.len() == length && part.chars().all(|c| c.is_ascii_digit())
+                    };
+                    if parts.len() != 3
+                        || !digits(parts[0], 4)
+                        || !digits(parts[1], 2)
+                        || !digits(parts[2], 2)
+                    {
+                        return bad("YYYY.MM.DD");
+                    }
+                    record.date = Some(date);
+                }
+                RED => names[0] = value,
+                BLUE => names[1] = value,
+                RESULT => result_tag = Some((value, line, column)),
+                BOARD => match value
+                    .split_once('x')
+                    .map(|(width, height)| (width.parse::<usize>(), height.parse::<usize>()))
+                {
+                    Some((Ok(width), Ok(height))) if width > 0 && height > 0 => {
+                        builder = builder.size(width, height);
+                    }
+                    _ => return bad("a width and height like 7x6"),
+                },
+                CONNECT => match value.parse::<usize>() {
+                    Ok(connect) if connect > 1 => builder = builder.connect(connect),
+                    _ => return bad("a number above 1"),
+                },
+                ID => id = Some(value),
+                PREVIOUS_GAME => previous = Some(value),
+                RULE_PRESET => rule_preset = Some(value),
+                VARIANT => match Variant::parse(&value) {
+                    Some(variant) => builder = builder.variant(variant),
+                    None => {
+                        return bad(
+                            "standard, popout, powerup, cylinder, misere, score, flip, or 3d",
+                        )
+                    }
+                },
+                SWAP if value == "yes" => swapped = true,
+                SWAP => return bad("yes"),
+                HANDICAP => match value
+                    .split_whitespace()
+                    .map(|column| column.parse::<usize>().ok()?.checked_sub(1))
+                    .collect::<Option<Vec<usize>>>()
+                {
+                    Some(columns) => handicap = Some((columns, line, column)),
+                    None => return bad("columns from 1, like \"4 3\""),
+                },
+                RANDOM_START => match value.parse::<usize>() {
+                    Ok(plies) if plies > 0 => random_start = Some((plies, line, column)),
+                    _ => return bad("a number of moves above 0"),
+                },
+                SEED => match value.parse::<u32>() {
+                    Ok(value) => seed = Some(value),
+                    Err(_) => return bad("a whole number"),
+                },
+                TERMINATION => termination = Some((value, line, column)),
+                // Imported games aren't played on, so their clock is only of record
+                TIME_CONTROL => match value.parse::<TimeControl>() {
+                    Ok(_) => record.extras.push((name, value)),
+                    Err(_) => return bad("a time control like 5m, 3m+2s, or 5m d3"),
+                },
+                _ => record.extras.push((name, value)),
+            }
+        }
+
+        // Rules the tags give that can't be played are reported where the moves start
+        let (line, column) = (self.line, self.column);
+        let mut main = builder
+            .names(&names)
+            .build()
+            .map_err(|err| (line, column, err.to_string()))?;
+        if let Some((columns, line, column)) = handicap {
+            main.set_handicap(&columns)
+                .map_err(|err| (line, column, err))?;
+        }
+        if let Some(id) = id {
+            main.id = id;
+        }
+        main.previous_game = previous;
+        main.date = record.date.clone();
+        main.rule_preset = rule_preset;
+        let mut lines = vec![Line {
+            game: main,
+            start: 0,
+            name: None,
+            variations: Vec::new(),
+        }];
+        let mut comments = Comments::new();
+        let mut ending = None;
+        loop {
+            self.skip_space();
+            let (line, column) = (self.line, self.column);
+            let Some(c) = self.peek() else {
+                break;
+            };
+            if ending.is_some() {
+                return self.fail("nothing can follow the result");
+            }
+            match c {
+                '{' => {
+                    self.next();
+                    let text = self.quoted('}', "comment")?;
+                    let nested = lines.len() > 1;
+                    let current = lines.last_mut().expect("the main line is never closed");
+                    let moves = current.game.history.len();
+                    if !nested {
+                        if let Err(err) = comment::set(&mut comments, moves, &text) {
+                            return Err((line, column, err));
+                        }
+                    } else if moves == current.start && current.name.is_none() {
+                        current.name = Some(text.trim().to_string());
+                    }
+                    // Other comments inside variations have nowhere to go
+                }
+                '(' => {
+                    self.next();
+                    let current = lines.last().expect("the main line is never closed");
+                    let moves = current.moves();
+                    let Some(start) = moves
+                        .len()
+                        .checked_sub(1)
+                        .filter(|&start| start >= current.start)
+                    else {
+                        return self.fail("a variation must follow the move it replaces");
+                    };
+                    let mut game = current.game.clone();
+                    if let Err(err) = game.seek(&moves, &current.game.specials, start) {
+                        return self.fail(err.to_string());
+                    }
+                    lines.push(Line {
+                        game,
+                        start,
+                        name: None,
+                        variations: Vec::new(),
+                    });
+                }
+                ')' => {
+                    self.next();
+                    if lines.len() == 1 {
+                        return self.fail("')' without a variation to close");
+                    }
+                    let closed = lines.pop().expect("checked above");
+                    let moves = closed.moves();
+                    if moves.len() == closed.start {
+                        return Err((line, column, "empty variation".to_string()));
+                    }
+                    let parent = lines.last_mut().expect("the main line is never closed");
+                    let name = closed
+                        .name
+                        .unwrap_or_else(|| format!("variation {}", parent.variations.len() + 1));
+                    parent.variations.push(Variation {
+                        name,
+                        ply: closed.start,
+                        moves: moves[closed.start..].to_vec(),
+                        variations: closed.variations,
+                    });
+                }
+                _ => {
+                    let word = self.word();
+                    if word.is_empty() {
+                        self.next();
+                        return Err((line, column, format!("unexpected '{}'", c)));
+                    }
+                    if [RED_WINS, BLUE_WINS, DRAW, UNFINISHED].contains(&word.as_str()) {
+                        if lines.len() > 1 {
+                            return Err((
+                                line,
+                                column,
+                                "the result came inside a variation".to_string(),
+                            ));
+                        }
+                        ending = Some((word, line, column));
+                        continue;
+                    }
+                    // Move numbers are only a guide and aren't checked
+                    if word.ends_with('.') && word.trim_end_matches('.').parse::<usize>().is_ok() {
+                        continue;
+                    }
+                    let nested = lines.len() > 1;
+                    let current = lines.last_mut().expect("the main line is never closed");
+                    let Some(mv) =
+                        Move::parse(&word, |number| number.parse::<usize>().ok()?.checked_sub(1))
+                    else {
+                        return Err((line, column, format!("'{}' is not a move", word)));
+                    };
+                    let played = if nested && !matches!(mv, Move::Drop(_)) {
+                        Err("variations can only drop pieces".to_string())
+                    } else {
+                        current.game.play(mv).map_err(|err| err.to_string())
+                    };
+                    if let Err(err) = played {
+                        return Err((
+                            line,
+                            column,
+                            format!(
+                                "move {} in column {} is illegal: {}",
+                                current.game.history.len() + 1,
+                                word,
+                                err
+                            ),
+                        ));
+                    }
+                }
+            }
+        }
+        if lines.len() > 1 {
+            return self.fail("unterminated variation");
+        }
+
+        let main = lines.pop().expect("the main line is never closed");
+        let mut game = main.game;
+        game.variations = main.variations;
+        game.comments = comments;
+        let (line, column) = (self.line, self.column);
+        let Some((ending, ending_line, ending_column)) = ending else {
+            return Err((
+                line,
+                column,
+                "the moves must end with the result".to_string(),
+            ));
+        };
+        if let Some((tag, tag_line, tag_column)) = &result_tag {
+            if *tag != ending {
+                return Err((
+                    *tag_line,
+                    *tag_column,
+                    format!(
+                        "the Result tag says {} but the moves end in {}",
+                        tag, ending
+                    ),
+                ));
+            }
+        }
+        // A result the moves don't reach was a loss on time, a resignation, or an agreed draw
+        if !game.is_finished() {
+            match ending.as_str() {
+                RED_WINS | BLUE_WINS
+                    if termination
+                        .as_ref()
+                        .is_some_and(|(termination, _, _)| termination == "time") =>
+                {
+                    game.lose_on_time();
+                }
+                RED_WINS | BLUE_WINS => {
+                    game.resign();
+                }
+                DRAW => game.agree_to_draw(),
+                _ => {}
+            }
+        }
+        if result(&game) != ending {
+            return Err((
+                ending_line,
+                ending_column,
+                format!(
+                    "the game ends in {} but the result given is {}",
+                    result(&game),
+                    ending
+                ),
+            ));
+        }
+        if let Some((termination, line, column)) = termination {
+            let actual = if game.is_finished() {
+                history::ending(&game)
+            } else {
+                "none"
+            };
+            if termination != actual {
+                return Err((
+                    line,
+                    column,
+                    format!(
+                        "the Termination tag says {} but the game ended by {}",
+                        termination, actual
+                    ),
+                ));
+            }
+        }
+        game.swapped = swapped && !game.history.is_empty();
+        game.rules.pie_rule = game.swapped;
+        match (random_start, seed) {
+            (Some((plies, line, column)), _) if plies > game.history.len() => {
+                return Err((
+                    line,
+                    column,
+                    format!(
+                        "the RandomStart tag gives {} moves but the game has {}",
+                        plies,
+                        game.history.len()
+                    ),
+                ));
+            }
+            (Some((plies, _, _)), Some(seed)) => {
+                game.random_start = plies;
+                game.seed = Some(seed);
+            }
+            (Some((_, line, column)), None) => {
+                return Err((
+                    line,
+                    column,
+                    "a RandomStart tag needs a Seed tag".to_string(),
+                ));
+            }
+            (None, _) => {}
+        }
+        if let Err(errors) = game.validate() {
+            return Err((line, column, validate::summary(&errors)));
+        }
+        record.game = game;
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::save;
+    use crate::session::Session;
+    use std::fs;
+
+    // Two games as `write_all` writes them, one with a comment, a variation, and a tag of
+    // its own
+    const CANONICAL: &str = r#"[Event "Casual game"]
+[Date "2024.05.04"]
+[Red "Ada"]
+[Blue "Grace"]
+[Result "1-0"]
+[Board "7x6"]
+[Connect "4"]
+[Id "01HX8W2N5Q3R7T9V0B1C2D3E4F"]
+[Termination "connect"]
+[Site "Home"]
+
+1. 4 {center} 1... 4 ({variation 1} 1... 3 2. 3) 2. 3 5 3. 2 5 4. 1 1-0
+
+[Event "Casual game"]
+[Date "????.??.??"]
+[Red "Player 1"]
+[Blue "Player 2"]
+[Result "*"]
+[Board "7x6"]
+[Connect "4"]
+[Id "01HX8W2N5Q3R7T9V0B1C2D3E4G"]
+
+1. 4 3 *
+"#;
+
+    #[test]
+    fn canonical_output_round_trips_byte_for_byte() {
+        let (records, errors) = read(CANONICAL, &Settings::default());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].date.as_deref(), Some("2024-05-04"));
+        assert_eq!(
+            records[0].extras,
+            [("Site".to_string(), "Home".to_string())]
+        );
+        assert_eq!(write_all(&records), CANONICAL);
+    }
+
+    #[test]
+    fn the_date_survives_an_import_into_a_save() {
+        let (records, _) = read(CANONICAL, &Settings::default());
+        let path = std::env::temp_dir().join(format!("pgn-date-{}.toml", std::process::id()));
+        let slot = path
+            .to_str()
+            .expect("the temporary directory has a UTF-8 path");
+        save::write(
+            &records[0].game,
+            &Settings::default(),
+            &Session::default(),
+            slot,
+        )
+        .expect("the save can be written");
+        let loaded = save::load(&path, &Settings::default());
+        fs::remove_file(&path).expect("the save can be removed");
+
+        let loaded = loaded.expect("the save can be read");
+        assert_eq!(loaded.date.as_deref(), Some("2024-05-04"));
+        let text = write(&Record::new(loaded.clone(), loaded.date));
+        assert!(text.contains("[Date \"2024.05.04\"]\n"), "{}", text);
+    }
+
+    #[test]
+    fn malformed_games_say_where_they_went_wrong() {
+        let first = CANONICAL
+            .split("\n\n[")
+            .next()
+            .expect("there is a first game");
+        let cases = [
+            (
+                first.replace("4. 1 1-0", "4. 1 {never closed 1-0"),
+                "game 1, line 12, column 70: unterminated comment",
+            ),
+            (
+                first.replace("2. 3 5", "2. 3 9"),
+                "game 1, line 12, column 55: move 4 in column 9 is illegal: There's no column 9; \
+                 it must be between 1 and 7",
+            ),
+            (
+                first.replace("[Result \"1-0\"]", "[Result \"0-1\"]"),
+                "game 1, line 5, column 1: the Result tag says 0-1 but the moves end in 1-0",
+            ),
+            (
+                first.replace(" 1-0", " 0-1"),
+                "game 1, line 5, column 1: the Result tag says 1-0 but the moves end in 0-1",
+            ),
+        ];
+        for (text, error) in cases {
+            let (records, errors) = read(&text, &Settings::default());
+            assert!(records.is_empty(), "{}", text);
+            let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            assert_eq!(errors, [error]);
+        }
+    }
+
+    #[test]
+    fn a_bad_game_doesnt_stop_the_ones_after_it() {
+        let text = CANONICAL.replacen("4. 1 1-0", "4. 1 {never closed 1-0", 1);
+        let (records, errors) = read(&text, &Settings::default());
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].game, errors[0].line), (1, 12));
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].game.id, "01HX8W2N5Q3R7T9V0B1C2D3E4G");
+    }
+}
@@ -0,0 +1,255 @@
+use crate::coord::Coord;
+use crate::variant::{Move, Variant};
+use crate::{Game, MoveError, Player};
+
+impl Game {
+    // Play any kind of move for the player to move
+    pub fn play(&mut self, mv: Move) -> Result<(), MoveError> {
+        match mv {
+            Move::Drop(column) => self.play_move(self.column(column)?).map(|_| ()),
+            Move::Pop(column) => self.pop(column),
+            Move::Anvil(column) => self.anvil(column),
+            Move::Bomb(column) => self.bomb(column),
+            Move::Flip => self.flip(),
+        }
+    }
+
+    // Whether the player to move may pop `column`: their own piece is at its bottom
+    pub fn can_pop(&self, column: usize) -> bool {
+        self.rules.variant == Variant::PopOut
+            && !self.is_finished()
+            && !self.placing()
+            && self.board[self.rules.height - 1][column] == Some(self.current_player)
+    }
+
+    // Whether `player` has a piece on the bottom row they could pop out
+    pub fn can_pop_any(&self, player: Player) -> bool {
+        self.rules.variant == Variant::PopOut
+            && self.board[self.rules.height - 1].contains(&Some(player))
+    }
+
+    // Whether move number `ply`, from 1, popped a piece out rather than dropping one
+    pub fn popped(&self, ply: usize) -> bool {
+        self.specials.pops.contains(&ply)
+    }
+
+    // Under PopOut, take the mover's own piece out of the bottom of `column`, letting the
+    // ones above it fall a row. Pieces of both players can connect as they fall.
+    pub fn pop(&mut self, column: usize) -> Result<(), MoveError> {
+        if self.is_finished() {
+            return Err(MoveError::GameFinished);
+        }
+        self.column(column)?;
+        if self.rules.variant != Variant::PopOut || self.placing() {
+            return Err(MoveError::NoPops);
+        }
+        let bottom = self.rules.height - 1;
+        if self.board[bottom][column] != Some(self.current_player) {
+            return Err(MoveError::NotYourPiece);
+        }
+        if self.draw_offered() {
+            self.draw_offer = None;
+        }
+
+        for row in (1..self.rules.height).rev() {
+            self.board[row][column] = self.board[row - 1][column];
+        }
+        self.board[0][column] = None;
+        self.current_move += 1;
+        self.history.push(Coord { column, row: 0 });
+        self.specials.record(self.history.len(), Move::Pop(column));
+        self.settle();
+        Ok(())
+    }
+
+    // Take back the pop that was the last move, putting the piece back under the column
+    pub fn unpop(&mut self) {
+        let Some(coord) = self.history.pop() else {
+            return;
+        };
+        self.specials.truncate(self.history.len());
+        self.current_player = self.last_mover();
+        for row in 0..self.rules.height - 1 {
+            self.board[row][coord.column] = self.board[row + 1][coord.column];
+        }
+        self.board[self.rules.height - 1][coord.column] = Some(self.current_player);
+        self.current_move -= 1;
+        self.outcome = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Renderer;
+    use crate::settings::Settings;
+    use crate::style::Style;
+    use crate::theme::Theme;
+
+    // A PopOut game with these moves played: columns counted from 1, and negative columns
+    // popped
+    fn played(moves: &[i32]) -> Game {
+        let mut game = Game::builder(&Settings::default())
+            .variant(Variant::PopOut)
+            .build()
+            .expect("PopOut is playable");
+        for &column in moves {
+            let mv = match column {
+                column if column < 0 => Move::Pop((-column - 1) as usize),
+                column => Move::Drop((column - 1) as usize),
+            };
+            game.play(mv).expect("the moves are legal");
+        }
+        game
+    }
+
+    #[test]
+    fn only_your_own_bottom_piece_can_be_popped() {
+        let mut game = played(&[1, 2]);
+        assert_eq!(game.pop(1), Err(MoveError::NotYourPiece));
+        assert_eq!(game.pop(2), Err(MoveError::NotYourPiece));
+        assert_eq!(
+            game.pop(9),
+            Err(MoveError::InvalidColumn {
+                attempted: 9,
+                width: 7
+            })
+        );
+        assert!(game.can_pop(0));
+        assert!(!game.can_pop(1));
+
+        let mut standard = Game::builder(&Settings::default())
+            .build()
+            .expect("the rules are valid");
+        standard.play(Move::Drop(0)).expect("the column is empty");
+        standard.play(Move::Drop(1)).expect("the column is empty");
+        assert_eq!(standard.pop(0), Err(MoveError::NoPops));
+    }
+
+    #[test]
+    fn a_pop_lets_the_column_fall() {
+        let game = played(&[1, 1, 1, 2, -1]);
+        let bottom: Vec<String> = game
+            .board
+            .to_string()
+            .lines()
+            .skip(4)
+            .map(str::to_string)
+            .collect();
+        assert_eq!(bottom, ["X......", "OO....."]);
+        assert_eq!(game.current_player, Player::Two);
+        assert!(game.popped(5));
+        assert!(!game.is_finished());
+    }
+
+    #[test]
+    fn the_popper_wins_when_both_players_connect() {
+        // Player one pops column 1: their row of three above drops onto the fourth piece's
+        // row while player two's bottom row gets the piece that fell into the corner
+        let game = played(&[1, 2, 2, 3, 3, 4, 4, 1, 1, 7, -1]);
+        let rows: Vec<String> = game
+            .board
+            .to_string()
+            .lines()
+            .skip(4)
+            .map(str::to_string)
+            .collect();
+        assert_eq!(rows, ["XXXX...", "OOOO..O"]);
+        assert_eq!(game.winner(), Some(Player::One));
+        assert_eq!(
+            game.winning_line(),
+            Some(&[(4, 0), (4, 1), (4, 2), (4, 3)][..])
+        );
+
+        // The same with the colors the other way round
+        let game = played(&[2, 1, 3, 2, 4, 3, 1, 4, 7, 1, 7, -1]);
+        let rows: Vec<String> = game
+            .board
+            .to_string()
+            .lines()
+            .skip(4)
+            .map(str::to_string)
+            .collect();
+        assert_eq!(rows, ["OOOO..X", "XXXX..X"]);
+        assert_eq!(game.winner(), Some(Player::Two));
+        assert_eq!(
+            game.winning_line(),
+            Some(&[(4, 0), (4, 1), (4, 2), (4, 3)][..])
+        );
+    }
+
+    #[test]
+    fn a_pop_can_connect_the_opponents_four() {
+        let game = played(&[1, 2, 2, 3, 3, 4, 4, 1, -1]);
+        assert_eq!(game.winner(), Some(Player::Two));
+        assert_eq!(
+            game.winning_line(),
+            Some(&[(5, 0), (5, 1), (5, 2), (5, 3)][..])
+        );
+    }
+
+    #[test]
+    fn a_pop_can_break_up_a_line() {
+        // Player two has three in the second row with both ends open, until popping column
+        // 2 drops one of them a row down
+        let mut game = played(&[2, 2, 3, 3, 4, 4, 7]);
+        assert_eq!(game.board.to_string().lines().nth(4), Some(".OOO..."));
+        game.play(Move::Drop(6)).expect("the column has room");
+        game.play(Move::Pop(1))
+            .expect("player one's piece is at the bottom of column 2");
+        let rows: Vec<String> = game
+            .board
+            .to_string()
+            .lines()
+            .skip(4)
+            .map(str::to_string)
+            .collect();
+        assert_eq!(rows, ["..OO..O", ".OXX..X"]);
+        assert!(!game.is_finished());
+        assert_eq!(game.current_player, Player::Two);
+    }
+
+    #[test]
+    fn undoing_a_winning_pop_unmakes_the_four() {
+        let before = played(&[1, 2, 2, 3, 3, 4, 4, 1, 1, 7]);
+        let mut game = before.clone();
+        game.play(Move::Pop(0))
+            .expect("player one's piece is at the bottom of column 1");
+        assert!(game.is_finished());
+
+        assert!(game.undo());
+        assert_eq!(game.board, before.board);
+        assert_eq!(game.outcome, None);
+        assert_eq!(game.current_player, Player::One);
+        assert_eq!(game.current_move, before.current_move);
+        assert!(!game.popped(11));
+        assert!(game.can_pop(0));
+    }
+
+    #[test]
+    fn a_pop_tried_out_of_turn_is_taken_back_for_the_one_who_popped() {
+        // Describing a move looks for wins for each player in turn, trying their pops too
+        let game = played(&[1, 2, 3]);
+        let mut search = game.clone();
+        search.current_player = Player::One;
+        search
+            .play(Move::Pop(0))
+            .expect("player one's piece is at the bottom of column 1");
+        assert!(search.undo());
+        assert_eq!(search.board, game.board);
+        assert_eq!(search.current_player, Player::One);
+
+        let style = Style {
+            color: false,
+            truecolor: false,
+        };
+        let theme = Theme::builtin("classic").expect("the theme is built in");
+        let lines = game.describe_move(&Renderer::new(theme, style, None));
+        assert_eq!(lines.first().map(String::as_str), Some("Move 3."));
+        assert!(
+            lines.last().is_some_and(|line| line.ends_with("to move.")),
+            "{:?}",
+            lines
+        );
+    }
+}
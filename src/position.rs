@@ -0,0 +1,141 @@
+use std::hash::{Hash, Hasher};
+
+use crate::board::Cell;
+use crate::coord::Coord;
+use crate::{Game, Player};
+
+// Read access to the position, with rows counted from the bottom like a Coord's; the board
+// itself is kept with its top row first. Anything only looking at the position goes through
+// these rather than indexing the board.
+impl Game {
+    // What's `row` up from the bottom of `column`, both counted from 0
+    pub fn cell(&self, row: usize, column: usize) -> Cell {
+        self.board[self.rules.height - 1 - row][column]
+    }
+
+    // Pieces stacked in `column`, which is also the row the next one lands on
+    pub fn column_height(&self, column: usize) -> usize {
+        (0..self.rules.height)
+            .take_while(|&row| self.cell(row, column).is_some())
+            .count()
+    }
+
+    // Pieces of `player` on the board
+    pub fn pieces_count(&self, player: Player) -> u32 {
+        self.cells()
+            .filter(|&(_, cell)| cell == Some(player))
+            .count() as u32
+    }
+
+    // Every cell with its square, along the bottom row first and then up
+    pub fn cells(&self) -> impl Iterator<Item = (Coord, Cell)> + '_ {
+        (0..self.rules.height).flat_map(move |row| {
+            (0..self.rules.width).map(move |column| (Coord { column, row }, self.cell(row, column)))
+        })
+    }
+
+    // Whether the games stand at the same position, however they got there: the same rules,
+    // pieces on the board, player to move, and handicap pieces still to place
+    pub fn same_position(&self, other: &Game) -> bool {
+        self.rules == other.rules
+            && self.board == other.board
+            && self.current_player == other.current_player
+            && self.handicap_left == other.handicap_left
+    }
+}
+
+// Games are equal when they were played the same way: the same position, reached with the
+// same handicap and moves, and ended the same way. Who played, when, on what clock, and with
+// what notes doesn't count; `same_position` is the looser test.
+impl PartialEq for Game {
+    fn eq(&self, other: &Game) -> bool {
+        self.same_position(other)
+            && self.handicap == other.handicap
+            && self.history == other.history
+            && self.specials == other.specials
+            && self.outcome == other.outcome
+    }
+}
+
+impl Eq for Game {}
+
+// A game hashes by its position alone, so equal games hash the same and so do games that
+// reach one position by different moves
+impl Hash for Game {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.rules.hash(state);
+        self.board.hash(state);
+        self.current_player.hash(state);
+        self.handicap_left.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+    use crate::variant::Move;
+
+    // A classic game with these columns played, counted from 0
+    fn played(columns: &[usize]) -> Game {
+        let mut game = Game::new();
+        for &column in columns {
+            game.play(Move::Drop(column)).expect("the moves are legal");
+        }
+        game
+    }
+
+    fn hash(game: &Game) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        game.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn moves_played_in_another_order_reach_the_same_position_but_another_game() {
+        let game = played(&[3, 2, 4, 5]);
+        let transposed = played(&[4, 5, 3, 2]);
+        assert!(game.same_position(&transposed));
+        assert_eq!(hash(&game), hash(&transposed));
+        assert_ne!(game, transposed);
+
+        assert!(!game.same_position(&played(&[3, 2, 4])));
+        assert!(!game.same_position(&played(&[3, 2, 5, 4])));
+    }
+
+    #[test]
+    fn equal_games_hash_the_same() {
+        let game = played(&[3, 3, 2]);
+        let mut again = played(&[3, 3, 2]);
+        again.names[0] = "Ada".to_string();
+        assert_eq!(game, again, "who played doesn't count");
+        assert_eq!(hash(&game), hash(&again));
+        assert_eq!(Game::new(), Game::default());
+        assert_eq!(hash(&Game::new()), hash(&Game::default()));
+    }
+
+    #[test]
+    fn rows_count_up_from_the_bottom() {
+        let game = played(&[3, 3, 3, 0]);
+        assert_eq!(game.cell(0, 3), Some(Player::One));
+        assert_eq!(game.cell(1, 3), Some(Player::Two));
+        assert_eq!(game.cell(2, 3), Some(Player::One));
+        assert_eq!(game.cell(3, 3), None);
+        assert_eq!(game.cell(0, 0), Some(Player::Two));
+        assert_eq!(game.cell(5, 0), None);
+        // The board keeps its top row first
+        assert_eq!(game.board[5][3], Some(Player::One));
+        assert_eq!(game.board[0][3], None);
+
+        assert_eq!(game.column_height(3), 3);
+        assert_eq!(game.column_height(0), 1);
+        assert_eq!(game.column_height(6), 0);
+        let first: Vec<Coord> = game.cells().take(2).map(|(coord, _)| coord).collect();
+        assert_eq!(
+            first,
+            [Coord { column: 0, row: 0 }, Coord { column: 1, row: 0 }],
+            "the bottom row comes first"
+        );
+    }
+}
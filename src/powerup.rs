@@ -0,0 +1,156 @@
+use crate::coord::Coord;
+use crate::profile;
+use crate::variant::{Move, Variant};
+use crate::{Game, MoveError, Player};
+
+// Anvils and bombs each player has for a game
+pub const STOCK: usize = 1;
+
+impl Game {
+    // Anvils and bombs `player` has yet to play
+    pub fn anvils_left(&self, player: Player) -> usize {
+        STOCK.saturating_sub(plies_of(&self.specials.anvils, player))
+    }
+
+    pub fn bombs_left(&self, player: Player) -> usize {
+        STOCK.saturating_sub(plies_of(&self.specials.bombs, player))
+    }
+
+    // Whether the player to move could blow up the top piece of `column`
+    pub fn can_bomb(&self, column: usize) -> bool {
+        self.rules.variant == Variant::PowerUp
+            && !self.is_finished()
+            && !self.placing()
+            && self.bombs_left(self.current_player) > 0
+            && self.board[self.rules.height - 1][column].is_some()
+    }
+
+    // Whether `player` has a bomb left and there's a piece for it
+    pub fn can_bomb_any(&self, player: Player) -> bool {
+        self.rules.variant == Variant::PowerUp
+            && self.bombs_left(player) > 0
+            && self.board[self.rules.height - 1]
+                .iter()
+                .any(|cell| cell.is_some())
+    }
+
+    // Whether move number `ply`, from 1, was an anvil or a bomb
+    pub fn powered(&self, ply: usize) -> bool {
+        self.specials.anvils.contains(&ply) || self.specials.bombs.contains(&ply)
+    }
+
+    // Each player's anvils and bombs left, for showing under the board
+    pub fn stock_line(&self) -> Option<String> {
+        if self.rules.variant != Variant::PowerUp {
+            return None;
+        }
+        let seat = |player: Player| {
+            format!(
+                "{} {}, {}",
+                self.name(player),
+                profile::plural(self.anvils_left(player) as u32, "anvil"),
+                profile::plural(self.bombs_left(player) as u32, "bomb")
+            )
+        };
+        Some(format!(
+            "Still to play: {}; {}",
+            seat(Player::One),
+            seat(Player::Two)
+        ))
+    }
+
+    // Under Power Up, drop an anvil into `column`: it crushes every piece below it and
+    // settles on the bottom row as the mover's piece
+    pub fn anvil(&mut self, column: usize) -> Result<(), MoveError> {
+        self.check_power(column, self.anvils_left(self.current_player))?;
+        if self.board[0][column].is_some() {
+            return Err(MoveError::ColumnFull { column });
+        }
+        self.remember_column(column, Move::Anvil(column));
+        for row in 0..self.rules.height - 1 {
+            self.board[row][column] = None;
+        }
+        self.board[self.rules.height - 1][column] = Some(self.current_player);
+        self.history.push(Coord { column, row: 0 });
+        self.settle();
+        Ok(())
+    }
+
+    // Under Power Up, blow up the top piece of `column`, whoever's it is. Nothing is left
+    // floating, since only the top piece goes.
+    pub fn bomb(&mut self, column: usize) -> Result<(), MoveError> {
+        self.check_power(column, self.bombs_left(self.current_player))?;
+        let Some(row) = (0..self.rules.height).find(|&row| self.board[row][column].is_some())
+        else {
+            return Err(MoveError::NothingToBomb);
+        };
+        self.remember_column(column, Move::Bomb(column));
+        self.board[row][column] = None;
+        self.history.push(Coord {
+            column,
+            row: self.rules.height - 1 - row,
+        });
+        self.settle();
+        Ok(())
+    }
+
+    // Take back the anvil or bomb that was the last move, putting back the pieces it
+    // removed
+    pub fn unpower(&mut self) {
+        let (Some(coord), Some(cells)) = (self.history.pop(), self.cleared.pop()) else {
+            return;
+        };
+        let ply = self.history.len() + 1;
+        self.specials.truncate(ply - 1);
+        self.current_player = self.last_mover();
+        for (row, cell) in cells.into_iter().enumerate() {
+            self.board[row][coord.column] = cell;
+        }
+        self.current_move -= 1;
+        self.outcome = None;
+    }
+
+    // Refuse a special move the rules or the mover's stock don't allow
+    fn check_power(&self, column: usize, left: usize) -> Result<(), MoveError> {
+        if self.is_finished() {
+            return Err(MoveError::GameFinished);
+        }
+        self.column(column)?;
+        if self.rules.variant != Variant::PowerUp || self.placing() {
+            return Err(MoveError::NoPowers);
+        }
+        if left == 0 {
+            return Err(MoveError::NoneLeft);
+        }
+        Ok(())
+    }
+
+    // Record the special move `mv`, keeping what was in its column so the move can be
+    // taken back
+    fn remember_column(&mut self, column: usize, mv: Move) {
+        if self.draw_offered() {
+            self.draw_offer = None;
+        }
+        self.cleared.push(
+            (0..self.rules.height)
+                .map(|row| self.board[row][column])
+                .collect(),
+        );
+        self.current_move += 1;
+        self.specials.record(self.history.len() + 1, mv);
+    }
+}
+
+// The seat that plays move number `ply`: player 1 makes the odd moves
+pub fn seat_of(ply: usize) -> Player {
+    if ply.is_multiple_of(2) {
+        Player::Two
+    } else {
+        Player::One
+    }
+}
+
+// How many of the move numbers in `plies` were played by `player`
+pub fn plies_of(plies: &[usize], player: Player) -> usize {
+    plies.iter().filter(|&&ply| seat_of(ply) == player).count()
+}
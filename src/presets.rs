@@ -0,0 +1,41 @@
+use crate::rules::GameRules;
+
+// Entries a preset in the config file may give
+const RULES: [&str; 6] = ["width", "height", "connect", "variant", "first", "pie_rule"];
+
+// The presets every install has, by name
+pub const BUILT_IN: [(&str, GameRules); 3] = [
+    ("classic", GameRules::CLASSIC),
+    ("five", GameRules::CLASSIC.with_size(9, 7).with_connect(5)),
+    ("mini", GameRules::CLASSIC.with_size(5, 4).with_connect(3)),
+];
+
+// A preset from a config file's [rules] section: a table of any of its rules, with the rest
+// as in classic. Whether the rules make a game that can be played is checked once they're
+// picked.
+pub fn parse(name: &str, value: &toml::Value) -> Result<GameRules, String> {
+    let Some(table) = value.as_table() else {
+        return Err(format!(
+            "preset '{}' must be a table like {{ width = 9, height = 7, connect = 5 }}",
+            name
+        ));
+    };
+    if let Some(key) = table.keys().find(|key| !RULES.contains(&key.as_str())) {
+        return Err(format!("preset '{}' has an unknown rule '{}'", name, key));
+    }
+    GameRules::from_toml(table).map_err(|err| format!("preset '{}': {}", name, err))
+}
+
+// Every preset on a line of its own, its name then its rules
+pub fn list(presets: &[(String, GameRules)]) -> String {
+    let width = presets
+        .iter()
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    presets
+        .iter()
+        .map(|(name, rules)| format!("{:<width$}  {}", name, rules.describe(), width = width))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,828 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::achievements::{self, Achievement, ACHIEVEMENTS};
+use crate::ai::Difficulty;
+use crate::history;
+use crate::settings::Settings;
+use crate::variant::Variant;
+use crate::{Game, Player};
+
+// Elo rating of a player before their first rated game
+pub const START_RATING: i32 = 1200;
+
+// Wins, losses, and draws against one kind of opponent
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Record {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl Record {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+}
+
+// What the game remembers about a player across sessions
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    // Results by opponent: "human", or the computer's difficulty
+    pub records: Vec<(String, Record)>,
+    // Moves played in all their games together, for the average length
+    pub moves: u32,
+    pub streak: u32,
+    pub longest_streak: u32,
+    // How often each column was their first move, from the left
+    pub first_columns: Vec<u32>,
+    pub hints: u32,
+    pub takebacks: u32,
+    // Date of the last game, as YYYY-MM-DD
+    pub last_played: Option<String>,
+    // Elo rating, once they've played a rated game
+    pub rating: Option<i32>,
+    pub rated_games: u32,
+    // Ids of the achievements earned, in the order they were
+    pub achievements: Vec<String>,
+    // Ids of the tutorial's lessons finished, in the order they were
+    pub tutorial: Vec<String>,
+}
+
+// Directory with a player's stats and settings. Names are turned into safe file names that
+// only fold case, so "Ann Lee" and "ann lee" share one but "Ann_Lee" has its own.
+pub fn profile_dir(name: &str) -> Option<PathBuf> {
+    let dir = profiles_dir()?.join(slug(name));
+    migrate(&dir, name);
+    Some(dir)
+}
+
+// A name as a file name: letters and digits in lower case, spaces as underscores, and
+// every other character as its code point in hex between dashes, like "o-27-brien"
+fn slug(name: &str) -> String {
+    name.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            c if c.is_alphanumeric() => c.to_string(),
+            ' ' => "_".to_string(),
+            c => format!("-{:x}-", u32::from(c)),
+        })
+        .collect()
+}
+
+// Profiles were once kept under the name with every other character an underscore, which
+// names like "O'Brien" and "O_Brien" shared. The one of this player is moved to where it's
+// kept now, when it's theirs by its stats.
+fn migrate(dir: &Path, name: &str) {
+    let legacy: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+    let Some(legacy) = dir.parent().map(|parent| parent.join(legacy)) else {
+        return;
+    };
+    if legacy == dir || dir.exists() || !legacy.exists() {
+        return;
+    }
+    let owner = read_stats(&legacy.join("stats.toml"), name).ok().flatten();
+    if owner.is_some_and(|profile| profile.name.to_lowercase() == name.to_lowercase()) {
+        if let Err(err) = fs::rename(&legacy, dir) {
+            log::warn!(
+                "could not move {} to {}: {}",
+                legacy.display(),
+                dir.display(),
+                err
+            );
+        }
+    }
+}
+
+fn profiles_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("connect_four").join("profiles"))
+}
+
+// Settings file of a profile, read over the shared config file with --profile
+pub fn config_path(name: &str) -> Option<PathBuf> {
+    profile_dir(name).map(|dir| dir.join("config.toml"))
+}
+
+fn stats_path(name: &str) -> Option<PathBuf> {
+    profile_dir(name).map(|dir| dir.join("stats.toml"))
+}
+
+// The computer's fixed rating at each difficulty, so games against it can be rated
+pub fn computer_rating(difficulty: Difficulty) -> i32 {
+    match difficulty {
+        Difficulty::Easy => 800,
+        Difficulty::Medium => 1200,
+        Difficulty::Hard => 1600,
+    }
+}
+
+// Expected score, from 0 to 1, of a player rated `rating` against one rated `opponent`
+fn expected_score(rating: i32, opponent: i32) -> f64 {
+    1.0 / (1.0 + 10f64.powf(f64::from(opponent.saturating_sub(rating)) / 400.0))
+}
+
+// Rating after a game scoring `score`: 1 for a win, 0.5 for a draw, 0 for a loss
+fn elo(rating: i32, opponent: i32, score: f64, k_factor: u32) -> i32 {
+    let change = f64::from(k_factor) * (score - expected_score(rating, opponent));
+    rating.saturating_add(change.round() as i32)
+}
+
+// Whether a finished game counts for ratings: not in a casual session, not played with a
+// handicap or under Power Up, between players who gave their names, and with no player
+// leaning on more hints and takebacks than allowed
+pub fn rated(game: &Game, settings: &Settings) -> bool {
+    settings.rated
+        && game.is_finished()
+        && game.handicap_size() == 0
+        && game.rules.variant != Variant::PowerUp
+        && !game
+            .seats()
+            .into_iter()
+            .any(|seat| anonymous(game.name(seat)))
+        && (0..2).all(|seat| game.hints[seat] + game.takebacks[seat] <= settings.assists)
+}
+
+// Whether a seat still has its default name, like "Player 1": it could be anyone sitting
+// there, so it keeps no profile
+pub fn anonymous(name: &str) -> bool {
+    Player::SEATS.iter().any(|seat| seat.to_string() == name)
+}
+
+// Each seat's rating before and after a rated game, from the profiles of the human seats.
+// Both are worked out from the ratings before the game, so the order the profiles are
+// written in doesn't matter.
+fn rating_updates(
+    game: &Game,
+    settings: &Settings,
+    profiles: &[Option<Profile>],
+) -> Option<[(i32, i32); 2]> {
+    if !rated(game, settings) {
+        return None;
+    }
+    let before = [0, 1].map(|seat| match (&profiles[seat], game.computer) {
+        (Some(profile), _) => profile.rating.unwrap_or(START_RATING),
+        (None, Some((_, difficulty))) => computer_rating(difficulty),
+        (None, None) => START_RATING,
+    });
+    let score = |seat: Player| match game.winner() {
+        None => 0.5,
+        Some(winner) if winner == seat => 1.0,
+        Some(_) => 0.0,
+    };
+    Some([
+        (
+            before[0],
+            elo(before[0], before[1], score(Player::One), settings.k_factor),
+        ),
+        (
+            before[1],
+            elo(before[1], before[0], score(Player::Two), settings.k_factor),
+        ),
+    ])
+}
+
+// Profiles of the game's named human seats, in seat order, with the warnings about any that
+// couldn't be read
+fn seat_profiles(game: &Game) -> (Vec<Option<Profile>>, Vec<String>) {
+    let mut warnings = Vec::new();
+    let profiles = game
+        .seats()
+        .into_iter()
+        .map(|seat| {
+            if game.computer.is_some_and(|(computer, _)| computer == seat)
+                || anonymous(game.name(seat))
+            {
+                return None;
+            }
+            let (mut profile, warning) = Profile::load_or_new(game.name(seat));
+            warnings.extend(warning);
+            profile.name = game.name(seat).to_string();
+            Some(profile)
+        })
+        .collect();
+    (profiles, warnings)
+}
+
+// How a finished game moves the human players' ratings, e.g. "Alice 1215 → 1224 (+9)",
+// or nothing when it's unrated
+pub fn rating_changes(game: &Game, settings: &Settings) -> Vec<String> {
+    let (profiles, _) = seat_profiles(game);
+    let Some(updates) = rating_updates(game, settings, &profiles) else {
+        return Vec::new();
+    };
+    profiles
+        .iter()
+        .zip(updates)
+        .filter_map(|(profile, (before, after))| {
+            profile.as_ref().map(|profile| {
+                format!(
+                    "{} {} → {} ({:+})",
+                    profile.name,
+                    before,
+                    after,
+                    after - before
+                )
+            })
+        })
+        .collect()
+}
+
+// Announcements of the achievements a finished game earns its human players for the first
+// time, e.g. "Achievement unlocked: Alice, First win (Win a game)"
+pub fn achievement_banners(game: &Game) -> Vec<String> {
+    let (profiles, _) = seat_profiles(game);
+    game.seats()
+        .into_iter()
+        .zip(&profiles)
+        .filter_map(|(seat, profile)| profile.as_ref().map(|profile| (seat, profile)))
+        .flat_map(|(seat, profile)| {
+            profile
+                .new_achievements(game, seat)
+                .into_iter()
+                .map(move |achievement| {
+                    format!(
+                        "Achievement unlocked: {}, {} ({})",
+                        profile.name, achievement.name, achievement.description
+                    )
+                })
+        })
+        .collect()
+}
+
+// Every profile with a rating, highest first
+pub fn ratings() -> Result<Vec<Profile>, String> {
+    let mut profiles: Vec<Profile> = all()?
+        .into_iter()
+        .filter(|profile| profile.rating.is_some())
+        .collect();
+    profiles.sort_by(|a, b| b.rating.cmp(&a.rating).then_with(|| a.name.cmp(&b.name)));
+    Ok(profiles)
+}
+
+// Every player who has finished a game
+pub fn all() -> Result<Vec<Profile>, String> {
+    let dir = profiles_dir().ok_or("no data directory found")?;
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let entries =
+        fs::read_dir(&dir).map_err(|err| format!("could not read {}: {}", dir.display(), err))?;
+    let mut profiles = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|err| format!("could not read {}: {}", dir.display(), err))?;
+        let Some(slug) = entry.file_name().to_str().map(str::to_string) else {
+            continue;
+        };
+        // A profile can be kept for the tutorial alone, before any game
+        let stats = read_stats(&entry.path().join("stats.toml"), &slug)?;
+        profiles.extend(stats.filter(|profile| profile.games() > 0));
+    }
+    Ok(profiles)
+}
+
+// The profile in the stats file at `path`, or None if there's none there. `name` is the player's
+// when the file doesn't say.
+fn read_stats(path: &Path, name: &str) -> Result<Option<Profile>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+    let table: toml::Table = text
+        .parse()
+        .map_err(|err| format!("could not parse {}: {}", path.display(), err))?;
+    Profile::from_table(name, &table)
+        .map(Some)
+        .map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+impl Profile {
+    // A player's profile, or None if they've never finished a game
+    pub fn load(name: &str) -> Result<Option<Profile>, String> {
+        let path = stats_path(name).ok_or("no data directory found")?;
+        read_stats(&path, name)
+    }
+
+    // A player's profile for adding a game to. A damaged stats file is reported and
+    // replaced, so it never stops anyone from playing.
+    fn load_or_new(name: &str) -> (Profile, Option<String>) {
+        let fresh = Profile {
+            name: name.to_string(),
+            ..Profile::default()
+        };
+        match Profile::load(name) {
+            Ok(profile) => (profile.unwrap_or(fresh), None),
+            Err(err) => (fresh, Some(format!("{}; starting a new profile", err))),
+        }
+    }
+
+    // Write the whole profile at once: the game's result and the rating it earned land
+    // together or not at all, through a temporary file renamed over the old one
+    fn save(&self) -> Result<(), String> {
+        let path = stats_path(&self.name).ok_or("no data directory found")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|err| format!("could not create {}: {}", dir.display(), err))?;
+        }
+        let temporary = path.with_extension("toml.tmp");
+        log::debug!("saving the profile of {} to {}", self.name, path.display());
+        fs::write(&temporary, self.to_table().to_string())
+            .map_err(|err| format!("could not write {}: {}", temporary.display(), err))?;
+        fs::rename(&temporary, &path)
+            .map_err(|err| format!("could not write {}: {}", path.display(), err))
+    }
+
+    fn from_table(name: &str, table: &toml::Table) -> Result<Profile, String> {
+        let number = |table: &toml::Table, key: &str| match table.get(key) {
+            None => Ok(0),
+            Some(toml::Value::Integer(value)) => u32::try_from(*value)
+                .map_err(|_| format!("'{}' must be a non-negative number", key)),
+            Some(_) => Err(format!("'{}' must be a number", key)),
+        };
+
+        let mut records = Vec::new();
+        if let Some(opponents) = table.get("records") {
+            let opponents = opponents.as_table().ok_or("'records' must be a table")?;
+            for (opponent, record) in opponents {
+                let record = record
+                    .as_table()
+                    .ok_or_else(|| format!("'records.{}' must be a table", opponent))?;
+                records.push((
+                    opponent.clone(),
+                    Record {
+                        wins: number(record, "wins")?,
+                        losses: number(record, "losses")?,
+                        draws: number(record, "draws")?,
+                    },
+                ));
+            }
+        }
+        let first_columns = match table.get("first_columns") {
+            None => Vec::new(),
+            Some(toml::Value::Array(counts)) => counts
+                .iter()
+                .map(|count| match count {
+                    toml::Value::Integer(count) => u32::try_from(*count).ok(),
+                    _ => None,
+                })
+                .collect::<Option<Vec<u32>>>()
+                .ok_or("'first_columns' must list non-negative numbers")?,
+            Some(_) => return Err("'first_columns' must be a list".to_string()),
+        };
+        let rating = match table.get("rating") {
+            None => None,
+            Some(toml::Value::Integer(rating)) => {
+                Some(i32::try_from(*rating).map_err(|_| "'rating' is out of range".to_string())?)
+            }
+            Some(_) => return Err("'rating' must be a number".to_string()),
+        };
+        let achievements = match table.get("achievements") {
+            None => Vec::new(),
+            Some(toml::Value::Array(ids)) => ids
+                .iter()
+                .map(|id| id.as_str().map(str::to_string))
+                .collect::<Option<Vec<String>>>()
+                .ok_or("'achievements' must list names")?,
+            Some(_) => return Err("'achievements' must be a list".to_string()),
+        };
+        let tutorial = match table.get("tutorial") {
+            None => Vec::new(),
+            Some(toml::Value::Array(ids)) => ids
+                .iter()
+                .map(|id| id.as_str().map(str::to_string))
+                .collect::<Option<Vec<String>>>()
+                .ok_or("'tutorial' must list lessons")?,
+            Some(_) => return Err("'tutorial' must be a list".to_string()),
+        };
+        let last_played = match table.get("last_played") {
+            None => None,
+            Some(toml::Value::String(date)) => Some(date.clone()),
+            Some(_) => return Err("'last_played' must be a date".to_string()),
+        };
+
+        // The name as it was last played, since any spelling of it finds the profile
+        let name = match table.get("name") {
+            None => name.to_string(),
+            Some(toml::Value::String(name)) => name.clone(),
+            Some(_) => return Err("'name' must be a string".to_string()),
+        };
+
+        Ok(Profile {
+            name,
+            records,
+            moves: number(table, "moves")?,
+            streak: number(table, "streak")?,
+            longest_streak: number(table, "longest_streak")?,
+            first_columns,
+            hints: number(table, "hints")?,
+            takebacks: number(table, "takebacks")?,
+            last_played,
+            rating,
+            rated_games: number(table, "rated_games")?,
+            achievements,
+            tutorial,
+        })
+    }
+
+    fn to_table(&self) -> toml::Table {
+        let number = |value: u32| toml::Value::Integer(i64::from(value));
+        let mut table = toml::Table::new();
+        table.insert("name".to_string(), toml::Value::String(self.name.clone()));
+        table.insert("moves".to_string(), number(self.moves));
+        table.insert("streak".to_string(), number(self.streak));
+        table.insert("longest_streak".to_string(), number(self.longest_streak));
+        table.insert(
+            "first_columns".to_string(),
+            toml::Value::Array(
+                self.first_columns
+                    .iter()
+                    .map(|&count| number(count))
+                    .collect(),
+            ),
+        );
+        table.insert("hints".to_string(), number(self.hints));
+        table.insert("takebacks".to_string(), number(self.takebacks));
+        if let Some(date) = &self.last_played {
+            table.insert("last_played".to_string(), toml::Value::String(date.clone()));
+        }
+        table.insert(
+            "achievements".to_string(),
+            toml::Value::Array(
+                self.achievements
+                    .iter()
+                    .map(|id| toml::Value::String(id.clone()))
+                    .collect(),
+            ),
+        );
+        if !self.tutorial.is_empty() {
+            table.insert(
+                "tutorial".to_string(),
+                toml::Value::Array(
+                    self.tutorial
+                        .iter()
+                        .map(|id| toml::Value::String(id.clone()))
+                        .collect(),
+                ),
+            );
+        }
+        if let Some(rating) = self.rating {
+            table.insert(
+                "rating".to_string(),
+                toml::Value::Integer(i64::from(rating)),
+            );
+            table.insert("rated_games".to_string(), number(self.rated_games));
+        }
+        let records = self
+            .records
+            .iter()
+            .map(|(opponent, record)| {
+                let mut entry = toml::Table::new();
+                entry.insert("wins".to_string(), number(record.wins));
+                entry.insert("losses".to_string(), number(record.losses));
+                entry.insert("draws".to_string(), number(record.draws));
+                (opponent.clone(), toml::Value::Table(entry))
+            })
+            .collect();
+        table.insert("records".to_string(), toml::Value::Table(records));
+        table
+    }
+
+    // Add a finished game played in `seat`
+    fn add_game(&mut self, game: &Game, seat: Player) {
+        let opponent = match game.computer {
+            Some((_, difficulty)) => format!("{:?}", difficulty).to_lowercase(),
+            None => "human".to_string(),
+        };
+        let index = match self.records.iter().position(|(name, _)| *name == opponent) {
+            Some(index) => index,
+            None => {
+                self.records.push((opponent, Record::default()));
+                self.records.len() - 1
+            }
+        };
+        let record = &mut self.records[index].1;
+        match game.winner() {
+            None => {
+                record.draws += 1;
+                self.streak = 0;
+            }
+            Some(winner) if winner == seat => {
+                record.wins += 1;
+                self.streak += 1;
+                self.longest_streak = self.longest_streak.max(self.streak);
+            }
+            _ => {
+                record.losses += 1;
+                self.streak = 0;
+            }
+        }
+
+        // The players move in seat order, so a seat's first move is at its index
+        let seat_index = seat.index();
+        self.moves += game.history.len() as u32;
+        if let Some(first) = game.history.get(seat_index) {
+            if self.first_columns.len() <= first.column {
+                self.first_columns.resize(first.column + 1, 0);
+            }
+            self.first_columns[first.column] += 1;
+        }
+        self.hints += game.hints[seat_index];
+        self.takebacks += game.takebacks[seat_index];
+        self.last_played = Some(today());
+        for achievement in self.new_achievements(game, seat) {
+            self.achievements.push(achievement.id.to_string());
+        }
+    }
+
+    // The achievements `game` earns for the player in `seat` that they don't have yet
+    fn new_achievements(&self, game: &Game, seat: Player) -> Vec<&'static Achievement> {
+        achievements::earned(game, seat)
+            .into_iter()
+            .filter(|achievement| !self.achievements.iter().any(|id| id == achievement.id))
+            .collect()
+    }
+
+    // Every achievement, earned ones checked off
+    pub fn achievement_report(&self) -> String {
+        let earned =
+            |achievement: &Achievement| self.achievements.iter().any(|id| id == achievement.id);
+        let count = ACHIEVEMENTS
+            .iter()
+            .filter(|achievement| earned(achievement))
+            .count();
+        let mut lines = vec![format!(
+            "{}: {} of {} achievements",
+            self.name,
+            count,
+            ACHIEVEMENTS.len()
+        )];
+        for achievement in &ACHIEVEMENTS {
+            let mark = if earned(achievement) { "x" } else { " " };
+            lines.push(format!(
+                "  [{}] {} — {}",
+                mark, achievement.name, achievement.description
+            ));
+        }
+        lines.join("\n")
+    }
+
+    fn games(&self) -> u32 {
+        self.records.iter().map(|(_, record)| record.games()).sum()
+    }
+
+    // Everything in the profile, a line per figure
+    pub fn report(&self) -> String {
+        let games = self.games();
+        let mut lines = vec![format!("{}: {} played", self.name, plural(games, "game"))];
+        if let Some(rating) = self.rating {
+            lines.push(format!(
+                "  Rating                 {} ({} rated)",
+                rating,
+                plural(self.rated_games, "game")
+            ));
+        }
+        for (opponent, record) in &self.records {
+            let against = match opponent.as_str() {
+                "human" => "vs humans".to_string(),
+                level => format!("vs computer ({})", level),
+            };
+            lines.push(format!(
+                "  {:<22} {} won, {} lost, {} drawn",
+                against, record.wins, record.losses, record.draws
+            ));
+        }
+        if games > 0 {
+            lines.push(format!(
+                "  Average game length    {:.1} moves",
+                f64::from(self.moves) / f64::from(games)
+            ));
+        }
+        lines.push(format!(
+            "  Longest win streak     {} (current {})",
+            self.longest_streak, self.streak
+        ));
+        let favorite = self
+            .first_columns
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .max_by_key(|&(column, &count)| (count, std::cmp::Reverse(column)));
+        if let Some((column, count)) = favorite {
+            lines.push(format!(
+                "  Favorite first column  {} ({})",
+                column + 1,
+                plural(*count, "time")
+            ));
+        }
+        lines.push(format!(
+            "  Hints used             {}\n  Moves taken back       {}",
+            self.hints, self.takebacks
+        ));
+        if let Some(date) = &self.last_played {
+            lines.push(format!("  Last played            {}", date));
+        }
+        lines.join("\n")
+    }
+}
+
+// Add a finished game to the profiles of its human players, with their new ratings when
+// it's rated. Returns the warnings about profiles that couldn't be read or written.
+pub fn record_game(game: &Game, settings: &Settings) -> Vec<String> {
+    if !game.is_finished() {
+        return Vec::new();
+    }
+    let (mut profiles, mut warnings) = seat_profiles(game);
+    if let Err(err) = history::append(game, &today()) {
+        warnings.push(err);
+    }
+    let updates = rating_updates(game, settings, &profiles);
+    for (index, (seat, profile)) in game.seats().into_iter().zip(&mut profiles).enumerate() {
+        let Some(profile) = profile else {
+            continue;
+        };
+        profile.add_game(game, seat);
+        if let Some(updates) = updates {
+            profile.rating = Some(updates[index].1);
+            profile.rated_games += 1;
+        }
+        if let Err(err) = profile.save() {
+            warnings.push(err);
+        }
+    }
+    warnings
+}
+
+// The lessons of the tutorial a player has finished, none for a player with no profile
+pub fn lessons_done(name: &str) -> Result<Vec<String>, String> {
+    Ok(Profile::load(name)?.map_or_else(Vec::new, |profile| profile.tutorial))
+}
+
+// Note that a player finished the tutorial's lesson `lesson`, earning its achievement once
+// every one of `lessons` is done. Returns the achievement's announcement when it's new, and
+// a warning when the profile couldn't be read and was started over.
+pub fn finish_lesson(name: &str, lesson: &str, lessons: &[&str]) -> Result<Vec<String>, String> {
+    let (mut profile, warning) = Profile::load_or_new(name);
+    let mut lines: Vec<String> = warning.into_iter().collect();
+    if !profile.tutorial.iter().any(|done| done == lesson) {
+        profile.tutorial.push(lesson.to_string());
+    }
+    let finished = lessons
+        .iter()
+        .all(|id| profile.tutorial.iter().any(|done| done == id));
+    let achievement = ACHIEVEMENTS
+        .iter()
+        .find(|achievement| achievement.id == achievements::TUTORIAL)
+        .expect("the tutorial's achievement is listed");
+    if finished && !profile.achievements.iter().any(|id| id == achievement.id) {
+        profile.achievements.push(achievement.id.to_string());
+        lines.push(format!(
+            "Achievement unlocked: {}, {} ({})",
+            profile.name, achievement.name, achievement.description
+        ));
+    }
+    profile.save()?;
+    Ok(lines)
+}
+
+// "1 game", "2 games"
+pub fn plural(count: u32, noun: &str) -> String {
+    match count {
+        1 => format!("1 {}", noun),
+        count => format!("{} {}s", count, noun),
+    }
+}
+
+// Today's date in UTC as YYYY-MM-DD
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() / 86_400) as i64;
+    date(days)
+}
+
+// The YYYY-MM-DD date `days` days after 1970-01-01
+pub fn date(days: i64) -> String {
+    // Days since 1970-01-01 to a civil date, after Howard Hinnant's days_from_civil inverse
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::variant::Move;
+
+    // A game won by player one between players with these names, in a rated session
+    fn won_by(names: &[&str]) -> (Game, Settings) {
+        let mut settings = Settings::default();
+        settings.rated = true;
+        let names: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        let mut game = Game::builder(&settings)
+            .names(&names)
+            .build()
+            .expect("the default rules are valid");
+        for column in [0, 1, 0, 1, 0, 1, 0] {
+            game.play(Move::Drop(column)).expect("the moves are legal");
+        }
+        (game, settings)
+    }
+
+    #[test]
+    fn default_seat_names_keep_no_profile() {
+        let (game, _) = won_by(&["Player 1", "Player 2"]);
+        let (profiles, warnings) = seat_profiles(&game);
+        assert!(profiles.iter().all(Option::is_none));
+        assert!(warnings.is_empty());
+        assert!(achievement_banners(&game).is_empty());
+    }
+
+    #[test]
+    fn only_games_between_named_players_are_rated() {
+        for names in [
+            ["Player 1", "Player 2"],
+            ["Ada", "Player 2"],
+            ["Player 1", "Grace"],
+        ] {
+            let (game, settings) = won_by(&names);
+            assert!(!rated(&game, &settings), "{:?}", names);
+            assert_eq!(rating_updates(&game, &settings, &[None, None]), None);
+        }
+        let (game, settings) = won_by(&["Ada", "Grace"]);
+        assert!(rated(&game, &settings));
+        assert_eq!(
+            rating_updates(&game, &settings, &[None, None]),
+            Some([(1200, 1216), (1200, 1184)])
+        );
+    }
+
+    #[test]
+    fn an_upset_moves_ratings_further_than_the_expected_result() {
+        assert_eq!(elo(1600, 1200, 1.0, 32), 1603);
+        assert_eq!(elo(1200, 1600, 0.0, 32), 1197);
+        assert_eq!(elo(1200, 1600, 1.0, 32), 1229);
+        assert_eq!(elo(1600, 1200, 0.0, 32), 1571);
+        assert_eq!(elo(1400, 1400, 1.0, 16), 1408);
+    }
+
+    #[test]
+    fn a_draw_only_moves_uneven_ratings() {
+        assert_eq!(elo(1500, 1500, 0.5, 32), 1500);
+        assert_eq!(elo(1600, 1200, 0.5, 32), 1587);
+        assert_eq!(elo(1200, 1600, 0.5, 32), 1213);
+    }
+
+    #[test]
+    fn ratings_far_apart_dont_overflow() {
+        assert_eq!(expected_score(i32::MAX, i32::MIN), 1.0);
+        assert_eq!(expected_score(i32::MIN, i32::MAX), 0.0);
+        assert_eq!(elo(i32::MAX, i32::MIN, 1.0, 32), i32::MAX);
+        assert_eq!(elo(i32::MIN, i32::MAX, 0.0, 32), i32::MIN);
+        assert_eq!(elo(i32::MIN, i32::MAX, 1.0, 32), i32::MIN + 32);
+    }
+
+    #[test]
+    fn names_share_a_profile_only_when_they_differ_in_case() {
+        assert_eq!(slug("Ann Lee"), "ann_lee");
+        assert_eq!(slug("ann LEE"), slug("Ann Lee"));
+        assert_eq!(slug("Ann_Lee"), "ann-5f-lee");
+        assert_eq!(slug("O'Brien"), "o-27-brien");
+        let names = [
+            "Ann Lee", "Ann_Lee", "Ann-Lee", "Ann.Lee", "AnnLee", "Ann  Lee", "../Ann",
+        ];
+        for (i, a) in names.iter().enumerate() {
+            assert!(!slug(a).contains(['/', '\\', '.']), "{}", a);
+            for b in &names[i + 1..] {
+                assert_ne!(slug(a), slug(b), "{} and {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn a_name_is_only_anonymous_when_its_a_seats_default() {
+        for name in ["Player 1", "Player 4"] {
+            assert!(anonymous(name), "{}", name);
+        }
+        for name in ["Player", "Player 5", "player 1", "Computer", "Ada"] {
+            assert!(!anonymous(name), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,297 @@
+// Properties every game should have, checked over games played at random. The games come
+// from fixed seeds, so a failure names the seed that plays it again.
+use std::fs;
+
+use crate::export;
+use crate::rules::GameRules;
+use crate::session::Session;
+use crate::settings::Settings;
+use crate::start::Rng;
+use crate::variant::{Move, Variant};
+use crate::{save, Game, Player};
+
+// Games played for each property
+const GAMES: u32 = 200;
+
+// Every variant, each as likely to come up
+const VARIANTS: [Variant; 8] = [
+    Variant::Standard,
+    Variant::PopOut,
+    Variant::PowerUp,
+    Variant::Cylinder,
+    Variant::Misere,
+    Variant::Score,
+    Variant::Flip,
+    Variant::Cube,
+];
+
+// A new game of a variant picked by `rng` on a board of 4 to 9 columns and 4 to 8 rows,
+// played to 3 to 5 in a row, as far as the board reaches; the cube always has its own size
+fn new_game(rng: &mut Rng) -> Game {
+    let variant = VARIANTS[rng.below(VARIANTS.len())];
+    let mut builder = Game::builder(&Settings::default()).variant(variant);
+    if variant != Variant::Cube {
+        let (width, height) = (4 + rng.below(6), 4 + rng.below(5));
+        let connect = (3 + rng.below(3)).min(width.max(height));
+        builder = builder.size(width, height).connect(connect);
+    }
+    builder.build().expect("the rules are valid")
+}
+
+// Every move the game takes from the player to move, of every kind
+fn legal_moves(game: &Game) -> Vec<Move> {
+    (0..game.rules.width)
+        .flat_map(|column| {
+            [
+                Move::Drop(column),
+                Move::Pop(column),
+                Move::Anvil(column),
+                Move::Bomb(column),
+            ]
+        })
+        .chain([Move::Flip])
+        .filter(|&mv| game.clone().play(mv).is_ok())
+        .collect()
+}
+
+// Moves picked by `rng` from the legal ones until the game ends, or for as many as the board
+// has cells four times over, since pops and bombs can keep a game going forever. `visit`
+// sees the game before each move.
+fn play_out(game: &mut Game, rng: &mut Rng, mut visit: impl FnMut(&Game)) {
+    for _ in 0..game.rules.width * game.rules.height * 4 {
+        visit(game);
+        let moves = legal_moves(game);
+        if moves.is_empty() {
+            break;
+        }
+        game.play(moves[rng.below(moves.len())])
+            .expect("the move is legal");
+    }
+    visit(game);
+}
+
+// A game of `new_game` stopped partway: as many moves picked by `rng` as it takes the board
+// to fill, at most, and fewer if the game ends first
+fn mid_game(rng: &mut Rng) -> Game {
+    let mut game = new_game(rng);
+    for _ in 0..rng.below(game.rules.width * game.rules.height) {
+        let moves = legal_moves(&game);
+        if moves.is_empty() {
+            break;
+        }
+        game.play(moves[rng.below(moves.len())])
+            .expect("the move is legal");
+    }
+    game
+}
+
+// The game the moves in `text`, written the way to_moves_string writes them, play under `rules`
+fn from_moves(rules: GameRules, text: &str) -> Result<Game, String> {
+    let moves = text
+        .split_whitespace()
+        .map(|word| {
+            Move::parse(word, |column| column.parse::<usize>().ok()?.checked_sub(1))
+                .ok_or_else(|| format!("'{}' isn't a move", word))
+        })
+        .collect::<Result<Vec<Move>, String>>()?;
+    export::replay(
+        Game::builder(&Settings::default()).rules(rules),
+        &[],
+        &moves,
+    )
+}
+
+// The owner of `connect` pieces in a row anywhere on the board, found by trying every
+// window from every cell, going round the seam on a cylinder
+fn line_owner(game: &Game) -> Option<Player> {
+    let (width, height) = (game.rules.width as isize, game.rules.height as isize);
+    let connect = game.rules.connect as isize;
+    let wraps = game.rules.variant.wraps();
+    let mut owners = Vec::new();
+    for row in 0..height {
+        for column in 0..width {
+            for (row_step, column_step) in [(0, 1), (1, 0), (1, 1), (1, -1)] {
+                // A window round a cylinder narrower than the line would take a cell twice
+                if wraps && row_step == 0 && connect > width {
+                    continue;
+                }
+                let cells: Option<Vec<Player>> = (0..connect)
+                    .map(|i| {
+                        let row = row + i * row_step;
+                        let mut column = column + i * column_step;
+                        if wraps {
+                            column = column.rem_euclid(width);
+                        }
+                        let inside = (0..height).contains(&row) && (0..width).contains(&column);
+                        inside
+                            .then(|| game.cell(row as usize, column as usize))
+                            .flatten()
+                    })
+                    .collect();
+                if let Some(cells) = cells {
+                    if cells.iter().all(|&cell| cell == cells[0]) {
+                        owners.push(cells[0]);
+                    }
+                }
+            }
+        }
+    }
+    owners.dedup();
+    assert!(
+        owners.len() <= 1,
+        "both players have a line:\n{}",
+        game.board
+    );
+    owners.first().copied()
+}
+
+#[test]
+fn undoing_a_move_restores_the_game() {
+    for seed in 0..GAMES {
+        let mut rng = Rng::new(seed);
+        let mut game = new_game(&mut rng);
+        play_out(&mut game, &mut rng, |game| {
+            for mv in legal_moves(game) {
+                let mut tried = game.clone();
+                tried.play(mv).expect("the move is legal");
+                assert!(
+                    tried.undo(),
+                    "seed {}: {:?} couldn't be taken back",
+                    seed,
+                    mv
+                );
+                assert_eq!(&tried, game, "seed {}: {:?} taken back", seed, mv);
+                assert_eq!(
+                    tried.current_move, game.current_move,
+                    "seed {}: {:?}",
+                    seed, mv
+                );
+                assert_eq!(tried.board, game.board, "seed {}: {:?}", seed, mv);
+            }
+        });
+    }
+}
+
+#[test]
+fn the_legal_columns_are_the_drops_play_takes() {
+    for seed in 0..GAMES {
+        let mut rng = Rng::new(seed);
+        let mut game = new_game(&mut rng);
+        play_out(&mut game, &mut rng, |game| {
+            // A column past the edge is tried too, which neither may take
+            let taken: Vec<usize> = (0..=game.rules.width)
+                .filter(|&column| game.clone().play(Move::Drop(column)).is_ok())
+                .collect();
+            let legal: Vec<usize> = game.legal_moves().collect();
+            assert_eq!(legal, taken, "seed {}:\n{}", seed, game.board);
+            for column in 0..=game.rules.width {
+                assert_eq!(
+                    game.can_play(column),
+                    taken.contains(&column),
+                    "seed {}: column {}",
+                    seed,
+                    column + 1
+                );
+            }
+        });
+    }
+}
+
+#[test]
+fn games_end_in_time_and_always_validate() {
+    for seed in 0..GAMES {
+        let mut rng = Rng::new(seed);
+        let mut game = new_game(&mut rng);
+        play_out(&mut game, &mut rng, |game| {
+            assert_eq!(game.validate(), Ok(()), "seed {}", seed);
+        });
+        // Without pops or bombs taking pieces away, a game is over once the board is full,
+        // give or take a flip that turns it over instead of filling it
+        let cells = game.rules.width * game.rules.height;
+        let flips = game.specials.flips.len();
+        match game.rules.variant {
+            Variant::PopOut | Variant::PowerUp => {}
+            _ => assert!(
+                game.is_finished() && game.history.len() <= cells + flips,
+                "seed {}: {} moves on {} cells",
+                seed,
+                game.history.len(),
+                cells
+            ),
+        }
+    }
+}
+
+#[test]
+fn the_win_check_agrees_with_a_scan_of_the_board() {
+    for seed in 0..GAMES {
+        let mut rng = Rng::new(seed);
+        let mut game = new_game(&mut rng);
+        play_out(&mut game, &mut rng, |game| {
+            // The game ends at the first line, won by its owner, or under misère by the
+            // other player
+            let expected = match game.rules.variant {
+                Variant::Standard | Variant::Cylinder => line_owner(game),
+                Variant::Misere => line_owner(game).map(Player::other),
+                _ => return,
+            };
+            assert_eq!(game.winner(), expected, "seed {}:\n{}", seed, game.board);
+            if let (Some(winner), Some(line)) = (game.winner(), game.winning_line()) {
+                let owner = match game.rules.variant {
+                    Variant::Misere => winner.other(),
+                    _ => winner,
+                };
+                assert!(
+                    line.iter()
+                        .all(|&(row, column)| game.board[row][column] == Some(owner)),
+                    "seed {}: {:?}\n{}",
+                    seed,
+                    line,
+                    game.board
+                );
+            }
+        });
+    }
+}
+
+#[test]
+fn saved_games_load_back_the_same() {
+    let path = std::env::temp_dir().join(format!("properties-{}.toml", std::process::id()));
+    let slot = path
+        .to_str()
+        .expect("the temporary directory has a UTF-8 path");
+    for seed in 0..GAMES {
+        let mut rng = Rng::new(seed);
+        // A game stopped partway is saved along with one played out from there
+        let mut game = mid_game(&mut rng);
+        let stopped = game.clone();
+        play_out(&mut game, &mut rng, |_| {});
+        for game in [&stopped, &game] {
+            save::write(game, &Settings::default(), &Session::default(), slot)
+                .expect("the save can be written");
+            let loaded = save::load(&path, &Settings::default())
+                .unwrap_or_else(|err| panic!("seed {}: {}", seed, err));
+            assert_eq!(&loaded, game, "seed {}", seed);
+            assert_eq!(loaded.current_move, game.current_move, "seed {}", seed);
+            assert_eq!(loaded.names, game.names, "seed {}", seed);
+        }
+    }
+    fs::remove_file(&path).expect("the save can be removed");
+}
+
+#[test]
+fn a_game_written_as_its_moves_reads_back_the_same() {
+    for seed in 0..GAMES {
+        let mut rng = Rng::new(seed);
+        let mut game = mid_game(&mut rng);
+        let stopped = game.clone();
+        play_out(&mut game, &mut rng, |_| {});
+        for game in [&stopped, &game] {
+            let text = game.to_moves_string();
+            let read = from_moves(game.rules, &text)
+                .unwrap_or_else(|err| panic!("seed {}: {}: {}", seed, text, err));
+            assert_eq!(&read, game, "seed {}: {}", seed, text);
+            assert_eq!(read.to_moves_string(), text, "seed {}", seed);
+        }
+    }
+}
@@ -0,0 +1,773 @@
+use unicode_width::UnicodeWidthStr;
+
+use crate::board::Cell;
+use crate::coord::Coord;
+use crate::i18n;
+use crate::style::{Style, BOLD_UNDERLINE, DIM, DIM_STRIKE, INVERSE};
+use crate::theme::{Piece, Theme};
+use crate::Player;
+
+// Geometry shared by every renderer so columns, ruler, and separators line up
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    pub cell_width: usize,
+    pub separator: &'static str,
+    pub borders: bool,
+    // Width of the row-label gutter on the left, 0 when rows aren't labeled
+    pub margin: usize,
+    // Lines per board row; large print draws each cell as a block this many lines tall and
+    // this many glyphs wide, inside thick borders
+    pub scale: usize,
+    // Whether the sides of the grid are drawn open, for a board that wraps around
+    pub open_sides: bool,
+}
+
+impl Layout {
+    // Width of the cells and separators, without borders
+    fn inner_width(&self, columns: usize) -> usize {
+        columns * self.cell_width + columns.saturating_sub(1) * self.separator.len()
+    }
+
+    // Width of a full board row including borders and the row-label gutter
+    pub fn width(&self, columns: usize) -> usize {
+        self.margin + self.inner_width(columns) + if self.borders { 4 } else { 0 }
+    }
+
+    // Pad a glyph to the cell width; glyphs at least as wide as the cell are kept as is
+    pub fn pad(&self, glyph: &str) -> String {
+        let padding = self.cell_width.saturating_sub(glyph.width());
+        format!("{}{}", glyph, " ".repeat(padding))
+    }
+
+    // Join cells into a row, padding each to the cell width. A cell with a frame has its
+    // opening and closing characters drawn over the separators on either side, so framing
+    // never shifts the grid; at an edge without a border that side of the frame is dropped,
+    // and compact layouts have no room for frames at all.
+    pub fn row(&self, cells: &[&str], label: &str, frames: &[Option<(char, char)>]) -> String {
+        let frame = |column: usize| {
+            frames
+                .get(column)
+                .copied()
+                .flatten()
+                .filter(|_| !self.separator.is_empty())
+        };
+        let edge = if self.borders { " " } else { "" };
+
+        let mut row = self.gutter(label);
+        if self.borders {
+            row.push(self.border());
+        }
+        for (column, cell) in cells.iter().enumerate() {
+            let closing = column
+                .checked_sub(1)
+                .and_then(frame)
+                .map(|(_, close)| close);
+            let opening = frame(column).map(|(open, _)| open);
+            match closing.or(opening) {
+                Some(mark) if column > 0 || self.borders => row.push(mark),
+                _ if column == 0 => row.push_str(edge),
+                _ => row.push_str(self.separator),
+            }
+            row.push_str(&self.pad(cell));
+        }
+        match cells.len().checked_sub(1).and_then(frame) {
+            Some((_, close)) if self.borders => row.push(close),
+            _ => row.push_str(edge),
+        }
+        if self.borders {
+            row.push(self.border());
+        }
+        row
+    }
+
+    // Character for the sides of the grid
+    fn border(&self) -> char {
+        if self.open_sides {
+            ':'
+        } else if self.scale > 1 {
+            '#'
+        } else {
+            '|'
+        }
+    }
+
+    // Empty line between rows of large cells so neighboring blocks don't run together
+    pub fn spacer(&self, columns: usize) -> Option<String> {
+        (self.scale > 1).then(|| self.row(&vec![""; columns], "", &[]))
+    }
+
+    // Row label right-aligned in the gutter, followed by a space
+    fn gutter(&self, label: &str) -> String {
+        if self.margin == 0 {
+            String::new()
+        } else {
+            format!("{:>width$} ", label, width = self.margin - 1)
+        }
+    }
+
+    // Top and bottom edge of the grid, if the layout draws borders
+    pub fn edge(&self, columns: usize) -> Option<String> {
+        let (corner, line) = if self.scale > 1 {
+            ("#", "#")
+        } else {
+            ("+", "-")
+        };
+        self.borders.then(|| {
+            format!(
+                "{}{}{}{}",
+                self.gutter(""),
+                corner,
+                line.repeat(self.inner_width(columns) + 2),
+                corner
+            )
+        })
+    }
+
+    // Column where a cell starts, relative to the left edge of the row
+    pub fn cell_start(&self, column: usize) -> usize {
+        let border = if self.borders { 2 } else { 0 };
+        self.margin + border + column * (self.cell_width + self.separator.len())
+    }
+
+    // Board column under a screen column, relative to the left edge of the row. The
+    // separator to the right of a cell counts as part of it; the gutter, the borders, and
+    // anything past the last cell are outside the board.
+    pub fn column_at(&self, x: usize, columns: usize) -> Option<usize> {
+        let first = self.cell_start(0);
+        let last_end = self.cell_start(columns.checked_sub(1)?) + self.cell_width;
+        if x < first || x >= last_end {
+            return None;
+        }
+        Some((x - first) / (self.cell_width + self.separator.len()))
+    }
+
+    // Column numbers positioned with the same metrics as the cells: labels that fit are
+    // centered over their cell, wider labels end where the cell ends and spill into the
+    // separator on their left. Each label is passed through `paint` for styling.
+    pub fn ruler(&self, labels: &[String], paint: impl Fn(usize, &str) -> String) -> String {
+        let mut ruler = String::new();
+        // Printed width so far, excluding any escape codes added by `paint`
+        let mut used = 0;
+        for (column, label) in labels.iter().enumerate() {
+            let start = self.cell_start(column);
+            let position = if label.len() <= self.cell_width {
+                start + (self.cell_width - label.len()) / 2
+            } else {
+                (start + self.cell_width).saturating_sub(label.len())
+            };
+
+            // Never overlap the previous label, even on layouts too tight to fit them all,
+            // and keep labels apart when the cells are
+            let position = position.max(used + if used > 0 { self.separator.len() } else { 0 });
+            ruler.push_str(&" ".repeat(position - used));
+            ruler.push_str(&paint(column, label));
+            used = position + label.len();
+        }
+        ruler
+    }
+
+    // Dashed separator line spanning the board
+    pub fn rule(&self, columns: usize) -> String {
+        "-".repeat(self.width(columns))
+    }
+
+    // The same layout with cells touching instead of spaced apart
+    pub fn compact(self) -> Layout {
+        Layout {
+            separator: "",
+            ..self
+        }
+    }
+}
+
+// How tightly the cells are packed
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Density {
+    Auto,
+    Wide,
+    Compact,
+}
+
+// Layout for a board of `columns` on a terminal `terminal` columns wide, or of unknown width:
+// the spaced-out `wide` layout when it fits, the compact one when only that fits. A forced
+// density is used whether it fits or not. When nothing fits, the error is the terminal width
+// the compact layout needs.
+pub fn choose_layout(
+    wide: Layout,
+    density: Density,
+    columns: usize,
+    terminal: Option<usize>,
+) -> Result<Layout, usize> {
+    let compact = wide.compact();
+    match (density, terminal) {
+        // Large cells are never packed, and refuse rather than wrap
+        (_, Some(terminal)) if wide.scale > 1 && wide.width(columns) > terminal => {
+            Err(wide.width(columns))
+        }
+        (_, _) if wide.scale > 1 => Ok(wide),
+        (Density::Wide, _) | (Density::Auto, None) => Ok(wide),
+        (Density::Compact, _) => Ok(compact),
+        (Density::Auto, Some(terminal)) if wide.width(columns) <= terminal => Ok(wide),
+        (Density::Auto, Some(terminal)) if compact.width(columns) <= terminal => Ok(compact),
+        (Density::Auto, Some(_)) => Err(compact.width(columns)),
+    }
+}
+
+// Where the column-number ruler is drawn
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Ruler {
+    Top,
+    Bottom,
+    Both,
+    Off,
+}
+
+impl Ruler {
+    pub fn above(self) -> bool {
+        matches!(self, Ruler::Top | Ruler::Both)
+    }
+
+    pub fn below(self) -> bool {
+        matches!(self, Ruler::Bottom | Ruler::Both)
+    }
+}
+
+// The row of pieces each theme is shown with in the list of themes
+const PREVIEW: [Cell; 7] = [
+    Some(Player::One),
+    Some(Player::Two),
+    None,
+    Some(Player::One),
+    Some(Player::Two),
+    None,
+    None,
+];
+
+// How a cell stands out from the rest of the board
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Emphasis {
+    None,
+    LastMove,
+    Winning,
+    // Faint preview of the given player's piece in the empty cell it would land in
+    Ghost(Player),
+}
+
+// Blind play: the board is shown until the first move and again once the game is over, and
+// in between only while a player peeks at it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Blind {
+    // Peeks a game allows, and how many the game on the board has used
+    pub peeks: usize,
+    pub used: usize,
+    // Whether a peek is showing the board right now
+    pub peeking: bool,
+}
+
+impl Blind {
+    pub fn new(peeks: usize) -> Blind {
+        Blind {
+            peeks,
+            used: 0,
+            peeking: false,
+        }
+    }
+
+    pub fn left(&self) -> usize {
+        self.peeks.saturating_sub(self.used)
+    }
+
+    // Show the board until `end_peek`, if the game has a peek left
+    pub fn peek(&mut self) -> Result<(), String> {
+        if self.left() == 0 {
+            return Err(i18n::fill("blind.none_left", &[("count", &self.peeks)]));
+        }
+        self.used += 1;
+        self.peeking = true;
+        Ok(())
+    }
+
+    pub fn end_peek(&mut self) {
+        self.peeking = false;
+    }
+}
+
+// Draws boards, banners, and errors with the active theme
+#[derive(Clone, Debug, PartialEq)]
+pub struct Renderer {
+    pub theme: Theme,
+    pub layout: Layout,
+    pub style: Style,
+    pub ruler: Ruler,
+    // Label columns with letters and rows with numbers from the bottom
+    pub coords: bool,
+    // Point at the column of the last move under the board
+    pub marker: bool,
+    pub density: Density,
+    // Keeps the board out of sight for blind play
+    pub blind: Option<Blind>,
+    // Describe the board in words instead of drawing it
+    pub accessible: bool,
+}
+
+impl Renderer {
+    // Lay out a theme; the cell width is measured from its glyphs unless the terminal is
+    // known to disagree and an explicit width is given
+    pub fn new(theme: Theme, style: Style, cell_width: Option<usize>) -> Renderer {
+        let measured = theme
+            .players()
+            .iter()
+            .chain([&&theme.empty])
+            .map(|piece| piece.glyph.width())
+            .max()
+            .unwrap_or(1);
+
+        Renderer {
+            layout: Layout {
+                cell_width: cell_width.unwrap_or(measured),
+                separator: " ",
+                borders: theme.borders,
+                margin: 0,
+                scale: 1,
+                open_sides: false,
+            },
+            theme,
+            style,
+            ruler: Ruler::Bottom,
+            coords: false,
+            marker: false,
+            density: Density::Auto,
+            blind: None,
+            accessible: false,
+        }
+    }
+
+    // Give a new game its own peeks
+    pub fn new_game(&mut self) {
+        if let Some(blind) = &mut self.blind {
+            *blind = Blind::new(blind.peeks);
+        }
+    }
+
+    // Draw every cell as a block `scale` glyphs wide and lines tall
+    pub fn enlarge(&mut self, scale: usize) {
+        self.layout.cell_width *= scale;
+        self.layout.scale = scale;
+    }
+
+    // Widen the cells when the board has more columns than one-digit labels can number, so
+    // every label still fits over its own cell, and make room for row labels
+    pub fn fit_board(&mut self, columns: usize, rows: usize) {
+        if !self.coords {
+            let widest_label = columns.to_string().len();
+            self.layout.cell_width = self.layout.cell_width.max(widest_label);
+        } else {
+            self.layout.margin = rows.to_string().len() + 1;
+        }
+    }
+
+    // Column-number ruler in a dim style, with full columns struck through (or dashed out
+    // when there are no colors) so it's obvious they can't be played
+    pub fn ruler_row(&self, full_columns: &[bool]) -> String {
+        let labels: Vec<String> = (0..full_columns.len())
+            .map(|column| {
+                if self.coords {
+                    Coord::column_letter(column).to_string()
+                } else {
+                    (column + 1).to_string()
+                }
+            })
+            .collect();
+        self.layout.ruler(&labels, |column, label| {
+            match (full_columns[column], self.style.color) {
+                (true, true) => self.style.paint(DIM_STRIKE, label),
+                (true, false) => "-".repeat(label.len()),
+                (false, _) => self.style.paint(DIM, label),
+            }
+        })
+    }
+
+    pub fn piece(&self, cell: Cell) -> &Piece {
+        match cell {
+            Some(Player::One) => &self.theme.player_one,
+            Some(Player::Two) => &self.theme.player_two,
+            Some(Player::Three) => &self.theme.player_three,
+            Some(Player::Four) => &self.theme.player_four,
+            None => &self.theme.empty,
+        }
+    }
+
+    // Glyph for a board cell, without color
+    pub fn glyph(&self, cell: Cell) -> &str {
+        &self.piece(cell).glyph
+    }
+
+    // Glyph for a board cell in its piece color
+    fn painted(&self, cell: Cell, glyph: &str) -> String {
+        match self.piece(cell).color {
+            Some(color) => self.style.paint_color(color, glyph),
+            None => glyph.to_string(),
+        }
+    }
+
+    // A row of cells, padded to the layout and colored per piece; `number` is the row's
+    // label counted from the bottom, shown when coordinates are on. Emphasized cells are
+    // drawn in an extra style, or framed with plain characters when there are no colors;
+    // cells missing from `emphasis` are drawn normally. Large cells span several lines,
+    // with the label on the middle one.
+    pub fn row(&self, cells: &[Cell], number: usize, emphasis: &[Emphasis]) -> String {
+        let emphasis = |column: usize| emphasis.get(column).copied().unwrap_or(Emphasis::None);
+        let frames: Vec<Option<(char, char)>> = (0..cells.len())
+            .map(|column| match emphasis(column) {
+                _ if self.style.color => None,
+                Emphasis::None => None,
+                Emphasis::LastMove => Some(('[', ']')),
+                Emphasis::Winning => Some(('*', '*')),
+                Emphasis::Ghost(_) => Some(('(', ')')),
+            })
+            .collect();
+
+        let scale = self.layout.scale;
+        (0..scale)
+            .map(|line| {
+                let padded: Vec<String> = cells
+                    .iter()
+                    .enumerate()
+                    .map(|(column, &cell)| {
+                        let painted = self.painted(cell, &self.layout.pad(&self.tile(cell, line)));
+                        match emphasis(column) {
+                            Emphasis::None => painted,
+                            Emphasis::LastMove => self.style.paint(BOLD_UNDERLINE, &painted),
+                            Emphasis::Winning => self.style.paint(INVERSE, &painted),
+                            Emphasis::Ghost(player) if self.style.color => self.ghost(player, line),
+                            Emphasis::Ghost(_) => painted,
+                        }
+                    })
+                    .collect();
+                let padded: Vec<&str> = padded.iter().map(String::as_str).collect();
+                let label = if self.coords && line == scale / 2 {
+                    number.to_string()
+                } else {
+                    String::new()
+                };
+                self.layout.row(&padded, &label, &frames)
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // A row of a board kept hidden for blind play, with a dim question mark in every cell
+    pub fn hidden_row(&self, columns: usize, number: usize) -> String {
+        let scale = self.layout.scale;
+        let mark = self.style.paint(DIM, &self.layout.pad("?"));
+        (0..scale)
+            .map(|line| {
+                let (cell, label) = if line == scale / 2 {
+                    let label = if self.coords {
+                        number.to_string()
+                    } else {
+                        String::new()
+                    };
+                    (mark.as_str(), label)
+                } else {
+                    ("", String::new())
+                };
+                self.layout.row(&vec![cell; columns], &label, &[])
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // One line of a cell's block: a piece fills its block and an empty cell shows its glyph
+    // once in the middle. At normal size this is just the glyph.
+    fn tile(&self, cell: Cell, line: usize) -> String {
+        let scale = self.layout.scale;
+        let glyph = self.glyph(cell);
+        if cell.is_some() {
+            glyph.repeat(scale)
+        } else if line == scale / 2 {
+            format!("{}{}", " ".repeat(glyph.width() * (scale / 2)), glyph)
+        } else {
+            String::new()
+        }
+    }
+
+    // A line of a player's block in a faint shade of its color
+    fn ghost(&self, player: Player, line: usize) -> String {
+        let piece = Some(player);
+        let painted = self.painted(piece, &self.layout.pad(&self.tile(piece, line)));
+        self.style.paint(DIM, &painted)
+    }
+
+    // The player's piece hovering over the selected column, above the grid
+    pub fn preview_row(&self, column: usize, player: Player) -> String {
+        let piece = if self.style.color {
+            self.ghost(player, 0)
+        } else {
+            self.tile(Some(player), 0)
+        };
+        format!("{}{}", " ".repeat(self.layout.cell_start(column)), piece)
+    }
+
+    // A caret under the column of the last move, lined up with the ruler
+    pub fn marker_row(&self, column: usize) -> String {
+        let labels: Vec<String> = (0..=column)
+            .map(|index| if index == column { "^" } else { "" }.to_string())
+            .collect();
+        self.layout.ruler(&labels, |_, label| match label {
+            "" => String::new(),
+            _ => self.accent(label),
+        })
+    }
+
+    // Text in the theme's accent color
+    pub fn accent(&self, text: &str) -> String {
+        self.style.paint_color(self.theme.accent, text)
+    }
+
+    // Win announcement in the winner's own color, or the accent color for uncolored pieces
+    pub fn winner_banner(&self, name: &str, player: Player) -> String {
+        let cell = Some(player);
+        let text = if self.theme.needs_unicode {
+            format!(" {} {}", self.glyph(cell), i18n::fill("banner.won", &[("name", &name)]))
+        } else {
+            let name = format!("{} ({})", name, self.glyph(cell));
+            format!("*** {} ***", i18n::fill("banner.won", &[("name", &name)]))
+        };
+        match self.piece(cell).color {
+            Some(color) => self.style.paint_color(color, &text),
+            None => self.accent(&text),
+        }
+    }
+
+    // Misère result, in the color of the player who lost by connecting `connect`
+    pub fn loser_banner(&self, name: &str, player: Player, connect: usize) -> String {
+        let cell = Some(player);
+        let connect = count(connect);
+        let text = if self.theme.needs_unicode {
+            let lost = i18n::fill("banner.lost", &[("name", &name), ("count", &connect)]);
+            format!(" {} {}", self.glyph(cell), lost)
+        } else {
+            let name = format!("{} ({})", name, self.glyph(cell));
+            let lost = i18n::fill("banner.lost", &[("name", &name), ("count", &connect)]);
+            format!("*** {} ***", lost)
+        };
+        match self.piece(cell).color {
+            Some(color) => self.style.paint_color(color, &text),
+            None => self.accent(&text),
+        }
+    }
+
+    // Draw announcement in the accent color
+    pub fn draw_banner(&self) -> String {
+        let draw = i18n::text("banner.draw");
+        if self.theme.needs_unicode {
+            self.accent(&format!(" {}", draw))
+        } else {
+            self.accent(&format!("*** {} ***", draw))
+        }
+    }
+
+    // Error message text in the theme's error color, marked with plain characters when the
+    // theme is plain
+    pub fn error(&self, style: Style, message: &str) -> String {
+        let error = i18n::fill("error", &[("message", &message)]);
+        let text = if self.theme.needs_unicode {
+            error
+        } else {
+            format!("!! {}", error)
+        };
+        style.paint_color(self.theme.error, &text)
+    }
+
+    // One line per built-in theme with a one-row preview, for the :themes command
+    pub fn theme_list(&self, cell_width: Option<usize>) -> String {
+        Theme::all()
+            .into_iter()
+            .map(|theme| {
+                let preview = Renderer::new(theme.clone(), self.style, cell_width);
+                format!(
+                    "{:<14} {}   {}",
+                    theme.name,
+                    preview.row(&PREVIEW, 1, &[]),
+                    theme.description
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+// A line length in words, as in "connecting four"
+pub fn count(connect: usize) -> String {
+    match connect {
+        3 => "three".to_string(),
+        4 => "four".to_string(),
+        5 => "five".to_string(),
+        6 => "six".to_string(),
+        7 => "seven".to_string(),
+        8 => "eight".to_string(),
+        9 => "nine".to_string(),
+        10 => "ten".to_string(),
+        _ => connect.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An uncolored renderer for a built-in theme, fitted to a board `columns` wide
+    fn plain(theme: &str, cell_width: Option<usize>, columns: usize) -> Renderer {
+        let style = Style {
+            color: false,
+            truecolor: false,
+        };
+        let theme = Theme::builtin(theme).expect("the theme is built in");
+        let mut renderer = Renderer::new(theme, style, cell_width);
+        renderer.fit_board(columns, 6);
+        renderer
+    }
+
+    #[test]
+    fn ruler_snapshots() {
+        let cases = [
+            ("emoji", Some(1), 7, "1 2 3 4 5 6 7"),
+            ("emoji", Some(2), 7, "1  2  3  4  5  6  7"),
+            ("emoji", Some(1), 10, "1  2  3  4  5  6  7  8  9  10"),
+            ("emoji", Some(2), 10, "1  2  3  4  5  6  7  8  9  10"),
+            ("ascii", None, 7, "  1 2 3 4 5 6 7"),
+            ("ascii", None, 10, "  1  2  3  4  5  6  7  8  9  10"),
+            ("emoji", Some(1), 12, "1  2  3  4  5  6  7  8  9  10 11 12"),
+            ("emoji", Some(2), 12, "1  2  3  4  5  6  7  8  9  10 11 12"),
+            ("ascii", None, 12, "  1  2  3  4  5  6  7  8  9  10 11 12"),
+        ];
+        for (theme, cell_width, columns, expected) in cases {
+            let renderer = plain(theme, cell_width, columns);
+            assert_eq!(
+                renderer.ruler_row(&vec![false; columns]),
+                expected,
+                "{} at width {:?}, {} columns",
+                theme,
+                cell_width,
+                columns
+            );
+        }
+    }
+
+    #[test]
+    fn every_label_ends_inside_its_cell() {
+        for cell_width in [Some(1), Some(2)] {
+            for columns in 4..=10 {
+                let renderer = plain("ascii", cell_width, columns);
+                let layout = renderer.layout;
+                let ruler = renderer.ruler_row(&vec![false; columns]);
+                let mut labels = Vec::new();
+                let mut start = None;
+                for (at, c) in ruler.char_indices().chain([(ruler.len(), ' ')]) {
+                    match (c.is_whitespace(), start) {
+                        (false, None) => start = Some(at),
+                        (true, Some(from)) => {
+                            labels.push((from, &ruler[from..at]));
+                            start = None;
+                        }
+                        _ => {}
+                    }
+                }
+                assert_eq!(labels.len(), columns);
+                for (column, (at, label)) in labels.into_iter().enumerate() {
+                    assert_eq!(label, (column + 1).to_string());
+                    let cell = layout.cell_start(column);
+                    let end = at + label.len();
+                    assert!(
+                        end > cell && end <= cell + layout.cell_width,
+                        "label {} of {} at {:?} ends at {} outside its cell at {}",
+                        label,
+                        columns,
+                        cell_width,
+                        end,
+                        cell
+                    );
+                }
+                assert!(ruler.len() <= layout.width(columns));
+            }
+        }
+    }
+
+    #[test]
+    fn full_columns_are_dashed_without_color() {
+        let renderer = plain("ascii", None, 7);
+        let full = [false, true, false, false, false, false, true];
+        assert_eq!(renderer.ruler_row(&full), "  1 - 3 4 5 6 -");
+    }
+
+    #[test]
+    fn two_digit_boards_widen_the_cells() {
+        assert_eq!(plain("ascii", Some(1), 9).layout.cell_width, 1);
+        assert_eq!(plain("ascii", Some(1), 10).layout.cell_width, 2);
+        assert_eq!(plain("emoji", None, 7).layout.cell_width, 2);
+    }
+
+    #[test]
+    fn layouts_are_chosen_by_what_fits() {
+        let wide = Layout {
+            cell_width: 2,
+            separator: " ",
+            borders: false,
+            margin: 0,
+            scale: 1,
+            open_sides: false,
+        };
+        let compact = wide.compact();
+        // Seven columns take 20 terminal columns spaced out and 14 packed
+        assert_eq!((wide.width(7), compact.width(7)), (20, 14));
+        let cases = [
+            (Density::Auto, None, Ok(wide)),
+            (Density::Auto, Some(20), Ok(wide)),
+            (Density::Auto, Some(19), Ok(compact)),
+            (Density::Auto, Some(14), Ok(compact)),
+            (Density::Auto, Some(13), Err(14)),
+            (Density::Wide, Some(10), Ok(wide)),
+            (Density::Compact, Some(100), Ok(compact)),
+            (Density::Compact, None, Ok(compact)),
+        ];
+        for (density, terminal, expected) in cases {
+            assert_eq!(
+                choose_layout(wide, density, 7, terminal),
+                expected,
+                "{:?} on {:?}",
+                density,
+                terminal
+            );
+        }
+
+        // Large print is never packed, and needs its whole width
+        let large = Layout {
+            cell_width: 6,
+            borders: true,
+            scale: 3,
+            ..wide
+        };
+        assert_eq!(large.width(7), 52);
+        for density in [Density::Auto, Density::Wide, Density::Compact] {
+            assert_eq!(choose_layout(large, density, 7, None), Ok(large));
+            assert_eq!(choose_layout(large, density, 7, Some(52)), Ok(large));
+            assert_eq!(choose_layout(large, density, 7, Some(51)), Err(52));
+        }
+    }
+
+    #[test]
+    fn theme_list_previews_each_theme_on_one_row() {
+        let list = plain("ascii", None, 7).theme_list(None);
+        let expected = [
+            "classic        ● ● · ● ● · ·   Red and yellow discs like the original toy",
+            "emoji          🔴 🔵 ⚫ 🔴 🔵 ⚫ ⚫   Red and blue emoji discs",
+            "ascii          | X O . X O . . |   Plain characters with borders, safe everywhere",
+            "minimal        ● ○ · ● ○ · ·   Filled and hollow dots, no borders",
+            "high-contrast  | X O . X O . . |   Bold distinct shapes with bright borders",
+            "colorblind     ● ▲ · ● ▲ · ·   Distinct shapes in a colorblind-safe orange and sky blue",
+        ];
+        assert_eq!(list.lines().collect::<Vec<_>>(), expected);
+        assert_eq!(list.lines().count(), crate::theme::THEME_NAMES.len());
+    }
+}
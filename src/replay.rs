@@ -0,0 +1,908 @@
+use std::io::{self, IsTerminal, Write};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use crate::ai::{self, Difficulty};
+use crate::comment;
+use crate::coord::Coord;
+use crate::cube;
+use crate::i18n;
+use crate::keys;
+use crate::render::Renderer;
+use crate::save;
+use crate::session::Session;
+use crate::settings::Settings;
+use crate::terminal::{Capabilities, Screen};
+use crate::variant::{Move, Specials, Variant};
+use crate::variation::{self, Variation};
+use crate::{Found, Game, Player};
+
+// A line tried off another one, with what to go back to
+#[derive(Clone, Debug)]
+struct Branch {
+    // Moves of the line it branches from played before it does
+    at: usize,
+    // The line it branches from
+    line: Vec<usize>,
+    // Where that line is kept among the game's variations, as for `Replay::node`
+    node: Option<Vec<usize>>,
+}
+
+// A recorded game being stepped through, with the position shown and the game as it ended.
+// Moves tried along the way branch off the line shown into new ones, which can be kept with
+// the game as variations.
+pub struct Replay {
+    // The game as it was after `ply` moves
+    game: Game,
+    ply: usize,
+    // Every move of the line shown, 0-based columns in order: the game's own moves until a
+    // branch is tried
+    moves: Vec<usize>,
+    // The game as it ended, for a resignation or agreed draw after the last move, and with
+    // the variations kept so far
+    last: Game,
+    // The lines branched off to get to the one shown, first from the main line
+    branches: Vec<Branch>,
+    // Where the line shown is among the kept variations: indices from the top down, empty for
+    // the main line, and None for a branch that hasn't been kept
+    node: Option<Vec<usize>>,
+    // The computer answers every move tried in a branch
+    pub answer: bool,
+    // The save the game came from, and where kept variations go
+    save: Option<PathBuf>,
+}
+
+impl Replay {
+    // Start at the empty board of a recorded game. Fails for a game with no moves to step
+    // through.
+    pub fn new(found: Found) -> Result<Replay, String> {
+        let game = found.game;
+        if game.history.is_empty() {
+            return Err("the game has no recorded moves to replay".to_string());
+        }
+        let moves = game.history.iter().map(|coord| coord.column).collect();
+        let mut replay = Replay {
+            game: game.clone(),
+            ply: 0,
+            moves,
+            last: game,
+            branches: Vec::new(),
+            node: Some(Vec::new()),
+            answer: false,
+            save: found.save,
+        };
+        replay.seek(0)?;
+        Ok(replay)
+    }
+
+    // Moves in the line shown
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    // Numbers of the moves in the line shown that weren't drops: the game's own, as far as
+    // the line follows it. Moves tried in a branch are all drops.
+    fn specials(&self) -> Specials {
+        let end = self
+            .branches
+            .first()
+            .map_or(self.moves.len(), |branch| branch.at);
+        let mut specials = self.last.specials.clone();
+        specials.truncate(end);
+        specials
+    }
+
+    // Show the position after `ply` moves, kept within the line
+    pub fn seek(&mut self, ply: usize) -> Result<(), String> {
+        self.ply = ply.min(self.moves.len());
+        let specials = self.specials();
+        self.game
+            .seek(&self.moves, &specials, self.ply)
+            .map_err(|err| format!("move {} can't be replayed: {}", self.ply, err))?;
+        // Going back to the start takes a swap back; the players are in the seats it put
+        // them in from the first move on
+        if self.last.swapped && self.ply > 0 && !self.game.swapped {
+            self.game.trade_seats(Instant::now());
+            self.game.swapped = true;
+        }
+        // The game may have ended after its last move without a connection
+        if self.branches.is_empty() && self.ply == self.moves.len() && !self.game.is_finished() {
+            if let Some(outcome) = self
+                .last
+                .outcome
+                .as_ref()
+                .filter(|outcome| outcome.off_the_board())
+            {
+                self.game.outcome = Some(outcome.clone());
+            }
+        }
+        Ok(())
+    }
+
+    // Play `column` in the position shown. A move the line doesn't have starts a branch there,
+    // except at the end of a branch not kept yet, which it just goes on with. With `answer`
+    // on, the computer replies.
+    pub fn play(&mut self, column: usize) -> Result<(), String> {
+        let mut position = self.game.clone();
+        position
+            .column(column)
+            .and_then(|column| position.play_move(column))
+            .map_err(|err| format!("column {} can't be played here: {}", column + 1, err))?;
+        let drop = self.specials().at(self.ply + 1, column) == Move::Drop(column);
+        if self.moves.get(self.ply) == Some(&column) && drop {
+            return self.seek(self.ply + 1);
+        }
+        if self.ply < self.moves.len() || self.node.is_some() {
+            self.branches.push(Branch {
+                at: self.ply,
+                line: self.moves.clone(),
+                node: self.node.take(),
+            });
+        }
+        self.moves.truncate(self.ply);
+        self.moves.push(column);
+        self.seek(self.ply + 1)?;
+        // A branch can only keep drops, so a pop the computer would answer with goes unplayed
+        if self.answer && !self.game.is_finished() {
+            if let Some(Move::Drop(reply)) = ai::choose_move(&self.game, Difficulty::Hard) {
+                self.moves.push(reply);
+                self.seek(self.ply + 1)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Go back to the line the one shown branched off, where it did. Returns false on the main
+    // line.
+    pub fn back(&mut self) -> Result<bool, String> {
+        let Some(branch) = self.branches.pop() else {
+            return Ok(false);
+        };
+        self.moves = branch.line;
+        self.node = branch.node;
+        self.seek(branch.at)?;
+        Ok(true)
+    }
+
+    // The kept variations branching off the line shown at this move
+    pub fn variations_here(&self) -> Vec<(usize, &Variation)> {
+        let Some(node) = &self.node else {
+            return Vec::new();
+        };
+        let children = match variation::get(&self.last.variations, node) {
+            Some(parent) => &parent.variations,
+            None => &self.last.variations,
+        };
+        children
+            .iter()
+            .enumerate()
+            .filter(|(_, variation)| variation.ply == self.ply)
+            .collect()
+    }
+
+    // Follow the `number`th of `variations_here`, from 1, to its first move
+    pub fn enter(&mut self, number: usize) -> Result<(), String> {
+        let (index, line) = match number
+            .checked_sub(1)
+            .and_then(|index| self.variations_here().get(index).copied())
+        {
+            Some((index, variation)) => (index, variation.line(&self.moves)),
+            None => return Err(format!("no variation {} branches off here", number)),
+        };
+        let node = self.node.take().unwrap_or_default();
+        self.branches.push(Branch {
+            at: self.ply,
+            line: std::mem::replace(&mut self.moves, line),
+            node: Some(node.clone()),
+        });
+        self.node = Some(node.into_iter().chain([index]).collect());
+        self.seek(self.ply + 1)
+    }
+
+    // Keep the line shown with the game as a variation called `name`, under the kept line it
+    // shares most moves with, and write it to the game's save. A game from the log is saved
+    // under its id first. Returns what was done.
+    pub fn keep(&mut self, name: Option<&str>, settings: &Settings) -> Result<String, String> {
+        if self.node.is_some() {
+            return Err(if self.branches.is_empty() {
+                "this is the game itself; try a move to branch off first".to_string()
+            } else {
+                "this line is already kept".to_string()
+            });
+        }
+        let shared = |line: &[usize]| {
+            line.iter()
+                .zip(&self.moves)
+                .take_while(|(a, b)| a == b)
+                .count()
+        };
+        let Some((parent, ply)) = self
+            .branches
+            .iter()
+            .filter_map(|branch| Some((branch.node.clone()?, shared(&branch.line))))
+            .max_by_key(|(node, ply)| (*ply, node.len()))
+        else {
+            return Err("this line is already kept".to_string());
+        };
+        if ply == self.moves.len() {
+            return Err("this line is part of one already kept".to_string());
+        }
+
+        let mut game = self.last.clone();
+        let siblings = variation::children_mut(&mut game.variations, &parent)
+            .ok_or("the line this branches off is no longer kept")?;
+        let name = name.map_or_else(
+            || format!("variation {}", siblings.len() + 1),
+            str::to_string,
+        );
+        siblings.push(Variation {
+            name: name.clone(),
+            ply,
+            moves: self.moves[ply..].to_vec(),
+            variations: Vec::new(),
+        });
+        let index = siblings.len() - 1;
+
+        let path = self.persist(game, settings)?;
+        self.node = Some(parent.into_iter().chain([index]).collect());
+        Ok(format!("Kept '{}' in {}", name, path.display()))
+    }
+
+    // Comment on the move shown, or with no text remove its comment, and write it to the
+    // game's save as `keep` does. Only the game's own moves take comments.
+    pub fn comment(&mut self, text: &str, settings: &Settings) -> Result<String, String> {
+        if !self.branches.is_empty() {
+            return Err("comments go on the game's own moves; b goes back to them".to_string());
+        }
+        let mut game = self.last.clone();
+        let done = comment::set(&mut game.comments, self.ply, text)?;
+        let path = self.persist(game, settings)?;
+        Ok(format!("{} in {}", done, path.display()))
+    }
+
+    // The comment on the move shown, if it's one of the game's own
+    pub fn comment_here(&self) -> Option<&str> {
+        if !self.branches.is_empty() {
+            return None;
+        }
+        self.last.comments.get(&self.ply).map(String::as_str)
+    }
+
+    // Write the variations and comments of `game`, the recorded game with changes to them,
+    // to its save, or save it under its id when it came from the log. Only once that's done
+    // does it replace the game as it was.
+    fn persist(&mut self, game: Game, settings: &Settings) -> Result<PathBuf, String> {
+        let path = match &self.save {
+            Some(path) => {
+                save::write_annotations(path, &game)?;
+                path.clone()
+            }
+            None => save::write(&game, settings, &Session::default(), &game.id)?,
+        };
+        self.last = game;
+        self.save = Some(path.clone());
+        Ok(path)
+    }
+
+    // What the last move shown was, or that none has been played yet, naming columns the
+    // way `renderer` labels them
+    pub fn caption(&self, renderer: &Renderer) -> String {
+        let name = |column: usize| crate::column_name(column, renderer);
+        match self.ply.checked_sub(1) {
+            None => format!("Start of the game, {} moves to go", self.moves.len()),
+            Some(0) if self.game.swapped => format!(
+                "Move 1 of {}: {} played column {}, and {} swapped to take it over",
+                self.moves.len(),
+                self.game.name(Player::Two),
+                name(self.moves[0]),
+                self.game.name(Player::One)
+            ),
+            Some(index) => {
+                let seat = self.game.seat_of(index + 1);
+                let verb = match self.specials().at(self.ply, self.moves[index]) {
+                    Move::Flip => {
+                        return format!(
+                            "Move {} of {}: {} flipped the board",
+                            self.ply,
+                            self.moves.len(),
+                            self.game.name(seat)
+                        )
+                    }
+                    Move::Drop(_) if self.game.rules.variant == Variant::Cube => {
+                        return format!(
+                            "Move {} of {}: {} played peg {}",
+                            self.ply,
+                            self.moves.len(),
+                            self.game.name(seat),
+                            cube::peg_name(self.moves[index])
+                        )
+                    }
+                    Move::Drop(_) => "played",
+                    Move::Pop(_) => "popped",
+                    Move::Anvil(_) => "dropped an anvil into",
+                    Move::Bomb(_) => "bombed",
+                };
+                format!(
+                    "Move {} of {}: {} {} column {}",
+                    self.ply,
+                    self.moves.len(),
+                    self.game.name(seat),
+                    verb,
+                    name(self.moves[index])
+                )
+            }
+        }
+    }
+
+    // Where the line shown branched off the main line and how deep it is, off the main line,
+    // e.g. "Branch at move 12 of the main line > move 15, depth 2"
+    pub fn breadcrumb(&self) -> Option<String> {
+        let (first, rest) = self.branches.split_first()?;
+        let mut text = format!("Branch at move {} of the main line", first.at);
+        for branch in rest {
+            text.push_str(&format!(" > move {}", branch.at));
+        }
+        text.push_str(&format!(", depth {}", self.branches.len()));
+        if let Some(variation) = self
+            .node
+            .as_ref()
+            .and_then(|node| variation::get(&self.last.variations, node))
+        {
+            text.push_str(&format!(", kept as '{}'", variation.name));
+        }
+        Some(text)
+    }
+
+    // How the position stands for the player to move, by the hard level's search
+    pub fn eval(&self, renderer: &Renderer) -> String {
+        let best = |mv: Move| best(mv, renderer);
+        let Some((mv, score)) = ai::evaluation(&self.game) else {
+            return "Eval: the game is over".to_string();
+        };
+        let mover = self.game.name(self.game.current_player);
+        let other = self.game.name(self.game.current_player.other());
+        match score {
+            score if score >= ai::WIN => {
+                format!("Eval: {} can force a win (best: {})", mover, best(mv))
+            }
+            score if score <= -ai::WIN => format!("Eval: {} can force a win", other),
+            score => format!("Eval: {:+} for {} (best: {})", score, mover, best(mv)),
+        }
+    }
+}
+
+// A best move as the eval names it
+fn best(mv: Move, renderer: &Renderer) -> String {
+    let name = |column: usize| crate::column_name(column, renderer);
+    match mv {
+        Move::Drop(column) => format!("column {}", name(column)),
+        Move::Pop(column) => format!("popping column {}", name(column)),
+        Move::Anvil(column) => format!("an anvil in column {}", name(column)),
+        Move::Bomb(column) => format!("bombing column {}", name(column)),
+        Move::Flip => "flipping the board".to_string(),
+    }
+}
+
+// Seconds per move autoplay can be set to with + and -, fastest first
+pub const SPEEDS: [Duration; 3] = [
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+];
+
+// When autoplay shows its next move
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Autoplay {
+    pub interval: Duration,
+    // When the next move is due; None while paused
+    next: Option<Instant>,
+}
+
+impl Autoplay {
+    pub fn new(interval: Duration) -> Autoplay {
+        Autoplay {
+            interval,
+            next: None,
+        }
+    }
+
+    pub fn playing(&self) -> bool {
+        self.next.is_some()
+    }
+
+    // Start playing, with the next move one interval from `now`, or pause
+    pub fn toggle(&mut self, now: Instant) {
+        self.next = match self.next {
+            Some(_) => None,
+            None => Some(now + self.interval),
+        };
+    }
+
+    pub fn stop(&mut self) {
+        self.next = None;
+    }
+
+    // Step to the next speed in SPEEDS, faster or slower, staying at the ends. A speed from
+    // --speed between two of them steps to the nearer one in that direction.
+    pub fn change_speed(&mut self, faster: bool) {
+        let next = if faster {
+            SPEEDS.iter().rev().find(|&&speed| speed < self.interval)
+        } else {
+            SPEEDS.iter().find(|&&speed| speed > self.interval)
+        };
+        if let Some(&speed) = next {
+            self.interval = speed;
+        }
+    }
+
+    // How long to wait for a key before the next move is due: no time at all once it is, and
+    // without limit while paused
+    pub fn wait(&self, now: Instant) -> Option<Duration> {
+        self.next.map(|next| next.saturating_duration_since(now))
+    }
+
+    // Whether the next move is due at `now`, scheduling the one after it if so
+    pub fn due(&mut self, now: Instant) -> bool {
+        match self.next {
+            Some(next) if now >= next => {
+                self.next = Some(now + self.interval);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+// Step through a game found by `find_game` at the terminal. With single keys: n or right for
+// the next move, p or left for the previous one, digits and Enter to jump to a move, space to
+// play the moves by themselves every `speed` and + or - to change it, a for the engine's eval,
+// and q to stop. m tries a move of your own, branching off the game there, c has the computer
+// answer the moves tried, b goes back to the line a branch came off, v follows a kept
+// variation, and : takes a command: ":keep [name]" to keep the branch shown with the game, or
+// ":comment [text]" to comment on the move shown, with no text to remove the comment.
+// Without a terminal to read keys from, the same are entered a line at a time, or with
+// `autoplay` all moves are shown one after another.
+pub fn run(
+    found: Found,
+    settings: &Settings,
+    autoplay: bool,
+    speed: Duration,
+) -> Result<(), String> {
+    let mut replay = Replay::new(found)?;
+    // Size the board for the game as it was recorded
+    let mut sized = settings.clone();
+    sized.width = replay.game.rules.width;
+    sized.height = replay.game.rules.height;
+    let capabilities = Capabilities::detect(settings.color);
+    let (renderer, _) = crate::build_renderer(&sized, &capabilities);
+    let screen = Screen::new(settings.altscreen, capabilities, renderer);
+
+    let mut autoplay = {
+        let mut scheduler = Autoplay::new(speed);
+        if autoplay {
+            scheduler.toggle(Instant::now());
+        }
+        scheduler
+    };
+    if io::stdin().is_terminal() && screen.capabilities.interactive {
+        watch(&mut replay, &screen, &mut autoplay, settings)
+    } else if autoplay.playing() {
+        dump(&mut replay, &screen, autoplay.interval)
+    } else {
+        step_lines(&mut replay, &screen, settings)
+    }
+}
+
+// The position with its caption, where it branched, the comment on the move, the variations
+// off it, the eval when it's on, and what came of the last key: a message, or an error
+fn show(replay: &Replay, screen: &Screen, eval: bool, notice: Option<Result<String, String>>) {
+    replay.game.display_board(screen);
+    println!(
+        "{}",
+        screen.renderer.accent(&replay.caption(&screen.renderer))
+    );
+    if let Some(breadcrumb) = replay.breadcrumb() {
+        println!("{}", breadcrumb);
+    }
+    if let Some(text) = replay.comment_here() {
+        let width = screen.terminal_width().unwrap_or(80).min(80);
+        for line in comment::wrap(text, width.saturating_sub(2)) {
+            println!("  {}", line);
+        }
+    }
+    let here: Vec<String> = replay
+        .variations_here()
+        .iter()
+        .enumerate()
+        .map(|(number, (_, variation))| {
+            let moves: Vec<String> = variation
+                .moves
+                .iter()
+                .map(|&column| crate::column_name(column, &screen.renderer))
+                .collect();
+            format!("{}. {} ({})", number + 1, variation.name, moves.join(" "))
+        })
+        .collect();
+    if !here.is_empty() {
+        println!(
+            "{}",
+            i18n::fill("replay.variations", &[("list", &here.join(", "))])
+        );
+    }
+    if eval {
+        println!("{}", replay.eval(&screen.renderer));
+    }
+    match notice {
+        Some(Ok(message)) => println!("{}", screen.renderer.accent(&message)),
+        Some(Err(error)) => screen.error(&error),
+        None => {}
+    }
+}
+
+// A line typed after a prompt, outside raw mode; None at the end of input
+fn read_line(screen: &Screen, prompt: &str) -> Result<Option<String>, String> {
+    screen.prompt(prompt);
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) => Ok(None),
+        Ok(_) => Ok(Some(line.trim().to_string())),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// What the branching keys and commands do, shared by both viewers: m with a column, c, b, v
+// with a number, :keep with an optional name, and :comment with optional text. Returns a
+// notice to show, or None for input that isn't one of them.
+fn branch_command(
+    replay: &mut Replay,
+    settings: &Settings,
+    command: &str,
+    argument: &str,
+) -> Option<Result<String, String>> {
+    let result = match command {
+        "m" => match Coord::parse_column(argument) {
+            Some(column) => replay.play(column).map(|()| String::new()),
+            None => Err("Give the column to play, e.g. m 4".to_string()),
+        },
+        "c" => {
+            replay.answer = !replay.answer;
+            Ok(if replay.answer {
+                "The computer answers the moves you try".to_string()
+            } else {
+                "The computer no longer answers the moves you try".to_string()
+            })
+        }
+        "b" => match replay.back() {
+            Ok(true) => Ok(String::new()),
+            Ok(false) => Err("This is the main line".to_string()),
+            Err(err) => Err(err),
+        },
+        "v" => match argument.parse::<usize>() {
+            Ok(number) => replay.enter(number).map(|()| String::new()),
+            Err(_) => Err("Give the number of a variation from here, e.g. v 1".to_string()),
+        },
+        ":keep" => {
+            let name = Some(argument).filter(|name| !name.is_empty());
+            replay.keep(name, settings)
+        }
+        ":comment" => replay.comment(argument, settings),
+        _ => return None,
+    };
+    Some(result)
+}
+
+// The viewer on single keys, with autoplay
+fn watch(
+    replay: &mut Replay,
+    screen: &Screen,
+    autoplay: &mut Autoplay,
+    settings: &Settings,
+) -> Result<(), String> {
+    let mut eval = false;
+    let mut notice = None;
+    // Digits typed so far for a move to jump to
+    let mut typed = String::new();
+    loop {
+        show(replay, screen, eval, notice.take());
+        let last = replay.len();
+        let playing = if autoplay.playing() {
+            format!(
+                "pause ({:.1}s a move, +/- to change)",
+                autoplay.interval.as_secs_f64()
+            )
+        } else {
+            "play".to_string()
+        };
+        screen.prompt(&if typed.is_empty() {
+            format!(
+                "n/p: next/previous, 0-{} and Enter: jump, space: {}, m: try a move, {}a: eval, \
+                 q: quit",
+                last,
+                playing,
+                if replay.branches.is_empty() {
+                    ""
+                } else {
+                    "b: back, :keep: keep it, "
+                }
+            )
+        } else {
+            format!("Jump to move {} (Enter to go, Esc to cancel)", typed)
+        });
+
+        let Some(key) =
+            keys::poll_key(autoplay.wait(Instant::now())).map_err(|err| err.to_string())?
+        else {
+            if autoplay.due(Instant::now()) {
+                step(replay, replay.ply + 1, autoplay);
+            }
+            continue;
+        };
+        // Keys that take the rest of what they need as a line
+        let line = |prompt: &str| read_line(screen, prompt);
+        let branching = match key.code {
+            KeyCode::Char('m') if typed.is_empty() => {
+                autoplay.stop();
+                line(&format!("Column to try (1-{})", replay.game.rules.width))?
+                    .map(|column| ("m", column))
+            }
+            KeyCode::Char('v') if typed.is_empty() => {
+                line("Number of the variation to follow")?.map(|number| ("v", number))
+            }
+            KeyCode::Char(':') if typed.is_empty() => {
+                autoplay.stop();
+                line("Command (keep [name], comment [text])")?.map(|command| {
+                    let command = command.trim_start_matches(':');
+                    let (name, argument) = command.split_once(' ').unwrap_or((command, ""));
+                    match name {
+                        "keep" => (":keep", argument.trim().to_string()),
+                        "comment" => (":comment", argument.trim().to_string()),
+                        _ => ("", command.to_string()),
+                    }
+                })
+            }
+            KeyCode::Char(letter @ ('c' | 'b'))
+                if !key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                Some((if letter == 'c' { "c" } else { "b" }, String::new()))
+            }
+            _ => None,
+        };
+        if let Some((command, argument)) = branching {
+            notice = match branch_command(replay, settings, command, &argument) {
+                Some(Ok(text)) if text.is_empty() => None,
+                Some(result) => Some(result),
+                None => Some(Err(format!("Unknown command '{}'", argument))),
+            };
+            continue;
+        }
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(()),
+            KeyCode::Char(digit @ '0'..='9') => typed.push(digit),
+            KeyCode::Backspace => {
+                typed.pop();
+            }
+            KeyCode::Esc if !typed.is_empty() => typed.clear(),
+            KeyCode::Enter if !typed.is_empty() => {
+                match typed.parse::<usize>() {
+                    Ok(ply) if ply <= last => step(replay, ply, autoplay),
+                    _ => notice = Some(Err("There's no move with that number".to_string())),
+                }
+                typed.clear();
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('n') | KeyCode::Right | KeyCode::Enter if replay.ply == last => {
+                notice = Some(Err("That was the last move".to_string()));
+            }
+            KeyCode::Char('n') | KeyCode::Right | KeyCode::Enter => {
+                step(replay, replay.ply + 1, autoplay)
+            }
+            KeyCode::Char('p') | KeyCode::Left => {
+                step(replay, replay.ply.saturating_sub(1), autoplay)
+            }
+            KeyCode::Char(' ') if replay.ply == last && !autoplay.playing() => {
+                // Playing from the end starts over
+                step(replay, 0, autoplay);
+                autoplay.toggle(Instant::now());
+            }
+            KeyCode::Char(' ') => autoplay.toggle(Instant::now()),
+            KeyCode::Char('+') => autoplay.change_speed(true),
+            KeyCode::Char('-') => autoplay.change_speed(false),
+            KeyCode::Char('a') => eval = !eval,
+            _ => {}
+        }
+    }
+}
+
+// Show the position after `ply` moves, stopping autoplay at the end of the line
+fn step(replay: &mut Replay, ply: usize, autoplay: &mut Autoplay) {
+    // Every move of the line was played once, so replaying them can't fail
+    let _ = replay.seek(ply);
+    if replay.ply == replay.len() {
+        autoplay.stop();
+    }
+}
+
+// Every position in turn, `interval` apart, for output that isn't a terminal
+fn dump(replay: &mut Replay, screen: &Screen, interval: Duration) -> Result<(), String> {
+    loop {
+        show(replay, screen, false, None);
+        if replay.ply == replay.len() {
+            return Ok(());
+        }
+        let _ = io::stdout().flush();
+        thread::sleep(interval);
+        replay.seek(replay.ply + 1)?;
+    }
+}
+
+// The viewer on entered lines: n or an empty line for the next move, p for the previous
+// one, a number to jump to that move, a for the eval, q to stop, and the branching commands
+// as `watch` has them, "m 4" or "v 1" with what they need on the same line
+fn step_lines(replay: &mut Replay, screen: &Screen, settings: &Settings) -> Result<(), String> {
+    let mut eval = false;
+    let mut notice = None;
+    loop {
+        show(replay, screen, eval, notice.take());
+        let prompt = format!(
+            "n: next, p: previous, 0-{}: jump to a move, m <column>: try a move, c: computer \
+             answers {}, {}v <number>: follow a variation, a: eval {}, q: quit",
+            replay.len(),
+            if replay.answer { "off" } else { "on" },
+            if replay.branches.is_empty() {
+                ""
+            } else {
+                "b: back, :keep [name]: keep this line, "
+            },
+            if eval { "off" } else { "on" }
+        );
+        let Some(line) = read_line(screen, &prompt)? else {
+            return Ok(());
+        };
+        let (command, argument) = line.split_once(' ').unwrap_or((&line, ""));
+        if let Some(result) = branch_command(replay, settings, command, argument.trim()) {
+            notice = match result {
+                Ok(text) if text.is_empty() => None,
+                result => Some(result),
+            };
+            continue;
+        }
+        let target = match line.as_str() {
+            "q" | "Q" => return Ok(()),
+            "" | "n" | "N" => replay.ply + 1,
+            "p" | "P" => replay.ply.saturating_sub(1),
+            "a" | "A" => {
+                eval = !eval;
+                continue;
+            }
+            text => match text.parse::<usize>() {
+                Ok(ply) if ply <= replay.len() => ply,
+                _ => {
+                    notice = Some(Err(
+                        "Enter n, p, a, q, m, a move number, :keep, or :comment".to_string(),
+                    ));
+                    continue;
+                }
+            },
+        };
+        if target > replay.len() {
+            notice = Some(Err("That was the last move".to_string()));
+            continue;
+        }
+        replay.seek(target)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A classic game with these columns played, counted from 1
+    fn played(columns: &[usize]) -> Game {
+        let mut game = Game::builder(&Settings::default())
+            .build()
+            .expect("the rules are valid");
+        for &column in columns {
+            game.play(Move::Drop(column - 1))
+                .expect("the moves are legal");
+        }
+        game
+    }
+
+    fn replay(game: Game) -> Replay {
+        Replay::new(Found {
+            game,
+            date: None,
+            save: None,
+        })
+        .expect("the game has moves")
+    }
+
+    #[test]
+    fn seeking_stays_within_the_game_either_way() {
+        let moves = [4, 4, 3, 5, 2, 6, 1];
+        let mut replay = replay(played(&moves));
+        assert_eq!(replay.ply, 0);
+        assert_eq!(
+            replay.game.history.len(),
+            0,
+            "it starts from the empty board"
+        );
+
+        replay.seek(moves.len()).expect("the end can be shown");
+        assert_eq!(
+            replay.game,
+            played(&moves),
+            "the end is the game as it ended"
+        );
+        assert_eq!(replay.game.winner(), Some(Player::One));
+
+        replay.seek(100).expect("past the end shows the end");
+        assert_eq!(replay.ply, moves.len());
+        assert_eq!(replay.game, played(&moves));
+
+        replay.seek(3).expect("going back replays the moves before");
+        assert_eq!(replay.ply, 3);
+        assert_eq!(replay.game, played(&moves[..3]));
+        assert!(!replay.game.is_finished());
+
+        replay.seek(0).expect("the start can be shown");
+        assert_eq!(replay.game, played(&[]));
+    }
+
+    #[test]
+    fn a_game_ended_off_the_board_ends_again_at_its_last_move() {
+        let mut resigned = played(&[4, 4, 3]);
+        resigned.resign();
+        let mut replay = replay(resigned.clone());
+        replay.seek(3).expect("the end can be shown");
+        assert_eq!(replay.game.winner(), Some(Player::One));
+        replay.seek(2).expect("going back a move");
+        assert_eq!(replay.game.winner(), None);
+    }
+
+    #[test]
+    fn autoplay_steps_an_interval_apart_and_stops_at_the_end() {
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let mut autoplay = Autoplay::new(second);
+        assert!(!autoplay.playing());
+        assert_eq!(autoplay.wait(start), None, "paused, it waits for a key");
+        assert!(!autoplay.due(start + second * 10));
+
+        autoplay.toggle(start);
+        assert!(autoplay.playing());
+        assert_eq!(autoplay.wait(start), Some(second));
+        assert!(!autoplay.due(start + second / 2));
+        assert_eq!(autoplay.wait(start + second / 2), Some(second / 2));
+        // A move shown late puts the next one an interval after it, not after it was due
+        let late = start + second * 3 / 2;
+        assert!(autoplay.due(late));
+        assert!(!autoplay.due(late));
+        assert_eq!(autoplay.wait(late + second * 2), Some(Duration::ZERO));
+        assert!(autoplay.due(late + second));
+
+        autoplay.change_speed(true);
+        assert_eq!(autoplay.interval, Duration::from_millis(500));
+        autoplay.change_speed(true);
+        assert_eq!(
+            autoplay.interval,
+            Duration::from_millis(500),
+            "it's the fastest"
+        );
+
+        let moves = [4, 4, 3];
+        let mut replay = replay(played(&moves));
+        step(&mut replay, 2, &mut autoplay);
+        assert!(autoplay.playing());
+        step(&mut replay, 3, &mut autoplay);
+        assert_eq!(replay.ply, moves.len());
+        assert!(!autoplay.playing(), "the last move stops it");
+        autoplay.toggle(start);
+        step(&mut replay, 4, &mut autoplay);
+        assert!(!autoplay.playing(), "so does stepping past it");
+    }
+}
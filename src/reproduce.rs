@@ -0,0 +1,182 @@
+use crate::ai;
+use crate::cube;
+use crate::export;
+use crate::i18n;
+use crate::save::{self, Engine};
+use crate::settings::Settings;
+use crate::variant::{Move, Variant};
+use crate::Game;
+
+// What playing a game again found: whether every move the computer chose came out the
+// same, and the lines saying so
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub reproduced: bool,
+    pub lines: Vec<String>,
+}
+
+// Play the game saved in `slot` again from the start, the way `check` does
+pub fn run(slot: &str, settings: &Settings) -> Result<Report, String> {
+    let path = save::slot_path(slot).ok_or_else(|| i18n::text("reproduce.no_dir"))?;
+    let (game, engine) = save::load_with_engine(&path, settings)?;
+    let engine =
+        engine.ok_or_else(|| i18n::fill("reproduce.no_engine", &[("path", &path.display())]))?;
+    check(&game, &engine, settings)
+}
+
+// Play `game` again from the start, with the computer choosing each of its moves afresh at
+// the level and from the seed of `engine`, and report whether every one comes out the same.
+// Moves it didn't choose itself are played as they were: the random start, moves played at
+// random when its time for them ran out, and a first move it took over with a swap.
+fn check(game: &Game, engine: &Engine, settings: &Settings) -> Result<Report, String> {
+    let builder = Game::builder(settings)
+        .rules(game.rules)
+        .names(&game.names)
+        .seed(engine.seed);
+    let mut position = export::replay(builder, &game.handicap, &[])?;
+    let columns: Vec<usize> = game.history.iter().map(|coord| coord.column).collect();
+    let mut checked = 0;
+    for (index, mv) in game.specials.list(&columns).into_iter().enumerate() {
+        let ply = index + 1;
+        let chosen = position.current_player == engine.seat
+            && ply > game.random_start
+            && !game.timeouts.contains(&ply)
+            && !(game.swapped && ply == 1);
+        if chosen {
+            let now = ai::choose_move(&position, engine.difficulty);
+            if now != Some(mv) {
+                let now = now.map_or(i18n::text("reproduce.nothing"), |now| name(game, now));
+                let lines = vec![
+                    i18n::fill(
+                        "reproduce.differs",
+                        &[("ply", &ply), ("then", &name(game, mv)), ("now", &now)],
+                    ),
+                    cause(engine),
+                ];
+                return Ok(Report {
+                    reproduced: false,
+                    lines,
+                });
+            }
+            checked += 1;
+        }
+        position.play(mv).map_err(|err| {
+            i18n::fill("reproduce.unplayable", &[("ply", &ply), ("reason", &err)])
+        })?;
+    }
+
+    let mut lines = vec![i18n::plural("reproduce.same", checked)];
+    if engine.version != ai::VERSION {
+        lines.push(i18n::fill(
+            "reproduce.version",
+            &[("then", &engine.version), ("now", &ai::VERSION)],
+        ));
+    }
+    Ok(Report {
+        reproduced: true,
+        lines,
+    })
+}
+
+// Why the computer's move came out differently: a different version or search depth can
+// play differently by design, while the same ones should never
+fn cause(engine: &Engine) -> String {
+    let depth = engine.difficulty.depth();
+    if engine.version != ai::VERSION {
+        i18n::fill(
+            "reproduce.cause.version",
+            &[("then", &engine.version), ("now", &ai::VERSION)],
+        )
+    } else if engine.depth != depth {
+        let level = format!("{:?}", engine.difficulty).to_lowercase();
+        i18n::fill(
+            "reproduce.cause.depth",
+            &[("level", &level), ("then", &engine.depth), ("now", &depth)],
+        )
+    } else {
+        i18n::text("reproduce.cause.same")
+    }
+}
+
+// A move as the report names it, like "column 4", "p4", or "peg b3"
+fn name(game: &Game, mv: Move) -> String {
+    match (game.rules.variant, mv) {
+        (Variant::Cube, _) => i18n::fill("hint.peg", &[("peg", &cube::peg_name(mv.column()))]),
+        (_, Move::Drop(column)) => i18n::fill("hint.drop", &[("column", &(column + 1))]),
+        _ => mv.notation(|column| (column + 1).to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+    use crate::Player;
+
+    // The easy level as it was saved, playing second from seed 5
+    fn engine() -> Engine {
+        Engine {
+            seat: Player::Two,
+            difficulty: Difficulty::Easy,
+            depth: Difficulty::Easy.depth(),
+            seed: 5,
+            version: ai::VERSION.to_string(),
+        }
+    }
+
+    // A game of these columns for the first player, counted from 0, with the computer
+    // answering each one, or playing `instead` for its first move
+    fn against_the_computer(columns: &[usize], instead: Option<usize>) -> Game {
+        let mut game = Game::new();
+        game.engine_seed = engine().seed;
+        for (index, &column) in columns.iter().enumerate() {
+            game.play(Move::Drop(column)).expect("the column is open");
+            let chosen = ai::choose_move(&game, Difficulty::Easy).expect("the game goes on");
+            let answer = match instead {
+                Some(column) if index == 0 => Move::Drop(column),
+                _ => chosen,
+            };
+            game.play(answer).expect("the answer is legal");
+        }
+        game
+    }
+
+    #[test]
+    fn a_game_the_computer_plays_the_same_way_again_is_reproduced() {
+        let game = against_the_computer(&[3, 3, 2, 4], None);
+        let report = check(&game, &engine(), &Settings::default()).expect("the game replays");
+        assert_eq!(
+            report,
+            Report {
+                reproduced: true,
+                lines: vec!["The computer made the same 4 moves it did".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn a_move_the_computer_wouldnt_play_now_is_reported() {
+        let mut opened = Game::new();
+        opened.engine_seed = engine().seed;
+        opened.play(Move::Drop(3)).expect("the board is empty");
+        let chosen = ai::choose_move(&opened, Difficulty::Easy).expect("the game goes on");
+        let other = (chosen.column() + 1) % 7;
+        let game = against_the_computer(&[3, 2], Some(other));
+
+        let report = check(&game, &engine(), &Settings::default()).expect("the game replays");
+        assert!(!report.reproduced);
+        assert_eq!(
+            report.lines,
+            [
+                format!(
+                    "Move 2: the computer played column {} but now plays column {}",
+                    other + 1,
+                    chosen.column() + 1
+                ),
+                "The same version at the same level chose differently from the same position \
+                 and seed, so the engine isn't deterministic"
+                    .to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use clap::ValueEnum;
+
+use crate::cube;
+use crate::session::First;
+use crate::variant::Variant;
+
+// Board and win length of the classic game, which every other set of rules starts from
+const BOARD_WIDTH: usize = 7;
+const BOARD_HEIGHT: usize = 6;
+const WIN_LENGTH: usize = 4;
+
+// Smallest and largest board side
+const MIN_SIDE: usize = 4;
+const MAX_SIDE: usize = 20;
+
+// Fewest pieces in a row a game can be played to
+const MIN_CONNECT: usize = 3;
+
+// Everything that decides how a game is played, as opposed to who plays it and how it's
+// shown. New rules go here, with their place in `validate`, `write_toml`, and `from_toml`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GameRules {
+    pub width: usize,
+    pub height: usize,
+    // Pieces in a row needed to win
+    pub connect: usize,
+    pub variant: Variant,
+    // Who moves first
+    pub first: First,
+    // The second player may answer the first move by taking it over as their own
+    pub pie_rule: bool,
+}
+
+impl GameRules {
+    // Connect Four as it comes in the box
+    pub const CLASSIC: GameRules = GameRules {
+        width: BOARD_WIDTH,
+        height: BOARD_HEIGHT,
+        connect: WIN_LENGTH,
+        variant: Variant::Standard,
+        first: First::P1,
+        pie_rule: false,
+    };
+
+    // The rules with one of them changed, for building them up from CLASSIC a rule at a
+    // time, like GameRules::CLASSIC.with_size(9, 7).with_connect(5)
+    pub const fn with_size(self, width: usize, height: usize) -> GameRules {
+        GameRules {
+            width,
+            height,
+            ..self
+        }
+    }
+
+    pub const fn with_connect(self, connect: usize) -> GameRules {
+        GameRules { connect, ..self }
+    }
+
+    // The cube is always the same size, so a 3D game takes its board and win length too
+    pub const fn with_variant(self, variant: Variant) -> GameRules {
+        match variant {
+            Variant::Cube => GameRules {
+                width: cube::COLUMNS,
+                height: cube::SIDE,
+                connect: cube::SIDE,
+                variant,
+                ..self
+            },
+            _ => GameRules { variant, ..self },
+        }
+    }
+
+    // Every reason a game can't be played by these rules, not just the first
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+        if self.variant == Variant::Cube {
+            if (self.width, self.height, self.connect) != (cube::COLUMNS, cube::SIDE, cube::SIDE) {
+                problems.push(format!(
+                    "a 3D game is kept on a {}x{} board and played to {} in a row",
+                    cube::COLUMNS,
+                    cube::SIDE,
+                    cube::SIDE
+                ));
+            }
+        } else {
+            for (side, length, unit) in [
+                ("wide", self.width, "columns"),
+                ("high", self.height, "rows"),
+            ] {
+                if !(MIN_SIDE..=MAX_SIDE).contains(&length) {
+                    problems.push(format!(
+                        "the board must be {} to {} {} {}, not {}",
+                        MIN_SIDE, MAX_SIDE, unit, side, length
+                    ));
+                }
+            }
+        }
+        // A win length can only be judged against a board that can be played on
+        let longest = self.width.max(self.height);
+        if problems.is_empty() && !(MIN_CONNECT..=longest).contains(&self.connect) {
+            problems.push(format!(
+                "connect must be between {} and {} on a {}x{} board",
+                MIN_CONNECT, longest, self.width, self.height
+            ));
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    // The rules in a few words, like "9x7, connect 5" or "7x6, connect 4, popout, p2 first"
+    pub fn describe(&self) -> String {
+        // The cube is kept on a flat board, but is named by its own size
+        let size = match self.variant {
+            Variant::Cube => format!("{0}x{0}x{0}", cube::SIDE),
+            _ => format!("{}x{}", self.width, self.height),
+        };
+        let mut parts = vec![size, format!("connect {}", self.connect)];
+        if self.variant != Variant::Standard {
+            parts.push(self.variant.name().to_string());
+        }
+        if self.first != First::P1 {
+            parts.push(format!("{} first", first_name(self.first)));
+        }
+        if self.pie_rule {
+            parts.push("pie rule".to_string());
+        }
+        parts.join(", ")
+    }
+
+    // The rules as entries of a TOML table, each left out when it's the classic one but the
+    // board and win length, which are always written
+    pub fn write_toml(&self, table: &mut toml::Table) {
+        table.insert("width".to_string(), toml::Value::Integer(self.width as i64));
+        table.insert(
+            "height".to_string(),
+            toml::Value::Integer(self.height as i64),
+        );
+        table.insert(
+            "connect".to_string(),
+            toml::Value::Integer(self.connect as i64),
+        );
+        if self.variant != Variant::Standard {
+            table.insert(
+                "variant".to_string(),
+                toml::Value::String(self.variant.name().to_string()),
+            );
+        }
+        if self.first != First::P1 {
+            table.insert(
+                "first".to_string(),
+                toml::Value::String(first_name(self.first)),
+            );
+        }
+        if self.pie_rule {
+            table.insert("pie_rule".to_string(), toml::Value::Boolean(true));
+        }
+    }
+
+    // Rules read back from a TOML table, the ones it doesn't give as in classic. Other
+    // entries of the table are left alone; whether the rules can be played is up to the
+    // caller, with `validate`.
+    pub fn from_toml(table: &toml::Table) -> Result<GameRules, String> {
+        let number = |key: &str| match table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::Integer(value)) if *value > 0 => Ok(Some(*value as usize)),
+            Some(_) => Err(format!("'{}' must be a positive number", key)),
+        };
+        let text = |key: &str| match table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::String(value)) => Ok(Some(value.as_str())),
+            Some(_) => Err(format!("'{}' must be a string", key)),
+        };
+        let mut rules = GameRules::CLASSIC;
+        rules.width = number("width")?.unwrap_or(rules.width);
+        rules.height = number("height")?.unwrap_or(rules.height);
+        rules.connect = number("connect")?.unwrap_or(rules.connect);
+        if let Some(variant) = text("variant")? {
+            let variant = Variant::parse(variant).ok_or(
+                "'variant' must be standard, popout, powerup, cylinder, misere, score, flip, or 3d",
+            )?;
+            rules = rules.with_variant(variant);
+        }
+        if let Some(first) = text("first")? {
+            rules.first = First::from_str(first, true)
+                .map_err(|_| "'first' must be p1, p2, random, or alternate".to_string())?;
+        }
+        rules.pie_rule = match table.get("pie_rule") {
+            None => false,
+            Some(toml::Value::Boolean(pie_rule)) => *pie_rule,
+            Some(_) => return Err("'pie_rule' must be true or false".to_string()),
+        };
+        Ok(rules)
+    }
+}
+
+// Who moves first as settings spell it
+fn first_name(first: First) -> String {
+    format!("{:?}", first).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_board_is_checked_before_the_win_length() {
+        let problems = GameRules::CLASSIC
+            .with_size(1, 1)
+            .validate()
+            .expect_err("the board is too small");
+        assert_eq!(
+            problems,
+            [
+                "the board must be 4 to 20 columns wide, not 1",
+                "the board must be 4 to 20 rows high, not 1"
+            ]
+        );
+        let problems = GameRules::CLASSIC
+            .with_size(5, 4)
+            .with_connect(6)
+            .validate()
+            .expect_err("6 doesn't fit");
+        assert_eq!(problems, ["connect must be between 3 and 5 on a 5x4 board"]);
+        assert_eq!(
+            GameRules::CLASSIC
+                .with_size(4, 4)
+                .with_connect(3)
+                .validate(),
+            Ok(())
+        );
+    }
+}
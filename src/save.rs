@@ -0,0 +1,588 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+use crate::ai::{self, Difficulty};
+use crate::clock::{Clock, TimeControl};
+use crate::comment;
+use crate::export;
+use crate::session::Session;
+use crate::settings::Settings;
+use crate::rules::GameRules;
+use crate::validate;
+use crate::variant::Specials;
+use crate::variation;
+use crate::{Game, Player};
+
+// Slot used by :save without a name
+pub const DEFAULT_SLOT: &str = "game";
+
+// The computer a game was saved against: its seat and level, how far ahead that level
+// looked, the seed of its chance moves, and the version it was, for playing the game again
+pub struct Engine {
+    pub seat: Player,
+    pub difficulty: Difficulty,
+    pub depth: usize,
+    pub seed: u32,
+    pub version: String,
+}
+
+// File for a save slot: a name is kept in the data directory, while anything that looks like
+// a path (it has a slash or ends in .toml) is used as given
+pub fn slot_path(slot: &str) -> Option<PathBuf> {
+    if slot.contains('/') || slot.contains('\\') || slot.ends_with(".toml") {
+        return Some(PathBuf::from(slot));
+    }
+    dirs::data_dir().map(|dir| {
+        dir.join("connect_four")
+            .join("saves")
+            .join(format!("{}.toml", slot))
+    })
+}
+
+// Write the game's rules, named by their preset when they're one's, players, and moves so
+// far as TOML. Moves are 1-based columns in the order they were played, and the players
+// are in the seats they ended up in after any swap under the pie rule. A handicap is the
+// columns its pieces went into, with how many are still to be placed in a game saved
+// before they're down, and a random start is how many of the moves it played and its
+// seed. A game of a variant names it, and the moves that were pops under PopOut, anvils
+// or bombs under Power Up, or flips under Flip, are listed by number, a flip standing in
+// the moves as the first column. Games ended by resigning, by agreeing to a draw, or on
+// time say so, as do moves played at random for a player out of time for them and how
+// long the game was paused. The variations, move comments, and clock kept with a game are
+// written with it, the clock with the name of its preset and how long each move took, and
+// against the computer everything it needs to make the same moves again. An imported game
+// keeps the date it came with. The session's tally of earlier games comes along, and the
+// match format when there is one.
+pub fn write(
+    game: &Game,
+    settings: &Settings,
+    session: &Session,
+    slot: &str,
+) -> Result<PathBuf, String> {
+    let path = slot_path(slot).ok_or("no data directory found; save to a path instead")?;
+
+    let mut table = toml::Table::new();
+    table.insert("id".to_string(), toml::Value::String(game.id.clone()));
+    if let Some(previous) = &game.previous_game {
+        table.insert(
+            "previous_game".to_string(),
+            toml::Value::String(previous.clone()),
+        );
+    }
+    if let Some(date) = &game.date {
+        table.insert("date".to_string(), toml::Value::String(date.clone()));
+    }
+    game.rules.write_toml(&mut table);
+    if let Some(preset) = &game.rule_preset {
+        table.insert("rule_preset".to_string(), toml::Value::String(preset.clone()));
+    }
+    table.insert(
+        "names".to_string(),
+        toml::Value::Array(
+            game.names
+                .iter()
+                .map(|name| toml::Value::String(name.clone()))
+                .collect(),
+        ),
+    );
+    table.insert(
+        "moves".to_string(),
+        toml::Value::Array(
+            game.history
+                .iter()
+                .map(|coord| toml::Value::Integer(coord.column as i64 + 1))
+                .collect(),
+        ),
+    );
+    if !game.handicap.is_empty() {
+        table.insert(
+            "handicap".to_string(),
+            toml::Value::Array(
+                game.handicap
+                    .iter()
+                    .map(|&column| toml::Value::Integer(column as i64 + 1))
+                    .collect(),
+            ),
+        );
+    }
+    if game.placing() {
+        table.insert(
+            "handicap_left".to_string(),
+            toml::Value::Integer(game.handicap_left as i64),
+        );
+    }
+    if let Some(seed) = game.seed {
+        table.insert(
+            "random_start".to_string(),
+            toml::Value::Integer(game.random_start as i64),
+        );
+        table.insert("seed".to_string(), toml::Value::Integer(i64::from(seed)));
+    }
+    let specials = &game.specials;
+    for (key, plies) in [
+        ("pops", &specials.pops),
+        ("anvils", &specials.anvils),
+        ("bombs", &specials.bombs),
+        ("flips", &specials.flips),
+    ] {
+        if !plies.is_empty() {
+            table.insert(
+                key.to_string(),
+                toml::Value::Array(
+                    plies
+                        .iter()
+                        .map(|&ply| toml::Value::Integer(ply as i64))
+                        .collect(),
+                ),
+            );
+        }
+    }
+
+    // Only endings the moves don't make themselves are written; the rest come back when
+    // the moves are replayed
+    if let Some(outcome) = game.outcome.as_ref().filter(|outcome| outcome.off_the_board()) {
+        table.insert(
+            "ending".to_string(),
+            toml::Value::String(outcome.ending().to_string()),
+        );
+    }
+
+    if game.swapped {
+        table.insert("swapped".to_string(), toml::Value::Boolean(true));
+    }
+    if !game.timeouts.is_empty() {
+        table.insert(
+            "timeouts".to_string(),
+            toml::Value::Array(
+                game.timeouts
+                    .iter()
+                    .map(|&ply| toml::Value::Integer(ply as i64))
+                    .collect(),
+            ),
+        );
+    }
+    if !game.pauses.is_empty() {
+        table.insert(
+            "pauses".to_string(),
+            toml::Value::Array(
+                game.pauses
+                    .iter()
+                    .map(|length| toml::Value::Integer(length.as_millis() as i64))
+                    .collect(),
+            ),
+        );
+    }
+    if let Some(clock) = &game.clock {
+        // Times are kept in milliseconds, up to the moment of saving
+        let now = Instant::now();
+        let times = |time: fn(&Clock, Player, Instant) -> Duration| {
+            toml::Value::Array(
+                [Player::One, Player::Two]
+                    .iter()
+                    .map(|&player| toml::Value::Integer(time(clock, player, now).as_millis() as i64))
+                    .collect(),
+            )
+        };
+        let mut state = toml::Table::new();
+        state.insert(
+            "time".to_string(),
+            toml::Value::String(clock.control.to_string()),
+        );
+        if let Some(preset) = &game.preset {
+            state.insert(
+                "preset".to_string(),
+                toml::Value::String(preset.clone()),
+            );
+        }
+        if !game.move_times.is_empty() {
+            state.insert(
+                "moves".to_string(),
+                toml::Value::Array(
+                    game.move_times
+                        .iter()
+                        .map(|time| toml::Value::Integer(time.as_millis() as i64))
+                        .collect(),
+                ),
+            );
+        }
+        state.insert("left".to_string(), times(Clock::remaining));
+        state.insert("used".to_string(), times(Clock::used));
+        table.insert("clock".to_string(), toml::Value::Table(state));
+    }
+    if let Some((seat, difficulty)) = game.computer {
+        let mut engine = toml::Table::new();
+        engine.insert("seat".to_string(), toml::Value::Integer(seat as i64));
+        engine.insert(
+            "level".to_string(),
+            toml::Value::String(format!("{:?}", difficulty).to_lowercase()),
+        );
+        engine.insert(
+            "depth".to_string(),
+            toml::Value::Integer(difficulty.depth() as i64),
+        );
+        engine.insert(
+            "seed".to_string(),
+            toml::Value::Integer(i64::from(game.engine_seed)),
+        );
+        engine.insert(
+            "version".to_string(),
+            toml::Value::String(ai::VERSION.to_string()),
+        );
+        table.insert("engine".to_string(), toml::Value::Table(engine));
+    }
+    if !game.variations.is_empty() {
+        table.insert(
+            "variations".to_string(),
+            variation::to_toml(&game.variations),
+        );
+    }
+    if !game.comments.is_empty() {
+        table.insert("comments".to_string(), comment::to_toml(&game.comments));
+    }
+
+    if session.games() > 0 {
+        let mut tally = toml::Table::new();
+        tally.insert(
+            "draws".to_string(),
+            toml::Value::Integer(i64::from(session.draws)),
+        );
+        tally.insert(
+            "wins".to_string(),
+            toml::Value::Table(
+                session
+                    .wins()
+                    .iter()
+                    .map(|(name, wins)| (name.clone(), toml::Value::Integer(i64::from(*wins))))
+                    .collect(),
+            ),
+        );
+        table.insert("session".to_string(), toml::Value::Table(tally));
+    }
+    if let Some(format) = settings.match_format {
+        let mut state = toml::Table::new();
+        state.insert(
+            "format".to_string(),
+            toml::Value::String(format.to_string()),
+        );
+        state.insert(
+            "half_point_draws".to_string(),
+            toml::Value::Boolean(settings.half_point_draws),
+        );
+        table.insert("match".to_string(), toml::Value::Table(state));
+    }
+
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .map_err(|err| format!("could not create {}: {}", dir.display(), err))?;
+    }
+    log::debug!("saving the game to {}", path.display());
+    fs::write(&path, table.to_string())
+        .map_err(|err| format!("could not write {}: {}", path.display(), err))?;
+    Ok(path)
+}
+
+// Read back a game written by `write`, replaying its moves on the board and with the players
+// it was saved with. Everything else comes from `settings`.
+pub fn load(path: &Path, settings: &Settings) -> Result<Game, String> {
+    load_with_engine(path, settings).map(|(game, _)| game)
+}
+
+// A game read back by `load`, with the computer it was played against when the save says
+pub fn load_with_engine(
+    path: &Path,
+    settings: &Settings,
+) -> Result<(Game, Option<Engine>), String> {
+    log::debug!("loading the game from {}", path.display());
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+    let table: toml::Table = text
+        .parse()
+        .map_err(|err| format!("could not parse {}: {}", path.display(), err))?;
+    let error = |message: &str| format!("{}: {}", path.display(), message);
+
+    let mut builder =
+        Game::builder(settings).rules(GameRules::from_toml(&table).map_err(|err| error(&err))?);
+    // A name for every seat, so the list says how many players there were
+    if let Some(toml::Value::Array(names)) = table.get("names") {
+        if !(2..=4).contains(&names.len()) {
+            return Err(error("'names' must list 2 to 4 players"));
+        }
+        let names = names
+            .iter()
+            .map(|name| name.as_str().map(str::to_string))
+            .collect::<Option<Vec<String>>>()
+            .ok_or_else(|| error("'names' must list names"))?;
+        builder = builder.names(&names);
+    }
+    let columns = |key: &str| match table.get(key) {
+        Some(toml::Value::Array(columns)) => columns
+            .iter()
+            .map(|column| match column {
+                toml::Value::Integer(column) if *column > 0 => Some(*column as usize - 1),
+                _ => None,
+            })
+            .collect::<Option<Vec<usize>>>()
+            .ok_or_else(|| error(&format!("'{}' must list columns from 1", key))),
+        _ => Err(error(&format!("'{}' must be a list", key))),
+    };
+    let moves = columns("moves")?;
+    let handicap = match table.get("handicap") {
+        None => Vec::new(),
+        Some(_) => columns("handicap")?,
+    };
+
+    let plies = |key: &str| match table.get(key) {
+        None => Ok(Vec::new()),
+        Some(plies) => plies
+            .as_array()
+            .and_then(|plies| {
+                plies
+                    .iter()
+                    .map(|ply| match ply {
+                        toml::Value::Integer(ply) if (1..=moves.len() as i64).contains(ply) => {
+                            Some(*ply as usize)
+                        }
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<usize>>>()
+            })
+            .ok_or_else(|| error(&format!("'{}' must list numbers of moves of the game", key))),
+    };
+    let specials = Specials {
+        pops: plies("pops")?,
+        anvils: plies("anvils")?,
+        bombs: plies("bombs")?,
+        flips: plies("flips")?,
+    };
+
+    let mut game = export::replay(builder, &handicap, &specials.list(&moves))
+        .map_err(|err| error(&err))?;
+    match table.get("handicap_left") {
+        None => {}
+        Some(toml::Value::Integer(left)) if *left > 0 => game
+            .leave_handicap(*left as usize)
+            .map_err(|err| error(&err))?,
+        Some(_) => return Err(error("'handicap_left' must be a positive number")),
+    }
+    match (table.get("random_start"), table.get("seed")) {
+        (None, None) => {}
+        (Some(toml::Value::Integer(plies)), Some(toml::Value::Integer(seed)))
+            if (1..=moves.len() as i64).contains(plies) && u32::try_from(*seed).is_ok() =>
+        {
+            game.random_start = *plies as usize;
+            game.seed = u32::try_from(*seed).ok();
+        }
+        _ => {
+            return Err(error(
+                "'random_start' and 'seed' must give the moves of the random start and its seed",
+            ))
+        }
+    }
+    let flag = |key: &str| matches!(table.get(key), Some(toml::Value::Boolean(true)));
+    // The names are already in the seats the swap left them in
+    game.rules.pie_rule |= flag("swapped");
+    game.swapped = flag("swapped") && !moves.is_empty();
+    // Saves from before the ending was written give it as a flag of its own
+    let ending = match table.get("ending") {
+        None if flag("timed_out") => Some("time"),
+        None if flag("resigned") => Some("resignation"),
+        None if flag("agreed_draw") => Some("agreement"),
+        None => None,
+        Some(toml::Value::String(ending)) => Some(ending.as_str()),
+        Some(_) => return Err(error("'ending' must be time, resignation, or agreement")),
+    };
+    match ending {
+        _ if game.is_finished() => {}
+        None => {}
+        Some("time") => game.lose_on_time(),
+        Some("resignation") => game.resign(),
+        Some("agreement") => game.agree_to_draw(),
+        Some(_) => return Err(error("'ending' must be time, resignation, or agreement")),
+    }
+    // Saves from before games had ids get a new one
+    let text = |key: &str| table.get(key).and_then(|value| value.as_str());
+    if let Some(id) = text("id") {
+        game.id = id.to_string();
+    }
+    game.previous_game = text("previous_game").map(str::to_string);
+    game.date = text("date").map(str::to_string);
+    game.rule_preset = match table.get("rule_preset") {
+        None => None,
+        Some(toml::Value::String(preset)) => Some(preset.clone()),
+        Some(_) => return Err(error("'rule_preset' must be the name of one")),
+    };
+    if let Some(variations) = table.get("variations") {
+        game.variations = variation::from_toml(variations).map_err(|err| error(&err))?;
+    }
+    if let Some(comments) = table.get("comments") {
+        game.comments = comment::from_toml(comments, moves.len()).map_err(|err| error(&err))?;
+    }
+    if let Some(timeouts) = table.get("timeouts") {
+        game.timeouts = timeouts
+            .as_array()
+            .and_then(|timeouts| {
+                timeouts
+                    .iter()
+                    .map(|ply| match ply {
+                        toml::Value::Integer(ply) if (1..=moves.len() as i64).contains(ply) => {
+                            Some(*ply as usize)
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .ok_or_else(|| error("'timeouts' must list numbers of moves of the game"))?;
+    }
+    if let Some(pauses) = table.get("pauses") {
+        game.pauses = millis(pauses)
+            .ok_or_else(|| error("'pauses' must list how long each pause lasted in milliseconds"))?;
+    }
+    if let Some(state) = table.get("clock") {
+        game.clock = Some(read_clock(state, settings).map_err(|err| error(&err))?);
+        game.preset = match state.get("preset") {
+            None => None,
+            Some(toml::Value::String(preset)) => Some(preset.clone()),
+            Some(_) => return Err(error("the clock's 'preset' must be the name of one")),
+        };
+        if let Some(times) = state.get("moves") {
+            game.move_times = millis(times)
+                .filter(|times| times.len() <= moves.len())
+                .ok_or_else(|| error("the clock's 'moves' must give each move's time in milliseconds"))?;
+        }
+    }
+    let engine = match table.get("engine") {
+        None => None,
+        Some(state) => Some(read_engine(state).map_err(|err| error(&err))?),
+    };
+    if let Some(engine) = &engine {
+        game.computer = Some((engine.seat, engine.difficulty));
+        game.engine_seed = engine.seed;
+    }
+    game.validate()
+        .map_err(|errors| error(&validate::summary(&errors)))?;
+    Ok((game, engine))
+}
+
+// The computer a game was played against, from the table `write` keeps it in
+fn read_engine(state: &toml::Value) -> Result<Engine, String> {
+    let number = |key: &str| state.get(key).and_then(toml::Value::as_integer);
+    let text = |key: &str| state.get(key).and_then(toml::Value::as_str);
+    let seat = number("seat")
+        .filter(|seat| (1..=4).contains(seat))
+        .map(|seat| Player::from_index(seat as usize - 1))
+        .ok_or("the engine's 'seat' must be the number of a player")?;
+    let difficulty = text("level")
+        .and_then(|level| Difficulty::from_str(level, true).ok())
+        .ok_or("the engine's 'level' must be easy, medium, or hard")?;
+    let depth = number("depth")
+        .filter(|&depth| depth > 0)
+        .ok_or("the engine's 'depth' must be a positive number")?;
+    let seed = number("seed")
+        .and_then(|seed| u32::try_from(seed).ok())
+        .ok_or("the engine's 'seed' must be a whole number")?;
+    let version = text("version").ok_or("the engine's 'version' must be a string")?;
+    Ok(Engine {
+        seat,
+        difficulty,
+        depth: depth as usize,
+        seed,
+        version: version.to_string(),
+    })
+}
+
+// A list of times in milliseconds
+fn millis(values: &toml::Value) -> Option<Vec<Duration>> {
+    values
+        .as_array()?
+        .iter()
+        .map(|millis| match millis {
+            toml::Value::Integer(millis) if *millis >= 0 => {
+                Some(Duration::from_millis(*millis as u64))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+// A clock read back from a save's [clock] table, stopped. Saves from before time controls
+// had bonuses have no time left, which is then the main time less the time used.
+fn read_clock(state: &toml::Value, settings: &Settings) -> Result<Clock, String> {
+    let control: TimeControl = state
+        .get("time")
+        .and_then(|time| time.as_str())
+        .ok_or("the clock needs its time control")?
+        .parse()?;
+    let times = |key: &str| match state.get(key).and_then(|times| times.as_array()) {
+        Some(times) if times.len() == 2 => times
+            .iter()
+            .map(|millis| match millis {
+                toml::Value::Integer(millis) if *millis >= 0 => {
+                    Some(Duration::from_millis(*millis as u64))
+                }
+                _ => None,
+            })
+            .collect::<Option<Vec<Duration>>>()
+            .map(|times| [times[0], times[1]])
+            .ok_or_else(|| format!("the clock's '{}' must give each player's time in milliseconds", key)),
+        Some(_) => Err(format!("the clock's '{}' must give each player's time in milliseconds", key)),
+        None => Ok([Duration::ZERO; 2]),
+    };
+    let used = times("used")?;
+    let left = match state.get("left") {
+        Some(_) => times("left")?,
+        None => used.map(|used| control.main.saturating_sub(used)),
+    };
+    Ok(Clock::new(control, settings.pause_computer).restored(left, used))
+}
+
+// Replace the variations and comments kept in the save at `path` with the game's, leaving
+// the rest of it as it was
+pub fn write_annotations(path: &Path, game: &Game) -> Result<(), String> {
+    let text = fs::read_to_string(path)
+        .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+    let mut table: toml::Table = text
+        .parse()
+        .map_err(|err| format!("could not parse {}: {}", path.display(), err))?;
+    if game.variations.is_empty() {
+        table.remove("variations");
+    } else {
+        table.insert(
+            "variations".to_string(),
+            variation::to_toml(&game.variations),
+        );
+    }
+    if game.comments.is_empty() {
+        table.remove("comments");
+    } else {
+        table.insert("comments".to_string(), comment::to_toml(&game.comments));
+    }
+    log::debug!("saving variations and comments to {}", path.display());
+    fs::write(path, table.to_string())
+        .map_err(|err| format!("could not write {}: {}", path.display(), err))
+}
+
+// The save of the game with this id, looked for in the saves directory
+pub fn find(id: &str) -> Option<PathBuf> {
+    let dir = slot_path(DEFAULT_SLOT)?.parent()?.to_path_buf();
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "toml")
+        })
+        .find(|path| {
+            fs::read_to_string(path)
+                .ok()
+                .and_then(|text| text.parse::<toml::Table>().ok())
+                .is_some_and(|table| {
+                    table
+                        .get("id")
+                        .and_then(|value| value.as_str())
+                        .is_some_and(|saved| saved.eq_ignore_ascii_case(id))
+                })
+        })
+}
@@ -0,0 +1,98 @@
+use std::cmp::Ordering;
+
+use crate::ai;
+use crate::variant::Variant;
+use crate::{Game, Player};
+
+impl Game {
+    // Lines each of the two players has on the board, for the scoring variant. Every window
+    // of `connect` cells in a row counts, so a line of five is two fours and counts twice.
+    // Pieces only ever drop in under these rules, so a line once made stays counted.
+    pub fn line_tallies(&self) -> [usize; 2] {
+        let mut tallies = [0; 2];
+        for cells in ai::windows(self) {
+            match cells[0] {
+                Some(player @ (Player::One | Player::Two))
+                    if cells.iter().all(|&other| other == Some(player)) =>
+                {
+                    tallies[player.index()] += 1
+                }
+                _ => {}
+            }
+        }
+        tallies
+    }
+
+    // The tally as the header shows it, like "Alice 3 – Bob 2", under the scoring variant
+    pub fn tally_line(&self) -> Option<String> {
+        if self.rules.variant != Variant::Score {
+            return None;
+        }
+        let [one, two] = self.line_tallies();
+        Some(format!(
+            "{} {} – {} {}",
+            self.name(Player::One),
+            one,
+            self.name(Player::Two),
+            two
+        ))
+    }
+
+    // Whoever has more lines once the board is full, or nobody on a tie
+    pub fn scored_winner(&self) -> Option<Player> {
+        let [one, two] = self.line_tallies();
+        match one.cmp(&two) {
+            Ordering::Greater => Some(Player::One),
+            Ordering::Less => Some(Player::Two),
+            Ordering::Equal => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Settings;
+    use crate::variant::Move;
+
+    #[test]
+    fn every_window_of_a_longer_line_counts() {
+        let mut game = Game::builder(&Settings::default())
+            .variant(Variant::Score)
+            .build()
+            .expect("the scoring variant is playable");
+        // The first player along the bottom row, the second on top of them
+        let tallies = [
+            [0, 0],
+            [0, 0],
+            [0, 0],
+            [0, 0],
+            [0, 0],
+            [0, 0],
+            [1, 0],
+            [1, 1],
+            [2, 1],
+            [2, 2],
+            [3, 2],
+        ];
+        for (&column, tally) in [1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6].iter().zip(tallies) {
+            game.play(Move::Drop(column - 1))
+                .expect("the moves are legal");
+            assert_eq!(game.line_tallies(), tally, "after {:?}", game.history);
+        }
+        assert!(!game.is_finished(), "lines don't end a scored game");
+        assert_eq!(
+            game.tally_line().as_deref(),
+            Some("Player 1 3 – Player 2 2")
+        );
+
+        // A column of four up from the end of the bottom row counts as a line of its own
+        for column in [7, 6, 7, 6, 7] {
+            game.play(Move::Drop(column - 1))
+                .expect("the moves are legal");
+        }
+        assert_eq!(game.line_tallies(), [3, 2]);
+        game.play(Move::Drop(5)).expect("the column has room");
+        assert_eq!(game.line_tallies(), [4, 2]);
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ai;
+use crate::i18n;
+use crate::Game;
+
+// Who moves first: Player 1, Player 2, whoever a coin flip picks before every game, or the
+// two in turn, swapping seats with every rematch. Whoever it is plays as the first seat, so
+// everything counted by seat counts them as the one who started.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum First {
+    P1,
+    P2,
+    Random,
+    Alternate,
+}
+
+impl First {
+    // Whether the session's first game starts with the players swapped
+    pub fn swap_first(self) -> bool {
+        match self {
+            First::P2 => true,
+            First::Random => coin_flip(),
+            First::P1 | First::Alternate => false,
+        }
+    }
+
+    // Whether a rematch swaps the players, when `chosen` is what they asked for
+    pub fn swap_rematch(self, chosen: bool) -> bool {
+        match self {
+            First::Random => coin_flip(),
+            First::Alternate => true,
+            First::P1 | First::P2 => chosen,
+        }
+    }
+}
+
+// How long a match runs: until one player has won more than half of N games' points, or
+// until one has N points
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchFormat {
+    BestOf(u32),
+    FirstTo(u32),
+}
+
+impl MatchFormat {
+    // Whether a player with this many half points has won the match
+    fn won(self, half_points: u32) -> bool {
+        match self {
+            MatchFormat::BestOf(games) => half_points > games,
+            MatchFormat::FirstTo(points) => half_points >= 2 * points,
+        }
+    }
+}
+
+// "best-of-5" or "first-to-3"
+impl FromStr for MatchFormat {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<MatchFormat, String> {
+        let error = || format!("expected best-of-N or first-to-N, got '{}'", text);
+        let (format, count): (fn(u32) -> MatchFormat, &str) =
+            if let Some(count) = text.strip_prefix("best-of-") {
+                (MatchFormat::BestOf, count)
+            } else if let Some(count) = text.strip_prefix("first-to-") {
+                (MatchFormat::FirstTo, count)
+            } else {
+                return Err(error());
+            };
+        match count.parse::<u32>() {
+            Ok(count) if count > 0 => Ok(format(count)),
+            _ => Err(error()),
+        }
+    }
+}
+
+impl fmt::Display for MatchFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchFormat::BestOf(games) => write!(f, "best-of-{}", games),
+            MatchFormat::FirstTo(points) => write!(f, "first-to-{}", points),
+        }
+    }
+}
+
+// Results of the games played this session, credited to the players by name so swapping
+// colors between games doesn't mix up the tally
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Session {
+    // Wins of each player, in the order they first played
+    wins: Vec<(String, u32)>,
+    pub draws: u32,
+}
+
+impl Session {
+    // Count a finished game
+    pub fn record(&mut self, game: &Game) {
+        if !game.is_finished() {
+            return;
+        }
+        for name in &game.names {
+            if !self.wins.iter().any(|(player, _)| player == name) {
+                self.wins.push((name.clone(), 0));
+            }
+        }
+        match game.winner() {
+            None => self.draws += 1,
+            Some(winner) => {
+                let name = game.name(winner);
+                if let Some((_, wins)) = self.wins.iter_mut().find(|(player, _)| player == name) {
+                    *wins += 1;
+                }
+            }
+        }
+    }
+
+    // The tally as it would be with `game` counted too
+    pub fn with(&self, game: &Game) -> Session {
+        let mut tally = self.clone();
+        tally.record(game);
+        tally
+    }
+
+    // Games played so far
+    pub fn games(&self) -> u32 {
+        self.draws + self.wins.iter().map(|&(_, wins)| wins).sum::<u32>()
+    }
+
+    // Games won by each player so far, in the order they first played
+    pub fn wins(&self) -> &[(String, u32)] {
+        &self.wins
+    }
+
+    // Each player's match points, counted in halves: two for a win, and one for a draw when
+    // draws count
+    fn half_points(&self, half_point_draws: bool) -> Vec<(&str, u32)> {
+        let draws = if half_point_draws { self.draws } else { 0 };
+        self.wins
+            .iter()
+            .map(|(name, wins)| (name.as_str(), 2 * wins + draws))
+            .collect()
+    }
+
+    // "Alice wins the match 3–1", once someone has
+    pub fn match_result(&self, format: MatchFormat, half_point_draws: bool) -> Option<String> {
+        let points = self.half_points(half_point_draws);
+        let &(winner, won) = points.iter().find(|&&(_, points)| format.won(points))?;
+        let lost = points
+            .iter()
+            .filter(|&&(name, _)| name != winner)
+            .map(|&(_, points)| points)
+            .max()
+            .unwrap_or(0);
+        Some(format!(
+            "{} wins the match {}–{}",
+            winner,
+            format_points(won),
+            format_points(lost)
+        ))
+    }
+
+    // Match points in one line, e.g. "Alice 2½ – Bob 1½"
+    pub fn match_score(&self, half_point_draws: bool) -> String {
+        self.half_points(half_point_draws)
+            .iter()
+            .map(|&(name, points)| format!("{} {}", name, format_points(points)))
+            .collect::<Vec<String>>()
+            .join(" – ")
+    }
+
+    // The tally in one line, e.g. "Session: Alice 3 – Bob 2 – draws 1 (6 games)"
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = self
+            .wins
+            .iter()
+            .map(|(name, wins)| format!("{} {}", name, wins))
+            .collect();
+        parts.push(i18n::fill("session.draws", &[("count", &self.draws)]));
+        let games = i18n::plural("session.games", self.games() as usize);
+        i18n::fill("session.summary", &[("tally", &parts.join(" – ")), ("games", &games)])
+    }
+}
+
+// Heads or tails, going by the microseconds since some clocks don't count single nanoseconds
+fn coin_flip() -> bool {
+    (ai::random() / 1000).is_multiple_of(2)
+}
+
+// Half points as a score: 5 is "2½"
+fn format_points(half_points: u32) -> String {
+    match (half_points / 2, half_points % 2) {
+        (0, 1) => "½".to_string(),
+        (whole, 1) => format!("{}½", whole),
+        (whole, _) => whole.to_string(),
+    }
+}
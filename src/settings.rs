@@ -0,0 +1,1650 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::ai::{Difficulty, Opponent};
+use crate::cli::Options;
+use crate::clock::{self, TimeControl, TimeoutAction};
+use crate::handicap::{self, Placement};
+use crate::i18n;
+use crate::session::{First, MatchFormat};
+use crate::presets;
+use crate::profile;
+use crate::rules::GameRules;
+use crate::render::{Density, Ruler};
+use crate::style::{Color, ColorChoice};
+use crate::theme::{validate_symbol, Theme, THEME_NAMES};
+use crate::variant::Variant;
+use crate::Player;
+
+// One configurable setting: its "section.name" key in the config file and its
+// environment variable
+struct Key {
+    name: &'static str,
+    env: &'static str,
+}
+
+// Every setting, in the order `config show` lists them
+const KEYS: [Key; 54] = [
+    Key {
+        name: "display.ui",
+        env: "CONNECT_FOUR_UI",
+    },
+    Key {
+        name: "display.theme",
+        env: "CONNECT_FOUR_THEME",
+    },
+    Key {
+        name: "display.ascii",
+        env: "CONNECT_FOUR_ASCII",
+    },
+    Key {
+        name: "display.color",
+        env: "CONNECT_FOUR_COLOR",
+    },
+    Key {
+        name: "display.cell_width",
+        env: "CONNECT_FOUR_CELL_WIDTH",
+    },
+    Key {
+        name: "display.scale",
+        env: "CONNECT_FOUR_SCALE",
+    },
+    Key {
+        name: "display.altscreen",
+        env: "CONNECT_FOUR_ALTSCREEN",
+    },
+    Key {
+        name: "display.animation",
+        env: "CONNECT_FOUR_ANIMATION",
+    },
+    Key {
+        name: "display.ruler",
+        env: "CONNECT_FOUR_RULER",
+    },
+    Key {
+        name: "display.layout",
+        env: "CONNECT_FOUR_LAYOUT",
+    },
+    Key {
+        name: "display.coords",
+        env: "CONNECT_FOUR_COORDS",
+    },
+    Key {
+        name: "display.marker",
+        env: "CONNECT_FOUR_MARKER",
+    },
+    Key {
+        name: "display.accessible",
+        env: "CONNECT_FOUR_ACCESSIBLE",
+    },
+    Key {
+        name: "display.language",
+        env: "CONNECT_FOUR_LANGUAGE",
+    },
+    Key {
+        name: "gameplay.preset",
+        env: "CONNECT_FOUR_PRESET",
+    },
+    Key {
+        name: "gameplay.width",
+        env: "CONNECT_FOUR_WIDTH",
+    },
+    Key {
+        name: "gameplay.height",
+        env: "CONNECT_FOUR_HEIGHT",
+    },
+    Key {
+        name: "gameplay.connect",
+        env: "CONNECT_FOUR_CONNECT",
+    },
+    Key {
+        name: "gameplay.players",
+        env: "CONNECT_FOUR_PLAYERS",
+    },
+    Key {
+        name: "gameplay.opponent",
+        env: "CONNECT_FOUR_OPPONENT",
+    },
+    Key {
+        name: "gameplay.difficulty",
+        env: "CONNECT_FOUR_DIFFICULTY",
+    },
+    Key {
+        name: "gameplay.match",
+        env: "CONNECT_FOUR_MATCH",
+    },
+    Key {
+        name: "gameplay.match_draws",
+        env: "CONNECT_FOUR_MATCH_DRAWS",
+    },
+    Key {
+        name: "gameplay.first",
+        env: "CONNECT_FOUR_FIRST",
+    },
+    Key {
+        name: "gameplay.pie_rule",
+        env: "CONNECT_FOUR_PIE_RULE",
+    },
+    Key {
+        name: "gameplay.handicap",
+        env: "CONNECT_FOUR_HANDICAP",
+    },
+    Key {
+        name: "gameplay.placement",
+        env: "CONNECT_FOUR_PLACEMENT",
+    },
+    Key {
+        name: "gameplay.start_moves",
+        env: "CONNECT_FOUR_RANDOM_START",
+    },
+    Key {
+        name: "gameplay.seed",
+        env: "CONNECT_FOUR_SEED",
+    },
+    Key {
+        name: "gameplay.variant",
+        env: "CONNECT_FOUR_VARIANT",
+    },
+    Key {
+        name: "gameplay.blind",
+        env: "CONNECT_FOUR_BLIND",
+    },
+    Key {
+        name: "gameplay.peeks",
+        env: "CONNECT_FOUR_PEEKS",
+    },
+    Key {
+        name: "clock.time",
+        env: "CONNECT_FOUR_TIME",
+    },
+    Key {
+        name: "clock.preset",
+        env: "CONNECT_FOUR_TC",
+    },
+    Key {
+        name: "clock.pause_computer",
+        env: "CONNECT_FOUR_PAUSE_COMPUTER",
+    },
+    Key {
+        name: "clock.move_timeout",
+        env: "CONNECT_FOUR_MOVE_TIMEOUT",
+    },
+    Key {
+        name: "clock.timeout_action",
+        env: "CONNECT_FOUR_TIMEOUT_ACTION",
+    },
+    Key {
+        name: "ratings.rated",
+        env: "CONNECT_FOUR_RATED",
+    },
+    Key {
+        name: "ratings.k_factor",
+        env: "CONNECT_FOUR_K_FACTOR",
+    },
+    Key {
+        name: "ratings.assists",
+        env: "CONNECT_FOUR_RATED_ASSISTS",
+    },
+    Key {
+        name: "input.mode",
+        env: "CONNECT_FOUR_INPUT",
+    },
+    Key {
+        name: "input.digits",
+        env: "CONNECT_FOUR_DIGITS",
+    },
+    Key {
+        name: "players.one",
+        env: "CONNECT_FOUR_P1_NAME",
+    },
+    Key {
+        name: "players.two",
+        env: "CONNECT_FOUR_P2_NAME",
+    },
+    Key {
+        name: "players.three",
+        env: "CONNECT_FOUR_P3_NAME",
+    },
+    Key {
+        name: "players.four",
+        env: "CONNECT_FOUR_P4_NAME",
+    },
+    Key {
+        name: "players.one_symbol",
+        env: "CONNECT_FOUR_P1_SYMBOL",
+    },
+    Key {
+        name: "players.two_symbol",
+        env: "CONNECT_FOUR_P2_SYMBOL",
+    },
+    Key {
+        name: "players.one_color",
+        env: "CONNECT_FOUR_P1_COLOR",
+    },
+    Key {
+        name: "players.two_color",
+        env: "CONNECT_FOUR_P2_COLOR",
+    },
+    Key {
+        name: "transcript.enabled",
+        env: "CONNECT_FOUR_TRANSCRIPT",
+    },
+    Key {
+        name: "transcript.file",
+        env: "CONNECT_FOUR_LOG_FILE",
+    },
+    Key {
+        name: "transcript.max_size",
+        env: "CONNECT_FOUR_TRANSCRIPT_MAX_SIZE",
+    },
+    Key {
+        name: "transcript.keep",
+        env: "CONNECT_FOUR_TRANSCRIPT_KEEP",
+    },
+];
+
+// Which interface the game is played in. This and how moves are entered live here rather
+// than with tui and keys, which need a terminal, so the settings build without one.
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Ui {
+    // Prompts and redrawn boards, one line of input per move
+    Line,
+    // Full-screen panels with single-key input
+    Tui,
+}
+
+// How moves are entered in the line interface
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Input {
+    // A column per line, confirmed with Enter
+    Line,
+    // Single key presses steering a column cursor
+    Keys,
+}
+
+// What pressing a column's digit or letter does in key input
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum Digits {
+    // Move the cursor there; pressing it again or Enter drops the piece
+    Select,
+    // Drop the piece right away
+    Drop,
+}
+
+// Where a resolved value came from
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    Environment(&'static str),
+    CommandLine,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "config file {}", path.display()),
+            Source::Environment(var) => write!(f, "environment {}", var),
+            Source::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+// Longest player name, in characters
+const MAX_NAME: usize = 20;
+
+// Commented default file written by `config init`
+const DEFAULT_CONFIG: &str = r##"# Connect Four configuration
+# Precedence: command-line flags > environment > this file > built-in defaults
+# Every setting can also be set with a CONNECT_FOUR_ environment variable,
+# e.g. CONNECT_FOUR_THEME=ascii or CONNECT_FOUR_P1_NAME=Alice
+
+[display]
+# Interface: line (prompts between redrawn boards) or tui (full screen, single keys)
+# ui = "line"
+# Board appearance: classic, emoji, ascii, minimal, high-contrast, colorblind
+# theme = "emoji"
+# Shortcut for theme = "ascii"
+# ascii = false
+# When to use colors: always, auto, never
+# color = "auto"
+# Terminal columns per board cell (1 or 2); measured from the glyphs when unset
+# cell_width = 2
+# Large print: draw each cell as an NxN block (1 is normal size, up to 5)
+# scale = 1
+# Redraw on the alternate screen instead of appending every position
+# altscreen = true
+# Animate pieces dropping down their column (only when redrawing on the alternate screen)
+# animation = true
+# Where to draw the column numbers: top, bottom, both, off
+# ruler = "bottom"
+# Cell spacing: auto fits the terminal, wide always spaces cells out, compact packs them
+# layout = "auto"
+# Chess-like coordinates: letters for columns, numbers for rows from the bottom
+# coords = false
+# Caret under the column of the last move
+# marker = false
+# Describe the board in words after each move instead of drawing it, for screen readers
+# and braille displays; plays without colors, emoji, animation, or the alternate screen
+# accessible = false
+# Language of the messages, en or es; taken from LANG when unset
+# language = "en"
+
+[gameplay]
+# Rules by name: classic (7x6, connect 4), five (9x7, connect 5), mini (5x4, connect 3),
+# one from [rules], or "custom" to go by the settings below. A rule set here or anywhere
+# that counts for more, like the command line, wins over the preset's; `connect_four
+# presets` lists them all.
+# preset = "custom"
+# width = 7
+# height = 6
+# Pieces in a row needed to win
+# connect = 4
+# Players taking turns, 2 to 4. With more than two the board defaults to 9x7 for three and
+# 10x8 for four, and games are never rated and have no clock, handicap, or variant.
+# players = 2
+# Who plays as Player 2: human or computer
+# opponent = "human"
+# How hard the computer plays: easy, medium, hard
+# difficulty = "medium"
+# Play a match of several games: "best-of-N", "first-to-N", or "off"
+# match = "off"
+# What a drawn match game is worth: "half" a point to each player, or "none"
+# match_draws = "half"
+# Who moves first: p1, p2, random (a coin flip before every game), or alternate (the other
+# player every rematch)
+# first = "p1"
+# Let the second player answer the first move by swapping: taking it over as their own, so
+# the other player moves next
+# pie_rule = false
+# Pieces the second player starts with, for games between unequal players; the first player
+# moves once they're down. Handicap games are never rated.
+# handicap = 0
+# Where the handicap goes: fixed (the bottom of the center columns) or choose (dropped one
+# at a time by the player given it)
+# placement = "fixed"
+# Start every game from a random position this many moves in, with nobody connected and
+# neither side able to force a win within two moves; 0 starts from the empty board
+# start_moves = 0
+# Seed of the first game's random start, like 12345 to play one shown before again, or
+# "random" for a new one
+# seed = "random"
+# The rules: standard, popout (a player may instead pop one of their own pieces out of
+# the bottom row, letting the ones above fall), powerup (each player also has one anvil,
+# which crushes a column, and one bomb, which blows up a column's top piece; never rated),
+# cylinder (the board wraps around, so lines can run off the right edge onto the left),
+# misere (whoever connects a line loses), score (play goes on until the board is full,
+# and whoever made the most lines wins), flip (each player may once turn the board
+# upside down instead of dropping a piece), or 3d (pieces drop onto the pegs of a 4x4 grid
+# four levels high, and lines run along any axis or diagonal of the cube; the size is
+# fixed, and it's played in the line interface with typed pegs like b3)
+# variant = "standard"
+# Play blind: once the first move is made the board is hidden behind the move list, until
+# the game is over or a player peeks at it for two seconds with :peek
+# blind = false
+# How many peeks a blind game allows
+# peeks = 3
+
+[clock]
+# Time on each player's clock for the whole game, or "off" for untimed; a player whose time
+# runs out loses. "5m" counts down plainly, "3m+2s" adds 2 seconds after every move, and
+# "5m d3" lets the first 3 seconds of each move go by before the clock runs.
+# time = "off"
+# A named time control in place of time: blitz (3m+2s), rapid (10m), classical (30m), one
+# from [presets], or "custom" to go by time. Whichever of the two is set with the higher
+# precedence wins, and preset when they're set in the same place.
+# preset = "custom"
+# Stop the computer's clock while it works out its move
+# pause_computer = false
+# Time for every move on its own, like "30s", or "off"; the computer's moves aren't timed
+# move_timeout = "off"
+# What happens when a move's time runs out: "forfeit" the game, or play a "random" column
+# timeout_action = "forfeit"
+
+[presets]
+# Time controls to pick by name with clock.preset or --tc, replacing the built-in ones of the
+# same name
+# office = "2m+1s"
+# blitz = "5m+3s"
+
+[rules]
+# Rule presets to pick by name with gameplay.preset or --preset, replacing the built-in
+# ones of the same name. Each gives any of width, height, connect, variant, and first; the
+# rest are as in classic.
+# big = { width = 11, height = 9, connect = 5 }
+# flipper = { variant = "flip", first = "random" }
+
+[ratings]
+# Whether finished games change the players' Elo ratings; --casual turns it off for a session
+# rated = true
+# How far one game can move a rating
+# k_factor = 32
+# Hints and takebacks each player may use in a game that still counts for ratings
+# assists = 0
+
+[input]
+# How moves are entered: line (type a column and Enter) or keys (arrow keys and single presses)
+# mode = "line"
+# With keys, a column's digit or letter can select it (press again to drop) or drop right away
+# digits = "select"
+
+[players]
+# Names, up to 20 characters each; no two may be the same. The third and fourth only play
+# in games of more players.
+# one = "Player 1"
+# two = "Player 2"
+# three = "Player 3"
+# four = "Player 4"
+# Piece symbols and colors replacing the theme's (named colors or "#rrggbb")
+# one_symbol = "X"
+# two_symbol = "■"
+# one_color = "red"
+# two_color = "#ffd700"
+
+[transcript]
+# Log every game's moves, errors, and results with timestamps; --no-transcript turns it off
+# enabled = true
+# File to append to; by default one file a day in the data directory's connect_four/transcripts
+# file = "/home/me/connect_four.log"
+# Size in kilobytes at which the file is rotated to <file>.1, and how many rotated files to keep
+# max_size = 1024
+# keep = 5
+
+"##;
+
+// Every setting after resolution
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub ui: Ui,
+    pub theme: String,
+    pub color: ColorChoice,
+    pub cell_width: Option<usize>,
+    pub scale: usize,
+    pub altscreen: bool,
+    pub animation: bool,
+    pub ruler: Ruler,
+    pub density: Density,
+    pub coords: bool,
+    pub marker: bool,
+    // The board is described in words instead of drawn
+    pub accessible: bool,
+    // Language the game's messages are shown in
+    pub language: String,
+    pub width: usize,
+    pub height: usize,
+    pub connect: usize,
+    // How many players take turns, from two to four
+    pub players: usize,
+    // Player 2 is played by the computer at this difficulty
+    pub computer: bool,
+    pub difficulty: Difficulty,
+    // Games are played as a match with this format; draws give each player half a point
+    // unless `half_point_draws` is off
+    pub match_format: Option<MatchFormat>,
+    pub half_point_draws: bool,
+    // Who moves first, and whether the second player may swap after the first move
+    pub first: First,
+    pub pie_rule: bool,
+    // Pieces the second player is given before the first move, and how they're placed
+    pub handicap: usize,
+    pub placement: Placement,
+    // Moves played at random before the players take over, and the seed of the first game's
+    // random start when it was asked for
+    pub random_start: usize,
+    pub seed: Option<u32>,
+    // The rules games are played by
+    pub variant: Variant,
+    // The rule preset picked, if there is one, and every preset by name
+    pub rules_preset: Option<String>,
+    pub rule_presets: Vec<(String, GameRules)>,
+    // Whether games are played with the board hidden, and how many peeks at it each allows
+    pub blind: bool,
+    pub peeks: usize,
+    // The time control when games are played on a clock, and whether the computer's clock
+    // is stopped while it thinks
+    pub time: Option<TimeControl>,
+    pub pause_computer: bool,
+    // The preset the time control is taken from, if it's one, and every preset by name
+    pub preset: Option<String>,
+    pub presets: Vec<(String, TimeControl)>,
+    // The limit on every move, and what happens to a player who goes over it
+    pub move_timeout: Option<Duration>,
+    pub timeout_action: TimeoutAction,
+    // Elo ratings: whether games count, how much one can change a rating, and the hints and
+    // takebacks a player may use in a game that counts
+    pub rated: bool,
+    pub k_factor: u32,
+    pub assists: u32,
+    pub input: Input,
+    pub digits: Digits,
+    // Names of all four seats, of which the first `players` play
+    pub names: [String; 4],
+    // Piece glyph and color overrides for each player, on top of the theme
+    pub symbols: [Option<String>; 2],
+    pub piece_colors: [Option<String>; 2],
+    // Timestamped log of the session's games: whether to write one, the file instead of the
+    // daily one, and the size in kilobytes and number of old files for rotating it
+    pub transcript: bool,
+    pub log_file: Option<PathBuf>,
+    pub transcript_max_size: u64,
+    pub transcript_keep: u32,
+    // Origin of each value, by key
+    sources: Vec<(&'static str, Source)>,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            ui: Ui::Line,
+            theme: "emoji".to_string(),
+            color: ColorChoice::Auto,
+            cell_width: None,
+            scale: 1,
+            altscreen: true,
+            animation: true,
+            ruler: Ruler::Bottom,
+            density: Density::Auto,
+            coords: false,
+            marker: false,
+            accessible: false,
+            language: "en".to_string(),
+            width: GameRules::CLASSIC.width,
+            height: GameRules::CLASSIC.height,
+            connect: GameRules::CLASSIC.connect,
+            players: 2,
+            computer: false,
+            difficulty: Difficulty::Medium,
+            match_format: None,
+            half_point_draws: true,
+            first: First::P1,
+            pie_rule: false,
+            handicap: 0,
+            placement: Placement::Fixed,
+            random_start: 0,
+            seed: None,
+            variant: Variant::Standard,
+            rules_preset: None,
+            rule_presets: presets::BUILT_IN
+                .iter()
+                .map(|&(name, rules)| (name.to_string(), rules))
+                .collect(),
+            blind: false,
+            peeks: 3,
+            time: None,
+            pause_computer: false,
+            preset: None,
+            presets: clock::PRESETS
+                .iter()
+                .map(|&(name, text)| {
+                    let control = clock::preset(name, text).expect("built-in presets are valid");
+                    (name.to_string(), control)
+                })
+                .collect(),
+            move_timeout: None,
+            timeout_action: TimeoutAction::Forfeit,
+            rated: true,
+            k_factor: 32,
+            assists: 0,
+            input: Input::Line,
+            digits: Digits::Select,
+            names: Player::SEATS.map(|seat| seat.to_string()),
+            symbols: [None, None],
+            piece_colors: [None, None],
+            transcript: true,
+            log_file: None,
+            transcript_max_size: 1024,
+            transcript_keep: 5,
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl Settings {
+    // Resolve every setting once, in priority order: command-line flag, environment, config
+    // file, built-in default. Returns the settings and any warnings about the config file.
+    pub fn resolve(options: &Options) -> Result<(Settings, Vec<String>), String> {
+        Settings::resolve_with_env(options, |var| std::env::var(var).ok())
+    }
+
+    // Resolve with a custom environment lookup
+    pub fn resolve_with_env(
+        options: &Options,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<(Settings, Vec<String>), String> {
+        let mut settings = Settings::default();
+        let mut warnings = Vec::new();
+
+        // A missing default file is fine, a missing explicit one is not
+        if let Some(path) = config_path(options) {
+            if options.config.is_some() || path.exists() {
+                log::debug!("reading settings from {}", path.display());
+                warnings = settings.apply_file(&path)?;
+            }
+        }
+        // A profile's own settings go over the shared ones
+        if let Some(path) = options.profile.as_deref().and_then(profile::config_path) {
+            if path.exists() {
+                warnings.extend(settings.apply_file(&path)?);
+            }
+        }
+
+        settings.apply_env(&env)?;
+        settings.apply_options(options)?;
+        settings.apply_locale(&env);
+        settings.apply_preset()?;
+        settings.apply_rules_preset()?;
+        settings.apply_players();
+        settings.apply_variant();
+        settings.apply_accessible();
+        settings.validate()?;
+
+        Ok((settings, warnings))
+    }
+
+    // Set one key ("section.name") from its textual value and remember where it came from
+    fn set(&mut self, key: &str, value: &str, source: Source) -> Result<(), String> {
+        self.assign(key, value)?;
+
+        // --ascii and ascii = true are shorthands for the theme
+        let key = if key == "display.ascii" {
+            "display.theme"
+        } else {
+            key
+        };
+        let key = KEYS
+            .iter()
+            .find(|known| known.name == key)
+            .map_or("", |known| known.name);
+        self.sources.retain(|(name, _)| *name != key);
+        self.sources.push((key, source));
+        Ok(())
+    }
+
+    fn assign(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "display.ui" => {
+                self.ui = Ui::from_str(value, true)
+                    .map_err(|_| format!("expected line or tui, got '{}'", value))?
+            }
+            "display.theme" => {
+                if !THEME_NAMES.contains(&value) {
+                    return Err(format!("unknown theme '{}'", value));
+                }
+                self.theme = value.to_string();
+            }
+            "display.ascii" => {
+                if parse_bool(value)? {
+                    self.theme = "ascii".to_string();
+                }
+            }
+            "display.color" => {
+                self.color = match value {
+                    "always" => ColorChoice::Always,
+                    "auto" => ColorChoice::Auto,
+                    "never" => ColorChoice::Never,
+                    _ => return Err(format!("expected always, auto, or never, got '{}'", value)),
+                }
+            }
+            "display.cell_width" => self.cell_width = Some(parse_number(value)?),
+            "display.scale" => self.scale = parse_number(value)?,
+            "display.altscreen" => self.altscreen = parse_bool(value)?,
+            "display.animation" => self.animation = parse_bool(value)?,
+            "display.coords" => self.coords = parse_bool(value)?,
+            "display.marker" => self.marker = parse_bool(value)?,
+            "display.accessible" => self.accessible = parse_bool(value)?,
+            "display.language" => {
+                if !i18n::LANGUAGES.contains(&value) {
+                    return Err(format!("expected en or es, got '{}'", value));
+                }
+                self.language = value.to_string();
+            }
+            "display.ruler" => {
+                self.ruler = Ruler::from_str(value, true)
+                    .map_err(|_| format!("expected top, bottom, both, or off, got '{}'", value))?
+            }
+            "display.layout" => {
+                self.density = Density::from_str(value, true)
+                    .map_err(|_| format!("expected auto, wide, or compact, got '{}'", value))?
+            }
+            "gameplay.preset" => {
+                self.rules_preset = match value {
+                    "custom" => None,
+                    _ => Some(value.to_string()),
+                }
+            }
+            "gameplay.width" => self.width = parse_number(value)?,
+            "gameplay.height" => self.height = parse_number(value)?,
+            "gameplay.connect" => self.connect = parse_number(value)?,
+            "gameplay.players" => self.players = parse_number(value)?,
+            "gameplay.opponent" => {
+                self.computer = match value {
+                    "human" => false,
+                    "computer" => true,
+                    _ => return Err(format!("expected human or computer, got '{}'", value)),
+                }
+            }
+            "gameplay.difficulty" => {
+                self.difficulty = Difficulty::from_str(value, true)
+                    .map_err(|_| format!("expected easy, medium, or hard, got '{}'", value))?
+            }
+            "gameplay.match" => {
+                self.match_format = match value {
+                    "off" => None,
+                    _ => Some(value.parse()?),
+                }
+            }
+            "gameplay.first" => {
+                self.first = First::from_str(value, true).map_err(|_| {
+                    format!("expected p1, p2, random, or alternate, got '{}'", value)
+                })?
+            }
+            "gameplay.pie_rule" => self.pie_rule = parse_bool(value)?,
+            "gameplay.handicap" => self.handicap = parse_number(value)?,
+            "gameplay.start_moves" => self.random_start = parse_number(value)?,
+            "gameplay.seed" => {
+                self.seed = match value {
+                    "random" => None,
+                    _ => Some(value.parse().map_err(|_| {
+                        format!("expected a whole number or random, got '{}'", value)
+                    })?),
+                }
+            }
+            "gameplay.placement" => {
+                self.placement = Placement::from_str(value, true)
+                    .map_err(|_| format!("expected fixed or choose, got '{}'", value))?
+            }
+            "gameplay.variant" => {
+                self.variant = Variant::parse(value)
+                    .ok_or_else(|| format!("expected standard, popout, powerup, cylinder, misere, score, flip, or 3d, got '{}'", value))?
+            }
+            "gameplay.blind" => self.blind = parse_bool(value)?,
+            "gameplay.peeks" => {
+                self.peeks = value
+                    .parse()
+                    .map_err(|_| format!("expected a number, got '{}'", value))?
+            }
+            "gameplay.match_draws" => {
+                self.half_point_draws = match value {
+                    "half" => true,
+                    "none" => false,
+                    _ => return Err(format!("expected half or none, got '{}'", value)),
+                }
+            }
+            "clock.time" => {
+                self.time = match value {
+                    "off" => None,
+                    _ => Some(value.parse()?),
+                }
+            }
+            "clock.preset" => {
+                self.preset = match value {
+                    "custom" => None,
+                    _ => Some(value.to_string()),
+                }
+            }
+            "clock.pause_computer" => self.pause_computer = parse_bool(value)?,
+            "clock.move_timeout" => {
+                self.move_timeout = match value {
+                    "off" => None,
+                    _ => Some(clock::parse_limit(value)?),
+                }
+            }
+            "clock.timeout_action" => {
+                self.timeout_action = TimeoutAction::from_str(value, true)
+                    .map_err(|_| format!("expected forfeit or random, got '{}'", value))?
+            }
+            "ratings.rated" => self.rated = parse_bool(value)?,
+            "ratings.k_factor" => {
+                self.k_factor = match value.parse::<u32>() {
+                    Ok(k_factor) if (1..=100).contains(&k_factor) => k_factor,
+                    _ => return Err(format!("expected a number from 1 to 100, got '{}'", value)),
+                }
+            }
+            "ratings.assists" => {
+                self.assists = value
+                    .parse()
+                    .map_err(|_| format!("expected a number, got '{}'", value))?
+            }
+            "input.mode" => {
+                self.input = Input::from_str(value, true)
+                    .map_err(|_| format!("expected line or keys, got '{}'", value))?
+            }
+            "input.digits" => {
+                self.digits = Digits::from_str(value, true)
+                    .map_err(|_| format!("expected select or drop, got '{}'", value))?
+            }
+            "players.one" => self.names[0] = clean_name(value)?,
+            "players.two" => self.names[1] = clean_name(value)?,
+            "players.three" => self.names[2] = clean_name(value)?,
+            "players.four" => self.names[3] = clean_name(value)?,
+            "players.one_symbol" | "players.two_symbol" => {
+                validate_symbol(value)?;
+                self.symbols[usize::from(key == "players.two_symbol")] = Some(value.to_string());
+            }
+            "players.one_color" | "players.two_color" => {
+                Color::parse(value)?;
+                self.piece_colors[usize::from(key == "players.two_color")] =
+                    Some(value.to_string());
+            }
+            "transcript.enabled" => self.transcript = parse_bool(value)?,
+            "transcript.file" => {
+                self.log_file = (!value.is_empty()).then(|| PathBuf::from(value));
+            }
+            "transcript.max_size" => {
+                self.transcript_max_size = match value.parse::<u64>() {
+                    Ok(size) if size > 0 => size,
+                    _ => return Err(format!("expected a size in kilobytes, got '{}'", value)),
+                }
+            }
+            "transcript.keep" => {
+                self.transcript_keep = value
+                    .parse()
+                    .map_err(|_| format!("expected a number, got '{}'", value))?
+            }
+            _ => return Err(format!("unknown setting '{}'", key)),
+        }
+        Ok(())
+    }
+
+    // Apply the config file, warning about keys it doesn't know
+    fn apply_file(&mut self, path: &Path) -> Result<Vec<String>, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| format!("could not read config file {}: {}", path.display(), err))?;
+        let table: toml::Table = text
+            .parse()
+            .map_err(|err| format!("could not parse config file {}:\n{}", path.display(), err))?;
+
+        let mut warnings = Vec::new();
+        for (section, entries) in &table {
+            if section == "presets" {
+                self.add_presets(path, entries)?;
+                continue;
+            }
+            if section == "rules" {
+                self.add_rule_presets(path, entries)?;
+                continue;
+            }
+            let prefix = format!("{}.", section);
+            let known_section = KEYS.iter().any(|key| key.name.starts_with(&prefix));
+            let (true, Some(entries)) = (known_section, entries.as_table()) else {
+                warnings.push(format!("{}: unknown key '{}'", path.display(), section));
+                continue;
+            };
+
+            for (name, value) in entries {
+                let key = format!("{}{}", prefix, name);
+                if !KEYS.iter().any(|known| known.name == key) {
+                    warnings.push(format!("{}: unknown key '{}'", path.display(), key));
+                    continue;
+                }
+
+                let value = match value {
+                    toml::Value::String(text) => text.clone(),
+                    toml::Value::Integer(_) | toml::Value::Boolean(_) => value.to_string(),
+                    _ => {
+                        return Err(format!(
+                            "{}: '{}' must be a string, number, or boolean",
+                            path.display(),
+                            key
+                        ))
+                    }
+                };
+                self.set(&key, &value, Source::File(path.to_path_buf()))
+                    .map_err(|err| {
+                        format!("{}: invalid value for {}: {}", path.display(), key, err)
+                    })?;
+            }
+        }
+
+        Ok(warnings)
+    }
+
+    // Add or replace the presets of a config file's [presets] section
+    fn add_presets(&mut self, path: &Path, entries: &toml::Value) -> Result<(), String> {
+        let Some(entries) = entries.as_table() else {
+            return Err(format!("{}: 'presets' must be a section", path.display()));
+        };
+        for (name, value) in entries {
+            let Some(text) = value.as_str() else {
+                return Err(format!(
+                    "{}: preset '{}' must be a time control like \"3m+2s\"",
+                    path.display(),
+                    name
+                ));
+            };
+            let control = clock::preset(name, text)
+                .map_err(|err| format!("{}: invalid preset {}: {}", path.display(), name, err))?;
+            match self.presets.iter_mut().find(|(known, _)| known == name) {
+                Some(preset) => preset.1 = control,
+                None => self.presets.push((name.clone(), control)),
+            }
+        }
+        Ok(())
+    }
+
+    // Add or replace the rule presets of a config file's [rules] section
+    fn add_rule_presets(&mut self, path: &Path, entries: &toml::Value) -> Result<(), String> {
+        let Some(entries) = entries.as_table() else {
+            return Err(format!("{}: 'rules' must be a section", path.display()));
+        };
+        for (name, value) in entries {
+            let rules = presets::parse(name, value).map_err(|err| format!("{}: {}", path.display(), err))?;
+            match self.rule_presets.iter_mut().find(|(known, _)| known == name) {
+                Some(preset) => preset.1 = rules,
+                None => self.rule_presets.push((name.clone(), rules)),
+            }
+        }
+        Ok(())
+    }
+
+    // Take the rules from the preset picked, except any rule set as strongly as the preset
+    // or more, which keeps its own value, so --preset five --connect 4 plays to four
+    fn apply_rules_preset(&mut self) -> Result<(), String> {
+        let Some(name) = &self.rules_preset else {
+            return Ok(());
+        };
+        let Some(&(_, rules)) = self.rule_presets.iter().find(|(known, _)| known == name) else {
+            let names: Vec<&str> = self.rule_presets.iter().map(|(name, _)| name.as_str()).collect();
+            return Err(format!("unknown preset '{}'; expected {}, or custom", name, names.join(", ")));
+        };
+        let precedence = self.precedence("gameplay.preset");
+        let source = self.sources.iter().find(|(key, _)| *key == "gameplay.preset").map(|(_, source)| source.clone());
+        let keys = ["gameplay.width", "gameplay.height", "gameplay.connect", "gameplay.variant", "gameplay.first", "gameplay.pie_rule"];
+        for key in keys {
+            if precedence <= self.precedence(key) {
+                continue;
+            }
+            match key {
+                "gameplay.width" => self.width = rules.width,
+                "gameplay.height" => self.height = rules.height,
+                "gameplay.connect" => self.connect = rules.connect,
+                "gameplay.variant" => self.variant = rules.variant,
+                "gameplay.first" => self.first = rules.first,
+                _ => self.pie_rule = rules.pie_rule,
+            }
+            // The rule shows as coming from wherever the preset was picked
+            if let Some(source) = &source {
+                self.sources.retain(|(known, _)| *known != key);
+                self.sources.push((key, source.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    // The rules games are played by, as a preset would give them
+    pub fn rules(&self) -> GameRules {
+        GameRules {
+            width: self.width,
+            height: self.height,
+            connect: self.connect,
+            variant: self.variant,
+            first: self.first,
+            pie_rule: self.pie_rule,
+        }
+    }
+
+    // Play by `rules` for the session
+    pub fn set_rules(&mut self, rules: GameRules) {
+        (self.width, self.height, self.connect) = (rules.width, rules.height, rules.connect);
+        (self.variant, self.first, self.pie_rule) = (rules.variant, rules.first, rules.pie_rule);
+    }
+
+    // The preset picked, as long as the rules are still all its own
+    pub fn preset_name(&self) -> Option<String> {
+        let name = self.rules_preset.as_ref()?;
+        let (_, rules) = self.rule_presets.iter().find(|(known, _)| known == name)?;
+        (*rules == self.rules()).then(|| name.clone())
+    }
+
+    // Switch to the preset `name` for the session, rules and all, unless its rules can't be
+    // played with the other settings
+    pub fn use_preset(&mut self, name: &str, rules: GameRules) -> Result<(), String> {
+        let previous = self.clone();
+        self.rules_preset = Some(name.to_string());
+        self.set_rules(rules);
+        self.validate().inspect_err(|_| *self = previous)
+    }
+
+    // Take the time control from the preset picked, unless the time itself was set with a
+    // higher precedence, in which case there's no preset
+    fn apply_preset(&mut self) -> Result<(), String> {
+        let Some(name) = &self.preset else {
+            return Ok(());
+        };
+        let Some(&(_, control)) = self.presets.iter().find(|(known, _)| known == name) else {
+            let names: Vec<&str> = self.presets.iter().map(|(name, _)| name.as_str()).collect();
+            return Err(format!(
+                "unknown time control preset '{}'; expected {}, or custom",
+                name,
+                names.join(", ")
+            ));
+        };
+        if self.precedence("clock.preset") >= self.precedence("clock.time") {
+            self.time = Some(control);
+            // The time shows as coming from wherever the preset was picked
+            let source = self
+                .sources
+                .iter()
+                .find(|(key, _)| *key == "clock.preset")
+                .map(|(_, source)| source.clone());
+            if let Some(source) = source {
+                self.sources.retain(|(key, _)| *key != "clock.time");
+                self.sources.push(("clock.time", source));
+            }
+        } else {
+            self.preset = None;
+        }
+        Ok(())
+    }
+
+    // Give games of more than two players a bigger board, unless a size was asked for
+    fn apply_players(&mut self) {
+        if self.players <= 2
+            || self.precedence("gameplay.width") > 0
+            || self.precedence("gameplay.height") > 0
+        {
+            return;
+        }
+        (self.width, self.height) = if self.players == 3 { (9, 7) } else { (10, 8) };
+    }
+
+    // A screen reader can't make out colors, emoji, pieces falling, or a board redrawn in
+    // place, so describing the board leaves them all out, along with the full-screen
+    // interface
+    fn apply_accessible(&mut self) {
+        if self.accessible {
+            self.ui = Ui::Line;
+            self.theme = "ascii".to_string();
+            self.color = ColorChoice::Never;
+            self.altscreen = false;
+            self.animation = false;
+        }
+    }
+
+    // Lay a 3D game out on the flat board it's kept on, a column for each peg and a row for
+    // each level; the cube is always 4x4x4, whatever size is set for other games
+    fn apply_variant(&mut self) {
+        self.set_rules(self.rules().with_variant(self.variant));
+    }
+
+    // How strongly a key's value was set: by default, in a config file, in the environment,
+    // or on the command line
+    fn precedence(&self, key: &str) -> u8 {
+        match self.sources.iter().find(|(name, _)| *name == key) {
+            None | Some((_, Source::Default)) => 0,
+            Some((_, Source::File(_))) => 1,
+            Some((_, Source::Environment(_))) => 2,
+            Some((_, Source::CommandLine)) => 3,
+        }
+    }
+
+    // Apply the CONNECT_FOUR_ environment variables
+    fn apply_env(&mut self, env: impl Fn(&str) -> Option<String>) -> Result<(), String> {
+        for key in &KEYS {
+            if let Some(value) = env(key.env) {
+                self.set(key.name, &value, Source::Environment(key.env))
+                    .map_err(|err| format!("invalid value for {}: {}", key.env, err))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Take the language from the locale when nothing else set it, going by the first of
+    // LC_ALL, LC_MESSAGES, and LANG that's set, as other programs do. A locale there are no
+    // messages for leaves them in English.
+    fn apply_locale(&mut self, env: impl Fn(&str) -> Option<String>) {
+        if self.precedence("display.language") > 0 {
+            return;
+        }
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .find_map(|var| env(var).filter(|value| !value.is_empty()).map(|value| (var, value)));
+        if let Some((var, locale)) = locale {
+            if let Some(language) = i18n::from_locale(&locale) {
+                self.language = language.to_string();
+                self.sources.retain(|(name, _)| *name != "display.language");
+                self.sources.push(("display.language", Source::Environment(var)));
+            }
+        }
+    }
+
+    // Apply the flags that were given on the command line
+    fn apply_options(&mut self, options: &Options) -> Result<(), String> {
+        let color = options.color.map(|color| match color {
+            ColorChoice::Always => "always",
+            ColorChoice::Auto => "auto",
+            ColorChoice::Never => "never",
+        });
+        let flags = [
+            (
+                "display.altscreen",
+                options.no_altscreen.then(|| "false".to_string()),
+            ),
+            (
+                "display.animation",
+                options.no_animation.then(|| "false".to_string()),
+            ),
+            ("display.color", color.map(str::to_string)),
+            (
+                "display.color",
+                options.no_color.then(|| "never".to_string()),
+            ),
+            ("display.ui", options.ui.map(|ui| format!("{:?}", ui).to_lowercase())),
+            ("display.theme", options.theme.clone()),
+            (
+                "display.ruler",
+                options
+                    .ruler
+                    .map(|ruler| format!("{:?}", ruler).to_lowercase()),
+            ),
+            ("display.layout", options.wide.then(|| "wide".to_string())),
+            (
+                "display.layout",
+                options.compact.then(|| "compact".to_string()),
+            ),
+            ("display.ascii", options.ascii.then(|| "true".to_string())),
+            ("display.coords", options.coords.then(|| "true".to_string())),
+            ("display.marker", options.marker.then(|| "true".to_string())),
+            ("display.accessible", options.accessible.then(|| "true".to_string())),
+            ("display.language", options.lang.clone()),
+            (
+                "display.cell_width",
+                options.cell_width.map(|width| width.to_string()),
+            ),
+            ("display.scale", options.big.map(|scale| scale.to_string())),
+            (
+                "gameplay.width",
+                options.width.map(|width| width.to_string()),
+            ),
+            (
+                "gameplay.height",
+                options.height.map(|height| height.to_string()),
+            ),
+            ("gameplay.preset", options.preset.clone()),
+            (
+                "gameplay.connect",
+                options.connect.map(|connect| connect.to_string()),
+            ),
+            (
+                "gameplay.players",
+                options.players.map(|players| players.to_string()),
+            ),
+            (
+                "gameplay.opponent",
+                options.computer.then(|| "computer".to_string()),
+            ),
+            (
+                "gameplay.difficulty",
+                options
+                    .difficulty
+                    .map(|difficulty| format!("{:?}", difficulty).to_lowercase()),
+            ),
+            (
+                "gameplay.match",
+                options.match_format.map(|format| format.to_string()),
+            ),
+            (
+                "gameplay.first",
+                options.first.map(|first| format!("{:?}", first).to_lowercase()),
+            ),
+            (
+                "gameplay.pie_rule",
+                options.pie_rule.then(|| "true".to_string()),
+            ),
+            (
+                "gameplay.handicap",
+                options.handicap.map(|handicap| handicap.to_string()),
+            ),
+            (
+                "gameplay.placement",
+                options
+                    .placement
+                    .map(|placement| format!("{:?}", placement).to_lowercase()),
+            ),
+            (
+                "gameplay.start_moves",
+                options.random_start.map(|plies| plies.to_string()),
+            ),
+            ("gameplay.seed", options.seed.map(|seed| seed.to_string())),
+            (
+                "gameplay.variant",
+                options.variant.map(|variant| variant.name().to_string()),
+            ),
+            ("gameplay.blind", options.blind.then(|| "true".to_string())),
+            ("gameplay.peeks", options.peeks.map(|peeks| peeks.to_string())),
+            ("clock.time", options.time.map(|time| time.to_string())),
+            ("clock.preset", options.tc.clone()),
+            (
+                "clock.move_timeout",
+                options.move_timeout.map(clock::format_limit),
+            ),
+            (
+                "clock.timeout_action",
+                options
+                    .timeout_action
+                    .map(|action| format!("{:?}", action).to_lowercase()),
+            ),
+            ("ratings.rated", options.casual.then(|| "false".to_string())),
+            (
+                "input.mode",
+                options
+                    .input
+                    .or(options.quick_input.then_some(Input::Keys))
+                    .map(|input| format!("{:?}", input).to_lowercase()),
+            ),
+            (
+                "input.digits",
+                options
+                    .digits
+                    .or(options.quick_input.then_some(Digits::Drop))
+                    .map(|digits| format!("{:?}", digits).to_lowercase()),
+            ),
+            (
+                "players.one",
+                options.p1_name.clone().or_else(|| options.profile.clone()),
+            ),
+            ("players.two", options.p2_name.clone()),
+            ("players.three", options.p3_name.clone()),
+            ("players.four", options.p4_name.clone()),
+            ("players.one_symbol", options.p1_symbol.clone()),
+            ("players.two_symbol", options.p2_symbol.clone()),
+            ("players.one_color", options.p1_color.clone()),
+            ("players.two_color", options.p2_color.clone()),
+            (
+                "transcript.enabled",
+                options.no_transcript.then(|| "false".to_string()),
+            ),
+            (
+                "transcript.file",
+                options
+                    .log_file
+                    .as_ref()
+                    .map(|path| path.display().to_string()),
+            ),
+        ];
+
+        for (key, value) in flags {
+            if let Some(value) = value {
+                self.set(key, &value, Source::CommandLine)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Each setting's current value and origin, for `config show`
+    pub fn describe(&self) -> Vec<(&'static str, String, Source)> {
+        KEYS.iter()
+            .filter(|key| key.name != "display.ascii")
+            .map(|key| {
+                let value = match key.name {
+                    "display.ui" => format!("{:?}", self.ui).to_lowercase(),
+                    "display.theme" => self.theme.clone(),
+                    "display.color" => format!("{:?}", self.color).to_lowercase(),
+                    "display.cell_width" => self
+                        .cell_width
+                        .map_or("measured".to_string(), |width| width.to_string()),
+                    "display.scale" => self.scale.to_string(),
+                    "display.altscreen" => self.altscreen.to_string(),
+                    "display.animation" => self.animation.to_string(),
+                    "display.coords" => self.coords.to_string(),
+                    "display.marker" => self.marker.to_string(),
+                    "display.accessible" => self.accessible.to_string(),
+                    "display.language" => self.language.clone(),
+
+                    "display.ruler" => format!("{:?}", self.ruler).to_lowercase(),
+                    "display.layout" => format!("{:?}", self.density).to_lowercase(),
+                    "gameplay.width" => self.width.to_string(),
+                    "gameplay.height" => self.height.to_string(),
+                    "gameplay.connect" => self.connect.to_string(),
+                    "gameplay.players" => self.players.to_string(),
+                    "gameplay.opponent" => if self.computer { "computer" } else { "human" }.to_string(),
+                    "gameplay.difficulty" => format!("{:?}", self.difficulty).to_lowercase(),
+                    "gameplay.match" => self.match_format.map_or("off".to_string(), |format| format.to_string()),
+                    "gameplay.match_draws" => if self.half_point_draws { "half" } else { "none" }.to_string(),
+                    "gameplay.first" => format!("{:?}", self.first).to_lowercase(),
+                    "gameplay.pie_rule" => self.pie_rule.to_string(),
+                    "gameplay.handicap" => self.handicap.to_string(),
+                    "gameplay.placement" => format!("{:?}", self.placement).to_lowercase(),
+                    "gameplay.start_moves" => self.random_start.to_string(),
+                    "gameplay.seed" => self.seed.map_or("random".to_string(), |seed| seed.to_string()),
+                    "gameplay.preset" => self.rules_preset.clone().unwrap_or("custom".to_string()),
+                    "gameplay.variant" => self.variant.name().to_string(),
+                    "gameplay.blind" => self.blind.to_string(),
+                    "gameplay.peeks" => self.peeks.to_string(),
+                    "clock.time" => self.time.map_or("off".to_string(), |time| time.to_string()),
+                    "clock.preset" => self.preset.clone().unwrap_or("custom".to_string()),
+                    "clock.pause_computer" => self.pause_computer.to_string(),
+                    "clock.move_timeout" => self.move_timeout.map_or("off".to_string(), clock::format_limit),
+                    "clock.timeout_action" => format!("{:?}", self.timeout_action).to_lowercase(),
+                    "ratings.rated" => self.rated.to_string(),
+                    "ratings.k_factor" => self.k_factor.to_string(),
+                    "ratings.assists" => self.assists.to_string(),
+                    "input.mode" => format!("{:?}", self.input).to_lowercase(),
+                    "input.digits" => format!("{:?}", self.digits).to_lowercase(),
+                    "players.one" => self.names[0].clone(),
+                    "players.two" => self.names[1].clone(),
+                    "players.three" => self.names[2].clone(),
+                    "players.four" => self.names[3].clone(),
+                    "players.one_symbol" => self.symbols[0].clone().unwrap_or("theme".to_string()),
+                    "players.two_symbol" => self.symbols[1].clone().unwrap_or("theme".to_string()),
+                    "players.one_color" => {
+                        self.piece_colors[0].clone().unwrap_or("theme".to_string())
+                    }
+                    "players.two_color" => {
+                        self.piece_colors[1].clone().unwrap_or("theme".to_string())
+                    }
+                    "transcript.enabled" => self.transcript.to_string(),
+                    "transcript.file" => self
+                        .log_file
+                        .as_ref()
+                        .map_or("daily".to_string(), |path| path.display().to_string()),
+                    "transcript.max_size" => self.transcript_max_size.to_string(),
+                    _ => self.transcript_keep.to_string(),
+                };
+                let source = self
+                    .sources
+                    .iter()
+                    .find(|(name, _)| *name == key.name)
+                    .map_or(Source::Default, |(_, source)| source.clone());
+                (key.name, value, source)
+            })
+            .collect()
+    }
+
+    // Reject combinations the game can't be played with
+    fn validate(&self) -> Result<(), String> {
+        self.rules().validate().map_err(|problems| problems.join("; "))?;
+        self.check_setup()?;
+        if self.blind && self.ui == Ui::Tui {
+            return Err("blind games are played in the line interface; use --ui line".to_string());
+        }
+        if self.variant == Variant::Cube {
+            self.check_cube()?;
+        }
+        // With no head-to-head result, games of more than two players are never rated
+        if self.players > 2 && self.rated {
+            return Err(format!(
+                "games of {} players can't be rated; play them with --casual",
+                self.players
+            ));
+        }
+        if self.variant == Variant::PowerUp && self.rated {
+            return Err("Power Up games can't be rated; play them with --casual".to_string());
+        }
+        if !matches!(self.cell_width, None | Some(1) | Some(2)) {
+            return Err("cell width must be 1 or 2".to_string());
+        }
+        if !(1..=5).contains(&self.scale) {
+            return Err("scale must be between 1 and 5".to_string());
+        }
+        let names = &self.names[..self.players];
+        for (index, name) in names.iter().enumerate() {
+            if names[index + 1..].iter().any(|other| other.to_lowercase() == name.to_lowercase()) {
+                let who = if self.players == 2 { "both" } else { "two" };
+                return Err(format!("{} players are called '{}'", who, name));
+            }
+        }
+        self.theme().check_players_distinct(self.players)
+    }
+
+    // Reject a first game that can't be set up by playable rules: too many or too few
+    // players for what they play with, or a handicap or random start that doesn't fit
+    pub fn check_setup(&self) -> Result<(), String> {
+        self.check_players()?;
+        if self.handicap > handicap::most(self.connect) {
+            return Err(format!(
+                "a handicap can be at most {} pieces when {} in a row wins",
+                handicap::most(self.connect),
+                self.connect
+            ));
+        }
+        if self.handicap > 0 && self.pie_rule {
+            return Err("the pie rule can't be used with a handicap".to_string());
+        }
+        if self.handicap > 0 && !matches!(self.first, First::P1 | First::P2) {
+            return Err(
+                "with a handicap the stronger player always moves first, so who moves first \
+                 can't be random or alternate"
+                    .to_string(),
+            );
+        }
+        if self.random_start >= self.width * self.height {
+            return Err(format!(
+                "a random start must be fewer than the {} cells of a {}x{} board",
+                self.width * self.height,
+                self.width,
+                self.height
+            ));
+        }
+        if self.random_start > 0 && self.handicap > 0 {
+            return Err("a random start can't be used with a handicap".to_string());
+        }
+        if self.variant == Variant::PopOut && self.handicap > 0 {
+            return Err("PopOut can't be played with a handicap".to_string());
+        }
+        if self.variant == Variant::Cube && self.handicap > 0 {
+            return Err("3D games can't be played with a handicap".to_string());
+        }
+        Ok(())
+    }
+
+    // What 3D games can't be played with: the pegs are typed at the line prompt, as the
+    // columns of the cursor and the TUI don't match them, and the cube is never hidden
+    fn check_cube(&self) -> Result<(), String> {
+        if self.ui == Ui::Tui {
+            return Err("3D games are played in the line interface; use --ui line".to_string());
+        }
+        if self.input == Input::Keys {
+            return Err("3D games are played by typing pegs; use --input line".to_string());
+        }
+        if self.blind {
+            return Err("3D games can't be played blind".to_string());
+        }
+        Ok(())
+    }
+
+    // What games of more than two players can't be played with: they take turns round the
+    // table with nothing but drops, so there's no clock, handicap, pie rule, random start, or
+    // variant
+    fn check_players(&self) -> Result<(), String> {
+        if !(2..=4).contains(&self.players) {
+            return Err("a game is for 2 to 4 players".to_string());
+        }
+        if self.players == 2 {
+            return Ok(());
+        }
+        let unsupported = if self.time.is_some() || self.move_timeout.is_some() {
+            Some("a clock")
+        } else if self.handicap > 0 {
+            Some("a handicap")
+        } else if self.pie_rule {
+            Some("the pie rule")
+        } else if self.random_start > 0 {
+            Some("a random start")
+        } else if self.variant != Variant::Standard {
+            Some("a variant")
+        } else {
+            None
+        };
+        if let Some(unsupported) = unsupported {
+            return Err(format!(
+                "games of {} players can't be played with {}",
+                self.players, unsupported
+            ));
+        }
+        Ok(())
+    }
+
+    // Who plays as Player 2
+    pub fn opponent(&self) -> Opponent {
+        if self.computer {
+            Opponent::Computer(self.difficulty)
+        } else {
+            Opponent::Human
+        }
+    }
+
+    // The selected theme with the per-player symbol and color overrides applied
+    pub fn theme(&self) -> Theme {
+        let mut theme = Theme::builtin(&self.theme).expect("theme names are validated");
+        for index in 0..2 {
+            let color = self.piece_colors[index]
+                .as_deref()
+                .map(|color| Color::parse(color).expect("colors are validated"));
+            theme.customize(index, self.symbols[index].as_deref(), color);
+        }
+        theme
+    }
+}
+
+// The config file to read: --config, or the XDG default
+pub fn config_path(options: &Options) -> Option<PathBuf> {
+    options
+        .config
+        .clone()
+        .or_else(|| dirs::config_dir().map(|dir| dir.join("connect_four").join("config.toml")))
+}
+
+// Write the commented default config file for `config init`
+pub fn write_default_config(path: &Path, force: bool) -> Result<(), String> {
+    if path.exists() && !force {
+        return Err(format!(
+            "{} already exists; use --force to overwrite it",
+            path.display()
+        ));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|err| format!("could not create {}: {}", dir.display(), err))?;
+    }
+    fs::write(path, DEFAULT_CONFIG)
+        .map_err(|err| format!("could not write {}: {}", path.display(), err))
+}
+
+// Store `values` ("section.name" keys) in the config file, keeping its other settings. The
+// file is rewritten without its comments.
+pub fn save_config(path: &Path, values: &[(&str, String)]) -> Result<(), String> {
+    let mut table: toml::Table = if path.exists() {
+        fs::read_to_string(path)
+            .map_err(|err| format!("could not read config file {}: {}", path.display(), err))?
+            .parse()
+            .map_err(|err| format!("could not parse config file {}:\n{}", path.display(), err))?
+    } else {
+        toml::Table::new()
+    };
+    for (key, value) in values {
+        let (section, name) = key.split_once('.').expect("keys have a section");
+        let entries = table
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+        let Some(entries) = entries.as_table_mut() else {
+            return Err(format!("{}: '{}' is not a section", path.display(), section));
+        };
+        entries.insert(name.to_string(), toml::Value::String(value.clone()));
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|err| format!("could not create {}: {}", dir.display(), err))?;
+    }
+    log::debug!("saving settings to {}", path.display());
+    fs::write(path, table.to_string())
+        .map_err(|err| format!("could not write {}: {}", path.display(), err))
+}
+
+// A player's name with control characters dropped and surrounding spaces trimmed, as long
+// as something is left and it isn't too long to fit the banners
+pub fn clean_name(name: &str) -> Result<String, String> {
+    let name: String = name.chars().filter(|c| !c.is_control()).collect();
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("player names can't be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME {
+        return Err(format!(
+            "player names can be at most {} characters long",
+            MAX_NAME
+        ));
+    }
+    Ok(name.to_string())
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!("expected true or false, got '{}'", value)),
+    }
+}
+
+fn parse_number(value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("expected a positive number, got '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    use crate::cli::Cli;
+
+    // The settings a config file holding `config` and the flags `args` resolve to, with only
+    // the variables in `env` set
+    fn resolved(name: &str, config: &str, env: &[(&str, &str)], args: &[&str]) -> Result<(Settings, Vec<String>), String> {
+        let path = std::env::temp_dir().join(format!("settings-{}-{}.toml", name, std::process::id()));
+        fs::write(&path, config).expect("the config is written");
+        let path_arg = path.display().to_string();
+        let cli = Cli::try_parse_from(["connect_four", "--config", &path_arg].iter().chain(args)).expect("the flags parse");
+        let env: Vec<(String, String)> = env.iter().map(|(var, value)| (var.to_string(), value.to_string())).collect();
+        let settings = Settings::resolve_with_env(&cli.options, |var| env.iter().find(|(name, _)| name == var).map(|(_, value)| value.clone()));
+        let _ = fs::remove_file(&path);
+        settings
+    }
+
+    #[test]
+    fn flags_go_over_the_environment_which_goes_over_the_file() {
+        let config = "[display]\ntheme = \"ascii\"\n\n[gameplay]\nwidth = 9\nconnect = 5\n";
+        let env = [("CONNECT_FOUR_THEME", "minimal"), ("CONNECT_FOUR_WIDTH", "8")];
+        let (settings, warnings) = resolved("precedence", config, &env, &["--theme", "classic"]).expect("the settings resolve");
+        assert_eq!(warnings, Vec::<String>::new());
+        assert_eq!(settings.theme, "classic", "the flag wins");
+        assert_eq!(settings.rules().width, 8, "the environment goes over the file");
+        assert_eq!(settings.rules().connect, 5, "the file goes over the default");
+        assert_eq!(settings.rules().height, 6, "nothing sets the default");
+        assert_eq!(settings.precedence("display.theme"), 3);
+        assert_eq!(settings.precedence("gameplay.width"), 2);
+        assert_eq!(settings.precedence("gameplay.connect"), 1);
+        assert_eq!(settings.precedence("gameplay.height"), 0);
+
+        // Without the flag the environment's theme is used
+        let (settings, _) = resolved("precedence-env", config, &env, &[]).expect("the settings resolve");
+        assert_eq!(settings.theme, "minimal");
+    }
+
+    #[test]
+    fn unknown_keys_are_warned_about_and_bad_values_are_errors() {
+        let config = "[display]\ntheme = \"ascii\"\nshade = 3\n\n[sound]\nvolume = 11\n";
+        let (settings, warnings) = resolved("unknown", config, &[], &[]).expect("unknown keys are only warned about");
+        assert_eq!(settings.theme, "ascii");
+        let warnings: Vec<&str> = warnings.iter().map(|warning| warning.rsplit(": ").next().unwrap_or_default()).collect();
+        assert_eq!(warnings, ["unknown key 'display.shade'", "unknown key 'sound'"]);
+
+        let bad = [
+            ("", vec![("CONNECT_FOUR_DIFFICULTY", "impossible")], "invalid value for CONNECT_FOUR_DIFFICULTY: "),
+            ("", vec![("CONNECT_FOUR_ANIMATION", "sometimes")], "invalid value for CONNECT_FOUR_ANIMATION: "),
+            ("", vec![("CONNECT_FOUR_WIDTH", "-3")], "invalid value for CONNECT_FOUR_WIDTH: expected a positive number, got '-3'"),
+            ("[gameplay]\nwidth = \"wide\"\n", vec![], "expected a positive number, got 'wide'"),
+            ("[display]\ntheme = [\"ascii\"]\n", vec![], "'display.theme' must be a string, number, or boolean"),
+        ];
+        for (config, env, expected) in bad {
+            let err = resolved("bad", config, &env, &[]).map(|_| ()).expect_err(expected);
+            assert!(err.contains(expected), "{:?} has {:?}", err, expected);
+        }
+    }
+}
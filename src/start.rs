@@ -0,0 +1,98 @@
+use crate::ai;
+use crate::now::{self, TimeSource};
+use crate::Game;
+
+// Tries at a random start before giving up on finding one that meets the constraints
+pub const ATTEMPTS: usize = 1000;
+
+// Plies the engine looks ahead to make sure neither side starts with a forced win
+const SAFE_PLIES: usize = 2;
+
+// A small seeded generator (splitmix64), so a start can be played again from its seed
+#[derive(Clone, Copy, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u32) -> Rng {
+        Rng(u64::from(seed))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut mixed = self.0;
+        mixed = (mixed ^ (mixed >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        mixed = (mixed ^ (mixed >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        mixed ^ (mixed >> 31)
+    }
+
+    // A number from 0 up to but not including `bound`
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+}
+
+// A seed for a start nobody asked to replay
+pub fn fresh_seed() -> u32 {
+    now::System.wall().as_nanos() as u32
+}
+
+// Columns of `plies` random moves from the position of `game`, the same ones for the same
+// seed, ending where nobody has connected and neither side can force a win within two
+// plies. A continuation that breaks the rules is thrown away for the next one the generator
+// gives, up to ATTEMPTS of them.
+pub fn generate(game: &Game, plies: usize, seed: u32) -> Result<Vec<usize>, String> {
+    let mut rng = Rng::new(seed);
+    for attempt in 1..=ATTEMPTS {
+        let mut position = game.clone();
+        let mut moves = Vec::new();
+        while moves.len() < plies {
+            let Some(column) = position.random_move(&mut rng) else {
+                break;
+            };
+            position
+                .column(column)
+                .and_then(|column| position.play_move(column))
+                .expect("open columns can be played");
+            moves.push(column);
+        }
+        if !position.is_finished() && !ai::decided(&position, SAFE_PLIES) {
+            log::debug!(
+                "random start found on attempt {} from seed {}",
+                attempt,
+                seed
+            );
+            return Ok(moves);
+        }
+    }
+    Err(format!(
+        "no random start of {} moves without a forced win turned up in {} tries from seed {}",
+        plies, ATTEMPTS, seed
+    ))
+}
+
+impl Game {
+    // Play `plies` random moves for the players from `seed`, for a game that starts from a
+    // position nobody chose
+    pub fn start_randomly(&mut self, plies: usize, seed: u32) -> Result<(), String> {
+        if plies == 0 {
+            return Ok(());
+        }
+        for column in generate(self, plies, seed)? {
+            self.column(column)
+                .and_then(|column| self.play_move(column))
+                .expect("the generated moves are legal");
+        }
+        self.random_start = plies;
+        self.seed = Some(seed);
+        Ok(())
+    }
+
+    // How the game started, when it was from a random position
+    pub fn random_start_note(&self) -> Option<String> {
+        let seed = self.seed?;
+        Some(format!(
+            "Random start: {} moves from seed {}; --seed {} plays it again",
+            self.random_start, seed, seed
+        ))
+    }
+}
@@ -0,0 +1,225 @@
+// ANSI escape codes for terminal colors
+const RESET: &str = "\x1b[0m";
+pub const RED: &str = "\x1b[;31m";
+pub const GREEN: &str = "\x1b[32m";
+pub const YELLOW: &str = "\x1b[33m";
+pub const BLUE: &str = "\x1b[34m";
+pub const MAGENTA: &str = "\x1b[35m";
+pub const CYAN: &str = "\x1b[36m";
+pub const WHITE: &str = "\x1b[37m";
+pub const BLACK: &str = "\x1b[30m";
+pub const DIM: &str = "\x1b[2m";
+pub const BOLD_WHITE: &str = "\x1b[1;97m";
+pub const BOLD_UNDERLINE: &str = "\x1b[1;4m";
+pub const DIM_STRIKE: &str = "\x1b[2;9m";
+pub const INVERSE: &str = "\x1b[7m";
+
+// When to emit ANSI colors
+#[derive(Clone, Copy, Debug, PartialEq, clap::ValueEnum)]
+pub enum ColorChoice {
+    Always,
+    Auto,
+    Never,
+}
+
+// The eight basic colors with their usual RGB values, for approximating hex colors
+const PALETTE: [(&str, &str, (u8, u8, u8)); 8] = [
+    ("black", BLACK, (0, 0, 0)),
+    ("red", "\x1b[31m", (205, 0, 0)),
+    ("green", GREEN, (0, 205, 0)),
+    ("yellow", YELLOW, (205, 205, 0)),
+    ("blue", BLUE, (0, 0, 238)),
+    ("magenta", MAGENTA, (205, 0, 205)),
+    ("cyan", CYAN, (0, 205, 205)),
+    ("white", WHITE, (229, 229, 229)),
+];
+
+// A foreground color: a fixed escape code or a 24-bit color
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Color {
+    Code(&'static str),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    // Parse a named color ("red", "blue", ...) or a #rrggbb hex color
+    pub fn parse(text: &str) -> Result<Color, String> {
+        let text = text.trim().to_lowercase();
+        if let Some(hex) = text.strip_prefix('#') {
+            let channel = |range: std::ops::Range<usize>| {
+                hex.get(range)
+                    .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            };
+            return match (hex.len(), channel(0..2), channel(2..4), channel(4..6)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+                _ => Err(format!("'{}' is not a #rrggbb color", text)),
+            };
+        }
+
+        PALETTE
+            .iter()
+            .find(|(name, _, _)| *name == text)
+            .map(|(_, code, _)| Color::Code(code))
+            .ok_or_else(|| {
+                format!(
+                    "unknown color '{}'; use #rrggbb or one of black, red, green, yellow, blue, magenta, cyan, white",
+                    text
+                )
+            })
+    }
+
+    // Red, green, and blue values, for interfaces that take colors rather than escape codes;
+    // None for styles without a hue, like dim
+    pub fn rgb(&self) -> Option<(u8, u8, u8)> {
+        match *self {
+            Color::Rgb(r, g, b) => Some((r, g, b)),
+            Color::Code(RED) => Some((205, 0, 0)),
+            Color::Code(BOLD_WHITE) => Some((255, 255, 255)),
+            Color::Code(code) => PALETTE
+                .iter()
+                .find(|(_, palette_code, _)| *palette_code == code)
+                .map(|(_, _, rgb)| *rgb),
+        }
+    }
+
+    // Escape code for this color, approximating 24-bit colors with the nearest basic color
+    // when the terminal can't show them
+    fn code(&self, truecolor: bool) -> String {
+        match *self {
+            Color::Code(code) => code.to_string(),
+            Color::Rgb(r, g, b) if truecolor => format!("\x1b[38;2;{};{};{}m", r, g, b),
+            Color::Rgb(r, g, b) => {
+                let distance = |(pr, pg, pb): (u8, u8, u8)| {
+                    [(r, pr), (g, pg), (b, pb)]
+                        .iter()
+                        .map(|&(a, b)| (a as i32 - b as i32).pow(2))
+                        .sum::<i32>()
+                };
+                let (_, code, _) = PALETTE
+                    .iter()
+                    .min_by_key(|(_, _, rgb)| distance(*rgb))
+                    .expect("palette is not empty");
+                code.to_string()
+            }
+        }
+    }
+}
+
+// Styling applied to one output stream, resolved once at startup
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+    pub color: bool,
+    pub truecolor: bool,
+}
+
+impl Style {
+    // Resolve whether to color a stream, in priority order: an explicit --color flag,
+    // NO_COLOR, CLICOLOR_FORCE, and finally whether the stream is an ANSI-capable terminal
+    pub fn resolve(
+        choice: ColorChoice,
+        no_color: bool,
+        clicolor_force: bool,
+        tty: bool,
+        ansi_enabled: bool,
+    ) -> Style {
+        let color = match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto if no_color => false,
+            ColorChoice::Auto if clicolor_force => true,
+            ColorChoice::Auto => tty && ansi_enabled,
+        };
+
+        Style {
+            color,
+            truecolor: false,
+        }
+    }
+
+    // Read the NO_COLOR and CLICOLOR_FORCE conventions from the environment
+    pub fn from_env(choice: ColorChoice, tty: bool, ansi_enabled: bool) -> Style {
+        // NO_COLOR only counts when set to a non-empty value
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let clicolor_force =
+            std::env::var("CLICOLOR_FORCE").is_ok_and(|value| value != "0" && !value.is_empty());
+
+        // COLORTERM advertises 24-bit color support
+        let truecolor =
+            std::env::var("COLORTERM").is_ok_and(|value| value == "truecolor" || value == "24bit");
+
+        Style {
+            truecolor,
+            ..Style::resolve(choice, no_color, clicolor_force, tty, ansi_enabled)
+        }
+    }
+
+    // Wrap text in an ANSI color when this stream is colored
+    pub fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    // Wrap text in a foreground color when this stream is colored
+    pub fn paint_color(&self, color: Color, text: &str) -> String {
+        self.paint(&color.code(self.truecolor), text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolution_follows_the_priority_order() {
+        // (choice, NO_COLOR, CLICOLOR_FORCE, terminal) and whether to color
+        let cases = [
+            (ColorChoice::Always, true, false, false, true),
+            (ColorChoice::Always, false, false, false, true),
+            (ColorChoice::Never, false, true, true, false),
+            (ColorChoice::Never, false, false, true, false),
+            (ColorChoice::Auto, true, true, true, false),
+            (ColorChoice::Auto, true, false, true, false),
+            (ColorChoice::Auto, false, true, false, true),
+            (ColorChoice::Auto, false, false, true, true),
+            (ColorChoice::Auto, false, false, false, false),
+        ];
+        for (choice, no_color, clicolor_force, tty, color) in cases {
+            let style = Style::resolve(choice, no_color, clicolor_force, tty, true);
+            assert_eq!(
+                style.color, color,
+                "{:?}, NO_COLOR {}, CLICOLOR_FORCE {}, terminal {}",
+                choice, no_color, clicolor_force, tty
+            );
+        }
+    }
+
+    #[test]
+    fn a_terminal_without_ansi_is_not_colored() {
+        let style = Style::resolve(ColorChoice::Auto, false, false, true, false);
+        assert!(!style.color);
+    }
+
+    #[test]
+    fn uncolored_text_has_no_escapes() {
+        let style = Style::resolve(ColorChoice::Auto, true, false, true, true);
+        assert_eq!(style.paint(RED, "text"), "text");
+        assert_eq!(style.paint_color(Color::Rgb(1, 2, 3), "text"), "text");
+    }
+
+    #[test]
+    fn colors_parse_by_name_and_hex() {
+        assert_eq!(Color::parse("Blue"), Ok(Color::Code(BLUE)));
+        assert_eq!(Color::parse("#ff8000"), Ok(Color::Rgb(255, 128, 0)));
+        assert!(Color::parse("#ff80").is_err());
+        assert!(Color::parse("mauve").is_err());
+    }
+
+    #[test]
+    fn rgb_colors_fall_back_to_the_nearest_basic_one() {
+        assert_eq!(Color::Rgb(250, 10, 10).code(false), "\x1b[31m");
+        assert_eq!(Color::Rgb(250, 10, 10).code(true), "\x1b[38;2;250;10;10m");
+    }
+}
@@ -0,0 +1,212 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::Once;
+
+use crate::render::{self, Layout, Renderer};
+use crate::style::{ColorChoice, Style};
+use crate::transcript::Transcript;
+
+use crossterm::{
+    cursor::MoveTo,
+    execute,
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+// What the attached console can display, detected once at startup
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capabilities {
+    pub style: Style,
+    pub error_style: Style,
+    pub unicode: bool,
+    pub interactive: bool,
+}
+
+impl Capabilities {
+    // Probe the current console
+    pub fn detect(choice: ColorChoice) -> Capabilities {
+        let ansi_enabled = enable_ansi();
+        let stdout_tty = io::stdout().is_terminal();
+
+        Capabilities {
+            style: Style::from_env(choice, stdout_tty, ansi_enabled),
+            error_style: Style::from_env(choice, io::stderr().is_terminal(), ansi_enabled),
+            unicode: unicode_supported(stdout_tty, cfg!(windows), |var| std::env::var(var).ok()),
+            interactive: stdout_tty,
+        }
+    }
+}
+
+// Redirected output uses ASCII cells, and so does a terminal that calls itself dumb. On
+// Windows only Windows Terminal, which sets WT_SESSION, renders the emoji and box glyphs at a
+// predictable width; elsewhere the locale has to be UTF-8, the locale being the first of
+// LC_ALL, LC_CTYPE, and LANG that's set, as POSIX reads them, and "C" when none is. `env`
+// looks up a variable.
+pub fn unicode_supported(stdout_tty: bool, windows: bool, env: impl Fn(&str) -> Option<String>) -> bool {
+    if !stdout_tty || env("TERM").as_deref() == Some("dumb") {
+        return false;
+    }
+    if windows {
+        return env("WT_SESSION").is_some();
+    }
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"].into_iter().find_map(|var| env(var).filter(|value| !value.is_empty()));
+    locale.is_some_and(|locale| {
+        let locale = locale.to_ascii_lowercase();
+        locale.contains("utf-8") || locale.contains("utf8")
+    })
+}
+
+// Enable virtual terminal processing on Windows consoles; other platforms always speak ANSI
+#[cfg(windows)]
+fn enable_ansi() -> bool {
+    crossterm::ansi_support::supports_ansi()
+}
+
+#[cfg(not(windows))]
+fn enable_ansi() -> bool {
+    true
+}
+
+// Guard that owns the terminal screen for the lifetime of the game
+pub struct Screen {
+    alternate: bool,
+    pub capabilities: Capabilities,
+    pub renderer: Renderer,
+    // Column being previewed before the move is committed
+    pub selected_column: Option<usize>,
+    // Where the session's games and the errors shown are logged
+    pub transcript: Transcript,
+}
+
+impl Screen {
+    // Enter the alternate screen (unless disabled) and make sure it is left again on panic
+    pub fn new(use_alternate: bool, capabilities: Capabilities, renderer: Renderer) -> Screen {
+        let alternate = use_alternate
+            && capabilities.interactive
+            && execute!(io::stdout(), EnterAlternateScreen).is_ok();
+
+        if alternate {
+            // Restore the main screen before the panic message is printed so it stays visible.
+            // The hook goes in once, however many screens a session opens, rather than each
+            // one wrapping the last.
+            static PANIC_HOOK: Once = Once::new();
+            PANIC_HOOK.call_once(|| {
+                let default_hook = std::panic::take_hook();
+                std::panic::set_hook(Box::new(move |info| {
+                    let _ = execute!(io::stdout(), LeaveAlternateScreen);
+                    default_hook(info);
+                }));
+            });
+        }
+
+        Screen {
+            alternate,
+            capabilities,
+            renderer,
+            selected_column: None,
+            transcript: Transcript::off(),
+        }
+    }
+
+    // Print an error message to stderr, styled for stderr, and log it
+    pub fn error(&self, text: &str) {
+        self.transcript.error(text);
+        eprintln!(
+            "{}",
+            self.renderer.error(self.capabilities.error_style, text)
+        );
+    }
+
+    // Clear the screen and move the cursor home before a render
+    pub fn clear(&self) {
+        // Append-only rendering keeps every position in the scrollback, except in a blind
+        // game, where a board seen on a peek mustn't be left there to scroll back to
+        if self.alternate {
+            let _ = execute!(io::stdout(), Clear(ClearType::All), MoveTo(0, 0));
+        } else if self.renderer.blind.is_some() && self.capabilities.interactive {
+            let _ = execute!(io::stdout(), Clear(ClearType::Purge), Clear(ClearType::All), MoveTo(0, 0));
+        }
+    }
+
+    // Current width of the terminal, re-read on every call so resizes are picked up; unknown
+    // when the output isn't a terminal
+    pub fn terminal_width(&self) -> Option<usize> {
+        if !self.capabilities.interactive {
+            return None;
+        }
+        terminal::size().ok().map(|(columns, _)| usize::from(columns))
+    }
+
+    // Current height of the terminal in lines, unknown when the output isn't a terminal
+    pub fn terminal_height(&self) -> Option<usize> {
+        if !self.capabilities.interactive {
+            return None;
+        }
+        terminal::size().ok().map(|(_, rows)| usize::from(rows))
+    }
+
+    // Layout for a board of `columns` on the terminal as it is now: the renderer's own, or
+    // the compact one when that doesn't fit. The error is the width needed.
+    pub fn board_layout(&self, columns: usize) -> Result<Layout, usize> {
+        render::choose_layout(
+            self.renderer.layout,
+            self.renderer.density,
+            columns,
+            self.terminal_width(),
+        )
+    }
+
+    // Whether renders replace each other, which animations need
+    pub fn redraws_in_place(&self) -> bool {
+        self.alternate
+    }
+
+    // Print a prompt and flush so it appears before read_line blocks
+    pub fn prompt(&self, text: &str) {
+        println!("{}", text);
+        let _ = io::stdout().flush();
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        if self.alternate {
+            let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Environment variables and their values
+    type Vars<'a> = &'a [(&'a str, &'a str)];
+
+    // An environment of these variables and no others
+    fn env<'a>(vars: Vars<'a>) -> impl Fn(&str) -> Option<String> + 'a {
+        |var| vars.iter().find(|(name, _)| *name == var).map(|(_, value)| value.to_string())
+    }
+
+    #[test]
+    fn unicode_needs_a_utf8_locale_a_terminal_that_isnt_dumb_and_on_windows_windows_terminal() {
+        let cases: [(Vars, bool, bool); 12] = [
+            (&[("LANG", "en_US.UTF-8")], false, true),
+            (&[("LANG", "de_DE.utf8")], false, true),
+            (&[("LANG", "C")], false, false),
+            (&[], false, false),
+            // LC_ALL overrides LANG, either way, and an empty one is as good as unset
+            (&[("LANG", "C"), ("LC_ALL", "en_US.UTF-8")], false, true),
+            (&[("LANG", "en_US.UTF-8"), ("LC_ALL", "POSIX")], false, false),
+            (&[("LANG", "en_US.UTF-8"), ("LC_ALL", "")], false, true),
+            (&[("LANG", "C"), ("LC_CTYPE", "C.UTF-8")], false, true),
+            (&[("LANG", "en_US.UTF-8"), ("TERM", "dumb")], false, false),
+            (&[("LANG", "en_US.UTF-8"), ("TERM", "xterm-256color")], false, true),
+            // Windows has no locale to go by, only Windows Terminal
+            (&[("WT_SESSION", "1")], true, true),
+            (&[("LANG", "en_US.UTF-8")], true, false),
+        ];
+        for (vars, windows, expected) in cases {
+            assert_eq!(unicode_supported(true, windows, env(vars)), expected, "{:?} windows={}", vars, windows);
+        }
+        assert!(!unicode_supported(false, false, env(&[("LANG", "en_US.UTF-8")])), "redirected output");
+    }
+}
@@ -0,0 +1,315 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::style::{Color, BOLD_UNDERLINE, BOLD_WHITE, CYAN, DIM, GREEN, MAGENTA, RED, YELLOW};
+
+// Glyph and optional color for one kind of board cell
+#[derive(Clone, Debug, PartialEq)]
+pub struct Piece {
+    pub glyph: String,
+    pub color: Option<Color>,
+}
+
+impl Piece {
+    fn new(glyph: &str, color: Option<&'static str>) -> Piece {
+        Piece {
+            glyph: glyph.to_string(),
+            color: color.map(Color::Code),
+        }
+    }
+}
+
+// Check a user-supplied piece symbol: one visible character (grapheme cluster) at most two
+// columns wide, so it can't wreck the board alignment
+pub fn validate_symbol(symbol: &str) -> Result<(), String> {
+    let graphemes = symbol.graphemes(true).count();
+    if graphemes != 1 || symbol.chars().any(char::is_control) {
+        return Err(format!("symbol '{}' must be a single character", symbol));
+    }
+    if !(1..=2).contains(&symbol.width()) {
+        return Err(format!(
+            "symbol '{}' must be one or two columns wide",
+            symbol
+        ));
+    }
+    Ok(())
+}
+
+// Everything that decides how the board looks
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub player_one: Piece,
+    pub player_two: Piece,
+    // Pieces of the players who only sit down in games of three or four
+    pub player_three: Piece,
+    pub player_four: Piece,
+    pub empty: Piece,
+    // Draw |/- borders around the grid
+    pub borders: bool,
+    // Color for the banner, separators, and announcements
+    pub accent: Color,
+    // Color or emphasis for error messages
+    pub error: Color,
+    // The glyphs are outside ASCII and need a Unicode-capable terminal
+    pub needs_unicode: bool,
+    // The players are told apart by color alone
+    pub needs_color: bool,
+}
+
+// Colors from the Okabe-Ito palette, which stays distinguishable with every common form of
+// color blindness
+const OKABE_ITO_ORANGE: Color = Color::Rgb(0xe6, 0x9f, 0x00);
+const OKABE_ITO_SKY_BLUE: Color = Color::Rgb(0x56, 0xb4, 0xe9);
+const OKABE_ITO_BLUE: Color = Color::Rgb(0x00, 0x72, 0xb2);
+const OKABE_ITO_VERMILLION: Color = Color::Rgb(0xd5, 0x5e, 0x00);
+const OKABE_ITO_GREEN: Color = Color::Rgb(0x00, 0x9e, 0x73);
+
+// Names of the built-in themes, in the order they are listed
+pub const THEME_NAMES: [&str; 6] = [
+    "classic",
+    "emoji",
+    "ascii",
+    "minimal",
+    "high-contrast",
+    "colorblind",
+];
+
+impl Theme {
+    // Look up a built-in theme by name
+    pub fn builtin(name: &str) -> Option<Theme> {
+        let theme = match name {
+            "classic" => Theme {
+                name: "classic",
+                description: "Red and yellow discs like the original toy",
+                player_one: Piece::new("●", Some(RED)),
+                player_two: Piece::new("●", Some(YELLOW)),
+                player_three: Piece::new("●", Some(GREEN)),
+                player_four: Piece::new("●", Some(MAGENTA)),
+                empty: Piece::new("·", Some(DIM)),
+                borders: false,
+                accent: Color::Code(CYAN),
+                error: Color::Code(RED),
+                needs_unicode: true,
+                needs_color: true,
+            },
+            "emoji" => Theme {
+                name: "emoji",
+                description: "Red and blue emoji discs",
+                player_one: Piece::new("🔴", None), // Red for Player One
+                player_two: Piece::new("🔵", None), // Blue for Player Two
+                player_three: Piece::new("🟢", None), // Green for Player Three
+                player_four: Piece::new("🟡", None), // Yellow for Player Four
+                empty: Piece::new("⚫", None),      // Black for empty cell
+                borders: false,
+                accent: Color::Code(GREEN),
+                error: Color::Code(RED),
+                needs_unicode: true,
+                needs_color: false,
+            },
+            "ascii" => Theme {
+                name: "ascii",
+                description: "Plain characters with borders, safe everywhere",
+                player_one: Piece::new("X", None),
+                player_two: Piece::new("O", None),
+                player_three: Piece::new("+", None),
+                player_four: Piece::new("#", None),
+                empty: Piece::new(".", None),
+                borders: true,
+                accent: Color::Code(GREEN),
+                error: Color::Code(RED),
+                needs_unicode: false,
+                needs_color: false,
+            },
+            "minimal" => Theme {
+                name: "minimal",
+                description: "Filled and hollow dots, no borders",
+                player_one: Piece::new("●", None),
+                player_two: Piece::new("○", None),
+                player_three: Piece::new("◆", None),
+                player_four: Piece::new("◇", None),
+                empty: Piece::new("·", None),
+                borders: false,
+                accent: Color::Code(DIM),
+                error: Color::Code(RED),
+                needs_unicode: true,
+                needs_color: false,
+            },
+            "high-contrast" => Theme {
+                name: "high-contrast",
+                description: "Bold distinct shapes with bright borders",
+                player_one: Piece::new("X", Some(BOLD_WHITE)),
+                player_two: Piece::new("O", Some(BOLD_WHITE)),
+                player_three: Piece::new("+", Some(BOLD_WHITE)),
+                player_four: Piece::new("#", Some(BOLD_WHITE)),
+                empty: Piece::new(".", None),
+                borders: true,
+                accent: Color::Code(BOLD_WHITE),
+                error: Color::Code(BOLD_UNDERLINE),
+                needs_unicode: false,
+                needs_color: false,
+            },
+            "colorblind" => Theme {
+                name: "colorblind",
+                description: "Distinct shapes in a colorblind-safe orange and sky blue",
+                player_one: Piece {
+                    glyph: "●".to_string(),
+                    color: Some(OKABE_ITO_ORANGE),
+                },
+                player_two: Piece {
+                    glyph: "▲".to_string(),
+                    color: Some(OKABE_ITO_SKY_BLUE),
+                },
+                player_three: Piece {
+                    glyph: "■".to_string(),
+                    color: Some(OKABE_ITO_VERMILLION),
+                },
+                player_four: Piece {
+                    glyph: "◆".to_string(),
+                    color: Some(OKABE_ITO_GREEN),
+                },
+                empty: Piece::new("·", Some(DIM)),
+                borders: false,
+                accent: OKABE_ITO_BLUE,
+                error: Color::Code(BOLD_UNDERLINE),
+                needs_unicode: true,
+                needs_color: false,
+            },
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    // Pieces of the players in seat order
+    pub fn players(&self) -> [&Piece; 4] {
+        [
+            &self.player_one,
+            &self.player_two,
+            &self.player_three,
+            &self.player_four,
+        ]
+    }
+
+    // Replace a player's glyph and/or color; index 0 is Player One
+    pub fn customize(&mut self, index: usize, symbol: Option<&str>, color: Option<Color>) {
+        let piece = match index {
+            0 => &mut self.player_one,
+            1 => &mut self.player_two,
+            2 => &mut self.player_three,
+            _ => &mut self.player_four,
+        };
+        if let Some(symbol) = symbol {
+            piece.glyph = symbol.to_string();
+            self.needs_unicode |= !symbol.is_ascii();
+        }
+        if color.is_some() {
+            piece.color = color;
+        }
+
+        // Same glyphs can only be told apart by their colors
+        self.needs_color = self.player_one.glyph == self.player_two.glyph;
+    }
+
+    // The first `players` players must never look identical
+    pub fn check_players_distinct(&self, players: usize) -> Result<(), String> {
+        let pieces = &self.players()[..players];
+        for (index, piece) in pieces.iter().enumerate() {
+            if pieces[index + 1..].contains(piece) {
+                let who = if players == 2 { "both" } else { "two" };
+                return Err(format!(
+                    "{} players would be drawn as '{}' in the same color",
+                    who, piece.glyph
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // All built-in themes
+    pub fn all() -> Vec<Theme> {
+        THEME_NAMES
+            .iter()
+            .filter_map(|name| Theme::builtin(name))
+            .collect()
+    }
+
+    // Pick a theme the terminal can display, falling back to ASCII with a reason when it can't
+    pub fn fallback(self, unicode: bool, color: bool) -> (Theme, Option<String>) {
+        let reason = if self.needs_unicode && !unicode {
+            Some("the terminal can't display its Unicode glyphs")
+        } else if self.needs_color && !color {
+            Some("its players are only told apart by color")
+        } else {
+            None
+        };
+
+        match reason {
+            Some(reason) => {
+                let warning = format!("theme '{}' not used: {}", self.name, reason);
+                (
+                    Theme::builtin("ascii").expect("ascii theme is built in"),
+                    Some(warning),
+                )
+            }
+            None => (self, None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_symbol_is_one_character_one_or_two_columns_wide() {
+        for symbol in ["X", "@", "é", "e\u{301}", "●", "🔴", "中"] {
+            assert_eq!(validate_symbol(symbol), Ok(()), "{:?}", symbol);
+        }
+        let rejected = [
+            ("", "symbol '' must be a single character"),
+            ("XO", "symbol 'XO' must be a single character"),
+            ("🔴🟡", "symbol '🔴🟡' must be a single character"),
+            ("\t", "symbol '\t' must be a single character"),
+            ("\u{7}", "symbol '\u{7}' must be a single character"),
+            (
+                "\u{200b}",
+                "symbol '\u{200b}' must be one or two columns wide",
+            ),
+        ];
+        for (symbol, err) in rejected {
+            assert_eq!(
+                validate_symbol(symbol),
+                Err(err.to_string()),
+                "{:?}",
+                symbol
+            );
+        }
+    }
+
+    #[test]
+    fn players_must_differ_in_symbol_or_color() {
+        for theme in Theme::all() {
+            assert_eq!(theme.check_players_distinct(4), Ok(()), "{}", theme.name);
+        }
+
+        let mut theme = Theme::builtin("ascii").expect("the theme is built in");
+        theme.customize(1, Some("X"), None);
+        assert_eq!(
+            theme.check_players_distinct(2),
+            Err("both players would be drawn as 'X' in the same color".to_string())
+        );
+        // The same symbol in another color can still be told apart
+        theme.customize(1, None, Some(Color::Code(RED)));
+        assert_eq!(theme.check_players_distinct(2), Ok(()));
+
+        // Only the players who sit down are compared
+        let mut theme = Theme::builtin("ascii").expect("the theme is built in");
+        theme.customize(3, Some("X"), None);
+        assert_eq!(theme.check_players_distinct(3), Ok(()));
+        assert_eq!(
+            theme.check_players_distinct(4),
+            Err("two players would be drawn as 'X' in the same color".to_string())
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use crate::clock;
+use crate::{Game, Player};
+
+// Characters of the time-per-move chart, from no time at all to the longest think
+const BARS: &[u8] = b"_.:-=+*#%@";
+
+// How many of the longest thinks the summary lists
+pub const LONGEST: usize = 3;
+
+// One player's part of a timed game's thinking
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Share {
+    pub moves: usize,
+    pub total: Duration,
+    pub average: Duration,
+    pub median: Duration,
+}
+
+// Each player's share of `times`, the thinking time of every move in order: Player 1 makes the
+// odd moves and Player 2 the even ones
+pub fn shares(times: &[Duration]) -> [Share; 2] {
+    [0, 1].map(|seat| {
+        let mut mine: Vec<Duration> = times.iter().skip(seat).step_by(2).copied().collect();
+        if mine.is_empty() {
+            return Share::default();
+        }
+        mine.sort();
+        let total: Duration = mine.iter().sum();
+        let middle = mine.len() / 2;
+        let median = if mine.len().is_multiple_of(2) {
+            (mine[middle - 1] + mine[middle]) / 2
+        } else {
+            mine[middle]
+        };
+        Share {
+            moves: mine.len(),
+            total,
+            average: total / mine.len() as u32,
+            median,
+        }
+    })
+}
+
+// The `count` longest thinks as move numbers from 1 and their times, longest first; of two
+// equally long, the earlier comes first
+pub fn longest(times: &[Duration], count: usize) -> Vec<(usize, Duration)> {
+    let mut moves: Vec<(usize, Duration)> = times
+        .iter()
+        .enumerate()
+        .map(|(index, &time)| (index + 1, time))
+        .collect();
+    moves.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    moves.truncate(count);
+    moves
+}
+
+// Every move's time as one character, taller for longer against the longest think: "_" for
+// no time at all up to "@"
+pub fn sparkline(times: &[Duration]) -> String {
+    let most = times.iter().max().copied().unwrap_or_default();
+    times
+        .iter()
+        .map(|time| {
+            if most.is_zero() || time.is_zero() {
+                return BARS[0] as char;
+            }
+            let steps = (BARS.len() - 1) as f64;
+            let level = (time.as_secs_f64() / most.as_secs_f64() * steps).ceil() as usize;
+            BARS[level.clamp(1, BARS.len() - 1)] as char
+        })
+        .collect()
+}
+
+impl Game {
+    // How long every move took, for a game on a clock; moves the clock didn't run for, like
+    // the computer's with its clock stopped, took no time
+    pub fn move_times(&self) -> Option<Vec<Duration>> {
+        self.clock?;
+        let mut times = self.move_times.clone();
+        times.resize(self.history.len(), Duration::ZERO);
+        Some(times)
+    }
+
+    // Where the time went, for after a timed game: each player's average and median move,
+    // the longest thinks with where they were played, and a chart of every move's time
+    pub fn time_breakdown(&self) -> Vec<String> {
+        let Some(times) = self.move_times().filter(|times| !times.is_empty()) else {
+            return Vec::new();
+        };
+        let [one, two] = shares(&times);
+        let share = |player: Player, share: Share| {
+            format!(
+                "{} {} average, {} median",
+                self.name(player),
+                clock::display(share.average),
+                clock::display(share.median)
+            )
+        };
+        let longest: Vec<String> = longest(&times, LONGEST)
+            .iter()
+            .map(|&(ply, time)| {
+                let player = if ply % 2 == 1 { Player::One } else { Player::Two };
+                format!(
+                    "{} on move {} ({} at {})",
+                    clock::display(time),
+                    ply,
+                    self.name(player),
+                    self.history[ply - 1]
+                )
+            })
+            .collect();
+        vec![
+            format!(
+                "Per move: {}; {}",
+                share(Player::One, one),
+                share(Player::Two, two)
+            ),
+            format!("Longest thinks: {}", longest.join(", ")),
+            format!("Time per move: {}", sparkline(&times)),
+        ]
+    }
+}
@@ -0,0 +1,449 @@
+use std::cell::RefCell;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::Level;
+
+use crate::history;
+use crate::profile;
+use crate::settings::Settings;
+use crate::outcome::WinKind;
+use crate::variant::{Move, Variant};
+use crate::{Game, Player};
+
+// What has been written about the game on the board, to tell what's new when it's looked at
+// again
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Seen {
+    // Id of the game, tying its lines together
+    game: String,
+    moves: usize,
+    offer: Option<Player>,
+    // Whether the players have swapped seats under the pie rule, and whether a handicap has
+    // been placed
+    swapped: bool,
+    handicap: bool,
+    finished: bool,
+    // Whether the game is paused, and how many pauses it's had
+    paused: bool,
+    pauses: usize,
+}
+
+// The open transcript file
+#[derive(Debug)]
+struct Output {
+    path: PathBuf,
+    file: BufWriter<File>,
+    size: u64,
+    // Size in bytes past which the file is rotated, and how many rotated files are kept
+    max_size: u64,
+    keep: u32,
+    // Set once a write has failed
+    broken: bool,
+}
+
+// The file, if one is kept, and what's been written about the game on the board so far
+#[derive(Debug, Default)]
+struct Log {
+    output: Option<Output>,
+    seen: Seen,
+    // Whether moves also name the square they landed on, as with --coords
+    coords: bool,
+}
+
+// A timestamped line for everything that happens in a session's games: starts, handicaps,
+// moves, takebacks, draw offers, swaps, peeks, resignations, errors, and results. Each line is flushed as
+// it's written, so a crash loses at most the last one. Lines look like
+// "2024-05-04T19:22:11Z game=01HX5T8M2VQ3C9Z6K4R7N0PJWB move=17 player=Alice col=4", with
+// " square=d3" after it when coordinates are on.
+// The same events go to the diagnostic log, whether or not a file is kept.
+#[derive(Debug, Default)]
+pub struct Transcript {
+    log: RefCell<Log>,
+}
+
+impl Transcript {
+    // A transcript that writes no file
+    pub fn off() -> Transcript {
+        Transcript::default()
+    }
+
+    // The session's transcript: appended to the file from --log-file, or to today's file under
+    // the data directory. Off with --no-transcript.
+    pub fn open(settings: &Settings) -> Result<Transcript, String> {
+        if !settings.transcript {
+            return Ok(Transcript::off());
+        }
+        let path = match &settings.log_file {
+            Some(path) => path.clone(),
+            None => dirs::data_dir()
+                .ok_or("no data directory found for the transcript; give --log-file")?
+                .join("connect_four")
+                .join("transcripts")
+                .join(format!("{}.log", profile::date(now() as i64 / 86_400))),
+        };
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|err| format!("could not create {}: {}", dir.display(), err))?;
+        }
+        let (file, size) = append(&path)?;
+        log::debug!("appending the transcript to {}", path.display());
+        Ok(Transcript {
+            log: RefCell::new(Log {
+                output: Some(Output {
+                    path,
+                    file,
+                    size,
+                    max_size: settings.transcript_max_size * 1024,
+                    keep: settings.transcript_keep,
+                    broken: false,
+                }),
+                seen: Seen::default(),
+                coords: settings.coords,
+            }),
+        })
+    }
+
+    // Write whatever happened in the game since it was last looked at, starting with a line
+    // for the game itself when it's a new one
+    pub fn observe(&self, game: &Game) {
+        self.with_log(|log| {
+            if log.seen.game != game.id {
+                log.start(game);
+            }
+            log.catch_up(game);
+        });
+    }
+
+    // The session is over, with `game` on the board
+    pub fn end(&self, game: &Game) {
+        self.observe(game);
+        if !game.is_finished() {
+            self.with_log(|log| {
+                log.write(
+                    Level::Info,
+                    &format!("event=quit moves={}", game.history.len()),
+                )
+            });
+        }
+    }
+
+    // A peek at the board of a blind game, leaving `left` more
+    pub fn peek(&self, game: &Game, left: usize) {
+        self.observe(game);
+        self.with_log(|log| {
+            log.write(
+                Level::Info,
+                &format!(
+                    "event=peek player={} peeks_left={}",
+                    value(game.name(game.current_player)),
+                    left
+                ),
+            )
+        });
+    }
+
+    // An error shown to the players
+    pub fn error(&self, message: &str) {
+        self.with_log(|log| {
+            log.write(
+                Level::Info,
+                &format!("event=error message={}", value(message)),
+            )
+        });
+    }
+
+    fn with_log<T>(&self, action: impl FnOnce(&mut Log) -> T) -> T {
+        action(&mut self.log.borrow_mut())
+    }
+}
+
+impl Log {
+    fn start(&mut self, game: &Game) {
+        self.seen = Seen {
+            game: game.id.clone(),
+            ..Seen::default()
+        };
+        let computer = game.computer.map_or("none".to_string(), |(_, difficulty)| {
+            format!("{:?}", difficulty).to_lowercase()
+        });
+        let previous = game
+            .previous_game
+            .as_ref()
+            .map_or(String::new(), |previous| format!(" previous_game={}", previous));
+        let random_start = game.seed.map_or(String::new(), |seed| {
+            format!(" random_start={} seed={}", game.random_start, seed)
+        });
+        let variant = match game.rules.variant {
+            Variant::Standard => String::new(),
+            variant => format!(" variant={}", variant.name()),
+        };
+        // Any players past the second, named after the first two
+        let others: String = ["three", "four"]
+            .iter()
+            .zip(game.names.iter().skip(2))
+            .map(|(seat, name)| format!(" player_{}={}", seat, value(name)))
+            .collect();
+        self.write(
+            Level::Info,
+            &format!(
+                "event=start player_one={} player_two={}{} computer={} size={}x{} connect={}{}{}{}",
+                value(&game.names[0]),
+                value(&game.names[1]),
+                others,
+                computer,
+                game.rules.width,
+                game.rules.height,
+                game.rules.connect,
+                previous,
+                random_start,
+                variant
+            ),
+        );
+    }
+
+    // Lines for the handicap, moves, takebacks, offers, and ending of `game` not written yet
+    fn catch_up(&mut self, game: &Game) {
+        if !game.handicap.is_empty() && !game.placing() && !self.seen.handicap {
+            let columns: Vec<String> = game
+                .handicap
+                .iter()
+                .map(|column| (column + 1).to_string())
+                .collect();
+            self.write(
+                Level::Info,
+                &format!(
+                    "event=handicap player={} cols={}",
+                    value(game.name(Player::Two)),
+                    columns.join(",")
+                ),
+            );
+            self.seen.handicap = true;
+        }
+        let moves = game.history.len();
+        if moves < self.seen.moves {
+            self.write(
+                Level::Info,
+                &format!("event=undo count={}", self.seen.moves - moves),
+            );
+            self.seen.finished = false;
+        }
+        for (index, coord) in game
+            .history
+            .iter()
+            .enumerate()
+            .skip(self.seen.moves.min(moves))
+        {
+            let seat = game.seat_of(index + 1);
+            // A pop, an anvil, or a bomb names the column it went into that way instead, and
+            // a flip, which has no column, is flip=board
+            let column = (coord.column + 1).to_string();
+            let (kind, column) = match game.specials.at(index + 1, coord.column) {
+                // With coordinates on a drop names its square too, but in a 3D game, whose
+                // columns are pegs
+                Move::Drop(_) if self.coords && game.rules.variant != Variant::Cube => {
+                    ("col", format!("{} square={}", column, coord))
+                }
+                Move::Drop(_) => ("col", column),
+                Move::Pop(_) => ("pop", column),
+                Move::Anvil(_) => ("anvil", column),
+                Move::Bomb(_) => ("bomb", column),
+                Move::Flip => ("flip", "board".to_string()),
+            };
+            self.write(
+                Level::Debug,
+                &format!(
+                    "move={} player={} {}={}",
+                    index + 1,
+                    value(game.name(seat)),
+                    kind,
+                    column
+                ),
+            );
+            if game.timeouts.contains(&(index + 1)) {
+                self.write(
+                    Level::Info,
+                    &format!(
+                        "event=timeout move={} player={} action=random",
+                        index + 1,
+                        value(game.name(seat))
+                    ),
+                );
+            }
+        }
+        self.seen.moves = moves;
+
+        if game.draw_offer != self.seen.offer {
+            if let Some(seat) = game.draw_offer {
+                self.write(
+                    Level::Info,
+                    &format!("event=draw_offer player={}", value(game.name(seat))),
+                );
+            }
+            self.seen.offer = game.draw_offer;
+        }
+
+        if game.swapped && !self.seen.swapped {
+            self.write(
+                Level::Info,
+                &format!(
+                    "event=swap player={}",
+                    value(game.name(Player::One))
+                ),
+            );
+        }
+        self.seen.swapped = game.swapped;
+
+        let paused = game.paused_since().is_some();
+        if paused && !self.seen.paused {
+            self.write(
+                Level::Info,
+                &format!(
+                    "event=pause player={} pauses={}",
+                    value(game.name(game.current_player)),
+                    game.pauses.len() + 1
+                ),
+            );
+        }
+        if let Some(length) = game.pauses.get(self.seen.pauses..).and_then(|new| new.last()) {
+            self.write(
+                Level::Info,
+                &format!("event=resume paused_ms={}", length.as_millis()),
+            );
+        }
+        self.seen.paused = paused;
+        self.seen.pauses = game.pauses.len();
+
+        if game.is_finished() && !self.seen.finished {
+            let event = match game.won_by() {
+                Some(WinKind::Time) => Some("flag"),
+                Some(WinKind::Resignation) => Some("resign"),
+                _ => None,
+            };
+            if let (Some(event), Some(winner)) = (event, game.winner()) {
+                let loser = winner.other();
+                self.write(
+                    Level::Info,
+                    &format!("event={} player={}", event, value(game.name(loser))),
+                );
+            }
+            let result = match game.winner() {
+                None => "result=draw".to_string(),
+                Some(winner) => format!("result=win winner={}", value(game.name(winner))),
+            };
+            self.write(
+                Level::Info,
+                &format!(
+                    "event=result {} ending={} moves={}",
+                    result,
+                    value(history::ending(game)),
+                    moves
+                ),
+            );
+        }
+        self.seen.finished = game.is_finished();
+    }
+
+    // Log one event of the current game, and append its line to the file if one is kept
+    fn write(&mut self, level: Level, fields: &str) {
+        let fields = format!("game={} {}", self.seen.game, fields);
+        log::log!(target: "connect_four::game", level, "{}", fields);
+        if let Some(output) = &mut self.output {
+            output.write(&format!("{} {}\n", timestamp(), fields));
+        }
+    }
+}
+
+impl Output {
+    // Append a line, rotating the file first if it's full. A file that can't be written any
+    // more is given up on rather than reported after every move.
+    fn write(&mut self, line: &str) {
+        if self.broken {
+            return;
+        }
+        let written = self
+            .rotate(line.len() as u64)
+            .and_then(|()| {
+                self.file
+                    .write_all(line.as_bytes())
+                    .and_then(|()| self.file.flush())
+                    .map_err(|err| err.to_string())
+            })
+            .is_ok();
+        if written {
+            self.size += line.len() as u64;
+        } else {
+            self.broken = true;
+        }
+    }
+
+    // Move a file that `extra` more bytes would take past the limit to "<name>.1", shifting
+    // older ones up and dropping the oldest beyond `keep`
+    fn rotate(&mut self, extra: u64) -> Result<(), String> {
+        if self.size == 0 || self.size + extra <= self.max_size {
+            return Ok(());
+        }
+        let numbered = |number: u32| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", number));
+            PathBuf::from(name)
+        };
+        let move_file = |from: &Path, to: &Path| match fs::rename(from, to) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.to_string()),
+            _ => Ok(()),
+        };
+        for number in (1..self.keep).rev() {
+            move_file(&numbered(number), &numbered(number + 1))?;
+        }
+        if self.keep > 0 {
+            move_file(&self.path, &numbered(1))?;
+        } else {
+            fs::remove_file(&self.path).map_err(|err| err.to_string())?;
+        }
+        let (file, size) = append(&self.path)?;
+        self.file = file;
+        self.size = size;
+        Ok(())
+    }
+}
+
+// The file opened for appending, and its size so far
+fn append(path: &Path) -> Result<(BufWriter<File>, u64), String> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| format!("could not open {}: {}", path.display(), err))?;
+    let size = file.metadata().map_or(0, |metadata| metadata.len());
+    Ok((BufWriter::new(file), size))
+}
+
+// Seconds since 1970-01-01 UTC
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+// The current time as YYYY-MM-DDTHH:MM:SSZ
+pub fn timestamp() -> String {
+    let seconds = now();
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        profile::date(seconds as i64 / 86_400),
+        seconds % 86_400 / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+// A field's value as it is, or quoted when it has spaces, quotes, or equals signs that would
+// make the line hard to split
+fn value(text: &str) -> String {
+    if !text.is_empty() && !text.contains(|c: char| c.is_whitespace() || c == '"' || c == '=') {
+        return text.to_string();
+    }
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
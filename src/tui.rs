@@ -0,0 +1,760 @@
+use std::io;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
+};
+use crossterm::execute;
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Color as TermColor, Modifier, Style as CellStyle};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::ai;
+use crate::board::Cell;
+use crate::clock;
+use crate::coord::{self, Coord};
+use crate::history;
+use crate::i18n;
+use crate::keys::{self, Action};
+use crate::outcome::{DrawKind, WinKind};
+use crate::profile;
+use crate::render::{self, Layout as BoardLayout, Renderer};
+use crate::session::{First, Session};
+use crate::settings::Settings;
+use crate::style::Color;
+use crate::transcript::Transcript;
+use crate::variant::{Move, Variant};
+use crate::Game;
+
+// Width of the move list and statistics panel
+const SIDE_PANEL: u16 = 26;
+
+// Play in the full-screen interface until the player quits. The terminal is restored on
+// return and on panic.
+pub fn run(settings: &Settings, renderer: &Renderer) -> io::Result<()> {
+    let transcript = crate::open_transcript(settings);
+    let mut terminal = ratatui::init();
+
+    // Hand mouse selection back to the terminal whichever way the game ends
+    let restore_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = execute!(io::stdout(), DisableMouseCapture);
+        restore_hook(info);
+    }));
+    let result = execute!(io::stdout(), EnableMouseCapture)
+        .and_then(|()| event_loop(&mut terminal, settings, renderer, &transcript));
+    let _ = execute!(io::stdout(), DisableMouseCapture);
+
+    ratatui::restore();
+
+    // The game on the board when the player quit still counts if it was over
+    for warning in profile::record_game(&result?, settings) {
+        eprintln!("{}", i18n::fill("warning", &[("message", &warning)]));
+    }
+    Ok(())
+}
+
+// Redraw after every event, resizes included, and play keys and clicks against the game,
+// logging what happens to `transcript`. Returns the game on the board when the player quits.
+fn event_loop(
+    terminal: &mut DefaultTerminal,
+    settings: &Settings,
+    renderer: &Renderer,
+    transcript: &Transcript,
+) -> io::Result<Game> {
+    let mut game = Game::opening(settings).map_err(io::Error::other)?;
+    let mut session = Session::default();
+    let notes: Vec<String> = game
+        .coin_flip_note(settings)
+        .into_iter()
+        .chain(game.random_start_note())
+        .collect();
+    let mut message = if notes.is_empty() {
+        help(&game, renderer)
+    } else {
+        notes.join("  |  ")
+    };
+    // Column under the mouse pointer, previewed above the board
+    let mut selected = None;
+    // Screen column where the board's rows start, as of the last draw
+    let mut origin = 0;
+    // Figures over the game log, shown in place of the move list while toggled on
+    let mut statistics: Option<Vec<String>> = None;
+    // While a timed game is paused, whether the players have been asked if they're ready
+    let mut paused: Option<bool> = None;
+    // Key of the kind of move the next column pressed makes when it isn't a drop: a pop
+    // under PopOut, or an anvil or a bomb under Power Up
+    let mut armed: Option<char> = None;
+
+    loop {
+        // The clock and the move timer run for the player to move, whose time may have run
+        // out
+        if game.tick(Instant::now()) {
+            message = match game.timeout_note() {
+                Some(note) if !game.is_finished() => note,
+                _ if game.is_finished() => game_over(&game, &session, settings),
+                _ => help(&game, renderer),
+            };
+        }
+        transcript.observe(&game);
+
+        // The board stays hidden until a key and a yes bring it back
+        if let Some(asked) = paused {
+            terminal.draw(|frame| draw_paused(frame, renderer, asked))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            paused = match (asked, key.code) {
+                (false, _) => Some(true),
+                (true, KeyCode::Char('y' | 'Y')) => {
+                    let length = game.resume(Instant::now());
+                    message = i18n::fill("pause.resumed", &[("length", &clock::display(length))]);
+                    None
+                }
+                (true, _) => Some(false),
+            };
+            continue;
+        }
+
+        terminal.draw(|frame| {
+            origin = draw(
+                frame,
+                &game,
+                renderer,
+                selected,
+                statistics.as_deref(),
+                &message,
+            )
+        })?;
+
+        // The computer answers without waiting for an event
+        if let Some(difficulty) = game.computer_to_move() {
+            if game.can_swap() && ai::takes_swap(&game, difficulty) {
+                game.swap(Instant::now()).expect("the computer may swap");
+                message = game.swap_note();
+                continue;
+            }
+            if let Some(mv) = ai::choose_move(&game, difficulty) {
+                // A move found after the computer's time ran out is too late
+                if game.tick(Instant::now()) {
+                    message = game_over(&game, &session, settings);
+                    continue;
+                }
+                game.play(mv).expect("the computer only picks legal moves");
+                game.debug_validate();
+                message = if game.is_finished() {
+                    game_over(&game, &session, settings)
+                } else {
+                    help(&game, renderer)
+                };
+            }
+            continue;
+        }
+
+        // On a clock or a move timer, wake up every second to show it going down and to
+        // catch the time running out
+        if let Some(left) = game.time_left(Instant::now()) {
+            if !event::poll(left.min(Duration::from_secs(1)))? {
+                continue;
+            }
+        }
+        let mv = match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    transcript.end(&game);
+                    return Ok(game);
+                }
+                KeyCode::Char('s') if game.can_swap() => {
+                    game.swap(Instant::now())
+                        .expect("swapping is allowed in answer to the first move");
+                    message = game.swap_note();
+                    continue;
+                }
+                KeyCode::Char('p') if !game.is_finished() => {
+                    match game.can_pause(settings.rated) {
+                        Ok(()) => {
+                            game.pause(Instant::now());
+                            paused = Some(false);
+                        }
+                        Err(err) => message = i18n::fill("error", &[("message", &err)]),
+                    }
+                    continue;
+                }
+                // A flip doesn't need a column, so it's played straight away
+                KeyCode::Char('F') if !game.is_finished() && game.rules.variant == Variant::Flip => {
+                    armed = None;
+                    Move::Flip
+                }
+                KeyCode::Char(key)
+                    if !game.is_finished() && special_move(game.rules.variant, key, 0).is_some() =>
+                {
+                    armed = (armed != Some(key)).then_some(key);
+                    let question = match special_move(game.rules.variant, key, 0) {
+                        Some(Move::Anvil(_)) => "tui.ask.anvil",
+                        Some(Move::Bomb(_)) => "tui.ask.bomb",
+                        _ => "tui.ask.pop",
+                    };
+                    message = match armed {
+                        Some(_) => i18n::fill(question, &[("key", &key)]),
+                        None => help(&game, renderer),
+                    };
+                    continue;
+                }
+                KeyCode::Tab => {
+                    statistics = match statistics {
+                        Some(_) => None,
+                        None => Some(statistics_panel()),
+                    };
+                    continue;
+                }
+                KeyCode::Char(key @ ('r' | 'R')) if game.is_finished() => {
+                    // Colors alternate through a match, and a decided one starts over
+                    session.record(&game);
+                    let warnings = profile::record_game(&game, settings);
+                    let swap = match settings.match_format {
+                        Some(format) => {
+                            if session
+                                .match_result(format, settings.half_point_draws)
+                                .is_some()
+                            {
+                                session = Session::default();
+                                settings.first.swap_rematch(true)
+                            } else {
+                                true
+                            }
+                        }
+                        None => settings.first.swap_rematch(key == 'r'),
+                    };
+                    if statistics.is_some() {
+                        statistics = Some(statistics_panel());
+                    }
+                    game = game.rematch(swap);
+                    // Games within a match alternate instead of going by a coin flip
+                    let flipped = settings.match_format.is_none() || session.games() == 0;
+                    message = warnings.first().map_or_else(
+                        || {
+                            game.coin_flip_note(settings)
+                                .filter(|_| flipped)
+                                .or_else(|| game.random_start_note())
+                                .unwrap_or_else(|| help(&game, renderer))
+                        },
+                        |warning| i18n::fill("warning", &[("message", warning)]),
+                    );
+                    continue;
+                }
+                KeyCode::Char(symbol) => match Coord::parse_column(&symbol.to_string()) {
+                    Some(column) => armed
+                        .take()
+                        .and_then(|key| special_move(game.rules.variant, key, column))
+                        .unwrap_or(Move::Drop(column)),
+                    None => {
+                        message = i18n::fill("tui.not_a_column", &[("key", &symbol)]);
+                        continue;
+                    }
+                },
+                _ => continue,
+            },
+            Event::Mouse(mouse) => {
+                let x = usize::from(mouse.column).checked_sub(usize::from(origin));
+                match keys::mouse_action(mouse, x, &board_layout(renderer), game.rules.width) {
+                    Action::Drop(column) => Move::Drop(column),
+                    Action::Special(mv) => mv,
+                    Action::Hover(column) => {
+                        selected = Some(column);
+                        continue;
+                    }
+                    Action::Outside => {
+                        message = i18n::text("tui.click");
+                        continue;
+                    }
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+
+        // A move made after the time ran out is too late
+        if game.tick(Instant::now()) {
+            message = match game.timeout_note() {
+                Some(note) if !game.is_finished() => note,
+                _ => game_over(&game, &session, settings),
+            };
+            continue;
+        }
+        let played = game.play(mv);
+        if played.is_ok() {
+            game.debug_validate();
+        }
+        message = match played {
+            Ok(()) if game.is_finished() => game_over(&game, &session, settings),
+            Ok(()) => match game.flip_line() {
+                Some(flips) if game.flipped(game.history.len()) => {
+                    format!("{}  |  {}", flips, help(&game, renderer))
+                }
+                _ => help(&game, renderer),
+            },
+            Err(err) => {
+                transcript.error(&err.to_string());
+                i18n::fill("error", &[("message", &err)])
+            }
+        };
+    }
+}
+
+// Cell geometry of the board widget, for finding the column under the mouse
+fn board_layout(renderer: &Renderer) -> BoardLayout {
+    BoardLayout {
+        cell_width: renderer.layout.cell_width,
+        separator: " ",
+        borders: false,
+        margin: 0,
+        scale: 1,
+        open_sides: false,
+    }
+}
+
+// The session's tally or the match score with this game counted, and the keys for what
+// comes next
+fn game_over(game: &Game, session: &Session, settings: &Settings) -> String {
+    let tally = session.with(game);
+    let mut message = match settings.match_format {
+        None => {
+            let keys = match settings.first {
+                First::Random => "tui.over.rematch_random",
+                First::Alternate => "tui.over.rematch_alternate",
+                First::P1 | First::P2 if game.handicap_size() > 0 => "tui.over.rematch_handicap",
+                First::P1 | First::P2 => "tui.over.rematch",
+            };
+            format!("{}  |  {}", tally.summary(), i18n::text(keys))
+        }
+        Some(format) => match tally.match_result(format, settings.half_point_draws) {
+            Some(result) => format!("{}  |  {}", result, i18n::text("tui.over.new_match")),
+            None => i18n::fill(
+                "tui.over.next_game",
+                &[
+                    ("format", &format),
+                    ("score", &tally.match_score(settings.half_point_draws)),
+                ],
+            ),
+        },
+    };
+    // Rating changes and new achievements lead, since they're news
+    let news: Vec<String> = profile::rating_changes(game, settings)
+        .into_iter()
+        .chain(profile::achievement_banners(game))
+        .collect();
+    if !news.is_empty() {
+        message = format!("{}  |  {}", news.join(", "), message);
+    }
+    if let Some(used) = game.time_used(Instant::now()) {
+        // The full breakdown is too long for one line; the averages fit
+        let averages = game.time_breakdown().into_iter().next();
+        let used = averages.map_or(used.clone(), |averages| format!("{}  |  {}", used, averages));
+        message = format!("{}  |  {}", used, message);
+    }
+    format!("{}  |  {}", message, i18n::fill("tui.game_id", &[("id", &game.id)]))
+}
+
+// The move `key` makes of `column` under `variant`, when it makes one other than a drop
+fn special_move(variant: Variant, key: char, column: usize) -> Option<Move> {
+    match (variant, key) {
+        (Variant::PopOut, 'o') => Some(Move::Pop(column)),
+        (Variant::PowerUp, 'A') => Some(Move::Anvil(column)),
+        (Variant::PowerUp, 'B') => Some(Move::Bomb(column)),
+        _ => None,
+    }
+}
+
+// Keys for the next move
+fn help(game: &Game, renderer: &Renderer) -> String {
+    let last = if renderer.coords {
+        Coord::column_letter(game.rules.width - 1).to_string()
+    } else {
+        game.rules.width.min(9).to_string()
+    };
+    let first = if renderer.coords { "a" } else { "1" };
+    let part = |shown: bool, key: &str| {
+        if shown {
+            i18n::text(key)
+        } else {
+            String::new()
+        }
+    };
+    let pause = part(
+        game.clock.is_some() || game.move_timer.is_some(),
+        "tui.keys.pause",
+    );
+    let swap = part(game.can_swap(), "tui.keys.swap");
+    let pop = match game.rules.variant {
+        Variant::Standard | Variant::Cylinder | Variant::Misere | Variant::Score | Variant::Cube => {
+            String::new()
+        }
+        Variant::PopOut => i18n::text("tui.keys.pop"),
+        Variant::PowerUp => i18n::text("tui.keys.powers"),
+        Variant::Flip => i18n::text("tui.keys.flip"),
+    };
+    let placing = if game.placing() {
+        format!("{}. ", game.placement_note())
+    } else {
+        String::new()
+    };
+    i18n::fill(
+        "tui.keys",
+        &[
+            ("placing", &placing),
+            ("first", &first),
+            ("last", &last),
+            ("pop", &pop),
+            ("swap", &swap),
+            ("pause", &pause),
+        ],
+    )
+}
+
+// Nothing of the game while it's paused, just how to resume
+fn draw_paused(frame: &mut Frame, renderer: &Renderer, asked: bool) {
+    let text = i18n::text(if asked {
+        "tui.paused.ready"
+    } else {
+        "tui.paused"
+    });
+    let area = frame.area();
+    let [_, middle, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ])
+    .areas(area);
+    frame.render_widget(
+        Paragraph::new(text)
+            .alignment(Alignment::Center)
+            .style(accent(renderer)),
+        middle,
+    );
+}
+
+// Headline figures over every logged game
+fn statistics_panel() -> Vec<String> {
+    match history::summarize(&history::Filter::default()) {
+        Ok((summary, _)) => summary.panel(),
+        Err(err) => vec![i18n::fill("error", &[("message", &err)])],
+    }
+}
+
+// Board in the middle, move list on the right, and the status and input lines at the bottom.
+// Returns the screen column where the board's rows start.
+fn draw(
+    frame: &mut Frame,
+    game: &Game,
+    renderer: &Renderer,
+    selected: Option<usize>,
+    statistics: Option<&[String]>,
+    message: &str,
+) -> u16 {
+    let [main, status, input] = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [board, side] =
+        Layout::horizontal([Constraint::Min(0), Constraint::Length(SIDE_PANEL)]).areas(main);
+
+    let origin = draw_board(frame, board, game, renderer, selected);
+    draw_side_panel(frame, side, game, renderer, statistics);
+    frame.render_widget(
+        Paragraph::new(status_line(game, renderer)).style(accent(renderer)),
+        status,
+    );
+    frame.render_widget(Paragraph::new(format!("> {}", message)), input);
+    origin
+}
+
+// The grid centered in a bordered block, with column labels under it. Returns the screen
+// column where its rows start.
+fn draw_board(
+    frame: &mut Frame,
+    area: Rect,
+    game: &Game,
+    renderer: &Renderer,
+    selected: Option<usize>,
+) -> u16 {
+    let connect = game.rules.connect;
+    let title = if game.rules.variant.wraps() {
+        i18n::fill("tui.title.cylinder", &[("connect", &connect)])
+    } else if let Some(tally) = game.tally_line() {
+        i18n::fill("tui.title.score", &[("connect", &connect), ("tally", &tally)])
+    } else {
+        i18n::fill("tui.title", &[("connect", &connect)])
+    };
+    let block = Block::bordered().title(format!(" {} ", title));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let lines = board_lines(game, renderer, selected);
+    let height = lines.len() as u16;
+    let width = board_layout(renderer).width(game.rules.width) as u16;
+    let [_, centered, _] = Layout::vertical([
+        Constraint::Fill(1),
+        Constraint::Length(height),
+        Constraint::Fill(1),
+    ])
+    .areas(inner);
+    frame.render_widget(Paragraph::new(lines).alignment(Alignment::Center), centered);
+    // Centered the way the paragraph centers its lines, which rounds each half down
+    centered.x + (centered.width / 2).saturating_sub(width / 2)
+}
+
+// The current player's piece faintly over the selected column, if it's open, then one line
+// per board row from the top, then the column labels
+fn board_lines(game: &Game, renderer: &Renderer, selected: Option<usize>) -> Vec<Line<'static>> {
+    let width = renderer.layout.cell_width;
+    let last_move = game.last_move();
+    let player = Some(game.current_player);
+    let preview: Vec<Span> = (0..game.rules.width)
+        .map(|column| {
+            let open = selected == Some(column) && game.can_play(column);
+            let separator = if column > 0 { " " } else { "" };
+            if open {
+                Span::styled(
+                    format!(
+                        "{}{}",
+                        separator,
+                        renderer.layout.pad(renderer.glyph(player))
+                    ),
+                    piece_style(renderer, player).add_modifier(Modifier::DIM),
+                )
+            } else {
+                Span::raw(format!("{}{}", separator, renderer.layout.pad("")))
+            }
+        })
+        .collect();
+    let mut lines = vec![Line::from(preview)];
+    lines.extend(game.board.rows().enumerate().map(|(index, row)| {
+        let spans: Vec<Span> = row
+            .iter()
+            .enumerate()
+            .flat_map(|(column, &cell)| {
+                let mut style = piece_style(renderer, cell);
+                let winning = game
+                    .winning_line()
+                    .is_some_and(|line| line.contains(&(index, column)));
+                let last = last_move.is_some_and(|coord| {
+                    coord.column == column && coord.row == game.rules.height - 1 - index
+                });
+                if winning {
+                    style = style.add_modifier(Modifier::REVERSED);
+                } else if last {
+                    style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+                }
+                let separator = if column > 0 { " " } else { "" };
+                [
+                    Span::raw(separator),
+                    Span::styled(renderer.layout.pad(renderer.glyph(cell)), style),
+                ]
+            })
+            .collect();
+        Line::from(spans)
+    }));
+
+    let labels: Vec<String> = (0..game.rules.width)
+        .map(|column| {
+            let label = if renderer.coords {
+                Coord::column_letter(column).to_string()
+            } else {
+                (column + 1).to_string()
+            };
+            format!("{:^width$}", label, width = width)
+        })
+        .collect();
+    lines.push(Line::styled(
+        labels.join(" "),
+        CellStyle::new().add_modifier(Modifier::DIM),
+    ));
+    lines
+}
+
+// Moves so far, or the game log's statistics when they're toggled on, and how many pieces
+// each player has on the board
+fn draw_side_panel(
+    frame: &mut Frame,
+    area: Rect,
+    game: &Game,
+    renderer: &Renderer,
+    statistics: Option<&[String]>,
+) {
+    // A line for each player, and under Power Up or Flip another for the anvil and bomb or
+    // the flip they have left
+    let powers = matches!(game.rules.variant, Variant::PowerUp | Variant::Flip);
+    let height = 2 + game.players() as u16 * if powers { 2 } else { 1 };
+    let [moves, stats] = Layout::vertical([Constraint::Min(0), Constraint::Length(height)]).areas(area);
+
+    if let Some(lines) = statistics {
+        frame.render_widget(
+            Paragraph::new(lines.join("\n")).block(Block::bordered().title(format!(" {} ", i18n::text("tui.panel.statistics")))),
+            moves,
+        );
+    } else {
+        let history = if game.history.is_empty() {
+            i18n::text("tui.no_moves")
+        } else {
+            coord::format_history(&game.history, &game.specials)
+        };
+        frame.render_widget(
+            Paragraph::new(history)
+                .wrap(Wrap { trim: true })
+                .block(Block::bordered().title(format!(" {} ", i18n::text("tui.panel.moves")))),
+            moves,
+        );
+    }
+
+    let lines: Vec<Line> = game
+        .seats()
+        .into_iter()
+        .flat_map(|player| {
+            let mut lines = vec![Line::from(vec![
+                Span::styled(
+                    renderer.glyph(Some(player)).to_string(),
+                    piece_style(renderer, Some(player)),
+                ),
+                Span::raw(format!(
+                    " {}: {}",
+                    game.name(player),
+                    i18n::plural("count.pieces", game.pieces_count(player) as usize)
+                )),
+            ])];
+            if game.rules.variant == Variant::Flip {
+                lines.push(Line::raw(format!(
+                    "  {}",
+                    i18n::plural("count.flips", game.flips_left(player))
+                )));
+            } else if powers {
+                lines.push(Line::raw(format!(
+                    "  {}, {}",
+                    i18n::plural("count.anvils", game.anvils_left(player)),
+                    i18n::plural("count.bombs", game.bombs_left(player))
+                )));
+            }
+            lines
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(format!(" {} ", i18n::text("tui.panel.pieces")))),
+        stats,
+    );
+}
+
+// Whose turn it is, or the result
+fn status_line(game: &Game, renderer: &Renderer) -> String {
+    let clock = game
+        .clock_line(Instant::now())
+        .map_or(String::new(), |clock| format!("  |  {}", clock));
+    if !game.is_finished() {
+        let to_move = i18n::fill(
+            "tui.to_move",
+            &[
+                ("name", &game.name(game.current_player)),
+                ("piece", &renderer.glyph(Some(game.current_player))),
+                ("number", &(game.current_move + 1)),
+                ("time", &game.time_note(Instant::now())),
+            ],
+        );
+        return format!(" {}{}", to_move, clock);
+    }
+    let result = match (game.winner(), game.won_by()) {
+        (None, _) if game.drawn_by() == Some(DrawKind::Agreement) => i18n::text("banner.agreement"),
+        (None, _) => i18n::text("banner.draw"),
+        (Some(winner), Some(WinKind::Time)) => {
+            return format!(" {}{}", i18n::fill("banner.won_on_time", &[("name", &game.name(winner))]), clock)
+        }
+        (Some(winner), Some(WinKind::Resignation)) => i18n::fill("banner.won_by_resignation", &[("name", &game.name(winner))]),
+        (Some(winner), _) => match game.connected_loser() {
+            Some(loser) => {
+                let connect = render::count(game.rules.connect);
+                i18n::fill("banner.lost", &[("name", &game.name(loser)), ("count", &connect)])
+            }
+            None => i18n::fill("banner.won", &[("name", &game.name(winner))]),
+        },
+    };
+    format!(" {}", result)
+}
+
+// A theme color as a cell style, left plain when colors are off
+fn color_style(renderer: &Renderer, color: Option<Color>) -> CellStyle {
+    match color.and_then(|color| color.rgb()) {
+        Some((r, g, b)) if renderer.style.color => CellStyle::new().fg(TermColor::Rgb(r, g, b)),
+        _ => CellStyle::new(),
+    }
+}
+
+fn piece_style(renderer: &Renderer, cell: Cell) -> CellStyle {
+    color_style(renderer, renderer.piece(cell).color)
+}
+
+fn accent(renderer: &Renderer) -> CellStyle {
+    color_style(renderer, Some(renderer.theme.accent))
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    use super::*;
+    use crate::style::Style;
+    use crate::theme::Theme;
+
+    // One frame of the screen on a terminal `width` by `height`, a line of text per row, and
+    // the column the board's rows start at
+    fn frame(game: &Game, width: u16, height: u16) -> (Vec<String>, u16) {
+        let style = Style { color: false, truecolor: false };
+        let renderer = Renderer::new(Theme::builtin("ascii").expect("the theme is built in"), style, None);
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("the backend is in memory");
+        let mut origin = 0;
+        terminal
+            .draw(|frame| origin = draw(frame, game, &renderer, Some(3), None, "4"))
+            .expect("the frame is drawn");
+        let buffer = terminal.backend().buffer();
+        let lines = (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect::<String>())
+            .collect();
+        (lines, origin)
+    }
+
+    #[test]
+    fn a_frame_shows_the_board_and_whose_turn_it_is() {
+        let mut game = Game::new();
+        for column in [3, 3, 4] {
+            game.play(Move::Drop(column)).expect("the column is open");
+        }
+        let (lines, origin) = frame(&game, 60, 16);
+        // The rows start where draw says, for clicks to be matched to columns
+        let board: Vec<String> = lines[3..11]
+            .iter()
+            .map(|line| line.chars().skip(origin as usize).take(13).collect())
+            .collect();
+        assert_eq!(
+            board,
+            [
+                "      O      ",
+                ". . . . . . .",
+                ". . . . . . .",
+                ". . . . . . .",
+                ". . . . . . .",
+                ". . . O . . .",
+                ". . . X X . .",
+                "1 2 3 4 5 6 7",
+            ]
+        );
+        assert_eq!(lines[14].trim_end(), " Player 2 to move (O)  |  Move 4");
+        assert_eq!(lines[15].trim_end(), "> 4");
+        assert!(lines[0].starts_with("┌ Connect 4 "), "{}", lines[0]);
+        assert!(lines[1].contains("1. d1 d2 2. e1"), "{}", lines[1]);
+        assert!(lines[11].contains("X Player 1: 2 pieces"), "{}", lines[11]);
+    }
+}
@@ -0,0 +1,434 @@
+use std::io::{self, BufRead, Write};
+
+use crate::coord::Coord;
+use crate::export;
+use crate::i18n;
+use crate::profile;
+use crate::rules::GameRules;
+use crate::settings::Settings;
+use crate::terminal::{Capabilities, Screen};
+use crate::variant::Move;
+use crate::Game;
+
+// The lessons, in the format described at the top of the file
+const LESSONS: &str = include_str!("tutorial/lessons.toml");
+
+// A lesson of the tutorial: a few positions to find the right move in
+struct Lesson {
+    id: String,
+    title: String,
+    steps: Vec<Step>,
+}
+
+struct Step {
+    // Columns played to reach the position, counted from 0
+    moves: Vec<Move>,
+    text: String,
+    // Columns that do what the text asks, counted from 0
+    answers: Vec<usize>,
+    right: String,
+    wrong: String,
+    // What's wrong with a particular column, said instead of `wrong`
+    feedback: Vec<(usize, String)>,
+}
+
+impl Step {
+    // The position the step is played from, with whoever is to move named as the player
+    // taking the lesson
+    fn position(&self, settings: &Settings, player: &str) -> Result<Game, String> {
+        let opponent = i18n::text("tutorial.opponent");
+        let names = if self.moves.len().is_multiple_of(2) {
+            [player.to_string(), opponent]
+        } else {
+            [opponent, player.to_string()]
+        };
+        let builder = Game::builder(settings)
+            .rules(GameRules::CLASSIC)
+            .names(&names);
+        export::replay(builder, &[], &self.moves)
+    }
+
+    // What to say about a move in `column` that isn't one of the answers
+    fn feedback(&self, column: usize) -> &str {
+        self.feedback
+            .iter()
+            .find(|(wrong, _)| *wrong == column)
+            .map_or(&self.wrong, |(_, text)| text)
+    }
+}
+
+// Every lesson, read from LESSONS. Positions that can't be reached are found when their
+// step is played.
+fn lessons() -> Result<Vec<Lesson>, String> {
+    let table: toml::Table = LESSONS
+        .parse()
+        .map_err(|err| format!("could not parse the lessons: {}", err))?;
+    let lessons = match table.get("lesson") {
+        Some(toml::Value::Array(lessons)) => lessons,
+        _ => return Err("the lessons must be a list".to_string()),
+    };
+    lessons
+        .iter()
+        .enumerate()
+        .map(|(index, lesson)| {
+            let lesson = lesson
+                .as_table()
+                .ok_or_else(|| format!("lesson {} must be a table", index + 1))?;
+            let text = |key: &str| {
+                lesson
+                    .get(key)
+                    .and_then(toml::Value::as_str)
+                    .map(str::to_string)
+                    .ok_or_else(|| format!("lesson {} needs '{}'", index + 1, key))
+            };
+            let id = text("id")?;
+            let steps = match lesson.get("step") {
+                Some(toml::Value::Array(steps)) => steps
+                    .iter()
+                    .enumerate()
+                    .map(|(number, step)| {
+                        read_step(step)
+                            .map_err(|err| format!("lesson '{}', step {}: {}", id, number + 1, err))
+                    })
+                    .collect::<Result<Vec<Step>, String>>()?,
+                _ => return Err(format!("lesson '{}' has no steps", id)),
+            };
+            Ok(Lesson {
+                title: text("title")?,
+                id,
+                steps,
+            })
+        })
+        .collect()
+}
+
+fn read_step(step: &toml::Value) -> Result<Step, String> {
+    let step = step.as_table().ok_or("a step must be a table")?;
+    let text = |key: &str| {
+        step.get(key)
+            .and_then(toml::Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| format!("'{}' must be text", key))
+    };
+    // Columns are written from 1, as they're typed
+    let columns = |key: &str| match step.get(key) {
+        Some(toml::Value::Array(columns)) => columns
+            .iter()
+            .map(|column| column.as_integer().and_then(column_index))
+            .collect::<Option<Vec<usize>>>()
+            .ok_or_else(|| format!("'{}' must list columns from 1", key)),
+        _ => Err(format!("'{}' must be a list", key)),
+    };
+    let feedback = match step.get("feedback") {
+        None => Vec::new(),
+        Some(toml::Value::Table(feedback)) => feedback
+            .iter()
+            .map(|(column, text)| {
+                let column = column.parse::<i64>().ok().and_then(column_index);
+                match (column, text.as_str()) {
+                    (Some(column), Some(text)) => Ok((column, text.to_string())),
+                    _ => Err("'feedback' must give text for columns from 1".to_string()),
+                }
+            })
+            .collect::<Result<Vec<(usize, String)>, String>>()?,
+        Some(_) => return Err("'feedback' must be a table".to_string()),
+    };
+    Ok(Step {
+        moves: columns("moves")?.into_iter().map(Move::Drop).collect(),
+        text: text("text")?,
+        answers: columns("answers")?,
+        right: text("right")?,
+        wrong: text("wrong")?,
+        feedback,
+    })
+}
+
+// A column written from 1 as one counted from 0
+fn column_index(column: i64) -> Option<usize> {
+    usize::try_from(column).ok()?.checked_sub(1)
+}
+
+// Teach the lessons to the player named first in the settings, starting from the one named
+// `start`, or else the first they haven't finished. Each lesson finished is kept in their
+// profile at once, so stopping part way through loses at most the lesson under way.
+pub fn run(settings: &Settings, start: Option<&str>) -> Result<(), String> {
+    let lessons = lessons()?;
+    let ids: Vec<&str> = lessons.iter().map(|lesson| lesson.id.as_str()).collect();
+    let player = settings.names[0].clone();
+    let first = match start {
+        Some(start) => ids.iter().position(|&id| id == start).ok_or_else(|| {
+            i18n::fill(
+                "tutorial.no_lesson",
+                &[("lesson", &start), ("lessons", &ids.join(", "))],
+            )
+        })?,
+        None => {
+            let done = profile::lessons_done(&player)?;
+            match ids
+                .iter()
+                .position(|id| !done.iter().any(|done| done == id))
+            {
+                Some(first) => first,
+                None => {
+                    println!("{}", i18n::fill("tutorial.all_done", &[("name", &player)]));
+                    0
+                }
+            }
+        }
+    };
+
+    // The lessons are always played on the classic board, drawn with the player's settings
+    let mut sized = settings.clone();
+    sized.set_rules(GameRules::CLASSIC);
+    let capabilities = Capabilities::detect(settings.color);
+    let (renderer, _) = crate::build_renderer(&sized, &capabilities);
+    let screen = Screen::new(settings.altscreen, capabilities, renderer);
+
+    // Lessons finished, and achievements earned, are shown above the next step, and again
+    // at the end when the screen they were on is left
+    let mut said = Vec::new();
+    let mut news = Vec::new();
+    for (number, lesson) in lessons.iter().enumerate().skip(first) {
+        for (index, step) in lesson.steps.iter().enumerate() {
+            let heading = i18n::fill(
+                "tutorial.heading",
+                &[
+                    ("number", &(number + 1)),
+                    ("count", &lessons.len()),
+                    ("title", &lesson.title),
+                    ("step", &(index + 1)),
+                    ("steps", &lesson.steps.len()),
+                ],
+            );
+            let game = step
+                .position(&sized, &player)
+                .map_err(|err| format!("lesson '{}', step {}: {}", lesson.id, index + 1, err))?;
+            let finished = teach(
+                step,
+                game,
+                &screen,
+                &news,
+                &heading,
+                &mut io::stdin().lock(),
+                &mut io::stdout(),
+            )
+            .map_err(|err| err.to_string())?;
+            news.clear();
+            if !finished {
+                leave(screen, &said, &[]);
+                println!(
+                    "{}",
+                    i18n::fill(
+                        "tutorial.stopped",
+                        &[("number", &(number + 1)), ("title", &lesson.title)]
+                    )
+                );
+                println!("{}", i18n::text("tutorial.carry_on"));
+                return Ok(());
+            }
+        }
+        news.push(i18n::fill(
+            "tutorial.finished",
+            &[("number", &(number + 1)), ("title", &lesson.title)],
+        ));
+        news.extend(profile::finish_lesson(&player, &lesson.id, &ids)?);
+        said.extend(news.iter().cloned());
+    }
+    leave(screen, &said, &news);
+    println!("{}", i18n::text("tutorial.done"));
+    Ok(())
+}
+
+// Leave the screen the lessons were drawn on, repeating what was said on it that would be
+// lost with it, and saying the `news` there was no next step to show above
+fn leave(screen: Screen, said: &[String], news: &[String]) {
+    let lines = if screen.redraws_in_place() {
+        said
+    } else {
+        news
+    };
+    drop(screen);
+    for line in lines {
+        println!("{}", line);
+    }
+}
+
+// Ask for the step's move on `input` until one of its answers is played, explaining what's
+// wrong with every other on `output`, below the board the screen draws. Returns false when
+// the player stops instead.
+fn teach(
+    step: &Step,
+    game: Game,
+    screen: &Screen,
+    news: &[String],
+    heading: &str,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> io::Result<bool> {
+    let mut note: Option<String> = None;
+    loop {
+        game.display_board(screen);
+        for line in news {
+            writeln!(output, "{}", screen.renderer.accent(line))?;
+        }
+        writeln!(output, "{}", heading)?;
+        writeln!(output, "{}", step.text)?;
+        if let Some(note) = &note {
+            screen.transcript.error(note);
+            let error_style = screen.capabilities.error_style;
+            writeln!(output, "{}", screen.renderer.error(error_style, note))?;
+        }
+        writeln!(output, "{}", i18n::text("tutorial.prompt"))?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(false);
+        }
+        let line = line.trim();
+        if matches!(line, "q" | "Q" | "quit" | ":quit") {
+            return Ok(false);
+        }
+        let Some(column) = Coord::parse_column(line) else {
+            note = Some(i18n::fill(
+                "tutorial.not_a_column",
+                &[("text", &line), ("width", &game.rules.width)],
+            ));
+            continue;
+        };
+        let mut played = game.clone();
+        if let Err(err) = played.play(Move::Drop(column)) {
+            note = Some(game.move_error(&err));
+            continue;
+        }
+        if !step.answers.contains(&column) {
+            note = Some(i18n::fill(
+                "tutorial.try_again",
+                &[("feedback", &step.feedback(column))],
+            ));
+            continue;
+        }
+
+        played.display_board(screen);
+        writeln!(output, "{}", heading)?;
+        writeln!(output, "{}", screen.renderer.accent(&step.right))?;
+        writeln!(output, "{}", i18n::text("tutorial.go_on"))?;
+        output.flush()?;
+        let mut line = String::new();
+        return Ok(input.read_line(&mut line)? > 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai;
+    use crate::render::Renderer;
+    use crate::style::Style;
+    use crate::theme::Theme;
+
+    fn screen() -> Screen {
+        let style = Style {
+            color: false,
+            truecolor: false,
+        };
+        let capabilities = Capabilities {
+            style,
+            error_style: style,
+            unicode: false,
+            interactive: false,
+        };
+        let theme = Theme::builtin("classic").expect("the theme is built in");
+        Screen::new(false, capabilities, Renderer::new(theme, style, None))
+    }
+
+    #[test]
+    fn every_step_is_set_up_and_its_answers_win_or_block() {
+        let settings = Settings::default();
+        for lesson in lessons().expect("the lessons parse") {
+            for (index, step) in lesson.steps.iter().enumerate() {
+                let at = format!("lesson '{}', step {}", lesson.id, index + 1);
+                let game = step.position(&settings, "Ada").expect(&at);
+                assert!(!game.is_finished(), "{}", at);
+                assert_eq!(game.name(game.current_player), "Ada", "{}", at);
+                assert!(!step.answers.is_empty(), "{}", at);
+                for (column, _) in &step.feedback {
+                    assert!(!step.answers.contains(column), "{}: {}", at, column + 1);
+                }
+
+                let learner = game.current_player;
+                let wins = ai::winning_moves(&game, learner);
+                for &column in &step.answers {
+                    let mut played = game.clone();
+                    played
+                        .play(Move::Drop(column))
+                        .unwrap_or_else(|err| panic!("{}: {} {}", at, column + 1, err));
+                    // A win there and then is taken, and otherwise none is left to the opponent
+                    if wins.is_empty() {
+                        let threats = ai::winning_moves(&played, played.current_player);
+                        assert_eq!(threats, Vec::new(), "{}: {}", at, column + 1);
+                    } else {
+                        assert_eq!(played.winner(), Some(learner), "{}: {}", at, column + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wrong_answers_are_explained_until_a_right_one_is_played() {
+        let lessons = lessons().expect("the lessons parse");
+        let double = lessons
+            .iter()
+            .find(|lesson| lesson.id == "double")
+            .expect("there's a lesson on double threats");
+        let step = &double.steps[0];
+        let game = step
+            .position(&Settings::default(), "Ada")
+            .expect("it's set up");
+
+        let mut input = "?\n9\n1\n7\n5\n\n".as_bytes();
+        let mut output = Vec::new();
+        let finished = teach(
+            step,
+            game.clone(),
+            &screen(),
+            &[],
+            "Step",
+            &mut input,
+            &mut output,
+        )
+        .expect("the output is kept");
+        assert!(finished);
+        let output = String::from_utf8(output).expect("the output is text");
+        let notes: Vec<&str> = output
+            .lines()
+            .filter_map(|line| line.split_once("Error: ").map(|(_, note)| note))
+            .collect();
+        assert_eq!(
+            notes,
+            [
+                "'?' isn't a column; type a number from 1 to 7",
+                "There's no column 9; it must be between 1 and 7",
+                "That leaves one gap to fill, at column 2, and a single block stops it. Try again.",
+                "Make your two pieces on the bottom three in a row with an empty cell at each end. Try again.",
+            ]
+        );
+        assert!(output.contains(&step.right), "{}", output);
+
+        // Stopping, or running out of input, leaves the step unfinished
+        for input in ["q\n", "4\n", ""] {
+            let finished = teach(
+                step,
+                game.clone(),
+                &screen(),
+                &[],
+                "Step",
+                &mut input.as_bytes(),
+                &mut Vec::new(),
+            )
+            .expect("the output is kept");
+            assert!(!finished, "{:?}", input);
+        }
+    }
+}
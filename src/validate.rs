@@ -0,0 +1,560 @@
+use std::fmt;
+
+use crate::ai;
+use crate::board::Board;
+use crate::coord::Coord;
+use crate::outcome::{Outcome, WinKind};
+use crate::variant::{Specials, Variant};
+use crate::{Game, Player};
+
+// A way a game's state doesn't hang together, as found by `Game::validate`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    // A piece with an empty cell under it
+    FloatingPiece(Coord),
+    // The move counter disagrees with the moves in the history
+    MoveCount {
+        counted: usize,
+        played: usize,
+    },
+    // A player has a different number of pieces on the board than the moves they've
+    // played, and any handicap, put there
+    PieceCount {
+        player: Player,
+        expected: u32,
+        found: u32,
+    },
+    // More than one player has a line on the board, in a variant where only the move
+    // completing one can be played
+    SeveralLines(Vec<Player>),
+    // A player has a line on the board but the game isn't recorded as won by it
+    UnrecordedLine(Player),
+    // The game is recorded as won on the board by the wrong player
+    WrongWinner {
+        expected: Player,
+        found: Player,
+    },
+    // A cell of the recorded winning line doesn't hold the line's piece
+    MissingLine(Coord),
+    // The handicap can't be put back on an empty board
+    BadHandicap(String),
+    // Move number `ply` of the history can't be played where the moves before it leave
+    // the game
+    IllegalMove {
+        ply: usize,
+        reason: String,
+    },
+    // Playing the history through leaves a different piece, or none, on this square
+    BoardDiffers(Coord),
+    // Playing the history through leaves a different player to move
+    WrongMover {
+        expected: Player,
+        found: Player,
+    },
+    // Playing the history through ends the game differently, or not at all
+    OutcomeDiffers {
+        expected: String,
+        found: String,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::FloatingPiece(coord) => {
+                write!(f, "the piece on {} has nothing under it", coord)
+            }
+            ValidationError::MoveCount { counted, played } => write!(
+                f,
+                "the move counter says {} but {} moves were played",
+                counted, played
+            ),
+            ValidationError::PieceCount {
+                player,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} has {} pieces on the board but should have {}",
+                player, found, expected
+            ),
+            ValidationError::SeveralLines(players) => {
+                let players: Vec<String> = players.iter().map(Player::to_string).collect();
+                write!(f, "{} all have a line on the board", players.join(", "))
+            }
+            ValidationError::UnrecordedLine(player) => write!(
+                f,
+                "{} has a line on the board but the game isn't won by it",
+                player
+            ),
+            ValidationError::WrongWinner { expected, found } => write!(
+                f,
+                "the game is recorded as won by {} but the board gives it to {}",
+                found, expected
+            ),
+            ValidationError::MissingLine(coord) => {
+                write!(
+                    f,
+                    "the winning line runs through {}, which isn't part of it",
+                    coord
+                )
+            }
+            ValidationError::BadHandicap(reason) => write!(f, "the handicap: {}", reason),
+            ValidationError::IllegalMove { ply, reason } => {
+                write!(f, "move {} can't be played: {}", ply, reason)
+            }
+            ValidationError::BoardDiffers(coord) => write!(
+                f,
+                "the moves played leave {} different from the board",
+                coord
+            ),
+            ValidationError::WrongMover { expected, found } => write!(
+                f,
+                "{} is to move but the moves played leave it to {}",
+                found, expected
+            ),
+            ValidationError::OutcomeDiffers { expected, found } => write!(
+                f,
+                "the game is recorded as {} but the moves played leave it {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+// How a game stands, as an OutcomeDiffers error names it
+fn standing(outcome: &Option<Outcome>) -> String {
+    match outcome {
+        None => "going on".to_string(),
+        Some(won @ Outcome::Win { player, .. }) => format!("won by {} ({})", player, won.ending()),
+        Some(drawn) => format!("drawn ({})", drawn.ending()),
+    }
+}
+
+// Errors found by `Game::validate` on one line, for reporting a game that can't be read back
+pub fn summary(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(ValidationError::to_string)
+        .collect::<Vec<String>>()
+        .join("; ")
+}
+
+impl Game {
+    // Every way the game's state doesn't hang together, not just the first, for a game put
+    // together from a save or an import rather than played here: pieces in mid-air, counts
+    // of moves and pieces that don't add up, lines on the board the outcome doesn't account
+    // for, and a history that doesn't play through to the board, player to move, and
+    // outcome kept with it
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+
+        for column in 0..self.rules.width {
+            let height = self.column_height(column);
+            errors.extend(
+                (height..self.rules.height)
+                    .map(|row| Coord { column, row })
+                    .filter(|coord| self.cell(coord.row, coord.column).is_some())
+                    .map(ValidationError::FloatingPiece),
+            );
+        }
+
+        let played = self.history.len();
+        if self.current_move != played {
+            errors.push(ValidationError::MoveCount {
+                counted: self.current_move,
+                played,
+            });
+        }
+
+        // Pops, anvils, and bombs take pieces off the board, so only a game without them
+        // has a piece for every drop
+        if !matches!(self.rules.variant, Variant::PopOut | Variant::PowerUp) {
+            for player in self.seats() {
+                let drops = (1..=played)
+                    .filter(|&ply| self.seat_of(ply) == player && !self.flipped(ply))
+                    .count();
+                let given = if player == Player::Two {
+                    self.handicap.len()
+                } else {
+                    0
+                };
+                let expected = (drops + given) as u32;
+                let found = self.pieces_count(player);
+                if found != expected {
+                    errors.push(ValidationError::PieceCount {
+                        player,
+                        expected,
+                        found,
+                    });
+                }
+            }
+        }
+
+        // Under the scoring variant lines don't end the game
+        if self.rules.variant != Variant::Score {
+            let mut connected: Vec<Player> = Vec::new();
+            for cells in ai::windows(self) {
+                let line = cells[0].filter(|&piece| cells.iter().all(|&cell| cell == Some(piece)));
+                if let Some(piece) = line.filter(|piece| !connected.contains(piece)) {
+                    connected.push(piece);
+                }
+            }
+            connected.sort_by_key(|player| player.index());
+            // Where pieces move or come off the board, both players can be left with a line
+            let settles = matches!(
+                self.rules.variant,
+                Variant::PopOut | Variant::PowerUp | Variant::Flip
+            );
+            if connected.len() > 1 && !settles {
+                errors.push(ValidationError::SeveralLines(connected.clone()));
+            }
+            for &player in &connected {
+                let winner = if self.rules.variant.misere() {
+                    player.other()
+                } else {
+                    player
+                };
+                match self.outcome {
+                    Some(Outcome::Win {
+                        player: found,
+                        by: WinKind::Connect,
+                        ..
+                    }) => {
+                        if connected.len() == 1 && found != winner {
+                            errors.push(ValidationError::WrongWinner {
+                                expected: winner,
+                                found,
+                            });
+                        }
+                    }
+                    _ => errors.push(ValidationError::UnrecordedLine(player)),
+                }
+            }
+        }
+
+        if let Some(line) = self.winning_line() {
+            let piece = line
+                .first()
+                .and_then(|&(row, column)| self.board[row][column]);
+            errors.extend(
+                line.iter()
+                    .filter(|&&(row, column)| piece.is_none() || self.board[row][column] != piece)
+                    .map(|&(row, column)| {
+                        ValidationError::MissingLine(Coord {
+                            column,
+                            row: self.rules.height - 1 - row,
+                        })
+                    }),
+            );
+        }
+
+        errors.extend(self.replay_errors());
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Stop on a move just played that left the game inconsistent, in debug builds. The game
+    // loops call this rather than `play_move` itself, which the engine's search goes through
+    // far too often for every position to be replayed.
+    pub fn debug_validate(&self) {
+        if cfg!(debug_assertions) {
+            if let Err(errors) = self.validate() {
+                panic!(
+                    "move {} left the game inconsistent: {}",
+                    self.current_move,
+                    summary(&errors)
+                );
+            }
+        }
+    }
+
+    // Where playing the history through from an empty board, after the handicap, comes out
+    // differently from the game
+    fn replay_errors(&self) -> Vec<ValidationError> {
+        let mut replayed = self.clone();
+        replayed.board = Board::new(self.rules.width, self.rules.height);
+        replayed.history = Vec::new();
+        replayed.specials = Specials::default();
+        replayed.cleared = Vec::new();
+        replayed.boards = Vec::new();
+        replayed.outcome = None;
+        replayed.current_move = 0;
+        replayed.current_player = Player::One;
+        replayed.handicap = Vec::new();
+        replayed.handicap_left = 0;
+        replayed.draw_offer = None;
+
+        let handicap = replayed
+            .set_handicap(&self.handicap)
+            .and_then(|()| replayed.leave_handicap(self.handicap_left));
+        if let Err(reason) = handicap {
+            return vec![ValidationError::BadHandicap(reason)];
+        }
+        let columns: Vec<usize> = self.history.iter().map(|coord| coord.column).collect();
+        for (index, mv) in self.specials.list(&columns).into_iter().enumerate() {
+            if let Err(err) = replayed.play(mv) {
+                return vec![ValidationError::IllegalMove {
+                    ply: index + 1,
+                    reason: err.to_string(),
+                }];
+            }
+        }
+
+        let mut errors: Vec<ValidationError> = self
+            .cells()
+            .filter(|&(coord, cell)| replayed.cell(coord.row, coord.column) != cell)
+            .map(|(coord, _)| ValidationError::BoardDiffers(coord))
+            .collect();
+        // A resignation, an agreed draw, or a loss on time isn't in the moves, and leaves the
+        // player to move as it found them
+        let expected = match &self.outcome {
+            Some(outcome) if outcome.off_the_board() && replayed.outcome.is_none() => {
+                self.outcome.clone()
+            }
+            _ => replayed.outcome.clone(),
+        };
+        if self.current_player != replayed.current_player {
+            errors.push(ValidationError::WrongMover {
+                expected: replayed.current_player,
+                found: self.current_player,
+            });
+        }
+        if self.outcome != expected {
+            errors.push(ValidationError::OutcomeDiffers {
+                expected: standing(&expected),
+                found: standing(&self.outcome),
+            });
+        }
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::outcome::DrawKind;
+    use crate::settings::Settings;
+    use crate::variant::Move;
+
+    // A game under the default rules with these columns played, counted from 1
+    fn played(columns: &[usize]) -> Game {
+        let mut game = Game::builder(&Settings::default())
+            .build()
+            .expect("the default rules are valid");
+        for &column in columns {
+            game.play(Move::Drop(column - 1))
+                .expect("the moves are legal");
+        }
+        game
+    }
+
+    // The errors `validate` finds after `corrupt` has been done to the game
+    fn errors(columns: &[usize], corrupt: impl FnOnce(&mut Game)) -> Vec<ValidationError> {
+        let mut game = played(columns);
+        assert_eq!(game.validate(), Ok(()));
+        corrupt(&mut game);
+        game.validate().expect_err("the game is corrupt")
+    }
+
+    // Column 4 then 4 and 3: X on d1 and c1, O on d2
+    const OPENING: [usize; 3] = [4, 4, 3];
+    // X up column 1 and O up column 2, won by X on a1 to a4
+    const WON: [usize; 7] = [1, 2, 1, 2, 1, 2, 1];
+
+    fn at(column: usize, row: usize) -> Coord {
+        Coord { column, row }
+    }
+
+    fn won_by(player: Player, line: Option<Vec<(usize, usize)>>) -> Option<Outcome> {
+        Some(Outcome::Win {
+            player,
+            line,
+            by: WinKind::Connect,
+        })
+    }
+
+    #[test]
+    fn floating_pieces() {
+        assert_eq!(
+            errors(&OPENING, |game| game.board[0][0] = Some(Player::One)),
+            [
+                ValidationError::FloatingPiece(at(0, 5)),
+                ValidationError::PieceCount {
+                    player: Player::One,
+                    expected: 2,
+                    found: 3
+                },
+                ValidationError::BoardDiffers(at(0, 5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn move_counts() {
+        assert_eq!(
+            errors(&OPENING, |game| game.current_move = 5),
+            [ValidationError::MoveCount {
+                counted: 5,
+                played: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn piece_counts() {
+        assert_eq!(
+            errors(&OPENING, |game| game.board[4][3] = None),
+            [
+                ValidationError::PieceCount {
+                    player: Player::Two,
+                    expected: 1,
+                    found: 0
+                },
+                ValidationError::BoardDiffers(at(3, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn several_lines() {
+        let errors = errors(&OPENING, |game| {
+            for column in 0..4 {
+                game.board[5][column] = Some(Player::One);
+                game.board[4][column] = Some(Player::Two);
+            }
+        });
+        assert!(errors.contains(&ValidationError::SeveralLines(vec![
+            Player::One,
+            Player::Two
+        ])));
+        assert!(errors.contains(&ValidationError::UnrecordedLine(Player::One)));
+        assert!(errors.contains(&ValidationError::UnrecordedLine(Player::Two)));
+    }
+
+    #[test]
+    fn unrecorded_lines() {
+        assert_eq!(
+            errors(&WON, |game| game.outcome = None),
+            [
+                ValidationError::UnrecordedLine(Player::One),
+                ValidationError::OutcomeDiffers {
+                    expected: "won by Player 1 (connect)".to_string(),
+                    found: "going on".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn wrong_winners() {
+        assert_eq!(
+            errors(&WON, |game| game.outcome = won_by(Player::Two, None)),
+            [
+                ValidationError::WrongWinner {
+                    expected: Player::One,
+                    found: Player::Two
+                },
+                ValidationError::OutcomeDiffers {
+                    expected: "won by Player 1 (connect)".to_string(),
+                    found: "won by Player 2 (connect)".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_lines() {
+        // The bottom row, where only a1 is X's
+        let line = vec![(5, 0), (5, 1), (5, 2), (5, 3)];
+        let errors = errors(&WON, |game| game.outcome = won_by(Player::One, Some(line)));
+        assert_eq!(
+            errors[..3],
+            [
+                ValidationError::MissingLine(at(1, 0)),
+                ValidationError::MissingLine(at(2, 0)),
+                ValidationError::MissingLine(at(3, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn bad_handicaps() {
+        let errors = errors(&OPENING, |game| game.handicap = vec![0; 7]);
+        assert_eq!(
+            errors.last(),
+            Some(&ValidationError::BadHandicap(
+                "a handicap can be at most 3 pieces when 4 in a row wins".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn illegal_moves() {
+        assert_eq!(
+            errors(&OPENING, |game| game.history[0].column = 20),
+            [ValidationError::IllegalMove {
+                ply: 1,
+                reason: "There's no column 21; it must be between 1 and 7".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn boards_that_differ_from_the_moves() {
+        assert_eq!(
+            errors(&OPENING, |game| {
+                game.board[5][3] = Some(Player::Two);
+                game.board[4][3] = Some(Player::One);
+            }),
+            [
+                ValidationError::BoardDiffers(at(3, 0)),
+                ValidationError::BoardDiffers(at(3, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrong_movers() {
+        assert_eq!(
+            errors(&OPENING, |game| game.current_player = Player::One),
+            [ValidationError::WrongMover {
+                expected: Player::Two,
+                found: Player::One
+            }]
+        );
+    }
+
+    #[test]
+    fn outcomes_that_differ_from_the_moves() {
+        let drawn = Some(Outcome::Draw {
+            by: DrawKind::FullBoard,
+        });
+        assert_eq!(
+            errors(&OPENING, |game| game.outcome = drawn),
+            [ValidationError::OutcomeDiffers {
+                expected: "going on".to_string(),
+                found: "drawn (full board)".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn errors_read_as_sentences() {
+        let errors = [
+            ValidationError::FloatingPiece(at(0, 5)),
+            ValidationError::MoveCount {
+                counted: 5,
+                played: 3,
+            },
+        ];
+        assert_eq!(
+            summary(&errors),
+            "the piece on a6 has nothing under it; the move counter says 5 but 3 moves were played"
+        );
+    }
+}
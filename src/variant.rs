@@ -0,0 +1,197 @@
+// The rules a game is played by
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum Variant {
+    // Pieces are only ever dropped in
+    Standard,
+    // A player may pop one of their own pieces out of the bottom row instead of dropping one
+    #[value(name = "popout")]
+    PopOut,
+    // Each player may also play one anvil and one bomb a game
+    #[value(name = "powerup")]
+    PowerUp,
+    // The board wraps around from its right edge to its left, so lines can cross the seam
+    // sideways and diagonally
+    Cylinder,
+    // Misère play: whoever completes a line loses it to the other player
+    Misere,
+    // Lines don't end the game: it's played until the board is full, and whoever made the
+    // most lines wins
+    Score,
+    // Each player may once a game turn the board upside down instead of dropping a piece,
+    // and the pieces fall back down
+    Flip,
+    // Score Four: pieces drop onto the pegs of a 4x4 grid, four levels high, and a line of
+    // four can run along any axis or diagonal of the cube
+    #[value(name = "3d")]
+    Cube,
+}
+
+impl Variant {
+    // The name the variant goes by in settings, saves, and exports
+    pub fn name(self) -> &'static str {
+        match self {
+            Variant::Standard => "standard",
+            Variant::PopOut => "popout",
+            Variant::PowerUp => "powerup",
+            Variant::Cylinder => "cylinder",
+            Variant::Misere => "misere",
+            Variant::Score => "score",
+            Variant::Flip => "flip",
+            Variant::Cube => "3d",
+        }
+    }
+
+    // Whether lines run off one side of the board onto the other
+    pub fn wraps(self) -> bool {
+        self == Variant::Cylinder
+    }
+
+    // Whether connecting a line loses the game instead of winning it
+    pub fn misere(self) -> bool {
+        self == Variant::Misere
+    }
+
+    pub fn parse(text: &str) -> Option<Variant> {
+        match text.trim().to_lowercase().as_str() {
+            "standard" => Some(Variant::Standard),
+            "popout" => Some(Variant::PopOut),
+            "powerup" => Some(Variant::PowerUp),
+            "cylinder" => Some(Variant::Cylinder),
+            "misere" | "misère" => Some(Variant::Misere),
+            "score" => Some(Variant::Score),
+            "flip" => Some(Variant::Flip),
+            "3d" => Some(Variant::Cube),
+            _ => None,
+        }
+    }
+}
+
+// What a player does on their turn
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
+    // Drop a piece into this column, counted from 0
+    Drop(usize),
+    // Take their own piece out of the bottom of this column, under PopOut
+    Pop(usize),
+    // Under Power Up, drop an anvil that crushes every piece in the column and stays at its
+    // bottom as the player's piece
+    Anvil(usize),
+    // Under Power Up, blow the top piece off the column, whoever's it is
+    Bomb(usize),
+    // Under Flip, turn the board upside down and let every piece fall back down
+    Flip,
+}
+
+impl Move {
+    pub fn column(self) -> usize {
+        match self {
+            Move::Drop(column) | Move::Pop(column) | Move::Anvil(column) | Move::Bomb(column) => {
+                column
+            }
+            // A flip has no column of its own; it's kept as the first in the history
+            Move::Flip => 0,
+        }
+    }
+
+    // Letter written before the column in move lists: p for a pop, a for an anvil, b for a
+    // bomb, and nothing for a drop
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Move::Drop(_) => "",
+            Move::Pop(_) => "p",
+            Move::Anvil(_) => "a",
+            Move::Bomb(_) => "b",
+            Move::Flip => "",
+        }
+    }
+
+    // The move as it's written in move lists, with `column` naming the column: "flip" for a
+    // flip, and the prefix and column for the rest
+    pub fn notation(self, column: impl Fn(usize) -> String) -> String {
+        match self {
+            Move::Flip => "flip".to_string(),
+            _ => format!("{}{}", self.prefix(), column(self.column())),
+        }
+    }
+
+    // A move written with its prefix, like "p4", or as the column alone, which `column`
+    // reads. A lone letter is always the column, on a board wide enough to have one, so a
+    // flip is spelled out as "flip".
+    pub fn parse(word: &str, column: impl Fn(&str) -> Option<usize>) -> Option<Move> {
+        if word.eq_ignore_ascii_case("flip") {
+            return Some(Move::Flip);
+        }
+        let mut chars = word.chars();
+        let kind: Option<fn(usize) -> Move> = match chars.next()?.to_ascii_lowercase() {
+            'p' => Some(Move::Pop),
+            'a' => Some(Move::Anvil),
+            'b' => Some(Move::Bomb),
+            _ => None,
+        };
+        match kind {
+            Some(kind) if !chars.as_str().is_empty() => column(chars.as_str()).map(kind),
+            _ => column(word).map(Move::Drop),
+        }
+    }
+}
+
+// Numbers of the moves of a game, from 1, that did something other than drop a piece
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Specials {
+    // Pieces popped out under PopOut
+    pub pops: Vec<usize>,
+    // Anvils and bombs played under Power Up
+    pub anvils: Vec<usize>,
+    pub bombs: Vec<usize>,
+    // Boards turned over under Flip
+    pub flips: Vec<usize>,
+}
+
+impl Specials {
+    // Move number `ply`, played in `column`
+    pub fn at(&self, ply: usize, column: usize) -> Move {
+        if self.pops.contains(&ply) {
+            Move::Pop(column)
+        } else if self.anvils.contains(&ply) {
+            Move::Anvil(column)
+        } else if self.bombs.contains(&ply) {
+            Move::Bomb(column)
+        } else if self.flips.contains(&ply) {
+            Move::Flip
+        } else {
+            Move::Drop(column)
+        }
+    }
+
+    // The moves of a game recorded as the columns they were played in
+    pub fn list(&self, columns: &[usize]) -> Vec<Move> {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(index, &column)| self.at(index + 1, column))
+            .collect()
+    }
+
+    // Note that move number `ply` was `mv`
+    pub fn record(&mut self, ply: usize, mv: Move) {
+        match mv {
+            Move::Drop(_) => {}
+            Move::Pop(_) => self.pops.push(ply),
+            Move::Anvil(_) => self.anvils.push(ply),
+            Move::Bomb(_) => self.bombs.push(ply),
+            Move::Flip => self.flips.push(ply),
+        }
+    }
+
+    // Forget the moves after the first `ply`
+    pub fn truncate(&mut self, ply: usize) {
+        for plies in [
+            &mut self.pops,
+            &mut self.anvils,
+            &mut self.bombs,
+            &mut self.flips,
+        ] {
+            plies.retain(|&number| number <= ply);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use crate::export::json_string;
+
+// A line of play branching off a game, kept with its save: after the first `ply` moves of
+// the line it branches from, these `moves` were tried instead. Variations can branch again
+// from their own moves, so a game's variations form a tree under its main line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Variation {
+    pub name: String,
+    // Moves from the start of the game before the branch, main line and parent variations
+    // included
+    pub ply: usize,
+    // 0-based columns played from the branch on
+    pub moves: Vec<usize>,
+    pub variations: Vec<Variation>,
+}
+
+impl Variation {
+    // The whole line from the start of the game, given the line it branches from
+    pub fn line(&self, parent: &[usize]) -> Vec<usize> {
+        parent[..self.ply.min(parent.len())]
+            .iter()
+            .chain(&self.moves)
+            .copied()
+            .collect()
+    }
+}
+
+// The variation at `path`, indices from the game's top-level variations down
+pub fn get<'a>(variations: &'a [Variation], path: &[usize]) -> Option<&'a Variation> {
+    let (&first, rest) = path.split_first()?;
+    let variation = variations.get(first)?;
+    if rest.is_empty() {
+        Some(variation)
+    } else {
+        get(&variation.variations, rest)
+    }
+}
+
+// The variations under the one at `path`, or the top-level ones for an empty path
+pub fn children_mut<'a>(
+    variations: &'a mut Vec<Variation>,
+    path: &[usize],
+) -> Option<&'a mut Vec<Variation>> {
+    match path.split_first() {
+        None => Some(variations),
+        Some((&first, rest)) => children_mut(&mut variations.get_mut(first)?.variations, rest),
+    }
+}
+
+// Variations as a save stores them: an array of tables with 1-based columns
+pub fn to_toml(variations: &[Variation]) -> toml::Value {
+    toml::Value::Array(
+        variations
+            .iter()
+            .map(|variation| {
+                let mut table = toml::Table::new();
+                table.insert(
+                    "name".to_string(),
+                    toml::Value::String(variation.name.clone()),
+                );
+                table.insert(
+                    "ply".to_string(),
+                    toml::Value::Integer(variation.ply as i64),
+                );
+                table.insert(
+                    "moves".to_string(),
+                    toml::Value::Array(
+                        variation
+                            .moves
+                            .iter()
+                            .map(|&column| toml::Value::Integer(column as i64 + 1))
+                            .collect(),
+                    ),
+                );
+                if !variation.variations.is_empty() {
+                    table.insert("variations".to_string(), to_toml(&variation.variations));
+                }
+                toml::Value::Table(table)
+            })
+            .collect(),
+    )
+}
+
+// Variations read back from a save
+pub fn from_toml(value: &toml::Value) -> Result<Vec<Variation>, String> {
+    let variations = value
+        .as_array()
+        .ok_or("'variations' must be a list of tables")?;
+    variations
+        .iter()
+        .map(|variation| {
+            let table = variation
+                .as_table()
+                .ok_or("'variations' must be a list of tables")?;
+            let name = table
+                .get("name")
+                .and_then(|name| name.as_str())
+                .ok_or("every variation needs a name")?;
+            let ply = match table.get("ply") {
+                Some(toml::Value::Integer(ply)) if *ply >= 0 => *ply as usize,
+                _ => return Err(format!("variation '{}' needs the ply it branches at", name)),
+            };
+            let moves = table
+                .get("moves")
+                .and_then(|moves| moves.as_array())
+                .and_then(|moves| {
+                    moves
+                        .iter()
+                        .map(|column| match column {
+                            toml::Value::Integer(column) if *column > 0 => {
+                                Some(*column as usize - 1)
+                            }
+                            _ => None,
+                        })
+                        .collect::<Option<Vec<usize>>>()
+                })
+                .ok_or_else(|| format!("variation '{}' must list columns from 1", name))?;
+            let variations = match table.get("variations") {
+                Some(value) => from_toml(value)?,
+                None => Vec::new(),
+            };
+            Ok(Variation {
+                name: name.to_string(),
+                ply,
+                moves,
+                variations,
+            })
+        })
+        .collect()
+}
+
+// Variations as a JSON array, with 1-based columns
+pub fn json(variations: &[Variation]) -> String {
+    let items: Vec<String> = variations
+        .iter()
+        .map(|variation| {
+            let moves: Vec<String> = variation
+                .moves
+                .iter()
+                .map(|column| (column + 1).to_string())
+                .collect();
+            format!(
+                "{{\"name\": {}, \"ply\": {}, \"moves\": [{}], \"variations\": {}}}",
+                json_string(&variation.name),
+                variation.ply,
+                moves.join(", "),
+                json(&variation.variations)
+            )
+        })
+        .collect();
+    format!("[{}]", items.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variation(name: &str, ply: usize, moves: &[usize], variations: Vec<Variation>) -> Variation {
+        Variation {
+            name: name.to_string(),
+            ply,
+            moves: moves.to_vec(),
+            variations,
+        }
+    }
+
+    #[test]
+    fn a_tree_of_variations_reads_back_as_it_was_written() {
+        let tree = vec![
+            variation(
+                "center",
+                2,
+                &[3, 3, 2],
+                vec![
+                    variation("edge", 4, &[0, 6], vec![variation("deep", 5, &[5], vec![])]),
+                    variation("quiet", 3, &[1], vec![]),
+                ],
+            ),
+            variation("from the start", 0, &[6], vec![]),
+        ];
+        let written = to_toml(&tree);
+        assert_eq!(from_toml(&written), Ok(tree.clone()));
+
+        // The same again after going through the text of a save
+        let mut save = toml::Table::new();
+        save.insert("variations".to_string(), written);
+        let text = toml::to_string(&save).expect("the variations are written");
+        assert!(
+            text.contains("moves = [4, 4, 3]"),
+            "columns are written from 1:\n{}",
+            text
+        );
+        let read: toml::Table = text.parse().expect("the save parses");
+        assert_eq!(from_toml(&read["variations"]), Ok(tree.clone()));
+        assert_eq!(
+            get(&tree, &[0, 0, 0]).map(|deep| deep.name.as_str()),
+            Some("deep")
+        );
+    }
+}
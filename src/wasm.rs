@@ -0,0 +1,209 @@
+// The game for JavaScript, built into a WebAssembly module by web/ with wasm-pack. A
+// WasmGame is a two-player game of plain drops, with columns counted from 0 and players
+// numbered 1 and 2 the way the C API counts them. A call the game turns down throws an
+// Error whose name says why: InvalidRules, GameFinished, InvalidColumn, ColumnFull,
+// IllegalMove, or NothingToUndo.
+use std::time::Duration;
+
+use wasm_bindgen::prelude::*;
+
+use crate::rules::GameRules;
+use crate::variant::Move;
+use crate::{ai, Game, MoveError, Player};
+
+#[wasm_bindgen]
+pub struct WasmGame {
+    game: Game,
+}
+
+// The name of the Error thrown for a move the game turned down
+fn error_name(err: &MoveError) -> &'static str {
+    match err {
+        MoveError::GameFinished => "GameFinished",
+        MoveError::InvalidColumn { .. } => "InvalidColumn",
+        MoveError::ColumnFull { .. } => "ColumnFull",
+        _ => "IllegalMove",
+    }
+}
+
+// An Error for JavaScript with `name` and the message `message`
+fn error(name: &str, message: &str) -> js_sys::Error {
+    let error = js_sys::Error::new(message);
+    error.set_name(name);
+    error
+}
+
+// A player as JavaScript sees them, 1 or 2
+fn number(player: Player) -> u8 {
+    player as u8
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    // A new game on a board `width` columns by `height` rows, played to `connect` in a row;
+    // rules a game can't be played by throw an Error named InvalidRules
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize, connect: usize) -> Result<WasmGame, js_sys::Error> {
+        Game::with_rules(
+            GameRules::CLASSIC
+                .with_size(width, height)
+                .with_connect(connect),
+        )
+        .map(|game| WasmGame { game })
+        .map_err(|err| error("InvalidRules", &err.to_string()))
+    }
+
+    // Drop a piece in `column` for the player to move
+    pub fn play(&mut self, column: usize) -> Result<(), js_sys::Error> {
+        self.game
+            .play(Move::Drop(column))
+            .map_err(|err| error(error_name(&err), &err.to_string()))
+    }
+
+    // Take back the last move
+    pub fn undo(&mut self) -> Result<(), js_sys::Error> {
+        if self.game.undo() {
+            Ok(())
+        } else {
+            Err(error("NothingToUndo", "There's no move to take back"))
+        }
+    }
+
+    // The columns a piece can be dropped in, from the left
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<usize> {
+        self.game.legal_moves().collect()
+    }
+
+    // The board as JSON: its size, the player to move, null once the game is over, the
+    // winner, null while there's none, and the rows from the top, 0 for an empty cell and
+    // the player's number for a piece
+    #[wasm_bindgen(js_name = boardJson)]
+    pub fn board_json(&self) -> String {
+        let or_null = |player: Option<Player>| {
+            player.map_or("null".to_string(), |player| number(player).to_string())
+        };
+        let rows: Vec<String> = self
+            .game
+            .board
+            .rows()
+            .map(|row| {
+                let cells: Vec<String> = row
+                    .iter()
+                    .map(|&cell| cell.map_or(0, number).to_string())
+                    .collect();
+                format!("[{}]", cells.join(","))
+            })
+            .collect();
+        format!(
+            "{{\"width\":{},\"height\":{},\"toMove\":{},\"winner\":{},\"rows\":[{}]}}",
+            self.game.rules.width,
+            self.game.rules.height,
+            or_null((!self.game.is_finished()).then_some(self.game.current_player)),
+            or_null(self.game.winner()),
+            rows.join(",")
+        )
+    }
+
+    #[wasm_bindgen(js_name = isFinished)]
+    pub fn is_finished(&self) -> bool {
+        self.game.is_finished()
+    }
+
+    // The player who won, or undefined while the game goes on and for a draw
+    pub fn winner(&self) -> Option<u8> {
+        self.game.winner().map(number)
+    }
+
+    // The column the computer would play for the player to move looking `depth` moves
+    // ahead, or undefined once the game is over
+    #[wasm_bindgen(js_name = bestMove)]
+    pub fn best_move(&self, depth: usize) -> Option<usize> {
+        ai::move_at_depth(&self.game, depth).map(|mv| mv.column())
+    }
+
+    // The column the computer would play searching for about `millis`, for a page that
+    // can't have its thread held up for long
+    #[wasm_bindgen(js_name = bestMoveWithin)]
+    pub fn best_move_within(&self, millis: u32) -> Option<usize> {
+        ai::move_within(&self.game, Duration::from_millis(u64::from(millis))).map(|mv| mv.column())
+    }
+
+    // The board as rows of X, O, and ., top row first
+    #[wasm_bindgen(js_name = renderAscii)]
+    pub fn render_ascii(&self) -> String {
+        self.game.board.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A classic game with these columns played through the API. Only moves the game takes
+    // are played, since an Error can only be made in a browser.
+    fn played(columns: &[usize]) -> WasmGame {
+        let mut game = WasmGame::new(7, 6, 4).expect("the rules are valid");
+        for &column in columns {
+            assert!(game.play(column).is_ok(), "column {}", column);
+        }
+        game
+    }
+
+    #[test]
+    fn a_scripted_game_plays_through_to_a_win_and_back() {
+        let mut game = played(&[0, 1, 0, 1, 0]);
+        assert_eq!(game.best_move(2), Some(0), "the threat is blocked");
+        assert!(game.play(1).is_ok());
+        assert_eq!(
+            game.best_move_within(50),
+            Some(0),
+            "the computer takes the win"
+        );
+        assert!(game.play(0).is_ok());
+        assert!(game.is_finished());
+        assert_eq!(game.winner(), Some(1));
+        assert_eq!(game.legal_moves(), Vec::<usize>::new());
+        assert_eq!(game.best_move(2), None);
+        assert_eq!(
+            game.render_ascii(),
+            [".......", ".......", "X......", "XO.....", "XO.....", "XO....."].join("\n")
+        );
+        assert!(game.undo().is_ok());
+        assert_eq!(game.winner(), None);
+        assert_eq!(game.legal_moves(), (0..7).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn the_board_is_written_as_json() {
+        let game = played(&[3, 3]);
+        let empty = "[0,0,0,0,0,0,0]";
+        assert_eq!(
+            game.board_json(),
+            format!(
+                "{{\"width\":7,\"height\":6,\"toMove\":1,\"winner\":null,\"rows\":[{0},{0},{0},{0},{1},{2}]}}",
+                empty, "[0,0,0,2,0,0,0]", "[0,0,0,1,0,0,0]"
+            )
+        );
+        let won = played(&[0, 1, 0, 1, 0, 1, 0]);
+        assert!(won.board_json().contains("\"toMove\":null,\"winner\":1,"));
+    }
+
+    #[test]
+    fn turned_down_moves_are_named_by_why() {
+        for (err, name) in [
+            (MoveError::GameFinished, "GameFinished"),
+            (
+                MoveError::InvalidColumn {
+                    attempted: 7,
+                    width: 7,
+                },
+                "InvalidColumn",
+            ),
+            (MoveError::ColumnFull { column: 0 }, "ColumnFull"),
+            (MoveError::NoPops, "IllegalMove"),
+        ] {
+            assert_eq!(error_name(&err), name, "{:?}", err);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+// Build the library for the browser, the way the wasm feature is meant to be built, when
+// the toolchain has the wasm32 standard library to build it with
+#[test]
+fn the_engine_builds_for_wasm32_without_the_terminal() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let libdir = Command::new(&rustc)
+        .args([
+            "--print",
+            "target-libdir",
+            "--target",
+            "wasm32-unknown-unknown",
+        ])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    if !libdir.is_some_and(|libdir| Path::new(&libdir).exists()) {
+        eprintln!(
+            "skipped: no wasm32-unknown-unknown target (rustup target add wasm32-unknown-unknown)"
+        );
+        return;
+    }
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    // A target directory of its own, since the one running this test is locked
+    let output = Command::new(cargo)
+        .args(["build", "--lib", "--target", "wasm32-unknown-unknown"])
+        .args(["--no-default-features", "--features", "wasm"])
+        .arg("--manifest-path")
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(Path::new(env!("CARGO_TARGET_TMPDIR")).join("wasm"))
+        .output()
+        .expect("cargo runs");
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,3 @@
+// The game's JavaScript API, which lives in the game itself, built into a WebAssembly module
+// with `wasm-pack build web --target web`; examples/web/index.html plays against it
+pub use connect_four::wasm::*;